@@ -9,8 +9,11 @@ use std::{
     rc::Rc,
     sync::{Arc, Mutex},
 };
+use util::virtual_fs::{VirtualFsFile, VirtualFsInner};
 
-struct ExplorerPane;
+struct ExplorerPane {
+    event_sender: AppContextEventSender,
+}
 
 impl ExplorerPane {
     fn tab_icon_for_pane(&mut self, pane: &SharedExplorer) -> Option<egui::ImageSource<'static>> {
@@ -35,6 +38,46 @@ impl ExplorerPane {
             .map(|pane| self.tab_icon_for_pane(pane))
             .flatten()
     }
+
+    /// Other tabs to compare with, so files inside archives can be compared too.
+    fn compare_menu_ui(
+        &mut self,
+        tiles: &egui_tiles::Tiles<SharedExplorer>,
+        tile_id: egui_tiles::TileId,
+        ui: &mut egui::Ui,
+    ) {
+        let Some(egui_tiles::Tile::Pane(pane)) = tiles.get(tile_id) else {
+            return;
+        };
+        let others: Vec<&SharedExplorer> = tiles
+            .tiles()
+            .filter_map(|tile| match tile {
+                egui_tiles::Tile::Pane(other) if other.uuid != pane.uuid => Some(other),
+                _ => None,
+            })
+            .collect();
+        if others.is_empty() {
+            ui.label("No other tabs open");
+            return;
+        }
+        for other in others {
+            if ui.button(other.title()).clicked() {
+                ui.close_menu();
+                match crate::explorers::image_diff::ImageDiffExplorer::from_explorers(
+                    &mut pane.clone(),
+                    &mut other.clone(),
+                ) {
+                    Ok(explorer) => self
+                        .event_sender
+                        .push(AppContextEvent::NewExplorer(Box::new(explorer))),
+                    Err(err) => {
+                        println!("Failed to compare images");
+                        println!("{:#?}", err);
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl egui_tiles::Behavior<SharedExplorer> for ExplorerPane {
@@ -88,6 +131,12 @@ impl egui_tiles::Behavior<SharedExplorer> for ExplorerPane {
             .interact(tab_rect, id, egui::Sense::click_and_drag())
             .on_hover_cursor(egui::CursorIcon::Grab);
 
+        tab_response.context_menu(|ui| {
+            ui.menu_button("Compare with…", |ui| {
+                self.compare_menu_ui(tiles, tile_id, ui);
+            });
+        });
+
         // Close with middle click
         if tab_response.middle_clicked() {
             if self.on_tab_close(tiles, tile_id) {
@@ -325,19 +374,39 @@ impl SharedAppContext {
             .push(AppContextEvent::NewExplorer(explorer));
     }
 
-    pub fn open_file<F: Read + Seek>(&mut self, file: F, filename: Option<String>) -> Result<()> {
+    pub fn open_file<F: Read + Seek + 'static>(
+        &mut self,
+        file: F,
+        filename: Option<String>,
+    ) -> Result<()> {
         if let Some(explorer) = loader::open_file(self.clone(), file, filename)? {
             self.new_explorer(explorer);
         }
         Ok(())
     }
 
+    pub fn open_virtual_file<F: Read + Seek + 'static, I: VirtualFsInner<F> + 'static>(
+        &mut self,
+        file: VirtualFsFile<F, I>,
+    ) -> Result<()> {
+        if let Some(explorer) = loader::open_virtual_file(self.clone(), file)? {
+            self.new_explorer(explorer);
+        }
+        Ok(())
+    }
+
     pub fn open<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
         if let Some(explorer) = loader::open(self.clone(), path)? {
             self.new_explorer(explorer);
         }
         Ok(())
     }
+
+    pub fn compare<P: AsRef<Path>>(&mut self, a: P, b: P) -> Result<()> {
+        let explorer = loader::compare(self.clone(), a, b)?;
+        self.new_explorer(explorer);
+        Ok(())
+    }
 }
 
 impl eframe::App for SharedAppContext {
@@ -400,6 +469,47 @@ impl SharedAppContext {
                         }
                     }
 
+                    if ui
+                        .button("Hex")
+                        .on_hover_text("Open a file as hex")
+                        .clicked()
+                    {
+                        if let Some(file_path) = rfd::FileDialog::new()
+                            .set_title("File to Open as Hex")
+                            .pick_file()
+                        {
+                            match crate::explorers::hex::HexExplorer::open(&file_path) {
+                                Ok(explorer) => self.new_explorer(Box::new(explorer)),
+                                Err(err) => {
+                                    println!("Failed to open \"{}\" as hex", file_path.display());
+                                    println!("{:#?}", err);
+                                }
+                            }
+                        }
+                    }
+
+                    if ui
+                        .button("Compare")
+                        .on_hover_text("Compare two images")
+                        .clicked()
+                    {
+                        if let Some((a, b)) = rfd::FileDialog::new()
+                            .set_title("First Image to Compare")
+                            .pick_file()
+                            .and_then(|a| {
+                                rfd::FileDialog::new()
+                                    .set_title("Second Image to Compare")
+                                    .pick_file()
+                                    .map(|b| (a, b))
+                            })
+                        {
+                            if let Err(err) = self.compare(a, b) {
+                                println!("Failed to compare images");
+                                println!("{:#?}", err);
+                            }
+                        }
+                    }
+
                     ui.menu_button("Settings", |ui| {
                         ui.menu_button("Theme", |ui| {
                             ui.selectable_value(
@@ -477,7 +587,10 @@ impl SharedAppContext {
             egui::CentralPanel::default()
                 .frame(egui::Frame::central_panel(&ctx.style()).multiply_with_opacity(0.5))
                 .show(ctx, |ui| {
-                    self.context.borrow_mut().tree.ui(&mut ExplorerPane, ui);
+                    let mut behavior = ExplorerPane {
+                        event_sender: self.event_sender.clone(),
+                    };
+                    self.context.borrow_mut().tree.ui(&mut behavior, ui);
                 });
         } else {
             egui::CentralPanel::default()
@@ -509,6 +622,10 @@ pub trait Explorer {
     fn icon(&self) -> Option<egui::ImageSource<'static>> {
         None
     }
+    /// The image being shown, so it can be compared with another.
+    fn image(&mut self) -> Option<image::DynamicImage> {
+        None
+    }
     fn ui(&mut self, ui: &mut egui::Ui);
 }
 
@@ -528,6 +645,9 @@ impl Explorer for SharedExplorer {
     fn icon(&self) -> Option<egui::ImageSource<'static>> {
         self.explorer.borrow().icon()
     }
+    fn image(&mut self) -> Option<image::DynamicImage> {
+        self.explorer.borrow_mut().image()
+    }
     fn ui(&mut self, ui: &mut egui::Ui) {
         self.explorer.borrow_mut().ui(ui)
     }
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use audio::clip::AudioClip;
+use rfd::FileDialog;
+use std::{fs::File, io::BufWriter, path::PathBuf};
+use util::image_utils::filename_hint;
+
+pub fn save_wav(clip: &AudioClip, filename: Option<String>) -> Result<Option<PathBuf>> {
+    let mut dialog = FileDialog::new()
+        .set_title("Export WAV")
+        .add_filter("audio/wav", &["wav"]);
+
+    if let Some(filename) = filename_hint(filename) {
+        dialog = dialog.set_file_name(format!("{}.wav", filename));
+    }
+
+    if let Some(path) = dialog.save_file() {
+        clip.write_wav(BufWriter::new(File::create(&path)?))?;
+        Ok(Some(path))
+    } else {
+        Ok(None)
+    }
+}
@@ -5,11 +5,26 @@ use egui::{
 };
 use image::DynamicImage;
 use rfd::FileDialog;
-use std::path::PathBuf;
-use util::image_utils::filename_hint;
+use std::{fs::File, io::BufWriter, path::PathBuf};
+use util::{
+    image_utils::filename_hint,
+    texture::dds::{Dds, DxgiFormat},
+};
 use uuid::Uuid;
 
 pub fn image_egui_handle(image: &DynamicImage, ctx: &Context) -> TextureHandle {
+    let mut options = TextureOptions::default();
+    if (image.width() * image.height()) <= 4096 {
+        options.magnification = TextureFilter::Nearest;
+    }
+    image_egui_handle_with_options(image, ctx, options)
+}
+
+pub fn image_egui_handle_with_options(
+    image: &DynamicImage,
+    ctx: &Context,
+    options: TextureOptions,
+) -> TextureHandle {
     // TODO: Probably want to make my own texture loader because the built in one iterates over every pixel to transform them, Which is not necessary I think.
     let image = match image {
         DynamicImage::ImageRgba8(rgba8) => ColorImage::from_rgba_unmultiplied(
@@ -22,11 +37,6 @@ pub fn image_egui_handle(image: &DynamicImage, ctx: &Context) -> TextureHandle {
         ),
     };
 
-    let mut options = TextureOptions::default();
-    if (image.width() * image.height()) <= 4096 {
-        options.magnification = TextureFilter::Nearest;
-    }
-
     ctx.load_texture(Uuid::now_v7(), image, options)
 }
 
@@ -101,7 +111,144 @@ pub fn save_image(image: &DynamicImage, filename: Option<String>) -> Result<Opti
     }
 
     if let Some(path) = dialog.save_file() {
-        image.save(&path)?;
+        match image.save(&path) {
+            // Float images can only be saved as EXR, other formats get 8 bit colors.
+            Err(image::ImageError::Unsupported(_)) => image.to_rgba8().save(&path)?,
+            result => result?,
+        }
+        Ok(Some(path))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Each image is a frame, saved as a GIF or APNG depending on the picked extension. Frames are
+/// only decoded once a file is picked. Returns the file location if file was saved.
+pub fn save_animation(
+    frames: impl FnOnce() -> Result<Vec<DynamicImage>>,
+    fps: f32,
+    filename: Option<String>,
+) -> Result<Option<PathBuf>> {
+    let mut dialog = FileDialog::new()
+        .set_title("Export Animation")
+        .add_filter("image/gif", &["gif"])
+        .add_filter("image/apng", &["png", "apng"]);
+
+    if let Some(filename) = filename_hint(filename) {
+        dialog = dialog.set_file_name(format!("{}.gif", filename));
+    }
+
+    if let Some(path) = dialog.save_file() {
+        let frames = frames()?;
+        let writer = BufWriter::new(File::create(&path)?);
+        if path.extension().is_some_and(|extension| extension == "gif") {
+            util::animation::encode_gif(&frames, fps, writer)?;
+        } else {
+            util::animation::encode_apng(&frames, fps, writer)?;
+        }
+        Ok(Some(path))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Each image is saved as a numbered PNG in the picked folder, frames are only decoded once a
+/// folder is picked. Returns the folder if saved.
+pub fn save_image_sequence(
+    frames: impl FnOnce() -> Result<Vec<DynamicImage>>,
+    filename: Option<String>,
+) -> Result<Option<PathBuf>> {
+    let dialog = FileDialog::new().set_title("Export Image Sequence");
+
+    if let Some(folder) = dialog.pick_folder() {
+        let frames = frames()?;
+        let name = filename_hint(filename).unwrap_or("frame".to_owned());
+        let digits = frames.len().to_string().len().max(3);
+        for (i, frame) in frames.iter().enumerate() {
+            let path = folder.join(format!("{}_{:0digits$}.png", name, i, digits = digits));
+            match frame.save(&path) {
+                Err(image::ImageError::Unsupported(_)) => frame.to_rgba8().save(&path)?,
+                result => result?,
+            }
+        }
+        Ok(Some(folder))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Formats that can be picked when exporting to DDS.
+pub const DDS_EXPORT_FORMATS: [(&str, DxgiFormat); 6] = [
+    ("BC1 (DXT1)", DxgiFormat::BC1_UNORM),
+    ("BC3 (DXT5)", DxgiFormat::BC3_UNORM),
+    ("BC4", DxgiFormat::BC4_UNORM),
+    ("BC5", DxgiFormat::BC5_UNORM),
+    ("BC7", DxgiFormat::BC7_UNORM),
+    ("RGBA8", DxgiFormat::R8G8B8A8_UNORM),
+];
+
+/// Returns the file location if file was saved.
+pub fn save_dds(
+    image: &DynamicImage,
+    format: DxgiFormat,
+    filename: Option<String>,
+) -> Result<Option<PathBuf>> {
+    let mut dialog = FileDialog::new()
+        .set_title("Export DDS")
+        .add_filter("image/vnd-ms.dds", &["dds"]);
+
+    if let Some(filename) = filename_hint(filename) {
+        dialog = dialog.set_file_name(format!("{}.dds", filename));
+    }
+
+    if let Some(path) = dialog.save_file() {
+        let dds = Dds::from_image(image, format, true)?;
+        dds.save(&mut BufWriter::new(File::create(&path)?))?;
+        Ok(Some(path))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Formats that can be picked when exporting to VTF.
+#[cfg(feature = "source_engine")]
+pub const VTF_EXPORT_FORMATS: [(&str, source_engine::vtf::TextureFormat); 8] = [
+    ("DXT1", source_engine::vtf::TextureFormat::DXT1),
+    (
+        "DXT1 (1 bit alpha)",
+        source_engine::vtf::TextureFormat::DXT1_ONEBITALPHA,
+    ),
+    ("DXT5", source_engine::vtf::TextureFormat::DXT5),
+    ("BGR888", source_engine::vtf::TextureFormat::BGR888),
+    ("BGRA8888", source_engine::vtf::TextureFormat::BGRA8888),
+    ("RGBA8888", source_engine::vtf::TextureFormat::RGBA8888),
+    ("I8", source_engine::vtf::TextureFormat::I8),
+    ("ATI2N", source_engine::vtf::TextureFormat::ATI2N),
+];
+
+/// Each image is a frame, returns the file location if file was saved.
+#[cfg(feature = "source_engine")]
+pub fn save_vtf(
+    frames: &[DynamicImage],
+    format: Option<source_engine::vtf::TextureFormat>,
+    filename: Option<String>,
+) -> Result<Option<PathBuf>> {
+    let mut dialog = FileDialog::new()
+        .set_title("Export VTF")
+        .add_filter("image/x-vtf", &["vtf"]);
+
+    if let Some(filename) = filename_hint(filename) {
+        dialog = dialog.set_file_name(format!("{}.vtf", filename));
+    }
+
+    if let Some(path) = dialog.save_file() {
+        let mut builder = source_engine::vtf::VtfBuilder::new(frames.to_vec());
+        if let Some(format) = format {
+            builder = builder.format(format);
+        }
+        builder
+            .build()?
+            .save(&mut BufWriter::new(File::create(&path)?))?;
         Ok(Some(path))
     } else {
         Ok(None)
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use rfd::FileDialog;
+use std::{fs::File, io::BufWriter, path::PathBuf};
+use util::{
+    image_utils::filename_hint,
+    mesh::Mesh,
+    rasterizer::{Camera, RenderOptions},
+};
+
+/// Rendering is on the CPU, so bigger views are upscaled.
+const MAX_RENDER_SIZE: f32 = 1024.0;
+const ORBIT_SPEED: f32 = 0.01;
+const ZOOM_SPEED: f32 = 0.002;
+
+/// Software rendered mesh preview, drag to orbit, scroll to zoom & double click to reset.
+#[derive(Default)]
+pub struct MeshViewer {
+    camera: Camera,
+    rendered: Option<(RenderOptions, egui::TextureHandle)>,
+}
+
+impl MeshViewer {
+    /// Must be called when the mesh changes.
+    pub fn invalidate(&mut self) {
+        self.rendered = None;
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, mesh: &Mesh) {
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+
+        if response.dragged() {
+            let delta = response.drag_delta();
+            self.camera.yaw -= delta.x * ORBIT_SPEED;
+            self.camera.pitch = (self.camera.pitch + delta.y * ORBIT_SPEED).clamp(-1.5, 1.5);
+        }
+        if response.hovered() {
+            let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                self.camera.zoom =
+                    (self.camera.zoom * (-scroll * ZOOM_SPEED).exp()).clamp(0.05, 20.0);
+            }
+        }
+        if response.double_clicked() {
+            self.camera = Camera::default();
+        }
+
+        let size = rect.size() * ui.ctx().pixels_per_point();
+        let scale = (MAX_RENDER_SIZE / size.max_elem()).min(1.0);
+        let options = RenderOptions {
+            width: (size.x * scale) as u32,
+            height: (size.y * scale) as u32,
+            camera: self.camera,
+            ..Default::default()
+        };
+        if options.width == 0 || options.height == 0 {
+            return;
+        }
+        if !self
+            .rendered
+            .as_ref()
+            .is_some_and(|(rendered, _)| *rendered == options)
+        {
+            let image = util::rasterizer::render(mesh, &options);
+            self.rendered = Some((
+                options,
+                super::image_utils::image_egui_handle(&image, ui.ctx()),
+            ));
+        }
+        if let Some((_, texture)) = &self.rendered {
+            egui::Image::new(egui::ImageSource::Texture(
+                egui::load::SizedTexture::from_handle(texture),
+            ))
+            .paint_at(ui, rect);
+        }
+    }
+}
+
+/// Returns the file location if file was saved.
+///
+/// OBJ also writes a `.mtl` next to it with the material names.
+pub fn save_mesh(mesh: &Mesh, filename: Option<String>) -> Result<Option<PathBuf>> {
+    let mut dialog = FileDialog::new()
+        .set_title("Export Model")
+        .add_filter("model/gltf-binary", &["glb"])
+        .add_filter("model/obj", &["obj"]);
+
+    if let Some(filename) = filename_hint(filename) {
+        dialog = dialog.set_file_name(format!("{}.glb", filename));
+    }
+
+    if let Some(path) = dialog.save_file() {
+        if path.extension().is_some_and(|extension| extension == "obj") {
+            let mtl_path = path.with_extension("mtl");
+            let mtl_filename = mtl_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+            mesh.write_mtl(BufWriter::new(File::create(&mtl_path)?))?;
+            mesh.write_obj(
+                BufWriter::new(File::create(&path)?),
+                mtl_filename.as_deref(),
+            )?;
+        } else {
+            mesh.write_glb(BufWriter::new(File::create(&path)?))?;
+        }
+        Ok(Some(path))
+    } else {
+        Ok(None)
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "audio")]
+pub mod audio_utils;
 pub mod image_utils;
+pub mod mesh_utils;
 pub mod splitter;
 pub mod virtual_fs;
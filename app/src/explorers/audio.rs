@@ -0,0 +1,286 @@
+use anyhow::{anyhow, Result};
+use audio::{
+    clip::AudioClip,
+    decode::AudioInfo,
+    output::{AudioOutput, NullOutput, Playback},
+    visualize::SpectrogramOptions,
+};
+use image::DynamicImage;
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+    sync::Arc,
+};
+use uuid::Uuid;
+
+use crate::{app::Explorer, app_util};
+
+const WAVEFORM_HEIGHT: f32 = 160.0;
+
+fn format_time(seconds: f64) -> String {
+    format!("{}:{:06.3}", (seconds / 60.0) as u64, seconds % 60.0)
+}
+
+pub struct AudioExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    info: AudioInfo,
+    playback: Arc<Playback>,
+    output: Box<dyn AudioOutput>,
+    output_started: bool,
+    /// Why the output failed to start, shown instead of playing.
+    output_error: Option<String>,
+    /// Cached for the width they were made for.
+    peaks: Option<(usize, Vec<(f32, f32)>)>,
+    /// Only rendered when asked for, it's slow for long clips.
+    spectrogram: Option<(DynamicImage, egui::TextureHandle)>,
+    #[cfg(feature = "source_engine")]
+    wav: Option<source_engine::sound::WavInfo>,
+}
+
+impl AudioExplorer {
+    pub fn new(info: AudioInfo, clip: AudioClip, name: Option<String>) -> AudioExplorer {
+        AudioExplorer {
+            name,
+            uuid: Uuid::now_v7(),
+            info,
+            playback: Arc::new(Playback::new(Arc::new(clip))),
+            output: audio::output::default_output(),
+            output_started: false,
+            output_error: None,
+            peaks: None,
+            spectrogram: None,
+            #[cfg(feature = "source_engine")]
+            wav: None,
+        }
+    }
+
+    /// Formats are guessed by the extension, only files with a known one are loaded.
+    pub fn file<F: Read + Seek>(mut file: F, filename: Option<String>) -> Result<AudioExplorer> {
+        let extension = filename
+            .as_deref()
+            .and_then(audio::decode::audio_extension)
+            .ok_or(anyhow!("File is not audio"))?;
+
+        // Cue points & loops that the decoder doesn't read.
+        #[cfg(feature = "source_engine")]
+        let wav = source_engine::sound::WavInfo::load(&mut file).ok();
+
+        file.rewind()?;
+        let (info, clip) = audio::decode::decode(file, Some(extension))?;
+        let explorer = AudioExplorer::new(
+            info,
+            clip,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        );
+        #[cfg(feature = "source_engine")]
+        let explorer = AudioExplorer { wav, ..explorer };
+        Ok(explorer)
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<AudioExplorer> {
+        let path: PathBuf = path.into();
+        AudioExplorer::file(File::open(&path)?, util::file_utils::filename(&path))
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        if playing && !self.output_started {
+            self.output_started = true;
+            if let Err(err) = self.output.start(self.playback.clone()) {
+                println!("Failed to start audio output");
+                println!("{:#?}", err);
+                self.output = Box::new(NullOutput::default());
+                self.output_error = Some(format!("Failed to start audio output: {}", err));
+                return;
+            }
+        }
+        self.playback.set_playing(playing);
+    }
+
+    fn controls_ui(&mut self, ui: &mut egui::Ui) {
+        let duration = self.playback.clip().duration();
+        ui.horizontal(|ui| {
+            let playing = self.playback.is_playing();
+            let audible = self.output.is_audible();
+            if ui
+                .add_enabled(
+                    audible,
+                    egui::Button::new(if playing { "Pause" } else { "Play" }),
+                )
+                .clicked()
+            {
+                self.set_playing(!playing);
+            }
+            if ui.button("Stop").clicked() {
+                self.set_playing(false);
+                self.playback.seek(0.0);
+            }
+            ui.label(format!(
+                "{} / {}",
+                format_time(self.playback.position()),
+                format_time(duration),
+            ));
+
+            let mut volume = self.playback.volume();
+            if ui
+                .add(egui::Slider::new(&mut volume, 0.0..=1.0).text("Volume"))
+                .changed()
+            {
+                self.playback.set_volume(volume);
+            }
+
+            if !audible {
+                ui.weak(
+                    self.output_error.as_deref().unwrap_or(
+                        "Built without the audio_playback feature, audio can't be played",
+                    ),
+                );
+            }
+        });
+    }
+
+    /// Click or drag to seek.
+    fn waveform_ui(&mut self, ui: &mut egui::Ui) {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), WAVEFORM_HEIGHT),
+            egui::Sense::click_and_drag(),
+        );
+        let clip = self.playback.clip();
+
+        let columns = rect.width().max(1.0) as usize;
+        let peaks = match &mut self.peaks {
+            Some((width, peaks)) if *width == columns => peaks,
+            peaks => &mut peaks.insert((columns, clip.peaks(columns))).1,
+        };
+
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+        let stroke = egui::Stroke::new(1.0, visuals.widgets.inactive.fg_stroke.color);
+        let center = rect.center().y;
+        let scale = rect.height() / 2.0;
+        for (column, (min, max)) in peaks.iter().enumerate() {
+            let x = rect.left() + column as f32 + 0.5;
+            painter.line_segment(
+                [
+                    egui::pos2(x, center - max * scale),
+                    egui::pos2(x, center - min * scale + 1.0),
+                ],
+                stroke,
+            );
+        }
+
+        let duration = clip.duration();
+        if duration > 0.0 {
+            let x = rect.left() + (self.playback.position() / duration) as f32 * rect.width();
+            painter.line_segment(
+                [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                egui::Stroke::new(2.0, visuals.selection.stroke.color),
+            );
+        }
+
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let t = ((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+            self.playback.seek(t as f64 * duration);
+        }
+    }
+
+    fn spectrogram_ui(&mut self, ui: &mut egui::Ui) {
+        let Some((image, texture)) = &self.spectrogram else {
+            if ui.button("Show Spectrogram").clicked() {
+                let image: DynamicImage = audio::visualize::spectrogram(
+                    self.playback.clip(),
+                    &SpectrogramOptions::default(),
+                )
+                .into();
+                let texture = app_util::image_utils::image_egui_handle(&image, ui.ctx());
+                self.spectrogram = Some((image, texture));
+            }
+            return;
+        };
+
+        ui.add(
+            egui::Image::new(egui::ImageSource::Texture(
+                egui::load::SizedTexture::from_handle(texture),
+            ))
+            .fit_to_exact_size(egui::vec2(ui.available_width(), WAVEFORM_HEIGHT)),
+        );
+        if ui.button("Export Spectrogram").clicked() {
+            if let Err(err) = app_util::image_utils::save_image(
+                image,
+                util::image_utils::filename_hint(self.name.clone())
+                    .map(|name| format!("{}_spectrogram", name)),
+            ) {
+                println!("Failed to export spectrogram");
+                println!("{:#?}", err);
+            }
+        }
+    }
+
+    fn info_ui(&self, ui: &mut egui::Ui) {
+        let clip = self.playback.clip();
+        ui.label("Audio Information");
+        ui.label(format!("Codec: {}", self.info.codec));
+        ui.label(format!("Channels: {}", clip.channels()));
+        ui.label(format!("Sample rate: {} Hz", clip.sample_rate()));
+        if let Some(bits_per_sample) = self.info.bits_per_sample {
+            ui.label(format!("Bits per sample: {}", bits_per_sample));
+        }
+        ui.label(format!("Samples: {}", clip.num_frames()));
+        ui.label(format!("Duration: {:.3}s", clip.duration()));
+        ui.label(format!("Output: {}", self.output.name()));
+        if ui.button("Export WAV").clicked() {
+            if let Err(err) = app_util::audio_utils::save_wav(clip, self.name.clone()) {
+                println!("Failed to export WAV");
+                println!("{:#?}", err);
+            }
+        }
+
+        if !self.info.tags.is_empty() {
+            ui.add_space(16.0);
+            ui.label("Tags");
+            egui::Grid::new(egui::Id::new(self.uuid).with("tags"))
+                .striped(true)
+                .show(ui, |ui| {
+                    for (key, value) in &self.info.tags {
+                        ui.label(key);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+        }
+
+        #[cfg(feature = "source_engine")]
+        if let Some(wav) = &self.wav {
+            ui.add_space(16.0);
+            crate::explorers::source_engine::sound::wav_cues_ui(ui, egui::Id::new(self.uuid), wav);
+        }
+    }
+}
+
+impl Explorer for AudioExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("Audio".to_owned())
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.controls_ui(ui);
+        self.waveform_ui(ui);
+        ui.add_space(8.0);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            self.spectrogram_ui(ui);
+            ui.add_space(16.0);
+            self.info_ui(ui);
+        });
+
+        if self.playback.is_playing() {
+            ui.ctx().request_repaint();
+        }
+    }
+}
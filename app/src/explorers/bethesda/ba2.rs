@@ -0,0 +1,77 @@
+use crate::{
+    app::{Explorer, SharedAppContext},
+    explorers::virtual_fs::{VirtualFsExplorer, VirtualFsExplorerOptions},
+};
+use anyhow::Result;
+use bethesda::ba2::Ba2Archive;
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::{file_utils::ArchiveFile, virtual_fs::VirtualFs};
+use uuid::Uuid;
+
+pub struct Ba2ArchiveExplorer<F: Read + Seek> {
+    explorer: VirtualFsExplorer<ArchiveFile<F>, Ba2Archive<F>>,
+}
+
+impl<F: Read + Seek + 'static> Ba2ArchiveExplorer<F> {
+    pub fn new(
+        app_context: SharedAppContext,
+        archive: Ba2Archive<F>,
+        name: Option<String>,
+    ) -> Result<Self> {
+        Ok(Ba2ArchiveExplorer {
+            explorer: VirtualFsExplorer::new(
+                app_context,
+                VirtualFs::new(archive),
+                VirtualFsExplorerOptions {
+                    name,
+                    allow_download: true,
+                },
+            )?,
+        })
+    }
+
+    pub fn file(
+        app_context: SharedAppContext,
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<Self> {
+        file.rewind()?;
+        Ba2ArchiveExplorer::new(
+            app_context,
+            Ba2Archive::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        )
+    }
+}
+
+impl Ba2ArchiveExplorer<File> {
+    pub fn open<P: Into<PathBuf>>(
+        app_context: SharedAppContext,
+        path: P,
+    ) -> Result<Ba2ArchiveExplorer<File>> {
+        let path: PathBuf = path.into();
+        Ba2ArchiveExplorer::file(
+            app_context,
+            File::open(&path)?,
+            util::file_utils::filename(path),
+        )
+    }
+}
+
+impl<F: Read + Seek + 'static> Explorer for Ba2ArchiveExplorer<F> {
+    fn uuid(&self) -> &Uuid {
+        self.explorer.uuid()
+    }
+
+    fn title(&self) -> String {
+        self.explorer.title()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.explorer.ui(ui);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    app::{Explorer, SharedAppContext},
+    explorers::virtual_fs::{VirtualFsExplorer, VirtualFsExplorerOptions},
+};
+use anyhow::Result;
+use bethesda::bsa::BsaArchive;
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::{file_utils::ArchiveFile, virtual_fs::VirtualFs};
+use uuid::Uuid;
+
+pub struct BsaArchiveExplorer<F: Read + Seek> {
+    explorer: VirtualFsExplorer<ArchiveFile<F>, BsaArchive<F>>,
+}
+
+impl<F: Read + Seek + 'static> BsaArchiveExplorer<F> {
+    pub fn new(
+        app_context: SharedAppContext,
+        archive: BsaArchive<F>,
+        name: Option<String>,
+    ) -> Result<Self> {
+        Ok(BsaArchiveExplorer {
+            explorer: VirtualFsExplorer::new(
+                app_context,
+                VirtualFs::new(archive),
+                VirtualFsExplorerOptions {
+                    name,
+                    allow_download: true,
+                },
+            )?,
+        })
+    }
+
+    pub fn file(
+        app_context: SharedAppContext,
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<Self> {
+        file.rewind()?;
+        BsaArchiveExplorer::new(
+            app_context,
+            BsaArchive::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        )
+    }
+}
+
+impl BsaArchiveExplorer<File> {
+    pub fn open<P: Into<PathBuf>>(
+        app_context: SharedAppContext,
+        path: P,
+    ) -> Result<BsaArchiveExplorer<File>> {
+        let path: PathBuf = path.into();
+        BsaArchiveExplorer::file(
+            app_context,
+            File::open(&path)?,
+            util::file_utils::filename(path),
+        )
+    }
+}
+
+impl<F: Read + Seek + 'static> Explorer for BsaArchiveExplorer<F> {
+    fn uuid(&self) -> &Uuid {
+        self.explorer.uuid()
+    }
+
+    fn title(&self) -> String {
+        self.explorer.title()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.explorer.ui(ui);
+    }
+}
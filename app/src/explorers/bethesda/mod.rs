@@ -0,0 +1,2 @@
+pub mod ba2;
+pub mod bsa;
@@ -0,0 +1,162 @@
+use anyhow::Result;
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::texture::dds::Dds;
+use uuid::Uuid;
+
+use crate::{app::Explorer, app_util};
+
+pub struct DdsExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    dds: Dds,
+    mipmap: u32,
+    face: u32,
+    slice: u32,
+
+    // Failed decodes are cached as well, so they aren't retried every frame.
+    textures: Vec<Option<Result<egui::TextureHandle, String>>>,
+}
+
+impl DdsExplorer {
+    pub fn new(dds: Dds, name: Option<String>) -> DdsExplorer {
+        DdsExplorer {
+            name,
+            uuid: Uuid::now_v7(),
+            textures: vec![None; dds.total_num_textures().unwrap_or(0)],
+            dds,
+            mipmap: 0,
+            face: 0,
+            slice: 0,
+        }
+    }
+
+    pub fn file<F: Read + Seek>(mut file: F, filename: Option<String>) -> Result<DdsExplorer> {
+        file.rewind()?;
+        Ok(DdsExplorer::new(
+            Dds::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        ))
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<DdsExplorer> {
+        let path: PathBuf = path.into();
+        DdsExplorer::file(File::open(&path)?, util::file_utils::filename(&path))
+    }
+}
+
+impl Explorer for DdsExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("DDS Texture".to_owned())
+    }
+
+    fn image(&mut self) -> Option<image::DynamicImage> {
+        self.dds.to_image(self.mipmap, self.face, self.slice).ok()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        app_util::splitter::Splitter::horizontal(self.uuid)
+            .min_size(240.0)
+            .show(ui, |ui_a, ui_b| {
+                ui_a.vertical(|ui| {
+                    ui.label("DDS Information");
+                    ui.label(format!("Format: {:?}", self.dds.format()));
+                    ui.label(format!("Size: {}x{}", self.dds.width(), self.dds.height()));
+                    if self.dds.header().depth > 1 {
+                        ui.label(format!("Depth: {}", self.dds.header().depth));
+                    }
+                    if self.dds.header().array_size > 1 {
+                        ui.label(format!("Array size: {}", self.dds.header().array_size));
+                    }
+
+                    if self.dds.total_num_textures() > Some(1) {
+                        ui.add_space(32.0);
+                    }
+
+                    ui.horizontal(|ui| {
+                        if self.dds.mipmaps() > 1 {
+                            ui.menu_button(format!("Mipmap {}", self.mipmap), |ui| {
+                                for mipmap in 0..self.dds.mipmaps() {
+                                    if ui.button(format!("Mipmap {}", mipmap)).clicked() {
+                                        self.mipmap = mipmap;
+                                    }
+                                }
+                            });
+                        }
+                        if self.dds.faces() > 1 {
+                            ui.menu_button(format!("Face {}", self.face), |ui| {
+                                for face in 0..self.dds.faces() {
+                                    if ui.button(format!("Face {}", face)).clicked() {
+                                        self.face = face;
+                                    }
+                                }
+                            });
+                        }
+                        if self.dds.slices() > 1 {
+                            ui.menu_button(format!("Slice {}", self.slice), |ui| {
+                                for slice in 0..self.dds.slices() {
+                                    if ui.button(format!("Slice {}", slice)).clicked() {
+                                        self.slice = slice;
+                                    }
+                                }
+                            });
+                        }
+                    });
+                });
+
+                let Some(texture_handle_index) =
+                    self.dds.texture_index(self.mipmap, self.face, self.slice)
+                else {
+                    return;
+                };
+                let Some(texture_handle) = self.textures.get_mut(texture_handle_index) else {
+                    return;
+                };
+                let texture_handle = texture_handle.get_or_insert_with(|| {
+                    self.dds
+                        .to_image(self.mipmap, self.face, self.slice)
+                        .map(|image| app_util::image_utils::image_egui_handle(&image, ui_b.ctx()))
+                        .map_err(|err| err.to_string())
+                });
+                let texture_handle = match texture_handle {
+                    Ok(texture_handle) => texture_handle,
+                    Err(err) => {
+                        ui_b.centered_and_justified(|ui| {
+                            ui.label(format!("Failed to decode texture: {}", err));
+                        });
+                        return;
+                    }
+                };
+
+                ui_b.add_sized(
+                    ui_b.available_size(),
+                    egui::Image::new(egui::ImageSource::Texture(
+                        egui::load::SizedTexture::from_handle(texture_handle),
+                    ))
+                    .shrink_to_fit(),
+                )
+                .context_menu(|ui| {
+                    if ui.button("Save Texture").clicked() {
+                        app_util::image_utils::save_image(
+                            &self
+                                .dds
+                                .to_image(self.mipmap, self.face, self.slice)
+                                .expect("Failed to decode DDS image"),
+                            self.name
+                                .clone()
+                                .map(|filename| filename.trim_end_matches(".dds").to_owned()),
+                        )
+                        .expect("Failed to save DDS image");
+                    }
+                });
+            });
+    }
+}
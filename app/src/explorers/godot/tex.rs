@@ -43,6 +43,10 @@ impl Explorer for GodotTexExplorer {
         self.explorer.title()
     }
 
+    fn image(&mut self) -> Option<DynamicImage> {
+        self.explorer.image()
+    }
+
     fn ui(&mut self, ui: &mut egui::Ui) {
         self.explorer.ui(ui);
     }
@@ -0,0 +1,443 @@
+use anyhow::{anyhow, Result};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+use util::file_utils::ReadSeek;
+use uuid::Uuid;
+
+use crate::app::Explorer;
+
+const BYTES_PER_ROW: u64 = 16;
+/// Rows never cross pages, so this must be a multiple of `BYTES_PER_ROW`.
+const PAGE_SIZE: u64 = 4096;
+const MAX_CACHED_PAGES: usize = 64;
+/// Searching reads this much at a time.
+const SEARCH_CHUNK_SIZE: usize = 1024 * 1024;
+/// So searching big files doesn't freeze the UI.
+const SEARCH_CHUNKS_PER_FRAME: usize = 8;
+
+/// Pairs of hex digits, whitespace between them is ignored.
+fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(&pair.iter().collect::<String>(), 16).ok())
+        .collect()
+}
+
+/// Decimal, or hex with a `0x` prefix.
+fn parse_offset(text: &str) -> Option<u64> {
+    let text = text.trim();
+    match text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Searches from `start` to the end of the file, then wraps around to the start & stops at `start`.
+struct Search {
+    pattern: Vec<u8>,
+    start: u64,
+    offset: u64,
+    wrapped: bool,
+}
+
+impl Search {
+    /// Bytes searched so far.
+    fn searched(&self, size: u64) -> u64 {
+        if self.wrapped {
+            size - self.start + self.offset
+        } else {
+            self.offset - self.start
+        }
+    }
+}
+
+/// Only the pages that are shown are read, so any size of file can be opened.
+pub struct HexExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    file: Box<dyn ReadSeek>,
+    size: u64,
+    /// Least recently read first.
+    pages: VecDeque<(u64, Vec<u8>)>,
+
+    offset_text: String,
+    search_text: String,
+    search_hex: bool,
+    /// Offset & length of the last search result.
+    found: Option<(u64, u64)>,
+    search: Option<Search>,
+    message: Option<String>,
+    scroll_to: Option<u64>,
+}
+
+impl HexExplorer {
+    pub fn file<F: Read + Seek + 'static>(
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<HexExplorer> {
+        let size = file.seek(SeekFrom::End(0))?;
+        Ok(HexExplorer {
+            name: filename.and_then(|f| util::file_utils::filename(&f)),
+            uuid: Uuid::now_v7(),
+            file: Box::new(file),
+            size,
+            pages: VecDeque::new(),
+            offset_text: String::new(),
+            search_text: String::new(),
+            search_hex: false,
+            found: None,
+            search: None,
+            message: None,
+            scroll_to: None,
+        })
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<HexExplorer> {
+        let path: PathBuf = path.into();
+        HexExplorer::file(File::open(&path)?, util::file_utils::filename(&path))
+    }
+
+    fn read_at(&mut self, offset: u64, length: usize) -> Result<Vec<u8>> {
+        let length = length.min(self.size.saturating_sub(offset) as usize);
+        let mut buf = vec![0; length];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn page(&mut self, page: u64) -> Result<&[u8]> {
+        if let Some(index) = self.pages.iter().position(|(i, _)| *i == page) {
+            let cached = self.pages.remove(index).unwrap();
+            self.pages.push_back(cached);
+        } else {
+            let data = self.read_at(page * PAGE_SIZE, PAGE_SIZE as usize)?;
+            self.pages.push_back((page, data));
+            if self.pages.len() > MAX_CACHED_PAGES {
+                self.pages.pop_front();
+            }
+        }
+        Ok(&self.pages.back().unwrap().1)
+    }
+
+    fn row(&mut self, row: u64) -> Result<Vec<u8>> {
+        let offset = row * BYTES_PER_ROW;
+        let page = self.page(offset / PAGE_SIZE)?;
+        let start = ((offset % PAGE_SIZE) as usize).min(page.len());
+        let end = (start + BYTES_PER_ROW as usize).min(page.len());
+        Ok(page[start..end].to_vec())
+    }
+
+    /// Searches up to `max_chunks` chunks, the search is done once it's None.
+    fn continue_search(&mut self, max_chunks: usize) -> Result<()> {
+        let Some(mut search) = self.search.take() else {
+            return Ok(());
+        };
+        for _ in 0..max_chunks {
+            let end = if search.wrapped {
+                search.start
+            } else {
+                self.size
+            };
+            if search.offset >= end {
+                if search.wrapped || search.start == 0 {
+                    self.found = None;
+                    self.message = Some("Not found".to_owned());
+                    return Ok(());
+                }
+                search.wrapped = true;
+                search.offset = 0;
+                continue;
+            }
+
+            let chunk =
+                self.read_at(search.offset, SEARCH_CHUNK_SIZE + search.pattern.len() - 1)?;
+            if let Some(offset) = chunk
+                .windows(search.pattern.len())
+                .position(|window| window == search.pattern)
+                .map(|index| search.offset + index as u64)
+                .filter(|&offset| offset < end)
+            {
+                self.found = Some((offset, search.pattern.len() as u64));
+                self.scroll_to = Some(offset);
+                self.message = None;
+                return Ok(());
+            }
+            search.offset += SEARCH_CHUNK_SIZE as u64;
+        }
+        self.search = Some(search);
+        Ok(())
+    }
+
+    /// Starts searching after the last result.
+    fn find_next(&mut self) -> Result<()> {
+        let pattern = if self.search_hex {
+            parse_hex(&self.search_text).ok_or(anyhow!("Invalid hex bytes"))?
+        } else {
+            self.search_text.as_bytes().to_vec()
+        };
+        if pattern.is_empty() {
+            return Err(anyhow!("Nothing to search for"));
+        }
+        let start = self
+            .found
+            .map_or(0, |(offset, _)| offset + 1)
+            .min(self.size);
+        self.search = Some(Search {
+            pattern,
+            start,
+            offset: start,
+            wrapped: false,
+        });
+        self.message = None;
+        Ok(())
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!("Size: {} bytes", self.size));
+            ui.separator();
+
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.offset_text)
+                    .hint_text("Offset (1234 or 0x4D2)")
+                    .desired_width(160.0),
+            );
+            if ui.button("Go").clicked()
+                || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+            {
+                match parse_offset(&self.offset_text) {
+                    Some(offset) if offset < self.size => {
+                        self.scroll_to = Some(offset);
+                        self.message = None;
+                    }
+                    _ => self.message = Some("Invalid offset".to_owned()),
+                }
+            }
+            ui.separator();
+
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.search_text)
+                    .hint_text(if self.search_hex {
+                        "Bytes (DE AD BE EF)"
+                    } else {
+                        "Text"
+                    })
+                    .desired_width(200.0),
+            );
+            if response.changed() {
+                self.found = None;
+            }
+            if ui.checkbox(&mut self.search_hex, "Hex").changed() {
+                self.found = None;
+            }
+            if let Some(search) = &self.search {
+                ui.add(
+                    egui::ProgressBar::new(
+                        search.searched(self.size) as f32 / self.size.max(1) as f32,
+                    )
+                    .desired_width(120.0)
+                    .show_percentage(),
+                );
+                if ui.button("Cancel").clicked() {
+                    self.search = None;
+                }
+            } else if ui.button("Find Next").clicked()
+                || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+            {
+                if let Err(err) = self.find_next() {
+                    self.message = Some(err.to_string());
+                }
+            }
+
+            if let Some(message) = &self.message {
+                ui.separator();
+                ui.label(message);
+            }
+        });
+    }
+
+    fn row_job(&self, row: u64, bytes: &[u8], ui: &egui::Ui) -> egui::text::LayoutJob {
+        let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+        let visuals = ui.visuals();
+        let format = |color: egui::Color32, highlighted: bool| egui::TextFormat {
+            font_id: font_id.clone(),
+            color,
+            background: if highlighted {
+                visuals.selection.bg_fill
+            } else {
+                egui::Color32::TRANSPARENT
+            },
+            ..Default::default()
+        };
+        let is_found = |offset: u64| {
+            self.found
+                .is_some_and(|(start, length)| offset >= start && offset < start + length)
+        };
+
+        let offset = row * BYTES_PER_ROW;
+        let mut job = egui::text::LayoutJob::default();
+        job.append(
+            &format!("{:08X}  ", offset),
+            0.0,
+            format(visuals.weak_text_color(), false),
+        );
+        for i in 0..BYTES_PER_ROW {
+            let text = match bytes.get(i as usize) {
+                Some(byte) => format!("{:02X}", byte),
+                None => "  ".to_owned(),
+            };
+            job.append(
+                &text,
+                0.0,
+                format(visuals.text_color(), is_found(offset + i)),
+            );
+            // Extra gap in the middle of the row.
+            let gap = if i == BYTES_PER_ROW / 2 - 1 {
+                "  "
+            } else {
+                " "
+            };
+            job.append(gap, 0.0, format(visuals.text_color(), false));
+        }
+        job.append(" ", 0.0, format(visuals.text_color(), false));
+        for (i, byte) in bytes.iter().enumerate() {
+            let (char, color) = if byte.is_ascii_graphic() || *byte == b' ' {
+                (*byte as char, visuals.text_color())
+            } else {
+                ('.', visuals.weak_text_color())
+            };
+            job.append(
+                &char.to_string(),
+                0.0,
+                format(color, is_found(offset + i as u64)),
+            );
+        }
+        job
+    }
+}
+
+impl Explorer for HexExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("Hex".to_owned())
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        if let Err(err) = self.continue_search(SEARCH_CHUNKS_PER_FRAME) {
+            self.message = Some(err.to_string());
+        }
+        if self.search.is_some() {
+            ui.ctx().request_repaint();
+        }
+
+        self.toolbar_ui(ui);
+        ui.separator();
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let num_rows = self.size.div_ceil(BYTES_PER_ROW);
+        let mut scroll_area = egui::ScrollArea::vertical().auto_shrink(false);
+        if let Some(offset) = self.scroll_to.take() {
+            let row = offset / BYTES_PER_ROW;
+            scroll_area = scroll_area
+                .vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+        }
+        scroll_area.show_rows(ui, row_height, num_rows as usize, |ui, rows| {
+            for row in rows {
+                let row = row as u64;
+                match self.row(row) {
+                    Ok(bytes) => {
+                        let job = self.row_job(row, &bytes, ui);
+                        ui.add(egui::Label::new(job).extend());
+                    }
+                    Err(err) => {
+                        ui.label(format!(
+                            "{:08X}  Failed to read: {}",
+                            row * BYTES_PER_ROW,
+                            err
+                        ));
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn find_next(explorer: &mut HexExplorer, pattern: &str) -> Option<u64> {
+        explorer.search_text = pattern.to_owned();
+        explorer.find_next().unwrap();
+        while explorer.search.is_some() {
+            explorer.continue_search(1).unwrap();
+        }
+        explorer.found.map(|(offset, _)| offset)
+    }
+
+    #[test]
+    fn search_wraps_around() {
+        // Matches across chunk boundaries too.
+        let mut data = vec![0u8; SEARCH_CHUNK_SIZE * 2 + 16];
+        data[10..14].copy_from_slice(b"abcd");
+        data[(SEARCH_CHUNK_SIZE - 2)..(SEARCH_CHUNK_SIZE + 2)].copy_from_slice(b"abcd");
+        let mut explorer = HexExplorer::file(Cursor::new(data), None).unwrap();
+
+        assert_eq!(find_next(&mut explorer, "abcd"), Some(10));
+        assert_eq!(
+            find_next(&mut explorer, "abcd"),
+            Some(SEARCH_CHUNK_SIZE as u64 - 2)
+        );
+        assert_eq!(find_next(&mut explorer, "abcd"), Some(10));
+
+        explorer.search_hex = true;
+        assert_eq!(
+            find_next(&mut explorer, "61 62"),
+            Some(SEARCH_CHUNK_SIZE as u64 - 2)
+        );
+        assert_eq!(find_next(&mut explorer, "ff ff"), None);
+        assert_eq!(explorer.message.as_deref(), Some("Not found"));
+    }
+
+    #[test]
+    fn search_progress() {
+        let data = vec![0u8; SEARCH_CHUNK_SIZE * 4];
+        let mut explorer = HexExplorer::file(Cursor::new(data), None).unwrap();
+        explorer.found = Some((SEARCH_CHUNK_SIZE as u64 * 2 - 1, 1));
+        explorer.search_text = "x".to_owned();
+        explorer.find_next().unwrap();
+
+        // Two chunks to the end, then wrapping around.
+        explorer.continue_search(3).unwrap();
+        let search = explorer.search.as_ref().unwrap();
+        assert!(search.wrapped);
+        assert_eq!(search.searched(explorer.size), SEARCH_CHUNK_SIZE as u64 * 2);
+        explorer.continue_search(3).unwrap();
+        assert!(explorer.search.is_none());
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            parse_hex("de AD be\tEF"),
+            Some(vec![0xDE, 0xAD, 0xBE, 0xEF])
+        );
+        assert_eq!(parse_hex("abc"), None);
+        assert_eq!(parse_offset("0x4D2"), Some(1234));
+        assert_eq!(parse_offset(" 1234 "), Some(1234));
+        assert_eq!(parse_offset("-1"), None);
+    }
+}
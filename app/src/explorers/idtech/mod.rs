@@ -0,0 +1,2 @@
+pub mod pak;
+pub mod wad;
@@ -0,0 +1,77 @@
+use crate::{
+    app::{Explorer, SharedAppContext},
+    explorers::virtual_fs::{VirtualFsExplorer, VirtualFsExplorerOptions},
+};
+use anyhow::Result;
+use idtech::pak::QuakePak;
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::{file_utils::InnerFile, virtual_fs::VirtualFs};
+use uuid::Uuid;
+
+pub struct QuakePakExplorer<F: Read + Seek> {
+    explorer: VirtualFsExplorer<InnerFile<F>, QuakePak<F>>,
+}
+
+impl<F: Read + Seek + 'static> QuakePakExplorer<F> {
+    pub fn new(
+        app_context: SharedAppContext,
+        archive: QuakePak<F>,
+        name: Option<String>,
+    ) -> Result<Self> {
+        Ok(QuakePakExplorer {
+            explorer: VirtualFsExplorer::new(
+                app_context,
+                VirtualFs::new(archive),
+                VirtualFsExplorerOptions {
+                    name,
+                    allow_download: true,
+                },
+            )?,
+        })
+    }
+
+    pub fn file(
+        app_context: SharedAppContext,
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<Self> {
+        file.rewind()?;
+        QuakePakExplorer::new(
+            app_context,
+            QuakePak::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        )
+    }
+}
+
+impl QuakePakExplorer<File> {
+    pub fn open<P: Into<PathBuf>>(
+        app_context: SharedAppContext,
+        path: P,
+    ) -> Result<QuakePakExplorer<File>> {
+        let path: PathBuf = path.into();
+        QuakePakExplorer::file(
+            app_context,
+            File::open(&path)?,
+            util::file_utils::filename(path),
+        )
+    }
+}
+
+impl<F: Read + Seek + 'static> Explorer for QuakePakExplorer<F> {
+    fn uuid(&self) -> &Uuid {
+        self.explorer.uuid()
+    }
+
+    fn title(&self) -> String {
+        self.explorer.title()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.explorer.ui(ui);
+    }
+}
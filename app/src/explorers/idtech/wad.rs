@@ -0,0 +1,141 @@
+use crate::{
+    app::{Explorer, SharedAppContext},
+    explorers::virtual_fs::{VirtualFsExplorer, VirtualFsExplorerOptions},
+};
+use anyhow::Result;
+use idtech::{wad::DoomWad, wad3::TextureWad};
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::{file_utils::ArchiveFile, virtual_fs::VirtualFs};
+use uuid::Uuid;
+
+pub struct DoomWadExplorer<F: Read + Seek> {
+    explorer: VirtualFsExplorer<ArchiveFile<F>, DoomWad<F>>,
+}
+
+impl<F: Read + Seek + 'static> DoomWadExplorer<F> {
+    pub fn new(
+        app_context: SharedAppContext,
+        archive: DoomWad<F>,
+        name: Option<String>,
+    ) -> Result<Self> {
+        Ok(DoomWadExplorer {
+            explorer: VirtualFsExplorer::new(
+                app_context,
+                VirtualFs::new(archive),
+                VirtualFsExplorerOptions {
+                    name,
+                    allow_download: true,
+                },
+            )?,
+        })
+    }
+
+    pub fn file(
+        app_context: SharedAppContext,
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<Self> {
+        file.rewind()?;
+        DoomWadExplorer::new(
+            app_context,
+            DoomWad::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        )
+    }
+}
+
+impl DoomWadExplorer<File> {
+    pub fn open<P: Into<PathBuf>>(
+        app_context: SharedAppContext,
+        path: P,
+    ) -> Result<DoomWadExplorer<File>> {
+        let path: PathBuf = path.into();
+        DoomWadExplorer::file(
+            app_context,
+            File::open(&path)?,
+            util::file_utils::filename(path),
+        )
+    }
+}
+
+impl<F: Read + Seek + 'static> Explorer for DoomWadExplorer<F> {
+    fn uuid(&self) -> &Uuid {
+        self.explorer.uuid()
+    }
+
+    fn title(&self) -> String {
+        self.explorer.title()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.explorer.ui(ui);
+    }
+}
+
+pub struct TextureWadExplorer<F: Read + Seek> {
+    explorer: VirtualFsExplorer<ArchiveFile<F>, TextureWad<F>>,
+}
+
+impl<F: Read + Seek + 'static> TextureWadExplorer<F> {
+    pub fn new(
+        app_context: SharedAppContext,
+        archive: TextureWad<F>,
+        name: Option<String>,
+    ) -> Result<Self> {
+        Ok(TextureWadExplorer {
+            explorer: VirtualFsExplorer::new(
+                app_context,
+                VirtualFs::new(archive),
+                VirtualFsExplorerOptions {
+                    name,
+                    allow_download: true,
+                },
+            )?,
+        })
+    }
+
+    pub fn file(
+        app_context: SharedAppContext,
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<Self> {
+        file.rewind()?;
+        TextureWadExplorer::new(
+            app_context,
+            TextureWad::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        )
+    }
+}
+
+impl TextureWadExplorer<File> {
+    pub fn open<P: Into<PathBuf>>(
+        app_context: SharedAppContext,
+        path: P,
+    ) -> Result<TextureWadExplorer<File>> {
+        let path: PathBuf = path.into();
+        TextureWadExplorer::file(
+            app_context,
+            File::open(&path)?,
+            util::file_utils::filename(path),
+        )
+    }
+}
+
+impl<F: Read + Seek + 'static> Explorer for TextureWadExplorer<F> {
+    fn uuid(&self) -> &Uuid {
+        self.explorer.uuid()
+    }
+
+    fn title(&self) -> String {
+        self.explorer.title()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.explorer.ui(ui);
+    }
+}
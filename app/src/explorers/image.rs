@@ -1,18 +1,90 @@
 use crate::{app::Explorer, app_util};
-use anyhow::Result;
-use image::DynamicImage;
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, GenericImageView, Rgba};
 use std::{
     fs::File,
     io::{BufReader, Read, Seek},
     path::PathBuf,
 };
+use util::animation::AnimationFrame;
 use uuid::Uuid;
 
+/// Browsers play frames shorter than this at 100ms, a lot of GIFs depend on it.
+const MIN_FRAME_DELAY: f32 = 20.0;
+const DEFAULT_FRAME_DELAY: f32 = 100.0;
+const ZOOM_SPEED: f32 = 0.002;
+const MIN_ZOOM: f32 = 1.0 / 256.0;
+const MAX_ZOOM: f32 = 256.0;
+/// Zoomed in further than this, pixels are drawn as squares instead of blurred.
+const NEAREST_ZOOM: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Channel {
+    All,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Channel {
+    const CHANNELS: [(Channel, &'static str); 5] = [
+        (Channel::All, "RGBA"),
+        (Channel::Red, "R"),
+        (Channel::Green, "G"),
+        (Channel::Blue, "B"),
+        (Channel::Alpha, "A"),
+    ];
+
+    fn index(&self) -> Option<usize> {
+        match self {
+            Channel::All => None,
+            Channel::Red => Some(0),
+            Channel::Green => Some(1),
+            Channel::Blue => Some(2),
+            Channel::Alpha => Some(3),
+        }
+    }
+}
+
+/// Float images show their actual values, everything else as 8 bit.
+fn pixel_label(image: &DynamicImage, x: u32, y: u32) -> String {
+    match image {
+        DynamicImage::ImageRgb32F(image) => {
+            let [r, g, b] = image.get_pixel(x, y).0;
+            format!("RGB {:.3}, {:.3}, {:.3}", r, g, b)
+        }
+        DynamicImage::ImageRgba32F(image) => {
+            let [r, g, b, a] = image.get_pixel(x, y).0;
+            format!("RGBA {:.3}, {:.3}, {:.3}, {:.3}", r, g, b, a)
+        }
+        image => {
+            let [r, g, b, a] = image.get_pixel(x, y).0;
+            format!(
+                "RGBA {}, {}, {}, {} (#{:02X}{:02X}{:02X}{:02X})",
+                r, g, b, a, r, g, b, a
+            )
+        }
+    }
+}
+
 pub struct ImageExplorer {
     name: Option<String>,
     uuid: Uuid,
     image: DynamicImage,
-    texture: Option<egui::TextureHandle>,
+    /// Made for the shown channel & whether it is nearest filtered.
+    texture: Option<(Channel, bool, egui::TextureHandle)>,
+    channel: Channel,
+    /// Screen pixels per image pixel, none fits the image in the view.
+    zoom: Option<f32>,
+    offset: egui::Vec2,
+
+    /// More than one frame if animated, `image` is the current frame.
+    frames: Vec<AnimationFrame>,
+    frame: usize,
+    playing: bool,
+    /// In milliseconds, time since the frame was last advanced.
+    frame_time: f32,
 }
 
 impl ImageExplorer {
@@ -22,6 +94,92 @@ impl ImageExplorer {
             uuid: Uuid::now_v7(),
             image,
             texture: None,
+            channel: Channel::All,
+            zoom: None,
+            offset: egui::Vec2::ZERO,
+            frames: Vec::new(),
+            frame: 0,
+            playing: false,
+            frame_time: 0.0,
+        }
+    }
+
+    /// Animations with one frame are shown as still images.
+    pub fn new_animated(
+        frames: Vec<AnimationFrame>,
+        name: Option<String>,
+    ) -> Option<ImageExplorer> {
+        let first = frames.first()?.image.clone();
+        let mut explorer = ImageExplorer::new(first, name);
+        if frames.len() > 1 {
+            explorer.frames = frames;
+            explorer.playing = true;
+        }
+        Some(explorer)
+    }
+
+    fn frame_delay(&self) -> f32 {
+        match self.frames[self.frame].delay {
+            delay if delay < MIN_FRAME_DELAY => DEFAULT_FRAME_DELAY,
+            delay => delay,
+        }
+    }
+
+    fn set_frame(&mut self, frame: usize) {
+        if frame != self.frame {
+            self.frame = frame;
+            self.image = self.frames[frame].image.clone();
+            self.texture = None;
+        }
+    }
+
+    fn animation_ui(&mut self, ui: &mut egui::Ui) {
+        let num_frames = self.frames.len();
+        ui.horizontal(|ui| {
+            if ui
+                .button(if self.playing { "Pause" } else { "Play" })
+                .clicked()
+            {
+                self.playing = !self.playing;
+                self.frame_time = 0.0;
+            }
+            if ui.button("<").clicked() {
+                self.playing = false;
+                self.set_frame((self.frame + num_frames - 1) % num_frames);
+            }
+            if ui.button(">").clicked() {
+                self.playing = false;
+                self.set_frame((self.frame + 1) % num_frames);
+            }
+            let mut frame = self.frame;
+            if ui
+                .add(egui::Slider::new(&mut frame, 0..=(num_frames - 1)).text("Frame"))
+                .changed()
+            {
+                self.set_frame(frame);
+            }
+            ui.label(format!("{}ms", self.frames[self.frame].delay));
+        });
+
+        if self.playing {
+            self.frame_time += ui.ctx().input(|i| i.stable_dt) * 1000.0;
+            while self.frame_time >= self.frame_delay() {
+                self.frame_time -= self.frame_delay();
+                self.set_frame((self.frame + 1) % num_frames);
+            }
+            ui.ctx().request_repaint();
+        }
+    }
+
+    /// Every frame if animated, otherwise just the image.
+    fn export_frames(&self) -> Vec<DynamicImage> {
+        if self.frames.is_empty() {
+            vec![self.image.clone()]
+        } else {
+            self.frames
+                .iter()
+                .map(|frame| frame.image.clone())
+                .collect()
         }
     }
 
@@ -36,6 +194,22 @@ impl ImageExplorer {
         // }
         decoder = decoder.with_guessed_format()?;
 
+        if let Some(format) = decoder.format() {
+            let mut reader = decoder.into_inner();
+            reader.rewind()?;
+            if let Ok(frames) = util::animation::decode_animation(&mut reader, format) {
+                if let Some(explorer) = ImageExplorer::new_animated(
+                    frames,
+                    filename.and_then(|f| util::file_utils::filename(&f)),
+                ) {
+                    return Ok(explorer);
+                }
+                return Err(anyhow!("Animation has no frames"));
+            }
+            reader.rewind()?;
+            decoder = image::ImageReader::with_format(reader, format);
+        }
+
         let image = decoder.decode()?;
 
         Ok(ImageExplorer::new(
@@ -48,6 +222,203 @@ impl ImageExplorer {
         let path: PathBuf = path.into();
         ImageExplorer::file(File::open(&path)?, util::file_utils::filename(&path))
     }
+
+    fn toolbar_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Size: {}x{}",
+                self.image.width(),
+                self.image.height()
+            ));
+            ui.separator();
+            if ui.selectable_label(self.zoom.is_none(), "Fit").clicked() {
+                self.zoom = None;
+                self.offset = egui::Vec2::ZERO;
+            }
+            let actual_size = self.zoom == Some(1.0);
+            if ui.selectable_label(actual_size, "1:1").clicked() {
+                self.zoom = if actual_size { None } else { Some(1.0) };
+                self.offset = egui::Vec2::ZERO;
+            }
+            ui.separator();
+            for (channel, label) in Channel::CHANNELS {
+                ui.selectable_value(&mut self.channel, channel, label);
+            }
+        });
+    }
+
+    fn texture(&mut self, ctx: &egui::Context, nearest: bool) -> &egui::TextureHandle {
+        if self
+            .texture
+            .as_ref()
+            .is_none_or(|(channel, filter, _)| *channel != self.channel || *filter != nearest)
+        {
+            let filter = if nearest {
+                egui::TextureFilter::Nearest
+            } else {
+                egui::TextureFilter::Linear
+            };
+            let options = egui::TextureOptions {
+                magnification: filter,
+                ..Default::default()
+            };
+            let texture = match self.channel.index() {
+                None => {
+                    app_util::image_utils::image_egui_handle_with_options(&self.image, ctx, options)
+                }
+                // Single channels are shown as grayscale.
+                Some(index) => {
+                    let mut image = self.image.to_rgba8();
+                    for pixel in image.pixels_mut() {
+                        let value = pixel[index];
+                        *pixel = Rgba([value, value, value, 255]);
+                    }
+                    app_util::image_utils::image_egui_handle_with_options(
+                        &DynamicImage::ImageRgba8(image),
+                        ctx,
+                        options,
+                    )
+                }
+            };
+            self.texture = Some((self.channel, nearest, texture));
+        }
+        &self.texture.as_ref().unwrap().2
+    }
+
+    /// Drag to pan, scroll to zoom & double click to fit.
+    fn view_ui(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let image_size = egui::vec2(self.image.width() as f32, self.image.height() as f32);
+        let fit_zoom = ((rect.size() / image_size).min_elem() * pixels_per_point).max(MIN_ZOOM);
+        let mut zoom = self.zoom.unwrap_or(fit_zoom);
+
+        if response.dragged() {
+            self.offset += response.drag_delta();
+            self.zoom = Some(zoom);
+        }
+        if response.hovered() {
+            let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                let new_zoom = (zoom * (scroll * ZOOM_SPEED).exp()).clamp(MIN_ZOOM, MAX_ZOOM);
+                // Zoom towards the pointer.
+                if let Some(pointer) = response.hover_pos() {
+                    let anchor = pointer - rect.center() - self.offset;
+                    self.offset -= anchor * (new_zoom / zoom - 1.0);
+                }
+                zoom = new_zoom;
+                self.zoom = Some(zoom);
+            }
+        }
+        if response.double_clicked() {
+            self.zoom = None;
+            self.offset = egui::Vec2::ZERO;
+            zoom = fit_zoom;
+        }
+
+        let painter = ui.painter_at(rect);
+        // Snapped to the pixel grid so 1:1 stays sharp.
+        let image_rect = egui::Rect::from_min_size(
+            painter.round_pos_to_pixels(
+                rect.center() + self.offset - image_size * zoom / pixels_per_point / 2.0,
+            ),
+            image_size * zoom / pixels_per_point,
+        );
+        let texture = self.texture(ui.ctx(), zoom >= NEAREST_ZOOM);
+        painter.image(
+            texture.id(),
+            image_rect,
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+            egui::Color32::WHITE,
+        );
+
+        let mut text = format!("{:.0}%", zoom * 100.0);
+        if let Some(pointer) = response.hover_pos() {
+            let pixel = ((pointer - image_rect.min) / image_rect.size() * image_size).floor();
+            if pixel.x >= 0.0 && pixel.y >= 0.0 && pixel.x < image_size.x && pixel.y < image_size.y
+            {
+                let (x, y) = (pixel.x as u32, pixel.y as u32);
+                text = format!("{}  {}, {}  {}", text, x, y, pixel_label(&self.image, x, y));
+            }
+        }
+        let visuals = ui.visuals();
+        let galley = painter.layout_no_wrap(
+            text,
+            egui::TextStyle::Monospace.resolve(ui.style()),
+            visuals.text_color(),
+        );
+        let text_rect = egui::Align2::LEFT_BOTTOM
+            .anchor_size(rect.left_bottom() + egui::vec2(4.0, -4.0), galley.size());
+        painter.rect_filled(
+            text_rect.expand(2.0),
+            2.0,
+            visuals.extreme_bg_color.gamma_multiply(0.8),
+        );
+        painter.galley(text_rect.min, galley, visuals.text_color());
+
+        response
+    }
+
+    fn context_menu_ui(&self, ui: &mut egui::Ui) {
+        if ui
+            .button(if self.frames.is_empty() {
+                "Save Image"
+            } else {
+                "Save Frame"
+            })
+            .clicked()
+        {
+            app_util::image_utils::save_image(&self.image, self.name.clone())
+                .expect("Failed to save image");
+        }
+        if !self.frames.is_empty() && ui.button("Export Frames").clicked() {
+            app_util::image_utils::save_image_sequence(
+                || Ok(self.export_frames()),
+                self.name.clone(),
+            )
+            .expect("Failed to save frames");
+            ui.close_menu();
+        }
+        ui.menu_button("Export as DDS", |ui| {
+            for (name, format) in app_util::image_utils::DDS_EXPORT_FORMATS {
+                if ui.button(name).clicked() {
+                    if let Err(err) =
+                        app_util::image_utils::save_dds(&self.image, format, self.name.clone())
+                    {
+                        println!("Failed to export DDS");
+                        println!("{:#?}", err);
+                    }
+                    ui.close_menu();
+                }
+            }
+        });
+        #[cfg(feature = "source_engine")]
+        ui.menu_button("Export as VTF", |ui| {
+            if ui.button("Automatic").clicked() {
+                if let Err(err) =
+                    app_util::image_utils::save_vtf(&self.export_frames(), None, self.name.clone())
+                {
+                    println!("Failed to export VTF");
+                    println!("{:#?}", err);
+                }
+                ui.close_menu();
+            }
+            for (name, format) in app_util::image_utils::VTF_EXPORT_FORMATS {
+                if ui.button(name).clicked() {
+                    if let Err(err) = app_util::image_utils::save_vtf(
+                        &self.export_frames(),
+                        Some(format),
+                        self.name.clone(),
+                    ) {
+                        println!("Failed to export VTF");
+                        println!("{:#?}", err);
+                    }
+                    ui.close_menu();
+                }
+            }
+        });
+    }
 }
 
 impl Explorer for ImageExplorer {
@@ -59,26 +430,15 @@ impl Explorer for ImageExplorer {
         self.name.clone().unwrap_or("Image".to_owned())
     }
 
+    fn image(&mut self) -> Option<DynamicImage> {
+        Some(self.image.clone())
+    }
+
     fn ui(&mut self, ui: &mut egui::Ui) {
-        if self.texture.is_none() {
-            self.texture = Some(app_util::image_utils::image_egui_handle(
-                &self.image,
-                ui.ctx(),
-            ));
+        if !self.frames.is_empty() {
+            self.animation_ui(ui);
         }
-        let texture = self.texture.as_ref().unwrap();
-        ui.add_sized(
-            ui.available_size(),
-            egui::Image::new(egui::ImageSource::Texture(
-                egui::load::SizedTexture::from_handle(&texture),
-            ))
-            .shrink_to_fit(),
-        )
-        .context_menu(|ui| {
-            if ui.button("Save Image").clicked() {
-                app_util::image_utils::save_image(&self.image, self.name.clone())
-                    .expect("Failed to save image");
-            }
-        });
+        self.toolbar_ui(ui);
+        self.view_ui(ui).context_menu(|ui| self.context_menu_ui(ui));
     }
 }
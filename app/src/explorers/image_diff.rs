@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, GenericImageView};
+use std::path::Path;
+use util::image_diff::ImageDiff;
+use uuid::Uuid;
+
+use crate::{
+    app::{Explorer, SharedAppContext},
+    app_util, loader,
+};
+
+const FULL_UV: egui::Rect = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffMode {
+    SideBySide,
+    OnionSkin,
+    Swipe,
+    Difference,
+}
+
+/// Largest rect with the aspect ratio of `size` that fits in `rect`.
+fn fit_rect(rect: egui::Rect, size: egui::Vec2) -> egui::Rect {
+    let scale = (rect.size() / size).min_elem();
+    egui::Rect::from_center_size(rect.center(), size * scale)
+}
+
+/// Compares two images, the second one is resized if they aren't the same size.
+pub struct ImageDiffExplorer {
+    names: [String; 2],
+    uuid: Uuid,
+
+    images: [DynamicImage; 2],
+    /// Original size of the second image, if it was resized.
+    resized_from: Option<(u32, u32)>,
+    diff: ImageDiff,
+    /// Both images & the heatmap.
+    textures: Option<[egui::TextureHandle; 3]>,
+    mode: DiffMode,
+    /// Of the second image over the first.
+    opacity: f32,
+    /// From 0.0 to 1.0, the second image is shown right of it.
+    swipe: f32,
+}
+
+impl ImageDiffExplorer {
+    pub fn new(a: DynamicImage, b: DynamicImage, names: [String; 2]) -> Result<ImageDiffExplorer> {
+        let resized_from = (a.dimensions() != b.dimensions()).then(|| b.dimensions());
+        let b = if resized_from.is_some() {
+            b.resize_exact(a.width(), a.height(), image::imageops::FilterType::Triangle)
+        } else {
+            b
+        };
+        let diff = ImageDiff::new(&a.to_rgba8(), &b.to_rgba8())?;
+        Ok(ImageDiffExplorer {
+            names,
+            uuid: Uuid::now_v7(),
+            images: [a, b],
+            resized_from,
+            diff,
+            textures: None,
+            mode: DiffMode::SideBySide,
+            opacity: 0.5,
+            swipe: 0.5,
+        })
+    }
+
+    /// Anything that shows an image can be compared, with the explorers' titles as names.
+    pub fn from_explorers(a: &mut dyn Explorer, b: &mut dyn Explorer) -> Result<ImageDiffExplorer> {
+        let image = |explorer: &mut dyn Explorer| {
+            explorer
+                .image()
+                .ok_or(anyhow!("\"{}\" is not an image", explorer.title()))
+        };
+        ImageDiffExplorer::new(image(a)?, image(b)?, [a.title(), b.title()])
+    }
+
+    /// Both files are opened by the loader like any other.
+    pub fn open<P: AsRef<Path>>(
+        app_context: SharedAppContext,
+        a: P,
+        b: P,
+    ) -> Result<ImageDiffExplorer> {
+        let open = |path: P| {
+            loader::open(app_context.clone(), path)?.ok_or(anyhow!("Failed to open file"))
+        };
+        ImageDiffExplorer::from_explorers(open(a)?.as_mut(), open(b)?.as_mut())
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.mode, DiffMode::SideBySide, "Side by Side");
+            ui.selectable_value(&mut self.mode, DiffMode::OnionSkin, "Onion Skin");
+            ui.selectable_value(&mut self.mode, DiffMode::Swipe, "Swipe");
+            ui.selectable_value(&mut self.mode, DiffMode::Difference, "Difference");
+            ui.separator();
+            match self.mode {
+                DiffMode::OnionSkin => {
+                    ui.add(egui::Slider::new(&mut self.opacity, 0.0..=1.0).text("Opacity"));
+                }
+                DiffMode::Swipe => {
+                    ui.add(egui::Slider::new(&mut self.swipe, 0.0..=1.0).text("Position"));
+                }
+                DiffMode::Difference => {
+                    if ui.button("Export Heatmap").clicked() {
+                        if let Err(err) = app_util::image_utils::save_image(
+                            &DynamicImage::ImageRgba8(self.diff.heatmap.clone()),
+                            util::image_utils::filename_hint(Some(self.names[0].clone()))
+                                .map(|name| format!("{}_diff", name)),
+                        ) {
+                            println!("Failed to export heatmap");
+                            println!("{:#?}", err);
+                        }
+                    }
+                }
+                DiffMode::SideBySide => {}
+            }
+        });
+    }
+
+    fn statistics_ui(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let diff = &self.diff;
+            ui.label(format!(
+                "Changed pixels: {} / {} ({:.2}%)",
+                diff.changed_pixels,
+                diff.total_pixels,
+                diff.changed_pixels as f64 / diff.total_pixels.max(1) as f64 * 100.0,
+            ));
+            ui.separator();
+            ui.label(format!("Max difference: {}", diff.max_difference));
+            ui.separator();
+            ui.label(match diff.psnr() {
+                Some(psnr) => format!("PSNR: {:.2} dB", psnr),
+                None => "PSNR: ∞ (identical)".to_owned(),
+            });
+            if let Some((width, height)) = self.resized_from {
+                ui.separator();
+                ui.label(format!(
+                    "{} was resized from {}x{} to {}x{}",
+                    self.names[1],
+                    width,
+                    height,
+                    self.images[0].width(),
+                    self.images[0].height(),
+                ));
+            }
+        });
+    }
+
+    fn view_ui(&mut self, ui: &mut egui::Ui) {
+        let [a, b, heatmap] = self.textures.get_or_insert_with(|| {
+            [
+                app_util::image_utils::image_egui_handle(&self.images[0], ui.ctx()),
+                app_util::image_utils::image_egui_handle(&self.images[1], ui.ctx()),
+                app_util::image_utils::image_egui_handle(
+                    &DynamicImage::ImageRgba8(self.diff.heatmap.clone()),
+                    ui.ctx(),
+                ),
+            ]
+        });
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        let size = egui::vec2(
+            self.images[0].width() as f32,
+            self.images[0].height() as f32,
+        );
+        let text_color = ui.visuals().text_color();
+        let font = egui::TextStyle::Body.resolve(ui.style());
+
+        match self.mode {
+            DiffMode::SideBySide => {
+                let spacing = ui.spacing().item_spacing.x;
+                let label_height = ui.text_style_height(&egui::TextStyle::Body) + spacing;
+                let half = egui::vec2((rect.width() - spacing) / 2.0, rect.height());
+                for (i, texture) in [a, b].into_iter().enumerate() {
+                    let half_rect = egui::Rect::from_min_size(
+                        rect.min + egui::vec2(i as f32 * (half.x + spacing), 0.0),
+                        half,
+                    );
+                    painter.text(
+                        half_rect.center_top(),
+                        egui::Align2::CENTER_TOP,
+                        &self.names[i],
+                        font.clone(),
+                        text_color,
+                    );
+                    let mut image_rect = half_rect;
+                    image_rect.min.y += label_height;
+                    painter.image(
+                        texture.id(),
+                        fit_rect(image_rect, size),
+                        FULL_UV,
+                        egui::Color32::WHITE,
+                    );
+                }
+            }
+            DiffMode::OnionSkin => {
+                let image_rect = fit_rect(rect, size);
+                painter.image(a.id(), image_rect, FULL_UV, egui::Color32::WHITE);
+                painter.image(
+                    b.id(),
+                    image_rect,
+                    FULL_UV,
+                    egui::Color32::WHITE.gamma_multiply(self.opacity),
+                );
+            }
+            DiffMode::Swipe => {
+                let image_rect = fit_rect(rect, size);
+                if let Some(pointer) = response.interact_pointer_pos() {
+                    self.swipe =
+                        ((pointer.x - image_rect.left()) / image_rect.width()).clamp(0.0, 1.0);
+                }
+                let split = image_rect.left() + self.swipe * image_rect.width();
+                painter.image(a.id(), image_rect, FULL_UV, egui::Color32::WHITE);
+                painter.image(
+                    b.id(),
+                    egui::Rect::from_min_max(egui::pos2(split, image_rect.top()), image_rect.max),
+                    egui::Rect::from_min_max(egui::pos2(self.swipe, 0.0), egui::pos2(1.0, 1.0)),
+                    egui::Color32::WHITE,
+                );
+                painter.line_segment(
+                    [
+                        egui::pos2(split, image_rect.top()),
+                        egui::pos2(split, image_rect.bottom()),
+                    ],
+                    egui::Stroke::new(2.0, ui.visuals().selection.stroke.color),
+                );
+                painter.text(
+                    image_rect.left_top(),
+                    egui::Align2::LEFT_TOP,
+                    &self.names[0],
+                    font.clone(),
+                    text_color,
+                );
+                painter.text(
+                    image_rect.right_top(),
+                    egui::Align2::RIGHT_TOP,
+                    &self.names[1],
+                    font,
+                    text_color,
+                );
+            }
+            DiffMode::Difference => {
+                painter.image(
+                    heatmap.id(),
+                    fit_rect(rect, size),
+                    FULL_UV,
+                    egui::Color32::WHITE,
+                );
+            }
+        }
+    }
+}
+
+impl Explorer for ImageDiffExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        format!("{} vs {}", self.names[0], self.names[1])
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.toolbar_ui(ui);
+        self.statistics_ui(ui);
+        self.view_ui(ui);
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::PathBuf,
+};
+use util::mesh::Mesh;
+use uuid::Uuid;
+
+use crate::{
+    app::Explorer,
+    app_util::{self, mesh_utils::MeshViewer},
+};
+
+pub struct MeshExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    mesh: Mesh,
+    viewer: MeshViewer,
+}
+
+impl MeshExplorer {
+    pub fn new(mesh: Mesh, name: Option<String>) -> MeshExplorer {
+        MeshExplorer {
+            name,
+            uuid: Uuid::now_v7(),
+            mesh,
+            viewer: MeshViewer::default(),
+        }
+    }
+
+    /// OBJ has no identifier, so only `.obj` files are loaded.
+    pub fn file<F: Read + Seek>(mut file: F, filename: Option<String>) -> Result<MeshExplorer> {
+        if !filename
+            .as_ref()
+            .is_some_and(|filename| filename.to_lowercase().ends_with(".obj"))
+        {
+            return Err(anyhow!("File is not an OBJ"));
+        }
+        file.rewind()?;
+        Ok(MeshExplorer::new(
+            Mesh::read_obj(BufReader::new(file))?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        ))
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<MeshExplorer> {
+        let path: PathBuf = path.into();
+        MeshExplorer::file(File::open(&path)?, util::file_utils::filename(&path))
+    }
+}
+
+impl Explorer for MeshExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("Mesh".to_owned())
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        app_util::splitter::Splitter::horizontal(self.uuid)
+            .min_size(240.0)
+            .show(ui, |ui_a, ui_b| {
+                egui::ScrollArea::vertical().show(ui_a, |ui| {
+                    ui.label("Mesh Information");
+                    ui.label(format!("Vertices: {}", self.mesh.positions.len()));
+                    ui.label(format!("Triangles: {}", self.mesh.num_triangles()));
+                    if let Some((min, max)) = self.mesh.bounds() {
+                        ui.label(format!("Bounds: {:?} to {:?}", min, max));
+                    }
+                    if ui.button("Export Model").clicked() {
+                        if let Err(err) =
+                            app_util::mesh_utils::save_mesh(&self.mesh, self.name.clone())
+                        {
+                            println!("Failed to export model");
+                            println!("{:#?}", err);
+                        }
+                    }
+
+                    let materials = self.mesh.materials();
+                    if !materials.is_empty() {
+                        ui.add_space(16.0);
+                        ui.label("Materials");
+                        for material in materials {
+                            ui.label(egui::RichText::new(material).monospace());
+                        }
+                    }
+                });
+
+                self.viewer.ui(ui_b, &self.mesh);
+            });
+    }
+}
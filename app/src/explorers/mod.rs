@@ -1,9 +1,24 @@
+#[cfg(feature = "audio")]
+pub mod audio;
+#[cfg(feature = "bethesda")]
+pub mod bethesda;
+pub mod dds;
 #[cfg(feature = "godot")]
 pub mod godot;
+pub mod hex;
+#[cfg(feature = "idtech")]
+pub mod idtech;
 pub mod image;
+pub mod image_diff;
+pub mod mesh;
 #[cfg(feature = "renpy")]
 pub mod renpy;
+#[cfg(feature = "rpgmaker")]
+pub mod rpgmaker;
 #[cfg(feature = "source_engine")]
 pub mod source_engine;
+pub mod svg;
 pub mod text;
+#[cfg(feature = "video")]
+pub mod video;
 pub mod virtual_fs;
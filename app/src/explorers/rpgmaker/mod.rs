@@ -0,0 +1,2 @@
+pub mod mv;
+pub mod rgss;
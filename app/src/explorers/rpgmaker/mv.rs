@@ -0,0 +1,100 @@
+use crate::{
+    app::{Explorer, SharedAppContext},
+    explorers::{
+        image::ImageExplorer,
+        virtual_fs::{VirtualFsExplorer, VirtualFsExplorerOptions},
+    },
+};
+use anyhow::Result;
+use image::DynamicImage;
+use rpgmaker::mv::{RpgMakerFile, RpgMakerGame};
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::virtual_fs::VirtualFs;
+use uuid::Uuid;
+
+pub struct RpgMakerGameExplorer {
+    explorer: VirtualFsExplorer<RpgMakerFile<File>, RpgMakerGame>,
+}
+
+impl RpgMakerGameExplorer {
+    pub fn new(
+        app_context: SharedAppContext,
+        game: RpgMakerGame,
+        name: Option<String>,
+    ) -> Result<Self> {
+        Ok(RpgMakerGameExplorer {
+            explorer: VirtualFsExplorer::new(
+                app_context,
+                VirtualFs::new(game),
+                VirtualFsExplorerOptions {
+                    name,
+                    allow_download: true,
+                },
+            )?,
+        })
+    }
+
+    pub fn open<P: Into<PathBuf>>(app_context: SharedAppContext, path: P) -> Result<Self> {
+        let path: PathBuf = path.into();
+        let game = RpgMakerGame::open(&path)?;
+        RpgMakerGameExplorer::new(app_context, game, util::file_utils::filename(path))
+    }
+}
+
+impl Explorer for RpgMakerGameExplorer {
+    fn uuid(&self) -> &Uuid {
+        self.explorer.uuid()
+    }
+
+    fn title(&self) -> String {
+        self.explorer.title()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.explorer.ui(ui);
+    }
+}
+
+/// Standalone encrypted image, decrypted without the game's key.
+pub struct RpgMakerImageExplorer {
+    explorer: ImageExplorer,
+}
+
+impl RpgMakerImageExplorer {
+    pub fn file<F: Read + Seek>(mut file: F, filename: Option<String>) -> Result<Self> {
+        file.rewind()?;
+        Ok(Self {
+            explorer: ImageExplorer::file(
+                rpgmaker::mv::decrypt_image(file)?,
+                filename.and_then(|f| util::file_utils::filename(&f)),
+            )?,
+        })
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path: PathBuf = path.into();
+        Self::file(File::open(&path)?, util::file_utils::filename(&path))
+    }
+}
+
+impl Explorer for RpgMakerImageExplorer {
+    fn uuid(&self) -> &Uuid {
+        self.explorer.uuid()
+    }
+
+    fn title(&self) -> String {
+        self.explorer.title()
+    }
+
+    fn image(&mut self) -> Option<DynamicImage> {
+        self.explorer.image()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.explorer.ui(ui);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    app::{Explorer, SharedAppContext},
+    explorers::virtual_fs::{VirtualFsExplorer, VirtualFsExplorerOptions},
+};
+use anyhow::Result;
+use rpgmaker::rgss::{RgssArchive, RgssFile};
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::virtual_fs::VirtualFs;
+use uuid::Uuid;
+
+pub struct RgssArchiveExplorer<F: Read + Seek> {
+    explorer: VirtualFsExplorer<RgssFile<F>, RgssArchive<F>>,
+}
+
+impl<F: Read + Seek + 'static> RgssArchiveExplorer<F> {
+    pub fn new(
+        app_context: SharedAppContext,
+        archive: RgssArchive<F>,
+        name: Option<String>,
+    ) -> Result<Self> {
+        Ok(RgssArchiveExplorer {
+            explorer: VirtualFsExplorer::new(
+                app_context,
+                VirtualFs::new(archive),
+                VirtualFsExplorerOptions {
+                    name,
+                    allow_download: true,
+                },
+            )?,
+        })
+    }
+
+    pub fn file(
+        app_context: SharedAppContext,
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<Self> {
+        file.rewind()?;
+        RgssArchiveExplorer::new(
+            app_context,
+            RgssArchive::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        )
+    }
+}
+
+impl RgssArchiveExplorer<File> {
+    pub fn open<P: Into<PathBuf>>(
+        app_context: SharedAppContext,
+        path: P,
+    ) -> Result<RgssArchiveExplorer<File>> {
+        let path: PathBuf = path.into();
+        RgssArchiveExplorer::file(
+            app_context,
+            File::open(&path)?,
+            util::file_utils::filename(path),
+        )
+    }
+}
+
+impl<F: Read + Seek + 'static> Explorer for RgssArchiveExplorer<F> {
+    fn uuid(&self) -> &Uuid {
+        self.explorer.uuid()
+    }
+
+    fn title(&self) -> String {
+        self.explorer.title()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.explorer.ui(ui);
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Result};
+use source_engine::closecaption::{caption_source_tokens, CloseCaptions};
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::virtual_fs::{FullPath, VirtualFsFile, VirtualFsInner};
+use uuid::Uuid;
+
+use crate::app::Explorer;
+
+struct Caption {
+    /// None if the token isn't in the caption source.
+    name: Option<String>,
+    hash: u32,
+    text: String,
+}
+
+pub struct CloseCaptionExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    captions: Vec<Caption>,
+    num_named: usize,
+    search: String,
+    show_tags: bool,
+    /// Indices of captions matching the search.
+    filtered: Vec<usize>,
+}
+
+/// Formatting tags like <clr:255,0,0>, <I> & <cr>.
+fn strip_tags(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+impl CloseCaptionExplorer {
+    /// `source` is the caption source .txt, used to find the token names.
+    pub fn new(
+        captions: CloseCaptions,
+        name: Option<String>,
+        source: Option<Vec<u8>>,
+    ) -> CloseCaptionExplorer {
+        let names: Vec<String> = source
+            .and_then(|source| caption_source_tokens(&source).ok())
+            .unwrap_or_default();
+        let mut resolved = captions.resolve_names(names.iter().map(|name| name.as_str()));
+
+        let mut captions: Vec<Caption> = captions
+            .entries()
+            .iter()
+            .map(|entry| Caption {
+                name: resolved.remove(&entry.hash),
+                hash: entry.hash,
+                text: entry.text.clone(),
+            })
+            .collect();
+        // Named first, unnamed can only be sorted by hash.
+        captions.sort_by(|a, b| match (&a.name, &b.name) {
+            (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.hash.cmp(&b.hash),
+        });
+
+        CloseCaptionExplorer {
+            name,
+            uuid: Uuid::now_v7(),
+            num_named: captions.iter().filter(|c| c.name.is_some()).count(),
+            filtered: (0..captions.len()).collect(),
+            captions,
+            search: String::new(),
+            show_tags: false,
+        }
+    }
+
+    pub fn file<F: Read + Seek>(
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<CloseCaptionExplorer> {
+        file.rewind()?;
+        Ok(CloseCaptionExplorer::new(
+            CloseCaptions::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+            None,
+        ))
+    }
+
+    /// Token names are read from the .txt next to the .dat.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<CloseCaptionExplorer> {
+        let path: PathBuf = path.into();
+        let captions = CloseCaptions::load(File::open(&path)?)?;
+        Ok(CloseCaptionExplorer::new(
+            captions,
+            util::file_utils::filename(&path),
+            std::fs::read(path.with_extension("txt")).ok(),
+        ))
+    }
+
+    pub fn virtual_file<F: Read + Seek + 'static, I: VirtualFsInner<F> + 'static>(
+        mut file: VirtualFsFile<F, I>,
+    ) -> Result<CloseCaptionExplorer> {
+        let path = file.path().string();
+        let stem = path
+            .get(..(path.len() - 4))
+            .filter(|_| path.to_lowercase().ends_with(".dat"))
+            .ok_or(anyhow!("File is not a close captions .dat"))?
+            .to_owned();
+        file.rewind()?;
+        let captions = CloseCaptions::load(&mut file)?;
+        let source = file
+            .fs()
+            .clone()
+            .read(FullPath::new(format!("{}.txt", stem)))
+            .ok()
+            .and_then(|entry| entry.as_file())
+            .and_then(|mut file| {
+                let mut source = Vec::new();
+                file.read_to_end(&mut source).ok()?;
+                Some(source)
+            });
+        Ok(CloseCaptionExplorer::new(
+            captions,
+            file.path().name().map(|name| name.to_owned()),
+            source,
+        ))
+    }
+
+    fn update_filter(&mut self) {
+        let search = self.search.to_lowercase();
+        self.filtered = self
+            .captions
+            .iter()
+            .enumerate()
+            .filter(|(_, caption)| {
+                search.is_empty()
+                    || caption
+                        .name
+                        .as_ref()
+                        .is_some_and(|name| name.to_lowercase().contains(&search))
+                    || caption.text.to_lowercase().contains(&search)
+            })
+            .map(|(i, _)| i)
+            .collect();
+    }
+}
+
+impl Explorer for CloseCaptionExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("Close Captions".to_owned())
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!(
+            "{} captions, {} with known names",
+            self.captions.len(),
+            self.num_named
+        ));
+        ui.horizontal(|ui| {
+            ui.label("Search");
+            if ui.text_edit_singleline(&mut self.search).changed() {
+                self.update_filter();
+            }
+            ui.checkbox(&mut self.show_tags, "Show tags");
+        });
+        ui.separator();
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::both().auto_shrink(false).show_rows(
+            ui,
+            row_height,
+            self.filtered.len(),
+            |ui, range| {
+                for &index in &self.filtered[range] {
+                    let caption = &self.captions[index];
+                    ui.horizontal(|ui| {
+                        let name = match &caption.name {
+                            Some(name) => name.clone(),
+                            None => format!("{:08X}", caption.hash),
+                        };
+                        ui.add_sized(
+                            [320.0, row_height],
+                            egui::Label::new(egui::RichText::new(name).monospace()).truncate(),
+                        );
+                        let text = if self.show_tags {
+                            caption.text.clone()
+                        } else {
+                            strip_tags(&caption.text)
+                        };
+                        ui.add(egui::Label::new(text).extend());
+                    });
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use anyhow::{anyhow, Result};
+use source_engine::{mdl::Mdl, vtx::Vtx, vvd::Vvd};
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::PathBuf,
+};
+use util::{
+    file_utils::ReadSeek,
+    mesh::Mesh,
+    virtual_fs::{FullPath, VirtualFsFile, VirtualFsInner},
+};
+use uuid::Uuid;
+
+use crate::{
+    app::Explorer,
+    app_util::{self, mesh_utils::MeshViewer},
+};
+
+/// Mesh data files next to the .mdl, the VTX is tried in order.
+const VVD_EXTENSION: &str = "vvd";
+const VTX_EXTENSIONS: [&str; 2] = ["dx90.vtx", "vtx"];
+
+pub struct MdlExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    mdl: Mdl,
+    geometry: Result<(Vvd, Vtx), String>,
+    body_part_models: Vec<usize>,
+    skin: usize,
+    lod: usize,
+    /// Rebuilt when the selection changes.
+    mesh: Option<Result<Mesh, String>>,
+    viewer: MeshViewer,
+}
+
+impl MdlExplorer {
+    /// `resolve` finds the files next to the model by extension, the model is still shown
+    /// without them.
+    pub fn new(
+        mdl: Mdl,
+        name: Option<String>,
+        mut resolve: impl FnMut(&str) -> Option<Box<dyn ReadSeek>>,
+    ) -> MdlExplorer {
+        let geometry = (|| -> Result<(Vvd, Vtx)> {
+            let vvd = Vvd::load(resolve(VVD_EXTENSION).ok_or(anyhow!("Missing .vvd file"))?)?;
+            let vtx = Vtx::load(
+                VTX_EXTENSIONS
+                    .iter()
+                    .find_map(|extension| resolve(extension))
+                    .ok_or(anyhow!("Missing .dx90.vtx file"))?,
+            )?;
+            Ok((vvd, vtx))
+        })()
+        .map_err(|err| err.to_string());
+        MdlExplorer {
+            name,
+            uuid: Uuid::now_v7(),
+            body_part_models: vec![0; mdl.body_parts().len()],
+            mdl,
+            geometry,
+            skin: 0,
+            lod: 0,
+            mesh: None,
+            viewer: MeshViewer::default(),
+        }
+    }
+
+    pub fn file<F: Read + Seek>(mut file: F, filename: Option<String>) -> Result<MdlExplorer> {
+        file.rewind()?;
+        Ok(MdlExplorer::new(
+            Mdl::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+            |_| None,
+        ))
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<MdlExplorer> {
+        let path: PathBuf = path.into();
+        let mdl = Mdl::load(BufReader::new(File::open(&path)?))?;
+        Ok(MdlExplorer::new(
+            mdl,
+            util::file_utils::filename(&path),
+            |extension| {
+                let file = File::open(path.with_extension(extension)).ok()?;
+                Some(Box::new(BufReader::new(file)) as Box<dyn ReadSeek>)
+            },
+        ))
+    }
+
+    /// Mesh data is read from the filesystem the model is in.
+    pub fn virtual_file<F: Read + Seek + 'static, I: VirtualFsInner<F> + 'static>(
+        mut file: VirtualFsFile<F, I>,
+    ) -> Result<MdlExplorer> {
+        file.rewind()?;
+        let mdl = Mdl::load(&mut file)?;
+        let path = file.path().string();
+        let stem = path
+            .get(..(path.len() - 4))
+            .filter(|_| path.to_lowercase().ends_with(".mdl"))
+            .ok_or(anyhow!("File is not a MDL"))?
+            .to_owned();
+        let mut fs = file.fs().clone();
+        Ok(MdlExplorer::new(
+            mdl,
+            file.path().name().map(|name| name.to_owned()),
+            |extension| {
+                fs.read(FullPath::new(format!("{}.{}", stem, extension)))
+                    .ok()
+                    .and_then(|entry| entry.as_file())
+                    .map(|file| Box::new(file) as Box<dyn ReadSeek>)
+            },
+        ))
+    }
+
+    fn build_mesh(&mut self) {
+        let (Ok((vvd, vtx)), None) = (&self.geometry, &self.mesh) else {
+            return;
+        };
+        self.mesh = Some(
+            self.mdl
+                .mesh(vvd, vtx, &self.body_part_models, self.skin, self.lod)
+                .map_err(|err| err.to_string()),
+        );
+        self.viewer.invalidate();
+    }
+
+    fn info_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.label("MDL Information");
+            ui.label(format!("Name: {}", self.mdl.name()));
+            ui.label(format!("Version: {}", self.mdl.version()));
+            ui.label(format!("Checksum: {:08X}", self.mdl.checksum()));
+            ui.label(format!("Flags: {:08X}", self.mdl.flags()));
+            ui.label(format!("Mass: {}", self.mdl.mass()));
+            if !self.mdl.surface_prop().is_empty() {
+                ui.label(format!("Surface property: {}", self.mdl.surface_prop()));
+            }
+            let (hull_min, hull_max) = self.mdl.hull();
+            ui.label(format!("Hull: {:?} to {:?}", hull_min, hull_max));
+            ui.label(format!("Eye position: {:?}", self.mdl.eye_position()));
+
+            ui.add_space(16.0);
+            ui.horizontal(|ui| {
+                let mut changed = false;
+                if self.mdl.skins().len() > 1 {
+                    ui.menu_button(format!("Skin {}", self.skin), |ui| {
+                        for skin in 0..self.mdl.skins().len() {
+                            if ui.button(format!("Skin {}", skin)).clicked() {
+                                self.skin = skin;
+                                changed = true;
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                }
+                if let Ok((_, vtx)) = &self.geometry {
+                    if vtx.num_lods() > 1 {
+                        ui.menu_button(format!("LOD {}", self.lod), |ui| {
+                            for lod in 0..vtx.num_lods() {
+                                if ui.button(format!("LOD {}", lod)).clicked() {
+                                    self.lod = lod;
+                                    changed = true;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    }
+                }
+                if changed {
+                    self.mesh = None;
+                }
+            });
+
+            ui.add_space(16.0);
+            ui.label("Body parts");
+            for (i, body_part) in self.mdl.body_parts().iter().enumerate() {
+                let selected = &mut self.body_part_models[i];
+                if body_part.models.len() > 1 {
+                    let current = body_part
+                        .models
+                        .get(*selected)
+                        .map(|model| model.name.as_str())
+                        .unwrap_or("");
+                    ui.horizontal(|ui| {
+                        ui.label(&body_part.name);
+                        ui.menu_button(current, |ui| {
+                            for (j, model) in body_part.models.iter().enumerate() {
+                                let name = if model.name.is_empty() {
+                                    "(none)"
+                                } else {
+                                    &model.name
+                                };
+                                if ui.button(name).clicked() {
+                                    *selected = j;
+                                    self.mesh = None;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                } else {
+                    ui.label(&body_part.name);
+                }
+            }
+
+            ui.add_space(16.0);
+            ui.label("Mesh");
+            match &self.mesh {
+                None => {
+                    if let Err(err) = &self.geometry {
+                        ui.label(format!("Mesh data not loaded: {}", err));
+                    }
+                }
+                Some(Err(err)) => {
+                    ui.label(format!("Failed to build mesh: {}", err));
+                }
+                Some(Ok(mesh)) => {
+                    ui.label(format!("Vertices: {}", mesh.positions.len()));
+                    ui.label(format!("Triangles: {}", mesh.num_triangles()));
+                    if ui.button("Export Model").clicked() {
+                        if let Err(err) = app_util::mesh_utils::save_mesh(mesh, self.name.clone()) {
+                            println!("Failed to export model");
+                            println!("{:#?}", err);
+                        }
+                    }
+                }
+            }
+
+            ui.add_space(16.0);
+            ui.label("Materials");
+            for texture in 0..self.mdl.textures().len() {
+                let paths = self.mdl.material_paths(texture);
+                if let Some((first, rest)) = paths.split_first() {
+                    ui.label(egui::RichText::new(first).monospace());
+                    for path in rest {
+                        ui.label(egui::RichText::new(format!("    or {}", path)).monospace());
+                    }
+                } else {
+                    ui.label(egui::RichText::new(&self.mdl.textures()[texture]).monospace());
+                }
+            }
+
+            ui.add_space(16.0);
+            ui.collapsing(format!("Bones ({})", self.mdl.bones().len()), |ui| {
+                egui::Grid::new(egui::Id::new(self.uuid).with("bones"))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (i, bone) in self.mdl.bones().iter().enumerate() {
+                            ui.label(i.to_string());
+                            ui.label(egui::RichText::new(&bone.name).monospace());
+                            ui.label(
+                                bone.parent
+                                    .and_then(|parent| self.mdl.bones().get(parent))
+                                    .map(|parent| parent.name.as_str())
+                                    .unwrap_or(""),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+
+            if let Some(key_values) = self.mdl.key_values() {
+                ui.collapsing("Key values", |ui| {
+                    ui.label(egui::RichText::new(key_values).monospace());
+                });
+            }
+        });
+    }
+}
+
+impl Explorer for MdlExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("MDL Model".to_owned())
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        self.build_mesh();
+        app_util::splitter::Splitter::horizontal(self.uuid)
+            .min_size(240.0)
+            .show(ui, |ui_a, ui_b| {
+                self.info_ui(ui_a);
+                if let Some(Ok(mesh)) = &self.mesh {
+                    self.viewer.ui(ui_b, mesh);
+                }
+            });
+    }
+}
@@ -1,2 +1,6 @@
+pub mod closecaption;
+pub mod mdl;
+pub mod sound;
+pub mod vmt;
 pub mod vpk;
 pub mod vtf;
@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Result};
+use source_engine::sound::{Mp3Info, WavInfo};
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use uuid::Uuid;
+
+use crate::app::Explorer;
+
+enum SoundInfo {
+    Wav(WavInfo),
+    Mp3(Mp3Info),
+}
+
+/// Shows sound metadata, like the cue points & loops Source uses.
+pub struct SoundExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    info: SoundInfo,
+}
+
+impl SoundExplorer {
+    pub fn file<F: Read + Seek>(mut file: F, filename: Option<String>) -> Result<SoundExplorer> {
+        let info = if let Ok(wav) = WavInfo::load(&mut file) {
+            SoundInfo::Wav(wav)
+        } else if filename
+            .as_ref()
+            .is_some_and(|filename| filename.to_lowercase().ends_with(".mp3"))
+        {
+            // MP3 has no identifier, frames are searched for so only .mp3 files are loaded.
+            file.rewind()?;
+            SoundInfo::Mp3(Mp3Info::load(file)?)
+        } else {
+            return Err(anyhow!("File is not a WAV or MP3"));
+        };
+        Ok(SoundExplorer {
+            name: filename.and_then(|f| util::file_utils::filename(&f)),
+            uuid: Uuid::now_v7(),
+            info,
+        })
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<SoundExplorer> {
+        let path: PathBuf = path.into();
+        SoundExplorer::file(File::open(&path)?, util::file_utils::filename(&path))
+    }
+
+    fn wav_ui(&self, ui: &mut egui::Ui, wav: &WavInfo) {
+        ui.label("WAV Information");
+        ui.label(format!(
+            "Format: {} ({:#06X})",
+            wav.format.format_name(),
+            wav.format.format_tag
+        ));
+        ui.label(format!("Channels: {}", wav.format.channels));
+        ui.label(format!("Sample rate: {} Hz", wav.format.sample_rate));
+        ui.label(format!("Bits per sample: {}", wav.format.bits_per_sample));
+        if let Some(samples) = wav.num_samples() {
+            ui.label(format!("Samples: {}", samples));
+        }
+        if let Some(duration) = wav.duration() {
+            ui.label(format!("Duration: {:.3}s", duration));
+        }
+        wav_cues_ui(ui, egui::Id::new(self.uuid), wav);
+    }
+
+    fn mp3_ui(ui: &mut egui::Ui, mp3: &Mp3Info) {
+        ui.label("MP3 Information");
+        ui.label(format!("MPEG {} layer {}", mp3.version, mp3.layer));
+        ui.label(format!("Channels: {}", mp3.channels));
+        ui.label(format!("Sample rate: {} Hz", mp3.sample_rate));
+        ui.label(format!("Bitrate: {} kbit/s", mp3.bitrate));
+        ui.label(format!("Frames: {}", mp3.num_frames));
+        ui.label(format!("Duration: {:.3}s", mp3.duration()));
+        if let Some(size) = mp3.id3v2_size {
+            ui.label(format!("ID3v2 tag: {} bytes", size));
+        }
+        if mp3.id3v1 {
+            ui.label("ID3v1 tag");
+        }
+    }
+}
+
+impl Explorer for SoundExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("Sound".to_owned())
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| match &self.info {
+            SoundInfo::Wav(wav) => self.wav_ui(ui, wav),
+            SoundInfo::Mp3(mp3) => SoundExplorer::mp3_ui(ui, mp3),
+        });
+    }
+}
+
+/// Loop start, cue points, sampler loops & chunks of a WAV file.
+pub fn wav_cues_ui(ui: &mut egui::Ui, id: egui::Id, wav: &WavInfo) {
+    if let Some(loop_start) = wav.loop_start() {
+        ui.label(format!("Loops from sample {} to the end", loop_start));
+    }
+
+    if !wav.cues.is_empty() {
+        ui.add_space(16.0);
+        ui.label("Cue points");
+        egui::Grid::new(id.with("cues"))
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("ID");
+                ui.label("Sample");
+                ui.label("Label");
+                ui.end_row();
+                for cue in &wav.cues {
+                    ui.label(cue.id.to_string());
+                    ui.label(cue.sample_offset.to_string());
+                    ui.label(cue.label.as_deref().unwrap_or(""));
+                    ui.end_row();
+                }
+            });
+    }
+
+    if !wav.loops.is_empty() {
+        ui.add_space(16.0);
+        ui.label("Sampler loops");
+        egui::Grid::new(id.with("loops"))
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("ID");
+                ui.label("Start");
+                ui.label("End");
+                ui.label("Play count");
+                ui.end_row();
+                for wav_loop in &wav.loops {
+                    ui.label(wav_loop.id.to_string());
+                    ui.label(wav_loop.start.to_string());
+                    ui.label(wav_loop.end.to_string());
+                    ui.label(match wav_loop.play_count {
+                        0 => "Infinite".to_owned(),
+                        count => count.to_string(),
+                    });
+                    ui.end_row();
+                }
+            });
+    }
+
+    ui.add_space(16.0);
+    ui.collapsing(format!("Chunks ({})", wav.chunks.len()), |ui| {
+        egui::Grid::new(id.with("chunks"))
+            .striped(true)
+            .show(ui, |ui| {
+                for (id, size) in &wav.chunks {
+                    ui.label(egui::RichText::new(id).monospace());
+                    ui.label(format!("{} bytes", size));
+                    ui.end_row();
+                }
+            });
+    });
+}
@@ -0,0 +1,231 @@
+use anyhow::{anyhow, Result};
+use source_engine::{
+    keyvalues::{KeyValue, KeyValues},
+    vmt::Vmt,
+    vtf::Vtf,
+};
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::{
+    file_utils::ReadSeek,
+    image_utils::SizeHint,
+    virtual_fs::{FullPath, VirtualFsFile, VirtualFsInner},
+};
+use uuid::Uuid;
+
+use crate::{
+    app::{Explorer, SharedAppContext},
+    app_util,
+};
+
+use super::vtf::VtfExplorer;
+
+const PREVIEW_SIZE: f32 = 64.0;
+
+/// Opens a new reader of the texture every time, so it can be moved into an explorer.
+type OpenTexture = Box<dyn Fn() -> Box<dyn ReadSeek>>;
+
+struct LinkedTexture {
+    parameter: String,
+    path: String,
+    /// None if the texture isn't in the filesystem.
+    file: Option<OpenTexture>,
+    /// Loaded on first display, None inside if the texture failed to load.
+    preview: Option<Option<egui::TextureHandle>>,
+}
+
+pub struct VmtExplorer {
+    app_context: SharedAppContext,
+    name: Option<String>,
+    uuid: Uuid,
+
+    vmt: Vmt,
+    textures: Vec<LinkedTexture>,
+}
+
+impl VmtExplorer {
+    /// `resolve` finds referenced textures, they're opened from it when clicked.
+    pub fn new(
+        app_context: SharedAppContext,
+        vmt: Vmt,
+        name: Option<String>,
+        mut resolve: impl FnMut(&str) -> Option<OpenTexture>,
+    ) -> VmtExplorer {
+        let textures = vmt
+            .textures()
+            .map(|(parameter, path)| LinkedTexture {
+                parameter: parameter.to_owned(),
+                file: resolve(&path),
+                path,
+                preview: None,
+            })
+            .collect();
+        VmtExplorer {
+            app_context,
+            name,
+            uuid: Uuid::now_v7(),
+            vmt,
+            textures,
+        }
+    }
+
+    /// Materials have no identifier, so only `.vmt` files are loaded.
+    pub fn file<F: Read + Seek>(
+        app_context: SharedAppContext,
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<VmtExplorer> {
+        if !filename
+            .as_ref()
+            .is_some_and(|filename| filename.to_lowercase().ends_with(".vmt"))
+        {
+            return Err(anyhow!("File is not a VMT"));
+        }
+        file.rewind()?;
+        Ok(VmtExplorer::new(
+            app_context,
+            Vmt::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+            |_| None,
+        ))
+    }
+
+    pub fn open<P: Into<PathBuf>>(app_context: SharedAppContext, path: P) -> Result<VmtExplorer> {
+        let path: PathBuf = path.into();
+        VmtExplorer::file(
+            app_context,
+            File::open(&path)?,
+            util::file_utils::filename(&path),
+        )
+    }
+
+    /// Textures are resolved against the filesystem the material is in.
+    pub fn virtual_file<F: Read + Seek + 'static, I: VirtualFsInner<F> + 'static>(
+        app_context: SharedAppContext,
+        mut file: VirtualFsFile<F, I>,
+    ) -> Result<VmtExplorer> {
+        let filename = file.path().name().map(|name| name.to_owned());
+        if !filename
+            .as_ref()
+            .is_some_and(|filename| filename.to_lowercase().ends_with(".vmt"))
+        {
+            return Err(anyhow!("File is not a VMT"));
+        }
+        file.rewind()?;
+        let vmt = Vmt::load(&mut file)?;
+        let mut fs = file.fs().clone();
+        Ok(VmtExplorer::new(app_context, vmt, filename, |path| {
+            fs.read(FullPath::new(path))
+                .ok()
+                .and_then(|entry| entry.as_file())
+                .map(|file| {
+                    Box::new(move || Box::new(file.clone()) as Box<dyn ReadSeek>) as OpenTexture
+                })
+        }))
+    }
+
+    fn parameters_ui(ui: &mut egui::Ui, key_values: &KeyValues, id: egui::Id) {
+        egui::Grid::new(id).striped(true).show(ui, |ui| {
+            for (i, entry) in key_values.entries.iter().enumerate() {
+                let key = match &entry.condition {
+                    Some(condition) => format!("{} [{}]", entry.key, condition),
+                    None => entry.key.clone(),
+                };
+                let key = egui::RichText::new(key).monospace();
+                // Entries that don't apply on PC are dimmed.
+                ui.label(if entry.is_active() { key } else { key.weak() });
+                match &entry.value {
+                    KeyValue::String(value) => {
+                        ui.label(egui::RichText::new(value).monospace());
+                    }
+                    KeyValue::Section(section) => {
+                        ui.collapsing(format!("{} entries", section.entries.len()), |ui| {
+                            VmtExplorer::parameters_ui(ui, section, id.with(i));
+                        });
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
+
+impl Explorer for VmtExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("VMT Material".to_owned())
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.label(format!("Shader: {}", self.vmt.shader()));
+
+            if !self.textures.is_empty() {
+                ui.add_space(16.0);
+                ui.label("Textures");
+            }
+            for texture in self.textures.iter_mut() {
+                ui.horizontal(|ui| {
+                    let preview = texture.preview.get_or_insert_with(|| {
+                        let file = texture.file.as_ref()?;
+                        let thumbnail = Vtf::load_thumbnail(
+                            file(),
+                            SizeHint::SizeEither(PREVIEW_SIZE as u32, PREVIEW_SIZE as u32),
+                        )
+                        .ok()??;
+                        Some(app_util::image_utils::image_egui_handle(
+                            &thumbnail.to_image(),
+                            ui.ctx(),
+                        ))
+                    });
+
+                    let (rect, response) = ui
+                        .allocate_exact_size(egui::Vec2::splat(PREVIEW_SIZE), egui::Sense::click());
+                    if let Some(preview) = preview {
+                        egui::Image::new(egui::ImageSource::Texture(
+                            egui::load::SizedTexture::from_handle(preview),
+                        ))
+                        .shrink_to_fit()
+                        .paint_at(ui, rect);
+                    } else {
+                        ui.painter()
+                            .rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+                    }
+
+                    ui.vertical(|ui| {
+                        ui.label(egui::RichText::new(&texture.parameter).monospace());
+                        if let Some(file) = texture.file.as_ref() {
+                            let link = ui.link(&texture.path);
+                            if link.clicked() || response.clicked() {
+                                match VtfExplorer::file(
+                                    file(),
+                                    util::file_utils::filename(&texture.path),
+                                ) {
+                                    Ok(explorer) => {
+                                        self.app_context.new_explorer(Box::new(explorer))
+                                    }
+                                    Err(err) => {
+                                        println!("Failed to open \"{}\"", texture.path);
+                                        println!("{:#?}", err);
+                                    }
+                                }
+                            }
+                        } else {
+                            ui.label(format!("{} (not found)", texture.path));
+                        }
+                    });
+                });
+            }
+
+            ui.add_space(16.0);
+            ui.label("Parameters");
+            VmtExplorer::parameters_ui(ui, self.vmt.parameters(), egui::Id::new(self.uuid));
+        });
+    }
+}
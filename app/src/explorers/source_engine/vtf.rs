@@ -1,10 +1,13 @@
 use anyhow::Result;
-use source_engine::vtf::Vtf;
+use image::DynamicImage;
+use source_engine::vtf::{LazyVtf, TextureFormat, VtfTexture};
 use std::{
+    collections::VecDeque,
     fs::File,
-    io::{Read, Seek},
+    io::{BufReader, Read, Seek},
     path::PathBuf,
 };
+use util::{file_utils::ReadSeek, image_utils::ToneMap, texture::cubemap::Cubemap};
 use uuid::Uuid;
 
 use crate::{app::Explorer, app_util};
@@ -15,46 +18,219 @@ enum RenderedTextureType {
     Thumbnail,
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum CubemapView {
+    Face,
+    Cross,
+    Equirectangular,
+}
+
+impl CubemapView {
+    const ALL: [CubemapView; 3] = [
+        CubemapView::Face,
+        CubemapView::Cross,
+        CubemapView::Equirectangular,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            CubemapView::Face => "Single face",
+            CubemapView::Cross => "Cross",
+            CubemapView::Equirectangular => "Equirectangular",
+        }
+    }
+}
+
+/// Source's AnimatedTexture proxy defaults to 15 FPS.
+const DEFAULT_FPS: f32 = 15.0;
+
+/// Decoded textures kept around, the least recently viewed is dropped first.
+const MAX_CACHED_TEXTURES: usize = 16;
+
+/// View, mipmap, frame, face & slice.
+type TextureKey = (CubemapView, u32, u32, u32, u32);
+
 pub struct VtfExplorer {
     name: Option<String>,
     uuid: Uuid,
 
-    vtf: Vtf,
+    vtf: LazyVtf<Box<dyn ReadSeek>>,
     mipmap: u32,
     frame: u32,
     face: u32,
     slice: u32,
+    view: CubemapView,
+
+    playing: bool,
+    fps: f32,
+    /// Time since the frame was last advanced.
+    frame_time: f32,
+
+    compressed_hdr: bool,
+    exposure: f32,
+    tone_map: ToneMap,
 
     thumbnail: Option<egui::TextureHandle>,
-    textures: Vec<Option<egui::TextureHandle>>,
+    textures: VecDeque<(TextureKey, egui::TextureHandle)>,
 }
 
 impl VtfExplorer {
-    pub fn new(vtf: Vtf, name: Option<String>) -> VtfExplorer {
+    pub fn new(vtf: LazyVtf<Box<dyn ReadSeek>>, name: Option<String>) -> VtfExplorer {
+        // Source only knows a texture is compressed HDR by its name.
+        let compressed_hdr = matches!(vtf.format(), TextureFormat::BGRA8888)
+            && name.as_ref().is_some_and(|name| name.contains("_hdr"));
+        let frame = if vtf.first_frame() < vtf.frames() {
+            vtf.first_frame()
+        } else {
+            0
+        };
         VtfExplorer {
             name,
+            compressed_hdr,
+            exposure: 0.0,
+            tone_map: ToneMap::default(),
             uuid: Uuid::now_v7(),
-            textures: vec![None; vtf.total_num_textures()],
+            textures: VecDeque::new(),
             vtf,
             mipmap: 0,
-            frame: 0,
+            frame,
             face: 0,
             slice: 0,
+            view: CubemapView::Face,
+            playing: false,
+            fps: DEFAULT_FPS,
+            frame_time: 0.0,
             thumbnail: None,
         }
     }
 
-    pub fn file<F: Read + Seek>(mut file: F, filename: Option<String>) -> Result<VtfExplorer> {
-        file.rewind()?;
+    /// Textures are read from the file when viewed, so it's kept open.
+    pub fn file<F: Read + Seek + 'static>(
+        file: F,
+        filename: Option<String>,
+    ) -> Result<VtfExplorer> {
+        let reader: Box<dyn ReadSeek> = Box::new(file);
         Ok(VtfExplorer::new(
-            Vtf::load(file)?,
-            filename.map(|f| util::file_utils::filename(&f)).flatten(),
+            LazyVtf::load(reader)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
         ))
     }
 
     pub fn open<P: Into<PathBuf>>(path: P) -> Result<VtfExplorer> {
         let path: PathBuf = path.into();
-        VtfExplorer::file(File::open(&path)?, util::file_utils::filename(&path))
+        let reader: Box<dyn ReadSeek> = Box::new(BufReader::new(File::open(&path)?));
+        Ok(VtfExplorer::new(
+            LazyVtf::load(reader)?,
+            util::file_utils::filename(&path),
+        ))
+    }
+
+    fn texture_handle(&mut self, ctx: &egui::Context) -> Result<egui::TextureHandle> {
+        let key = (self.view, self.mipmap, self.frame, self.face, self.slice);
+        if let Some(index) = self.textures.iter().position(|(k, _)| *k == key) {
+            let entry = self.textures.remove(index).unwrap();
+            let handle = entry.1.clone();
+            self.textures.push_back(entry);
+            return Ok(handle);
+        }
+
+        let image = self.view_image(self.frame)?;
+        let image = self.display_image(image);
+        let handle = app_util::image_utils::image_egui_handle(&image, ctx);
+        self.textures.push_back((key, handle.clone()));
+        // Every frame is kept while playing, otherwise looping evicts each frame before it's
+        // shown again.
+        let max_cached = if self.playing {
+            MAX_CACHED_TEXTURES.max(self.vtf.frames() as usize)
+        } else {
+            MAX_CACHED_TEXTURES
+        };
+        while self.textures.len() > max_cached {
+            self.textures.pop_front();
+        }
+        Ok(handle)
+    }
+
+    fn texture_image(&self, texture: &VtfTexture) -> DynamicImage {
+        if self.compressed_hdr {
+            texture.to_compressed_hdr_image()
+        } else {
+            None
+        }
+        .unwrap_or_else(|| texture.to_image())
+    }
+
+    /// Current texture, or the faces stitched together for cubemap views.
+    fn view_image(&mut self, frame: u32) -> Result<DynamicImage> {
+        if self.view == CubemapView::Face {
+            let texture = self
+                .vtf
+                .texture(self.mipmap, frame, self.face, self.slice)?;
+            return Ok(self.texture_image(&texture));
+        }
+
+        // The 7th face is a spheremap, not part of the cube.
+        let faces = (0..6)
+            .map(|face| {
+                let texture = self.vtf.texture(self.mipmap, frame, face, self.slice)?;
+                Ok(self.texture_image(&texture))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let cubemap = Cubemap::new(&faces)?;
+        Ok(match self.view {
+            CubemapView::Cross => cubemap.to_cross(),
+            _ => cubemap.to_equirectangular(),
+        })
+    }
+
+    /// Every frame in playback order, tone mapped like they are displayed.
+    fn animation_frames(&mut self) -> Result<Vec<DynamicImage>> {
+        (0..self.vtf.frames())
+            .map(|i| {
+                let frame = (self.vtf.first_frame() + i) % self.vtf.frames();
+                let image = self.view_image(frame)?;
+                Ok(self.display_image(image))
+            })
+            .collect()
+    }
+
+    fn export_name(&self) -> Option<String> {
+        let suffix = match self.view {
+            CubemapView::Face => "",
+            CubemapView::Cross => "_cross",
+            CubemapView::Equirectangular => "_equirectangular",
+        };
+        self.name
+            .clone()
+            .map(|filename| format!("{}{}", filename.trim_end_matches(".vtf"), suffix))
+    }
+
+    /// Tone maps HDR images.
+    fn display_image(&self, image: DynamicImage) -> DynamicImage {
+        match image {
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+                DynamicImage::ImageRgba8(util::image_utils::tone_map(
+                    &image.to_rgba32f(),
+                    self.exposure,
+                    self.tone_map,
+                ))
+            }
+            image => image,
+        }
+    }
+
+    fn is_hdr(&self) -> bool {
+        matches!(self.vtf.format(), TextureFormat::BGRA8888) || self.is_float()
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(
+            self.vtf.format(),
+            TextureFormat::RGBA16161616F
+                | TextureFormat::R32F
+                | TextureFormat::RGB323232F
+                | TextureFormat::RGBA32323232F
+        )
     }
 }
 
@@ -67,6 +243,11 @@ impl Explorer for VtfExplorer {
         self.name.clone().unwrap_or("VTF Texture".to_owned())
     }
 
+    fn image(&mut self) -> Option<DynamicImage> {
+        let image = self.view_image(self.frame).ok()?;
+        Some(self.display_image(image))
+    }
+
     fn ui(&mut self, ui: &mut egui::Ui) {
         app_util::splitter::Splitter::horizontal(self.uuid)
             .min_size(240.0)
@@ -75,11 +256,85 @@ impl Explorer for VtfExplorer {
                     ui.label("VTF Information");
                     ui.label(format!("Format: {:?}", self.vtf.format()));
                     ui.label(format!("Size: {}x{}", self.vtf.width(), self.vtf.height()));
+                    ui.label(format!(
+                        "Version: {}.{}",
+                        self.vtf.version()[0],
+                        self.vtf.version()[1]
+                    ));
+                    ui.label(format!("Flags: {:?}", self.vtf.flags()));
+
+                    let resources = self.vtf.resources();
+                    // vtex computes this from the source image, so it can't be checked.
+                    if let Some(crc) = resources.crc {
+                        ui.label(format!("CRC: {:08X}", crc));
+                    }
+                    if let Some((u, v)) = resources.lod {
+                        ui.label(format!("LOD clamp: {}, {}", u, v));
+                    }
+                    if let Some(texture_settings) = resources.texture_settings {
+                        ui.label(format!("Extended flags: {:08X}", texture_settings));
+                    }
+                    for (tag, flags, offset) in &resources.unknown {
+                        ui.label(format!(
+                            "Unknown resource: {:?} {:02X} {:08X}",
+                            String::from_utf8_lossy(tag),
+                            flags,
+                            offset
+                        ));
+                    }
+                    if let Some(key_values) = &resources.key_values {
+                        ui.collapsing("KeyValues data", |ui| {
+                            ui.add(egui::Label::new(
+                                egui::RichText::new(key_values).monospace(),
+                            ));
+                        });
+                    }
+
+                    if self.is_hdr() {
+                        ui.add_space(32.0);
+                        let mut changed = false;
+                        if matches!(self.vtf.format(), TextureFormat::BGRA8888) {
+                            changed |= ui
+                                .checkbox(&mut self.compressed_hdr, "Compressed HDR")
+                                .changed();
+                        }
+                        if self.compressed_hdr || self.is_float() {
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut self.exposure, -8.0..=8.0)
+                                        .text("Exposure"),
+                                )
+                                .changed();
+                            ui.menu_button(format!("Tone map: {:?}", self.tone_map), |ui| {
+                                for tone_map in ToneMap::ALL {
+                                    if ui.button(format!("{:?}", tone_map)).clicked() {
+                                        changed |= self.tone_map != tone_map;
+                                        self.tone_map = tone_map;
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        }
+                        if changed {
+                            self.textures.clear();
+                        }
+                    }
 
                     if self.vtf.total_num_textures() > 1 {
                         ui.add_space(32.0);
                     }
 
+                    if self.vtf.faces() >= 6 {
+                        ui.menu_button(format!("View: {}", self.view.name()), |ui| {
+                            for view in CubemapView::ALL {
+                                if ui.button(view.name()).clicked() {
+                                    self.view = view;
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    }
+
                     ui.horizontal(|ui| {
                         if self.vtf.mipmaps() > 1 {
                             ui.menu_button(format!("Mipmap {}", self.mipmap), |ui| {
@@ -90,7 +345,7 @@ impl Explorer for VtfExplorer {
                                 }
                             });
                         }
-                        if self.vtf.faces() > 1 {
+                        if self.vtf.faces() > 1 && self.view == CubemapView::Face {
                             ui.menu_button(format!("Face {}", self.face), |ui| {
                                 for face in 0..self.vtf.faces() {
                                     if ui.button(format!("Face {}", face)).clicked() {
@@ -111,10 +366,34 @@ impl Explorer for VtfExplorer {
                     });
 
                     if self.vtf.frames() > 1 {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button(if self.playing { "Pause" } else { "Play" })
+                                .clicked()
+                            {
+                                self.playing = !self.playing;
+                                self.frame_time = 0.0;
+                            }
+                            ui.add(
+                                egui::DragValue::new(&mut self.fps)
+                                    .range(1.0..=60.0)
+                                    .speed(0.1)
+                                    .suffix(" FPS"),
+                            );
+                        });
                         ui.add(
                             egui::Slider::new(&mut self.frame, 0..=(self.vtf.frames() - 1))
                                 .text("Frame"),
                         );
+
+                        if self.playing {
+                            self.frame_time += ui.ctx().input(|i| i.stable_dt);
+                            while self.frame_time >= 1.0 / self.fps {
+                                self.frame_time -= 1.0 / self.fps;
+                                self.frame = (self.frame + 1) % self.vtf.frames();
+                            }
+                            ui.ctx().request_repaint();
+                        }
                     }
 
                     if let Some(thumbnail) = self.vtf.thumbnail() {
@@ -141,21 +420,8 @@ impl Explorer for VtfExplorer {
                     }
                 });
 
-                if let Some(texture_handle_index) =
-                    self.vtf
-                        .texture_index(self.mipmap, self.frame, self.face, self.slice)
-                {
-                    if let Some(texture) =
-                        self.vtf
-                            .texture(self.mipmap, self.frame, self.face, self.slice)
-                    {
-                        let texture_handle = self.textures[texture_handle_index]
-                            .get_or_insert_with(|| {
-                                app_util::image_utils::image_egui_handle(
-                                    &texture.to_image(),
-                                    ui_b.ctx(),
-                                )
-                            });
+                match self.texture_handle(ui_b.ctx()) {
+                    Ok(texture_handle) => {
                         ui_b.add_sized(
                             ui_b.available_size(),
                             egui::Image::new(egui::ImageSource::Texture(
@@ -165,16 +431,48 @@ impl Explorer for VtfExplorer {
                         )
                         .context_menu(|ui| {
                             if ui.button("Save Texture").clicked() {
-                                app_util::image_utils::save_image(
-                                    &texture.to_image(),
-                                    self.name.clone().map(|filename| {
-                                        filename.trim_end_matches(".vtf").to_owned()
-                                    }),
-                                )
-                                .expect("Failed to save VTF image");
+                                // Saved before tone mapping, so HDR keeps its range.
+                                let image = self
+                                    .view_image(self.frame)
+                                    .expect("Failed to read VTF texture");
+                                app_util::image_utils::save_image(&image, self.export_name())
+                                    .expect("Failed to save VTF image");
+                                ui.close_menu();
+                            }
+                            if self.vtf.frames() > 1 {
+                                ui.menu_button("Export Animation", |ui| {
+                                    if ui.button("GIF / APNG").clicked() {
+                                        let (fps, name) = (self.fps, self.export_name());
+                                        if let Err(err) = app_util::image_utils::save_animation(
+                                            || self.animation_frames(),
+                                            fps,
+                                            name,
+                                        ) {
+                                            println!("Failed to export VTF animation");
+                                            println!("{:#?}", err);
+                                        }
+                                        ui.close_menu();
+                                    }
+                                    if ui.button("PNG Sequence").clicked() {
+                                        let name = self.export_name();
+                                        if let Err(err) =
+                                            app_util::image_utils::save_image_sequence(
+                                                || self.animation_frames(),
+                                                name,
+                                            )
+                                        {
+                                            println!("Failed to export VTF frames");
+                                            println!("{:#?}", err);
+                                        }
+                                        ui.close_menu();
+                                    }
+                                });
                             }
                         });
                     }
+                    Err(err) => {
+                        ui_b.label(format!("Failed to read texture: {}", err));
+                    }
                 }
             });
     }
@@ -0,0 +1,200 @@
+use anyhow::{anyhow, Result};
+use image::DynamicImage;
+use std::{
+    fs::File,
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use util::svg::Svg;
+use uuid::Uuid;
+
+use crate::{app::Explorer, app_util};
+
+const ZOOM_SPEED: f32 = 0.002;
+const MAX_ZOOM: f32 = 256.0;
+const MAX_EXPORT_SIZE: u32 = 16384;
+
+/// Rendered part of the SVG, to know when it has to be rendered again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RenderKey {
+    width: u32,
+    height: u32,
+    scale: f32,
+    x: f32,
+    y: f32,
+}
+
+/// Drag to pan, scroll to zoom & double click to reset.
+pub struct SvgExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    svg: Svg,
+    /// 1.0 fits the SVG in the view.
+    zoom: f32,
+    offset: egui::Vec2,
+    rendered: Option<(RenderKey, egui::TextureHandle)>,
+    export_width: u32,
+}
+
+impl SvgExplorer {
+    pub fn new(svg: Svg, name: Option<String>) -> SvgExplorer {
+        SvgExplorer {
+            name,
+            uuid: Uuid::now_v7(),
+            export_width: (svg.width().ceil() as u32).max(1),
+            svg,
+            zoom: 1.0,
+            offset: egui::Vec2::ZERO,
+            rendered: None,
+        }
+    }
+
+    /// SVG is XML, so only `.svg` & `.svgz` files are loaded.
+    pub fn file<F: Read + Seek>(mut file: F, filename: Option<String>) -> Result<SvgExplorer> {
+        if !filename.as_ref().is_some_and(|filename| {
+            let filename = filename.to_lowercase();
+            filename.ends_with(".svg") || filename.ends_with(".svgz")
+        }) {
+            return Err(anyhow!("File is not an SVG"));
+        }
+        file.rewind()?;
+        Ok(SvgExplorer::new(
+            Svg::load(file)?,
+            filename.and_then(|f| util::file_utils::filename(&f)),
+        ))
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<SvgExplorer> {
+        let path: PathBuf = path.into();
+        SvgExplorer::file(File::open(&path)?, util::file_utils::filename(&path))
+    }
+
+    fn export_height(&self) -> u32 {
+        ((self.export_width as f32 * self.svg.height() / self.svg.width()).round() as u32).max(1)
+    }
+
+    fn export(&self) -> Result<()> {
+        let image = self.svg.render(self.export_width, self.export_height())?;
+        app_util::image_utils::save_image(&DynamicImage::ImageRgba8(image), self.name.clone())?;
+        Ok(())
+    }
+
+    fn view_ui(&mut self, ui: &mut egui::Ui) {
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+
+        if response.dragged() {
+            self.offset += response.drag_delta();
+        }
+        if response.hovered() {
+            let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                let zoom =
+                    (self.zoom * (scroll * ZOOM_SPEED).exp()).clamp(1.0 / MAX_ZOOM, MAX_ZOOM);
+                // Zoom towards the pointer.
+                if let Some(pointer) = response.hover_pos() {
+                    let anchor = pointer - rect.center() - self.offset;
+                    self.offset -= anchor * (zoom / self.zoom - 1.0);
+                }
+                self.zoom = zoom;
+            }
+        }
+        if response.double_clicked() {
+            self.zoom = 1.0;
+            self.offset = egui::Vec2::ZERO;
+        }
+
+        let (fit_width, fit_height) = self
+            .svg
+            .fit_size(rect.width().max(1.0) as u32, rect.height().max(1.0) as u32);
+        let image_rect = egui::Rect::from_center_size(
+            rect.center() + self.offset,
+            egui::vec2(fit_width as f32, fit_height as f32) * self.zoom,
+        );
+        let visible = image_rect.intersect(rect);
+        if visible.width() < 1.0 || visible.height() < 1.0 {
+            return;
+        }
+
+        // Only the visible part is rendered, at the screen's resolution.
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let key = RenderKey {
+            width: (visible.width() * pixels_per_point).round() as u32,
+            height: (visible.height() * pixels_per_point).round() as u32,
+            scale: image_rect.width() * pixels_per_point / self.svg.width(),
+            x: ((visible.min.x - image_rect.min.x) * pixels_per_point).round(),
+            y: ((visible.min.y - image_rect.min.y) * pixels_per_point).round(),
+        };
+        if self
+            .rendered
+            .as_ref()
+            .is_none_or(|(rendered, _)| *rendered != key)
+        {
+            match self
+                .svg
+                .render_region(key.width, key.height, key.scale, key.x, key.y)
+            {
+                Ok(image) => {
+                    let texture = app_util::image_utils::image_egui_handle(
+                        &DynamicImage::ImageRgba8(image),
+                        ui.ctx(),
+                    );
+                    self.rendered = Some((key, texture));
+                }
+                Err(err) => {
+                    println!("Failed to render SVG");
+                    println!("{:#?}", err);
+                    self.rendered = None;
+                }
+            }
+        }
+
+        if let Some((_, texture)) = &self.rendered {
+            ui.painter_at(rect).image(
+                texture.id(),
+                visible,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
+
+impl Explorer for SvgExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("SVG".to_owned())
+    }
+
+    fn image(&mut self) -> Option<DynamicImage> {
+        self.svg
+            .render(self.export_width, self.export_height())
+            .ok()
+            .map(DynamicImage::ImageRgba8)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!("Size: {}x{}", self.svg.width(), self.svg.height()));
+            ui.label(format!("Zoom: {:.0}%", self.zoom * 100.0));
+            ui.separator();
+            ui.add(
+                egui::DragValue::new(&mut self.export_width)
+                    .range(1..=MAX_EXPORT_SIZE)
+                    .suffix(" px"),
+            );
+            ui.label(format!("x {} px", self.export_height()));
+            if ui.button("Export PNG").clicked() {
+                if let Err(err) = self.export() {
+                    println!("Failed to export SVG");
+                    println!("{:#?}", err);
+                }
+            }
+        });
+        self.view_ui(ui);
+    }
+}
@@ -0,0 +1,326 @@
+use anyhow::{anyhow, Result};
+use image::DynamicImage;
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
+    process::Child,
+    sync::Arc,
+    thread::JoinHandle,
+};
+use util::file_utils::ReadSeek;
+use uuid::Uuid;
+use video::info::{Track, TrackKind, VideoInfo};
+
+use crate::{app::Explorer, app_util};
+
+/// Removed once neither the explorer nor the frame thread uses it.
+struct TempFile {
+    path: PathBuf,
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Where ffmpeg & ffplay read the video from.
+enum Source {
+    Path(PathBuf),
+    /// Files inside archives are copied to the temporary file the first time ffmpeg or ffplay
+    /// needs them.
+    File {
+        file: Box<dyn ReadSeek>,
+        temp_file: Option<Arc<TempFile>>,
+        temp_path: PathBuf,
+    },
+}
+
+impl Source {
+    /// Path that ffmpeg & ffplay can read.
+    fn path(&mut self) -> Result<&Path> {
+        match self {
+            Source::Path(path) => Ok(path),
+            Source::File {
+                file,
+                temp_file,
+                temp_path,
+            } => {
+                if temp_file.is_none() {
+                    // Removes partially written files too.
+                    let temp = TempFile {
+                        path: temp_path.clone(),
+                    };
+                    let mut writer = BufWriter::new(File::create(&temp.path)?);
+                    file.rewind()?;
+                    std::io::copy(file, &mut writer)?;
+                    writer.flush()?;
+                    *temp_file = Some(Arc::new(temp));
+                }
+                Ok(temp_path)
+            }
+        }
+    }
+
+    /// Keeps the temporary file around while ffmpeg reads it on another thread.
+    fn temp_file(&self) -> Option<Arc<TempFile>> {
+        match self {
+            Source::Path(_) => None,
+            Source::File { temp_file, .. } => temp_file.clone(),
+        }
+    }
+}
+
+fn track_details(track: &Track) -> String {
+    let mut details = Vec::new();
+    if let (Some(width), Some(height)) = (track.width, track.height) {
+        details.push(format!("{}x{}", width, height));
+    }
+    if let Some(frame_rate) = track.frame_rate {
+        details.push(format!("{:.2} fps", frame_rate));
+    }
+    if let Some(sample_rate) = track.sample_rate {
+        details.push(format!("{} Hz", sample_rate));
+    }
+    if let Some(channels) = track.channels {
+        details.push(format!("{} channels", channels));
+    }
+    details.join(", ")
+}
+
+/// Only the container is read, frames are decoded & played by ffmpeg & ffplay if they are on the
+/// `PATH`.
+pub struct VideoExplorer {
+    name: Option<String>,
+    uuid: Uuid,
+
+    info: VideoInfo,
+    source: Source,
+    ffmpeg: Option<PathBuf>,
+    ffplay: Option<PathBuf>,
+    /// Extracted on a thread the first time it is shown, ffmpeg can take a while.
+    frame_thread: Option<JoinHandle<Result<DynamicImage>>>,
+    frame: Option<Result<(DynamicImage, egui::TextureHandle), String>>,
+    player: Option<Child>,
+}
+
+impl VideoExplorer {
+    fn new(info: VideoInfo, source: Source, name: Option<String>, uuid: Uuid) -> VideoExplorer {
+        VideoExplorer {
+            name,
+            uuid,
+            info,
+            source,
+            ffmpeg: video::ffmpeg::find_program("ffmpeg"),
+            ffplay: video::ffmpeg::find_program("ffplay"),
+            frame_thread: None,
+            frame: None,
+            player: None,
+        }
+    }
+
+    /// Only the container header is read if it isn't a video, so other explorers can be tried
+    /// cheaply.
+    pub fn load_info<R: Read + Seek>(data: R) -> Result<VideoInfo> {
+        let info = VideoInfo::load(data)?;
+        if info.video_track().is_none() {
+            return Err(anyhow!("File has no video track"));
+        }
+        Ok(info)
+    }
+
+    /// The file is kept open & only copied to a temporary file once ffmpeg or ffplay needs it.
+    pub fn file<F: Read + Seek + 'static>(
+        mut file: F,
+        filename: Option<String>,
+    ) -> Result<VideoExplorer> {
+        let info = VideoExplorer::load_info(&mut file)?;
+
+        let name = filename.and_then(|f| util::file_utils::filename(&f));
+        let uuid = Uuid::now_v7();
+        let extension = name
+            .as_deref()
+            .and_then(|name| Path::new(name).extension())
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("bin");
+        let source = Source::File {
+            file: Box::new(file),
+            temp_file: None,
+            temp_path: std::env::temp_dir()
+                .join(format!("universal-explorer-{}.{}", uuid, extension)),
+        };
+        Ok(VideoExplorer::new(info, source, name, uuid))
+    }
+
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<VideoExplorer> {
+        let path: PathBuf = path.into();
+        let info = VideoExplorer::load_info(File::open(&path)?)?;
+        let name = util::file_utils::filename(&path);
+        Ok(VideoExplorer::new(
+            info,
+            Source::Path(path),
+            name,
+            Uuid::now_v7(),
+        ))
+    }
+
+    /// A bit into the video, the first frame is often black.
+    fn frame_time(&self) -> f64 {
+        self.info
+            .duration
+            .map_or(0.0, |duration| (duration * 0.1).min(10.0))
+    }
+
+    /// Repaints when done, so the result gets polled.
+    fn spawn_extract_frame(&mut self, ctx: &egui::Context) -> JoinHandle<Result<DynamicImage>> {
+        let ffmpeg = self.ffmpeg.clone();
+        let path = self.source.path().map(Path::to_owned);
+        let temp_file = self.source.temp_file();
+        let time = self.frame_time();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let image = ffmpeg
+                .ok_or(anyhow!("ffmpeg not found"))
+                .and_then(|ffmpeg| video::ffmpeg::extract_frame(&ffmpeg, &path?, time));
+            drop(temp_file);
+            ctx.request_repaint();
+            image
+        })
+    }
+
+    fn play(&mut self) -> Result<()> {
+        let ffplay = self.ffplay.clone().ok_or(anyhow!("ffplay not found"))?;
+        let title = self.title();
+        let path = self.source.path()?;
+        self.player = Some(video::ffmpeg::play(&ffplay, path, &title)?);
+        Ok(())
+    }
+
+    fn info_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Video Information");
+        ui.label(format!("Container: {}", self.info.container));
+        if let Some(duration) = self.info.duration {
+            ui.label(format!("Duration: {:.3}s", duration));
+        }
+
+        ui.add_space(16.0);
+        ui.label("Tracks");
+        egui::Grid::new(egui::Id::new(self.uuid).with("tracks"))
+            .striped(true)
+            .show(ui, |ui| {
+                for track in &self.info.tracks {
+                    ui.label(match track.kind {
+                        TrackKind::Video => "Video",
+                        TrackKind::Audio => "Audio",
+                        TrackKind::Subtitle => "Subtitle",
+                        TrackKind::Other => "Other",
+                    });
+                    ui.label(&track.codec);
+                    ui.label(track_details(track));
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(16.0);
+        // Forget about the player once it is closed.
+        if let Some(player) = &mut self.player {
+            if !matches!(player.try_wait(), Ok(None)) {
+                self.player = None;
+            }
+        }
+        if let Some(player) = &mut self.player {
+            if ui.button("Stop").clicked() {
+                let _ = player.kill();
+            }
+        } else if ui
+            .add_enabled(self.ffplay.is_some(), egui::Button::new("Play with ffplay"))
+            .on_disabled_hover_text("ffplay was not found on the PATH")
+            .clicked()
+        {
+            if let Err(err) = self.play() {
+                println!("Failed to play video");
+                println!("{:#?}", err);
+            }
+        }
+        if let Some(Ok((image, _))) = &self.frame {
+            if ui.button("Export Frame").clicked() {
+                if let Err(err) = app_util::image_utils::save_image(image, self.name.clone()) {
+                    println!("Failed to export frame");
+                    println!("{:#?}", err);
+                }
+            }
+        }
+    }
+
+    fn frame_ui(&mut self, ui: &mut egui::Ui) {
+        if self.ffmpeg.is_none() {
+            ui.centered_and_justified(|ui| {
+                ui.label("ffmpeg was not found on the PATH, only the metadata can be shown.");
+            });
+            return;
+        }
+        if self.frame.is_none() && self.frame_thread.is_none() {
+            self.frame_thread = Some(self.spawn_extract_frame(ui.ctx()));
+        }
+        if let Some(thread) = self.frame_thread.take_if(|thread| thread.is_finished()) {
+            let frame = thread
+                .join()
+                .unwrap_or_else(|_| Err(anyhow!("Frame extraction panicked")))
+                .map(|image| {
+                    let texture = app_util::image_utils::image_egui_handle(&image, ui.ctx());
+                    (image, texture)
+                })
+                .map_err(|err| err.to_string());
+            self.frame = Some(frame);
+        }
+        match &self.frame {
+            Some(Ok((_, texture))) => {
+                ui.add_sized(
+                    ui.available_size(),
+                    egui::Image::new(egui::ImageSource::Texture(
+                        egui::load::SizedTexture::from_handle(texture),
+                    ))
+                    .shrink_to_fit(),
+                );
+            }
+            Some(Err(err)) => {
+                ui.centered_and_justified(|ui| {
+                    ui.label(format!("Failed to extract frame: {}", err));
+                });
+            }
+            None => {
+                ui.centered_and_justified(|ui| ui.spinner());
+            }
+        }
+    }
+}
+
+impl Drop for VideoExplorer {
+    fn drop(&mut self) {
+        // Waited on so the temporary file isn't removed while ffplay still has it open.
+        if let Some(player) = &mut self.player {
+            let _ = player.kill();
+            let _ = player.wait();
+        }
+    }
+}
+
+impl Explorer for VideoExplorer {
+    fn uuid(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn title(&self) -> String {
+        self.name.clone().unwrap_or("Video".to_owned())
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        app_util::splitter::Splitter::horizontal(self.uuid)
+            .min_size(240.0)
+            .show(ui, |ui_a, ui_b| {
+                ui_a.vertical(|ui| self.info_ui(ui));
+                self.frame_ui(ui_b);
+            });
+    }
+}
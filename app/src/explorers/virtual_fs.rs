@@ -1,6 +1,6 @@
 use crate::{
     app::{Explorer, SharedAppContext},
-    app_util, assets, loader,
+    app_util, assets, explorers, loader,
 };
 use anyhow::Result;
 use std::{
@@ -127,8 +127,7 @@ impl<F: Read + Seek + 'static, I: VirtualFsInner<F> + 'static> VirtualFsExplorer
         if response.clicked() {
             match &entry {
                 VirtualFsEntry::File(file) => {
-                    let name = file.path().name().map(|s| s.to_owned());
-                    self.app_context.open_file(file.clone(), name).unwrap();
+                    self.app_context.open_virtual_file(file.clone()).unwrap();
                 }
                 VirtualFsEntry::Directory(directory) => {
                     self.new_view_directory = Some(directory.clone());
@@ -140,6 +139,21 @@ impl<F: Read + Seek + 'static, I: VirtualFsInner<F> + 'static> VirtualFsExplorer
             if ui.button("Copy Path").clicked() {
                 ui.output_mut(|o| o.copied_text = path.to_string());
             }
+            if let VirtualFsEntry::File(file) = &entry {
+                if ui.button("Open as Hex").clicked() {
+                    match explorers::hex::HexExplorer::file(
+                        file.clone(),
+                        path.name().map(|name| name.to_owned()),
+                    ) {
+                        Ok(explorer) => self.app_context.new_explorer(Box::new(explorer)),
+                        Err(err) => {
+                            println!("Failed to open \"{}\" as hex", path);
+                            println!("{:#?}", err);
+                        }
+                    }
+                    ui.close_menu();
+                }
+            }
             if ui.button("Extract").clicked() {
                 let dialog = rfd::FileDialog::new()
                     .set_title(format!("Extract {}", path))
@@ -1,13 +1,19 @@
 #![allow(unused)]
 
 extern crate anyhow;
+#[cfg(feature = "bethesda")]
+extern crate bethesda;
 extern crate egui;
 #[cfg(feature = "godot")]
 extern crate godot;
+#[cfg(feature = "idtech")]
+extern crate idtech;
 extern crate image;
 #[cfg(feature = "renpy")]
 extern crate renpy;
 extern crate rfd;
+#[cfg(feature = "rpgmaker")]
+extern crate rpgmaker;
 #[cfg(feature = "source_engine")]
 extern crate source_engine;
 extern crate util;
@@ -11,20 +11,36 @@ use std::{
 use util::{
     file_utils::{self, FileSize},
     image_utils::SizeHint,
+    virtual_fs::{VirtualFsFile, VirtualFsInner},
 };
 
-pub fn open_file<F: Read + Seek>(
-    _app_context: SharedAppContext,
+pub fn open_file<F: Read + Seek + 'static>(
+    app_context: SharedAppContext,
     mut file: F,
     filename: Option<String>,
 ) -> Result<Option<Box<dyn Explorer>>> {
     // FIXME: Do not clone filename.
+    // Before images, because the image crate only partially supports DDS.
+    if let Ok(explorer) = explorers::dds::DdsExplorer::file(&mut file, filename.clone()) {
+        return Ok(Some(Box::new(explorer)));
+    }
     if let Ok(explorer) = explorers::image::ImageExplorer::file(&mut file, filename.clone()) {
         return Ok(Some(Box::new(explorer)));
     }
+    if let Ok(explorer) = explorers::svg::SvgExplorer::file(&mut file, filename.clone()) {
+        return Ok(Some(Box::new(explorer)));
+    }
+    // Only the header is checked, the file is then moved into the explorer so textures can be
+    // read as they're viewed instead of copying the whole file.
+    #[cfg(feature = "source_engine")]
+    if source_engine::vtf::LazyVtf::load(&mut file).is_ok() {
+        return Ok(Some(Box::new(
+            explorers::source_engine::vtf::VtfExplorer::file(file, filename)?,
+        )));
+    }
     #[cfg(feature = "source_engine")]
     if let Ok(explorer) =
-        explorers::source_engine::vtf::VtfExplorer::file(&mut file, filename.clone())
+        explorers::source_engine::mdl::MdlExplorer::file(&mut file, filename.clone())
     {
         return Ok(Some(Box::new(explorer)));
     }
@@ -39,11 +55,83 @@ pub fn open_file<F: Read + Seek>(
     {
         return Ok(Some(Box::new(explorer)));
     }
+    #[cfg(feature = "rpgmaker")]
+    if let Ok(explorer) =
+        explorers::rpgmaker::mv::RpgMakerImageExplorer::file(&mut file, filename.clone())
+    {
+        return Ok(Some(Box::new(explorer)));
+    }
+    #[cfg(feature = "source_engine")]
+    if let Ok(explorer) = explorers::source_engine::vmt::VmtExplorer::file(
+        app_context.clone(),
+        &mut file,
+        filename.clone(),
+    ) {
+        return Ok(Some(Box::new(explorer)));
+    }
+    // Before audio, so Ogg & WebM files with a video track aren't opened as audio. Like VTF, the
+    // file is moved into the explorer instead of being copied.
+    #[cfg(feature = "video")]
+    if explorers::video::VideoExplorer::load_info(&mut file).is_ok() {
+        return Ok(Some(Box::new(explorers::video::VideoExplorer::file(
+            file, filename,
+        )?)));
+    }
+    #[cfg(feature = "audio")]
+    if let Ok(explorer) = explorers::audio::AudioExplorer::file(&mut file, filename.clone()) {
+        return Ok(Some(Box::new(explorer)));
+    }
+    // Formats the audio decoder doesn't support, like ADPCM WAV.
+    #[cfg(feature = "source_engine")]
+    if let Ok(explorer) =
+        explorers::source_engine::sound::SoundExplorer::file(&mut file, filename.clone())
+    {
+        return Ok(Some(Box::new(explorer)));
+    }
+    #[cfg(feature = "source_engine")]
+    if let Ok(explorer) = explorers::source_engine::closecaption::CloseCaptionExplorer::file(
+        &mut file,
+        filename.clone(),
+    ) {
+        return Ok(Some(Box::new(explorer)));
+    }
+    if let Ok(explorer) = explorers::mesh::MeshExplorer::file(&mut file, filename.clone()) {
+        return Ok(Some(Box::new(explorer)));
+    }
     if let Ok(explorer) = explorers::text::TextExplorer::file(&mut file, filename.clone()) {
         return Ok(Some(Box::new(explorer)));
     }
 
-    Ok(None)
+    // Anything else is shown as bytes.
+    Ok(Some(Box::new(explorers::hex::HexExplorer::file(
+        file, filename,
+    )?)))
+}
+
+/// Some files reference other files in the same filesystem, like materials referencing textures.
+pub fn open_virtual_file<F: Read + Seek + 'static, I: VirtualFsInner<F> + 'static>(
+    app_context: SharedAppContext,
+    file: VirtualFsFile<F, I>,
+) -> Result<Option<Box<dyn Explorer>>> {
+    #[cfg(feature = "source_engine")]
+    if let Ok(explorer) =
+        explorers::source_engine::vmt::VmtExplorer::virtual_file(app_context.clone(), file.clone())
+    {
+        return Ok(Some(Box::new(explorer)));
+    }
+    #[cfg(feature = "source_engine")]
+    if let Ok(explorer) = explorers::source_engine::mdl::MdlExplorer::virtual_file(file.clone()) {
+        return Ok(Some(Box::new(explorer)));
+    }
+    #[cfg(feature = "source_engine")]
+    if let Ok(explorer) =
+        explorers::source_engine::closecaption::CloseCaptionExplorer::virtual_file(file.clone())
+    {
+        return Ok(Some(Box::new(explorer)));
+    }
+
+    let filename = file.path().name().map(|name| name.to_owned());
+    open_file(app_context, file, filename)
 }
 
 pub fn open<P: AsRef<Path>>(
@@ -75,6 +163,65 @@ pub fn open<P: AsRef<Path>>(
         {
             return Ok(Some(Box::new(explorer)));
         }
+        #[cfg(feature = "rpgmaker")]
+        if let Ok(explorer) =
+            explorers::rpgmaker::rgss::RgssArchiveExplorer::open(app_context.clone(), &path)
+        {
+            return Ok(Some(Box::new(explorer)));
+        }
+        #[cfg(feature = "idtech")]
+        if let Ok(explorer) =
+            explorers::idtech::wad::DoomWadExplorer::open(app_context.clone(), &path)
+        {
+            return Ok(Some(Box::new(explorer)));
+        }
+        #[cfg(feature = "idtech")]
+        if let Ok(explorer) =
+            explorers::idtech::wad::TextureWadExplorer::open(app_context.clone(), &path)
+        {
+            return Ok(Some(Box::new(explorer)));
+        }
+        #[cfg(feature = "idtech")]
+        if let Ok(explorer) =
+            explorers::idtech::pak::QuakePakExplorer::open(app_context.clone(), &path)
+        {
+            return Ok(Some(Box::new(explorer)));
+        }
+        #[cfg(feature = "bethesda")]
+        if let Ok(explorer) =
+            explorers::bethesda::bsa::BsaArchiveExplorer::open(app_context.clone(), &path)
+        {
+            return Ok(Some(Box::new(explorer)));
+        }
+        #[cfg(feature = "bethesda")]
+        if let Ok(explorer) =
+            explorers::bethesda::ba2::Ba2ArchiveExplorer::open(app_context.clone(), &path)
+        {
+            return Ok(Some(Box::new(explorer)));
+        }
+
+        // Opened from the path so textures can be read from the file as needed.
+        #[cfg(feature = "source_engine")]
+        if let Ok(explorer) = explorers::source_engine::vtf::VtfExplorer::open(&path) {
+            return Ok(Some(Box::new(explorer)));
+        }
+        // Opened from the path so the mesh data next to it is found.
+        #[cfg(feature = "source_engine")]
+        if let Ok(explorer) = explorers::source_engine::mdl::MdlExplorer::open(&path) {
+            return Ok(Some(Box::new(explorer)));
+        }
+        // Opened from the path so the token names next to it are found.
+        #[cfg(feature = "source_engine")]
+        if let Ok(explorer) =
+            explorers::source_engine::closecaption::CloseCaptionExplorer::open(&path)
+        {
+            return Ok(Some(Box::new(explorer)));
+        }
+        // Opened from the path so ffmpeg can read it without copying the video.
+        #[cfg(feature = "video")]
+        if let Ok(explorer) = explorers::video::VideoExplorer::open(&path) {
+            return Ok(Some(Box::new(explorer)));
+        }
 
         return Ok(open_file(
             app_context,
@@ -83,9 +230,30 @@ pub fn open<P: AsRef<Path>>(
         )?);
     }
 
+    if path.is_dir() {
+        #[cfg(feature = "rpgmaker")]
+        if let Ok(explorer) =
+            explorers::rpgmaker::mv::RpgMakerGameExplorer::open(app_context.clone(), &path)
+        {
+            return Ok(Some(Box::new(explorer)));
+        }
+    }
+
     Ok(None)
 }
 
+pub fn compare<P: AsRef<Path>>(
+    app_context: SharedAppContext,
+    a: P,
+    b: P,
+) -> Result<Box<dyn Explorer>> {
+    Ok(Box::new(explorers::image_diff::ImageDiffExplorer::open(
+        app_context,
+        a,
+        b,
+    )?))
+}
+
 pub enum LoadedThumbnail {
     None,
     Image(image::DynamicImage),
@@ -94,6 +262,14 @@ pub enum LoadedThumbnail {
 
 const DEFAULT_DOWNSCALE_FILTER: image::imageops::FilterType = image::imageops::FilterType::Nearest;
 const MAX_THUMBNAIL_LOAD_FILESIZE: FileSize = FileSize::from_mebibytes(10);
+const MESH_THUMBNAIL_SIZE: u32 = 256;
+/// SVGs are rendered to fit this, then downscaled by the size hint.
+const SVG_THUMBNAIL_SIZE: u32 = 256;
+#[cfg(feature = "audio")]
+const WAVEFORM_THUMBNAIL_SIZE: (u32, u32) = (256, 128);
+// Catppuccin blue, readable on both light & dark themes.
+#[cfg(feature = "audio")]
+const WAVEFORM_THUMBNAIL_COLOR: image::Rgba<u8> = image::Rgba([137, 180, 250, 255]);
 
 pub fn thumbnail_file(
     mut file: impl Read + Seek,
@@ -104,6 +280,17 @@ pub fn thumbnail_file(
     let file_size = FileSize::from_file(&mut file)?;
 
     if let Some(filename) = &filename {
+        if filename.ends_with(".dds") {
+            if file_size < MAX_THUMBNAIL_LOAD_FILESIZE {
+                if let Ok(image) = util::texture::dds::Dds::load_thumbnail(&mut file, hint) {
+                    return Ok(LoadedThumbnail::Image(
+                        hint.downscale_image(image, DEFAULT_DOWNSCALE_FILTER),
+                    ));
+                }
+            }
+            return Ok(LoadedThumbnail::ImageSource(assets::LUCIDE_FILE_IMAGE));
+        }
+
         if let Ok(_) = image::ImageFormat::from_path(filename) {
             if file_size < MAX_THUMBNAIL_LOAD_FILESIZE {
                 if let Ok(image) = image::ImageReader::new(std::io::BufReader::new(&mut file))
@@ -119,6 +306,53 @@ pub fn thumbnail_file(
             return Ok(LoadedThumbnail::ImageSource(assets::LUCIDE_FILE_IMAGE));
         }
 
+        if filename.ends_with(".svg") || filename.ends_with(".svgz") {
+            if file_size < MAX_THUMBNAIL_LOAD_FILESIZE {
+                if let Ok(image) = util::svg::Svg::load(&mut file).and_then(|svg| {
+                    let (width, height) = svg.fit_size(SVG_THUMBNAIL_SIZE, SVG_THUMBNAIL_SIZE);
+                    let (width, height) = hint.rescale(width, height);
+                    svg.render(width.max(1), height.max(1))
+                }) {
+                    return Ok(LoadedThumbnail::Image(image.into()));
+                }
+            }
+            return Ok(LoadedThumbnail::ImageSource(assets::LUCIDE_FILE_IMAGE));
+        }
+
+        if filename.ends_with(".obj") {
+            if file_size < MAX_THUMBNAIL_LOAD_FILESIZE {
+                if let Ok(mesh) = util::mesh::Mesh::read_obj(std::io::BufReader::new(&mut file)) {
+                    let (width, height) = hint.rescale(MESH_THUMBNAIL_SIZE, MESH_THUMBNAIL_SIZE);
+                    return Ok(LoadedThumbnail::Image(util::rasterizer::render(
+                        &mesh,
+                        &util::rasterizer::RenderOptions {
+                            width,
+                            height,
+                            ..Default::default()
+                        },
+                    )));
+                }
+            }
+            return Ok(LoadedThumbnail::ImageSource(assets::LUCIDE_FILE_IMAGE));
+        }
+
+        #[cfg(feature = "audio")]
+        if let Some(extension) = audio::decode::audio_extension(filename) {
+            if file_size < MAX_THUMBNAIL_LOAD_FILESIZE {
+                let (width, height) =
+                    hint.rescale(WAVEFORM_THUMBNAIL_SIZE.0, WAVEFORM_THUMBNAIL_SIZE.1);
+                if let Ok((_, peaks)) =
+                    audio::decode::decode_peaks(&mut file, Some(extension), width as usize)
+                {
+                    return Ok(LoadedThumbnail::Image(
+                        audio::visualize::waveform_peaks(&peaks, height, WAVEFORM_THUMBNAIL_COLOR)
+                            .into(),
+                    ));
+                }
+            }
+            return Ok(LoadedThumbnail::ImageSource(assets::LUCIDE_FILE));
+        }
+
         #[cfg(feature = "godot")]
         if filename.ends_with(".stex") || filename.ends_with(".ctex") {
             if file_size < MAX_THUMBNAIL_LOAD_FILESIZE {
@@ -141,6 +375,22 @@ pub fn thumbnail_file(
 
             return Ok(LoadedThumbnail::ImageSource(assets::LUCIDE_FILE_IMAGE));
         }
+
+        #[cfg(feature = "rpgmaker")]
+        if rpgmaker::mv::decrypted_filename_extension(filename) == Some("png") {
+            if file_size < MAX_THUMBNAIL_LOAD_FILESIZE {
+                if let Ok(image) = rpgmaker::mv::decrypt_image(&mut file).and_then(|file| {
+                    Ok(image::ImageReader::new(std::io::BufReader::new(file))
+                        .with_guessed_format()?
+                        .decode()?)
+                }) {
+                    return Ok(LoadedThumbnail::Image(
+                        hint.downscale_image(image, DEFAULT_DOWNSCALE_FILTER),
+                    ));
+                }
+            }
+            return Ok(LoadedThumbnail::ImageSource(assets::LUCIDE_FILE_IMAGE));
+        }
     }
 
     Ok(LoadedThumbnail::None)
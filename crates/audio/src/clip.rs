@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+
+/// Fully decoded audio, samples are interleaved.
+#[derive(Debug, Clone)]
+pub struct AudioClip {
+    sample_rate: u32,
+    channels: usize,
+    samples: Vec<f32>,
+}
+
+impl AudioClip {
+    pub fn new(sample_rate: u32, channels: usize, samples: Vec<f32>) -> Result<AudioClip> {
+        if sample_rate == 0 || channels == 0 {
+            return Err(anyhow!("Audio clip must have a sample rate & channels"));
+        }
+        if !samples.len().is_multiple_of(channels) {
+            return Err(anyhow!("Audio clip samples don't divide into channels"));
+        }
+        Ok(AudioClip {
+            sample_rate,
+            channels,
+            samples,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+    /// Number of samples per channel.
+    pub fn num_frames(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+    /// In seconds.
+    pub fn duration(&self) -> f64 {
+        self.num_frames() as f64 / self.sample_rate as f64
+    }
+
+    pub fn frame(&self, frame: usize) -> &[f32] {
+        &self.samples[(frame * self.channels)..((frame + 1) * self.channels)]
+    }
+
+    /// Minimum & maximum of every channel, for `columns` equal parts of the clip.
+    pub fn peaks(&self, columns: usize) -> Vec<(f32, f32)> {
+        let num_frames = self.num_frames();
+        (0..columns)
+            .map(|column| {
+                let start = column * num_frames / columns;
+                let end = ((column + 1) * num_frames / columns).max(start + 1);
+                self.samples
+                    .get((start * self.channels)..(end * self.channels).min(self.samples.len()))
+                    .unwrap_or(&[])
+                    .iter()
+                    .fold((0.0f32, 0.0f32), |(min, max), &sample| {
+                        (min.min(sample), max.max(sample))
+                    })
+            })
+            .collect()
+    }
+
+    /// 16 bit PCM WAV.
+    pub fn write_wav<W: Write>(&self, mut writer: W) -> Result<()> {
+        let data_size = (self.samples.len() * 2) as u32;
+        let block_align = (self.channels * 2) as u16;
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(36 + data_size).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&(self.channels as u16).to_le_bytes())?;
+        writer.write_all(&self.sample_rate.to_le_bytes())?;
+        writer.write_all(&(self.sample_rate * block_align as u32).to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&16u16.to_le_bytes())?;
+        writer.write_all(b"data")?;
+        writer.write_all(&data_size.to_le_bytes())?;
+        for &sample in &self.samples {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+// Decoding goes through symphonia, except Opus which symphonia can demux but not decode.
+
+use crate::clip::AudioClip;
+use anyhow::{anyhow, Result};
+use opus_decoder::OpusDecoder;
+use std::io::{Cursor, Read};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL, CODEC_TYPE_OPUS},
+    errors::Error,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision},
+    probe::Hint,
+};
+
+/// Extensions of the formats that can be decoded.
+pub const AUDIO_EXTENSIONS: [&str; 6] = ["wav", "ogg", "oga", "mp3", "flac", "opus"];
+
+/// The extension of `filename` if it's one that can be decoded.
+pub fn audio_extension(filename: &str) -> Option<&'static str> {
+    let extension = filename.rsplit_once('.')?.1.to_lowercase();
+    AUDIO_EXTENSIONS
+        .into_iter()
+        .find(|audio_extension| *audio_extension == extension)
+}
+
+#[derive(Debug, Clone)]
+pub struct AudioInfo {
+    pub codec: String,
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub bits_per_sample: Option<u32>,
+    /// Tags like title & artist, in file order.
+    pub tags: Vec<(String, String)>,
+}
+
+enum TrackDecoder {
+    Symphonia(Box<dyn Decoder>),
+    Opus(Box<OpusDecoder>),
+}
+
+fn push_tags(tags: &mut Vec<(String, String)>, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        let key = match tag.std_key {
+            Some(std_key) => format!("{:?}", std_key),
+            None => tag.key.clone(),
+        };
+        tags.push((key, tag.value.to_string()));
+    }
+}
+
+/// Decodes the first audio track, `extension` helps to guess the format.
+pub fn decode(data: impl Read, extension: Option<&str>) -> Result<(AudioInfo, AudioClip)> {
+    let mut samples: Vec<f32> = Vec::new();
+    let info = decode_with(data, extension, |packet, _| {
+        samples.extend_from_slice(packet)
+    })?;
+    let clip = AudioClip::new(info.sample_rate, info.channels, samples)?;
+    Ok((info, clip))
+}
+
+/// Minimum & maximum of equal blocks of frames, blocks are merged in pairs when there's too many
+/// so long clips don't need much memory.
+struct PeakBlocks {
+    frames_per_block: usize,
+    frames: usize,
+    current: (f32, f32),
+    peaks: Vec<(f32, f32)>,
+}
+
+impl PeakBlocks {
+    const MAX_BLOCKS: usize = 4096;
+
+    fn push(&mut self, frame: &[f32]) {
+        for &sample in frame {
+            self.current = (self.current.0.min(sample), self.current.1.max(sample));
+        }
+        self.frames += 1;
+        if self.frames < self.frames_per_block {
+            return;
+        }
+        self.peaks.push(self.current);
+        self.current = (0.0, 0.0);
+        self.frames = 0;
+        if self.peaks.len() == PeakBlocks::MAX_BLOCKS {
+            self.peaks = self
+                .peaks
+                .chunks(2)
+                .map(|pair| (pair[0].0.min(pair[1].0), pair[0].1.max(pair[1].1)))
+                .collect();
+            self.frames_per_block *= 2;
+        }
+    }
+
+    /// Same as [`AudioClip::peaks`].
+    fn columns(mut self, columns: usize) -> Vec<(f32, f32)> {
+        if self.frames > 0 {
+            self.peaks.push(self.current);
+        }
+        let num_blocks = self.peaks.len();
+        (0..columns)
+            .map(|column| {
+                let start = column * num_blocks / columns;
+                let end = ((column + 1) * num_blocks / columns).max(start + 1);
+                self.peaks
+                    .get(start..end.min(num_blocks))
+                    .unwrap_or(&[])
+                    .iter()
+                    .fold((0.0f32, 0.0f32), |(min, max), &(block_min, block_max)| {
+                        (min.min(block_min), max.max(block_max))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Peaks of the first audio track for `columns` equal parts, without keeping the decoded samples.
+pub fn decode_peaks(
+    data: impl Read,
+    extension: Option<&str>,
+    columns: usize,
+) -> Result<(AudioInfo, Vec<(f32, f32)>)> {
+    let mut blocks = PeakBlocks {
+        frames_per_block: 1,
+        frames: 0,
+        current: (0.0, 0.0),
+        peaks: Vec::new(),
+    };
+    let info = decode_with(data, extension, |packet, channels| {
+        for frame in packet.chunks_exact(channels.max(1)) {
+            blocks.push(frame);
+        }
+    })?;
+    if info.sample_rate == 0 || info.channels == 0 {
+        return Err(anyhow!("Audio must have a sample rate & channels"));
+    }
+    Ok((info, blocks.columns(columns)))
+}
+
+/// Passes the interleaved samples of every packet & their channel count to `on_samples`.
+fn decode_with(
+    mut data: impl Read,
+    extension: Option<&str>,
+    mut on_samples: impl FnMut(&[f32], usize),
+) -> Result<AudioInfo> {
+    let mut buf = Vec::new();
+    data.read_to_end(&mut buf)?;
+    let source = MediaSourceStream::new(Box::new(Cursor::new(buf)), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &FormatOptions {
+            enable_gapless: true,
+            ..Default::default()
+        },
+        &MetadataOptions::default(),
+    )?;
+
+    let mut tags = Vec::new();
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            push_tags(&mut tags, revision);
+        }
+    }
+    let mut format = probed.format;
+    if let Some(revision) = format.metadata().current() {
+        push_tags(&mut tags, revision);
+    }
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(anyhow!("No audio track"))?
+        .clone();
+    let params = &track.codec_params;
+    let mut channels = params
+        .channels
+        .map(|channels| channels.count())
+        .unwrap_or(0);
+    let mut sample_rate = params.sample_rate.unwrap_or(0);
+
+    let (codec, mut decoder) = if params.codec == CODEC_TYPE_OPUS {
+        if channels > 2 {
+            return Err(anyhow!("Opus with more than 2 channels not supported"));
+        }
+        // Opus is always decoded at 48kHz.
+        (
+            "opus".to_owned(),
+            TrackDecoder::Opus(Box::new(OpusDecoder::new(48000, channels)?)),
+        )
+    } else {
+        let decoder = symphonia::default::get_codecs().make(params, &DecoderOptions::default())?;
+        (
+            decoder.codec_params().codec.to_string(),
+            TrackDecoder::Symphonia(decoder),
+        )
+    };
+    let codec = symphonia::default::get_codecs()
+        .get_codec(params.codec)
+        .map(|descriptor| descriptor.short_name.to_owned())
+        .unwrap_or(codec);
+
+    let mut sample_buffer: Option<SampleBuffer<f32>> = None;
+    let mut opus_buffer = vec![0.0f32; OpusDecoder::MAX_FRAME_SIZE_48K * channels.max(1)];
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(Error::ResetRequired) => break,
+            Err(err) => return Err(err.into()),
+        };
+        if packet.track_id() != track.id {
+            continue;
+        }
+
+        match &mut decoder {
+            TrackDecoder::Symphonia(decoder) => {
+                let buffer = match decoder.decode(&packet) {
+                    Ok(buffer) => buffer,
+                    // Corrupt packets are skipped.
+                    Err(Error::DecodeError(_)) => continue,
+                    Err(err) => return Err(err.into()),
+                };
+                let spec = *buffer.spec();
+                channels = spec.channels.count();
+                sample_rate = spec.rate;
+                let sample_buffer = match &mut sample_buffer {
+                    Some(sample_buffer) if sample_buffer.capacity() >= buffer.capacity() => {
+                        sample_buffer
+                    }
+                    sample_buffer => {
+                        sample_buffer.insert(SampleBuffer::new(buffer.capacity() as u64, spec))
+                    }
+                };
+                sample_buffer.copy_interleaved_ref(buffer);
+                on_samples(sample_buffer.samples(), channels);
+            }
+            TrackDecoder::Opus(decoder) => {
+                let frames = decoder.decode_float(packet.buf(), &mut opus_buffer, false)?;
+                // Gapless trimming, symphonia decoders do this themselves.
+                let start = (packet.trim_start as usize).min(frames);
+                let end = frames.saturating_sub(packet.trim_end as usize).max(start);
+                on_samples(&opus_buffer[(start * channels)..(end * channels)], channels);
+                sample_rate = 48000;
+            }
+        }
+    }
+
+    Ok(AudioInfo {
+        codec,
+        sample_rate,
+        channels,
+        bits_per_sample: params.bits_per_sample,
+        tags,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    /// One second of a 441 Hz sine in the left channel & silence in the right.
+    fn wav() -> Vec<u8> {
+        let samples = (0..44100)
+            .flat_map(|frame| [(frame as f32 * 441.0 / 44100.0 * 2.0 * PI).sin() * 0.5, 0.0])
+            .collect();
+        let mut wav = Vec::new();
+        AudioClip::new(44100, 2, samples)
+            .unwrap()
+            .write_wav(&mut wav)
+            .unwrap();
+        wav
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(audio_extension("Sound.OGG"), Some("ogg"));
+        assert_eq!(audio_extension("video.webm"), None);
+        assert_eq!(audio_extension("wav"), None);
+    }
+
+    #[test]
+    fn decode_wav() {
+        let (info, clip) = decode(wav().as_slice(), Some("wav")).unwrap();
+        assert_eq!((info.sample_rate, info.channels), (44100, 2));
+        assert_eq!(info.bits_per_sample, Some(16));
+        assert_eq!(clip.num_frames(), 44100);
+        assert!(clip.frame(25)[0] > 0.49);
+        assert!(decode([0u8; 64].as_slice(), Some("wav")).is_err());
+    }
+
+    #[test]
+    fn peaks_match_clip() {
+        let (_, clip) = decode(wav().as_slice(), Some("wav")).unwrap();
+        for columns in [1, 7, 256] {
+            let (_, peaks) = decode_peaks(wav().as_slice(), Some("wav"), columns).unwrap();
+            assert_eq!(peaks.len(), columns);
+            for ((min, max), (clip_min, clip_max)) in peaks.into_iter().zip(clip.peaks(columns)) {
+                // Blocks don't line up exactly with the columns of the clip.
+                assert!((min - clip_min).abs() < 0.05);
+                assert!((max - clip_max).abs() < 0.05);
+            }
+        }
+    }
+
+    #[test]
+    fn peak_blocks_are_bounded() {
+        let mut blocks = PeakBlocks {
+            frames_per_block: 1,
+            frames: 0,
+            current: (0.0, 0.0),
+            peaks: Vec::new(),
+        };
+        for frame in 0..100_000 {
+            blocks.push(&[if frame == 99_999 { -1.0 } else { 0.25 }]);
+        }
+        assert!(blocks.peaks.len() < PeakBlocks::MAX_BLOCKS);
+        let peaks = blocks.columns(4);
+        assert_eq!(peaks[0], (0.0, 0.25));
+        assert_eq!(peaks[3], (-1.0, 0.25));
+    }
+}
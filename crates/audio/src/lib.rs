@@ -0,0 +1,11 @@
+extern crate anyhow;
+#[cfg(feature = "cpal")]
+extern crate cpal;
+extern crate image;
+extern crate opus_decoder;
+extern crate symphonia;
+
+pub mod clip;
+pub mod decode;
+pub mod output;
+pub mod visualize;
@@ -0,0 +1,388 @@
+// Playback is pulled by the output backend, so backends only have to ask for samples at their own
+// rate & channel count.
+
+use crate::clip::AudioClip;
+use anyhow::{anyhow, Result};
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+struct PlaybackState {
+    /// In frames of the clip, fractional because outputs may have a different sample rate.
+    position: f64,
+    playing: bool,
+    volume: f32,
+}
+
+/// Shared between the UI & the output backend.
+pub struct Playback {
+    clip: Arc<AudioClip>,
+    state: Mutex<PlaybackState>,
+}
+
+impl Playback {
+    pub fn new(clip: Arc<AudioClip>) -> Playback {
+        Playback {
+            clip,
+            state: Mutex::new(PlaybackState {
+                position: 0.0,
+                playing: false,
+                volume: 1.0,
+            }),
+        }
+    }
+
+    pub fn clip(&self) -> &AudioClip {
+        &self.clip
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state.lock().unwrap().playing
+    }
+
+    /// Playing after the clip finished restarts it.
+    pub fn set_playing(&self, playing: bool) {
+        let mut state = self.state.lock().unwrap();
+        if playing && state.position >= self.clip.num_frames() as f64 {
+            state.position = 0.0;
+        }
+        state.playing = playing;
+    }
+
+    /// In seconds.
+    pub fn position(&self) -> f64 {
+        self.state.lock().unwrap().position / self.clip.sample_rate() as f64
+    }
+
+    /// In seconds.
+    pub fn seek(&self, position: f64) {
+        let frames =
+            (position * self.clip.sample_rate() as f64).clamp(0.0, self.clip.num_frames() as f64);
+        self.state.lock().unwrap().position = frames;
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.state.lock().unwrap().volume
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.state.lock().unwrap().volume = volume.max(0.0);
+    }
+
+    /// Fills interleaved samples & advances the position, silence if paused or finished.
+    ///
+    /// Resampled linearly, channels are repeated or averaged to fit. Returns the number of frames
+    /// that weren't silence.
+    pub fn read(&self, out: &mut [f32], channels: usize, sample_rate: u32) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let clip = &self.clip;
+        let num_frames = clip.num_frames();
+        let step = clip.sample_rate() as f64 / sample_rate as f64;
+        let mut played = 0;
+
+        for frame in out.chunks_mut(channels.max(1)) {
+            if !state.playing || state.position >= num_frames as f64 {
+                frame.fill(0.0);
+                continue;
+            }
+            let index = state.position as usize;
+            let t = (state.position - index as f64) as f32;
+            let a = clip.frame(index);
+            let b = clip.frame((index + 1).min(num_frames - 1));
+            let sample = |channel: usize| a[channel] + (b[channel] - a[channel]) * t;
+
+            if clip.channels() > 1 && frame.len() == 1 {
+                frame[0] = (0..clip.channels()).map(sample).sum::<f32>() / clip.channels() as f32;
+            } else {
+                for (channel, out) in frame.iter_mut().enumerate() {
+                    *out = sample(channel % clip.channels());
+                }
+            }
+            for out in frame.iter_mut() {
+                *out *= state.volume;
+            }
+
+            state.position += step;
+            played += 1;
+        }
+
+        if state.position >= num_frames as f64 {
+            state.position = num_frames as f64;
+            state.playing = false;
+        }
+        played
+    }
+}
+
+pub trait AudioOutput {
+    /// Shown to the user.
+    fn name(&self) -> &str;
+    /// Starts pulling from `playback`, replaces the previous playback.
+    fn start(&mut self, playback: Arc<Playback>) -> Result<()>;
+    fn stop(&mut self);
+    /// False if nothing can be heard, so playing can be disabled.
+    fn is_audible(&self) -> bool {
+        true
+    }
+}
+
+/// Consumes samples in real time without playing them, for when there's no audio device.
+#[derive(Default)]
+pub struct NullOutput {
+    thread: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl NullOutput {
+    const SAMPLE_RATE: u32 = 48000;
+    const INTERVAL: Duration = Duration::from_millis(10);
+}
+
+impl AudioOutput for NullOutput {
+    fn name(&self) -> &str {
+        "No audio output"
+    }
+
+    fn start(&mut self, playback: Arc<Playback>) -> Result<()> {
+        self.stop();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::spawn(move || {
+            let frames =
+                (NullOutput::SAMPLE_RATE as f64 * NullOutput::INTERVAL.as_secs_f64()) as usize;
+            let mut buffer = vec![0.0; frames];
+            while !thread_stop.load(Ordering::Relaxed) {
+                playback.read(&mut buffer, 1, NullOutput::SAMPLE_RATE);
+                std::thread::sleep(NullOutput::INTERVAL);
+            }
+        });
+        self.thread = Some((stop, thread));
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some((stop, thread)) = self.thread.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = thread.join();
+        }
+    }
+
+    fn is_audible(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for NullOutput {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Renders playback as fast as possible to a WAV file, until it stops playing.
+pub struct WavFileOutput {
+    path: PathBuf,
+    sample_rate: u32,
+    channels: usize,
+}
+
+impl WavFileOutput {
+    pub fn new<P: Into<PathBuf>>(
+        path: P,
+        sample_rate: u32,
+        channels: usize,
+    ) -> Result<WavFileOutput> {
+        // Nothing would be read, so playback would never finish.
+        if sample_rate == 0 || channels == 0 {
+            return Err(anyhow!(
+                "WAV file output must have a sample rate & channels"
+            ));
+        }
+        Ok(WavFileOutput {
+            path: path.into(),
+            sample_rate,
+            channels,
+        })
+    }
+}
+
+impl AudioOutput for WavFileOutput {
+    fn name(&self) -> &str {
+        "WAV file"
+    }
+
+    fn start(&mut self, playback: Arc<Playback>) -> Result<()> {
+        let mut samples = Vec::new();
+        let mut buffer = vec![0.0; 4096 * self.channels];
+        while playback.is_playing() {
+            let played = playback.read(&mut buffer, self.channels, self.sample_rate);
+            samples.extend_from_slice(&buffer[..(played * self.channels)]);
+        }
+        AudioClip::new(self.sample_rate, self.channels, samples)?
+            .write_wav(BufWriter::new(File::create(&self.path)?))
+    }
+
+    fn stop(&mut self) {}
+
+    fn is_audible(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "cpal")]
+pub use cpal_output::CpalOutput;
+
+#[cfg(feature = "cpal")]
+mod cpal_output {
+    use super::{AudioOutput, Playback};
+    use anyhow::{anyhow, Result};
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use std::sync::Arc;
+
+    /// The system's default output device.
+    #[derive(Default)]
+    pub struct CpalOutput {
+        stream: Option<cpal::Stream>,
+    }
+
+    fn build_stream<T: cpal::SizedSample + cpal::FromSample<f32>>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        playback: Arc<Playback>,
+    ) -> Result<cpal::Stream> {
+        let channels = config.channels as usize;
+        let sample_rate = config.sample_rate.0;
+        let mut buffer: Vec<f32> = Vec::new();
+        Ok(device.build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                buffer.resize(data.len(), 0.0);
+                playback.read(&mut buffer, channels, sample_rate);
+                for (out, &sample) in data.iter_mut().zip(&buffer) {
+                    *out = T::from_sample(sample);
+                }
+            },
+            |err| {
+                println!("Audio output error");
+                println!("{:#?}", err);
+            },
+            None,
+        )?)
+    }
+
+    impl AudioOutput for CpalOutput {
+        fn name(&self) -> &str {
+            "System audio output"
+        }
+
+        fn start(&mut self, playback: Arc<Playback>) -> Result<()> {
+            self.stop();
+            let device = cpal::default_host()
+                .default_output_device()
+                .ok_or(anyhow!("No audio output device"))?;
+            let config = device.default_output_config()?;
+            let stream = match config.sample_format() {
+                cpal::SampleFormat::F32 => {
+                    build_stream::<f32>(&device, &config.config(), playback)?
+                }
+                cpal::SampleFormat::I16 => {
+                    build_stream::<i16>(&device, &config.config(), playback)?
+                }
+                cpal::SampleFormat::U16 => {
+                    build_stream::<u16>(&device, &config.config(), playback)?
+                }
+                format => return Err(anyhow!("Audio output format {} not supported", format)),
+            };
+            stream.play()?;
+            self.stream = Some(stream);
+            Ok(())
+        }
+
+        fn stop(&mut self) {
+            self.stream = None;
+        }
+    }
+}
+
+/// System audio output if built with it, otherwise nothing is played.
+pub fn default_output() -> Box<dyn AudioOutput> {
+    #[cfg(feature = "cpal")]
+    {
+        Box::new(CpalOutput::default())
+    }
+    #[cfg(not(feature = "cpal"))]
+    {
+        Box::new(NullOutput::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip() -> Arc<AudioClip> {
+        // Stereo, left rises & right falls.
+        let samples = (0..100)
+            .flat_map(|frame| [frame as f32 / 100.0, -(frame as f32) / 100.0])
+            .collect();
+        Arc::new(AudioClip::new(100, 2, samples).unwrap())
+    }
+
+    #[test]
+    fn read_resamples() {
+        let playback = Playback::new(clip());
+        let mut out = [1.0; 8];
+        assert_eq!(playback.read(&mut out, 2, 100), 0);
+        assert_eq!(out, [0.0; 8]);
+
+        playback.set_playing(true);
+        let mut out = [0.0; 4];
+        assert_eq!(playback.read(&mut out, 1, 200), 4);
+        assert_eq!(out, [0.0; 4]);
+        assert_eq!(playback.position(), 0.02);
+
+        playback.set_volume(0.5);
+        let mut out = [0.0; 6];
+        playback.read(&mut out, 3, 100);
+        assert_eq!(out, [0.01, -0.01, 0.01, 0.015, -0.015, 0.015]);
+    }
+
+    #[test]
+    fn read_finishes() {
+        let playback = Playback::new(clip());
+        playback.seek(0.99);
+        playback.set_playing(true);
+        let mut out = [0.0; 8];
+        assert_eq!(playback.read(&mut out, 2, 100), 1);
+        assert!(!playback.is_playing());
+        assert_eq!(playback.position(), 1.0);
+
+        // Restarts from the beginning.
+        playback.set_playing(true);
+        assert_eq!(playback.position(), 0.0);
+    }
+
+    #[test]
+    fn wav_file_output() {
+        assert!(WavFileOutput::new("out.wav", 48000, 0).is_err());
+        assert!(WavFileOutput::new("out.wav", 0, 2).is_err());
+
+        let path = std::env::temp_dir().join(format!("audio_test_{}.wav", std::process::id()));
+        let playback = Arc::new(Playback::new(clip()));
+        playback.set_playing(true);
+        WavFileOutput::new(&path, 50, 1)
+            .unwrap()
+            .start(playback)
+            .unwrap();
+        let wav = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // Half the sample rate & one channel.
+        assert_eq!(wav.len(), 44 + 50 * 2);
+    }
+}
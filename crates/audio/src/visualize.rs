@@ -0,0 +1,155 @@
+use crate::clip::AudioClip;
+use image::{Rgb, RgbImage, Rgba, RgbaImage};
+use std::f32::consts::PI;
+
+/// Peaks of every channel, drawn over a transparent background.
+pub fn waveform(clip: &AudioClip, width: u32, height: u32, color: Rgba<u8>) -> RgbaImage {
+    waveform_peaks(&clip.peaks(width as usize), height, color)
+}
+
+/// A column for every peak, like [`waveform`] for peaks from [`crate::decode::decode_peaks`].
+pub fn waveform_peaks(peaks: &[(f32, f32)], height: u32, color: Rgba<u8>) -> RgbaImage {
+    let mut image = RgbaImage::new(peaks.len() as u32, height);
+    if peaks.is_empty() || height == 0 {
+        return image;
+    }
+    let center = height as f32 / 2.0;
+    for (x, &(min, max)) in peaks.iter().enumerate() {
+        let top = (center - max * center).floor().max(0.0) as u32;
+        // At least one pixel, so silence is a line.
+        let bottom = ((center - min * center).ceil() as u32).clamp(top + 1, height);
+        for y in top..bottom {
+            image.put_pixel(x as u32, y, color);
+        }
+    }
+    image
+}
+
+/// In place radix-2 FFT, `real` & `imag` must be the same power of two length.
+fn fft(real: &mut [f32], imag: &mut [f32]) {
+    let n = real.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imag.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= n {
+        let angle = -2.0 * PI / length as f32;
+        for start in (0..n).step_by(length) {
+            for k in 0..(length / 2) {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + length / 2;
+                let (br, bi) = (real[b] * cos - imag[b] * sin, real[b] * sin + imag[b] * cos);
+                real[b] = real[a] - br;
+                imag[b] = imag[a] - bi;
+                real[a] += br;
+                imag[a] += bi;
+            }
+        }
+        length <<= 1;
+    }
+}
+
+/// Black through purple & orange to yellow, `t` from 0.0 to 1.0.
+fn heat_color(t: f32) -> Rgb<u8> {
+    const STOPS: [[f32; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [0.23, 0.06, 0.44],
+        [0.71, 0.21, 0.48],
+        [0.98, 0.53, 0.24],
+        [0.99, 0.99, 0.75],
+    ];
+    let t = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let index = (t as usize).min(STOPS.len() - 2);
+    let t = t - index as f32;
+    let [a, b] = [STOPS[index], STOPS[index + 1]];
+    Rgb(std::array::from_fn(|i| {
+        ((a[i] + (b[i] - a[i]) * t) * 255.0).round() as u8
+    }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectrogramOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Samples per FFT, rounded up to a power of two.
+    pub window_size: usize,
+    /// Quieter frequencies are black.
+    pub min_db: f32,
+}
+
+impl Default for SpectrogramOptions {
+    fn default() -> Self {
+        SpectrogramOptions {
+            width: 1024,
+            height: 256,
+            window_size: 1024,
+            min_db: -90.0,
+        }
+    }
+}
+
+/// Channels are mixed to mono, low frequencies are at the bottom.
+pub fn spectrogram(clip: &AudioClip, options: &SpectrogramOptions) -> RgbImage {
+    let SpectrogramOptions {
+        width,
+        height,
+        min_db,
+        ..
+    } = *options;
+    let window_size = options.window_size.max(2).next_power_of_two();
+    let mut image = RgbImage::new(width, height);
+    if width == 0 || height == 0 {
+        return image;
+    }
+
+    let num_frames = clip.num_frames();
+    let mono =
+        |frame: usize| -> f32 { clip.frame(frame).iter().sum::<f32>() / clip.channels() as f32 };
+    // Hann window.
+    let window: Vec<f32> = (0..window_size)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / window_size as f32).cos())
+        .collect();
+    let window_sum: f32 = window.iter().sum();
+    let bins = window_size / 2;
+
+    let mut real = vec![0.0; window_size];
+    let mut imag = vec![0.0; window_size];
+    for x in 0..width {
+        let center = (x as f64 + 0.5) / width as f64 * num_frames as f64;
+        let start = center as isize - (window_size / 2) as isize;
+        for (i, (real, imag)) in real.iter_mut().zip(imag.iter_mut()).enumerate() {
+            let frame = start + i as isize;
+            *real = if frame >= 0 && (frame as usize) < num_frames {
+                mono(frame as usize) * window[i]
+            } else {
+                0.0
+            };
+            *imag = 0.0;
+        }
+        fft(&mut real, &mut imag);
+
+        for y in 0..height {
+            // Every pixel takes the loudest of the bins it covers.
+            let first = (y as usize * bins) / height as usize;
+            let last = (((y + 1) as usize * bins) / height as usize).max(first + 1);
+            let magnitude = (first..last)
+                .map(|bin| (real[bin] * real[bin] + imag[bin] * imag[bin]).sqrt())
+                .fold(0.0f32, f32::max);
+            let db = 20.0 * (magnitude * 2.0 / window_sum).max(1e-10).log10();
+            image.put_pixel(x, height - 1 - y, heat_color(1.0 - db / min_db));
+        }
+    }
+    image
+}
@@ -0,0 +1,421 @@
+// https://en.uesp.net/wiki/Fallout4Mod:Archive2_File_Format
+// https://github.com/Ryan-rsm-McKenzie/bsa/blob/master/src/bsa/fo4.cpp
+
+use anyhow::{anyhow, Result};
+use std::{
+    collections::HashMap,
+    io::{Read, Seek},
+    sync::{Arc, Mutex},
+};
+use util::{
+    file_utils::{ArchiveFile, InnerFile},
+    texture::dds::{DdsHeader, DxgiFormat},
+    tree_fs::TreeFs,
+    virtual_fs::{VirtualFsInner, VirtualFsInnerEntry},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ba2Type {
+    /// General files
+    General,
+    /// Textures, stored without their DDS header & split into chunks of mipmaps.
+    Texture,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ba2Compression {
+    Zlib,
+    Lz4,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Ba2Chunk {
+    offset: u64,
+    packed_size: u32,
+    unpacked_size: u32,
+}
+
+impl Ba2Chunk {
+    fn disk_size(&self) -> u64 {
+        if self.packed_size != 0 {
+            self.packed_size as u64
+        } else {
+            self.unpacked_size as u64
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Ba2File {
+    General(Ba2Chunk),
+    Texture {
+        width: u16,
+        height: u16,
+        mipmap_count: u8,
+        // Kept raw so an unsupported format only fails reading this texture.
+        format: u8,
+        cubemap: bool,
+        chunks: Vec<Ba2Chunk>,
+    },
+}
+
+pub struct Ba2Archive<F: Read + Seek> {
+    version: u32,
+    archive_type: Ba2Type,
+    compression: Ba2Compression,
+    file: Arc<Mutex<F>>,
+    size: u64,
+    // Only used for the directory structure, files are read through `files`.
+    fs: TreeFs<InnerFile<F>>,
+    files: HashMap<String, Ba2File>,
+}
+
+impl<F: Read + Seek> Ba2Archive<F> {
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn archive_type(&self) -> Ba2Type {
+        self.archive_type
+    }
+
+    pub fn load(mut data: F) -> Result<Self> {
+        data.rewind()?;
+        let mut reader = crate::util::reader::Reader::new_le(&mut data);
+
+        if &reader.read::<[u8; 4]>()? != b"BTDX" {
+            return Err(anyhow!("BA2 identifier doesn't match"));
+        }
+
+        let version = reader.read::<u32>()?;
+        let archive_type = match &reader.read::<[u8; 4]>()? {
+            b"GNRL" => Ba2Type::General,
+            b"DX10" => Ba2Type::Texture,
+            _ => return Err(anyhow!("BA2 archive type not supported")),
+        };
+        let file_count = reader.read::<u32>()?;
+        let name_table_offset = reader.read::<u64>()?;
+
+        // Starfield archives extend the header.
+        let compression = match version {
+            1 | 7 | 8 => Ba2Compression::Zlib,
+            2 => {
+                reader.skip(8)?;
+                Ba2Compression::Zlib
+            }
+            3 => {
+                reader.skip(8)?;
+                match reader.read::<u32>()? {
+                    3 => Ba2Compression::Lz4,
+                    _ => Ba2Compression::Zlib,
+                }
+            }
+            version => return Err(anyhow!("BA2 version {} not supported", version)),
+        };
+
+        let mut records: Vec<Ba2File> = Vec::new();
+        for _ in 0..file_count {
+            let _name_hash = reader.read::<u32>()?;
+            let _extension = reader.read::<[u8; 4]>()?;
+            let _directory_hash = reader.read::<u32>()?;
+
+            match archive_type {
+                Ba2Type::General => {
+                    let _flags = reader.read::<u32>()?;
+                    let offset = reader.read::<u64>()?;
+                    let packed_size = reader.read::<u32>()?;
+                    let unpacked_size = reader.read::<u32>()?;
+                    let _align = reader.read::<u32>()?;
+                    records.push(Ba2File::General(Ba2Chunk {
+                        offset,
+                        packed_size,
+                        unpacked_size,
+                    }));
+                }
+                Ba2Type::Texture => {
+                    let _unknown = reader.read::<u8>()?;
+                    let chunk_count = reader.read::<u8>()?;
+                    let _chunk_header_size = reader.read::<u16>()?;
+                    let height = reader.read::<u16>()?;
+                    let width = reader.read::<u16>()?;
+                    let mipmap_count = reader.read::<u8>()?;
+                    let format = reader.read::<u8>()?;
+                    let cubemap = reader.read::<u8>()?;
+                    let _tile_mode = reader.read::<u8>()?;
+
+                    let mut chunks = Vec::new();
+                    for _ in 0..chunk_count {
+                        let offset = reader.read::<u64>()?;
+                        let packed_size = reader.read::<u32>()?;
+                        let unpacked_size = reader.read::<u32>()?;
+                        let _start_mipmap = reader.read::<u16>()?;
+                        let _end_mipmap = reader.read::<u16>()?;
+                        let _align = reader.read::<u32>()?;
+                        chunks.push(Ba2Chunk {
+                            offset,
+                            packed_size,
+                            unpacked_size,
+                        });
+                    }
+
+                    records.push(Ba2File::Texture {
+                        width,
+                        height,
+                        mipmap_count,
+                        format,
+                        cubemap: cubemap != 0,
+                        chunks,
+                    });
+                }
+            }
+        }
+
+        reader.seek(std::io::SeekFrom::Start(name_table_offset))?;
+        let mut names: Vec<String> = Vec::new();
+        for _ in 0..file_count {
+            names.push(reader.read_length_string::<u16>()?.replace('\\', "/"));
+        }
+        let size = reader.size()?;
+
+        let file = Arc::new(Mutex::new(data));
+
+        let mut entries = Vec::new();
+        let mut files = HashMap::new();
+        for (path, record) in names.into_iter().zip(records) {
+            let (offset, size) = match &record {
+                Ba2File::General(chunk) => (chunk.offset, chunk.disk_size()),
+                Ba2File::Texture { chunks, .. } => chunks
+                    .first()
+                    .map(|chunk| (chunk.offset, chunk.disk_size()))
+                    .unwrap_or((0, 0)),
+            };
+            entries.push((
+                path.clone(),
+                InnerFile::new(Arc::clone(&file), offset, size),
+            ));
+            files.insert(path, record);
+        }
+
+        Ok(Self {
+            version,
+            archive_type,
+            compression,
+            file,
+            size,
+            fs: TreeFs::new(entries)?,
+            files,
+        })
+    }
+
+    fn read_chunk(&self, chunk: &Ba2Chunk) -> Result<Vec<u8>> {
+        if chunk
+            .offset
+            .checked_add(chunk.disk_size())
+            .is_none_or(|end| end > self.size)
+        {
+            return Err(anyhow!("BA2 chunk out of bounds"));
+        }
+        let mut data = vec![0u8; chunk.disk_size() as usize];
+        InnerFile::new(Arc::clone(&self.file), chunk.offset, chunk.disk_size())
+            .read_exact(&mut data)?;
+        if chunk.packed_size == 0 {
+            return Ok(data);
+        }
+
+        let unpacked = match self.compression {
+            Ba2Compression::Zlib => {
+                let mut unpacked =
+                    Vec::with_capacity((chunk.unpacked_size as u64).min(self.size) as usize);
+                flate2::read::ZlibDecoder::new(data.as_slice()).read_to_end(&mut unpacked)?;
+                unpacked
+            }
+            Ba2Compression::Lz4 => {
+                // LZ4 can't expand more than 255 times, don't trust larger sizes.
+                if chunk.unpacked_size as u64 > data.len() as u64 * 255 + 16 {
+                    return Err(anyhow!("BA2 decompressed chunk size mismatch"));
+                }
+                lz4_flex::block::decompress(&data, chunk.unpacked_size as usize)?
+            }
+        };
+        if unpacked.len() != chunk.unpacked_size as usize {
+            return Err(anyhow!("BA2 decompressed chunk size mismatch"));
+        }
+        Ok(unpacked)
+    }
+}
+
+impl<F: Read + Seek> VirtualFsInner<ArchiveFile<F>> for Ba2Archive<F> {
+    fn read(&mut self, path: &str) -> Result<VirtualFsInnerEntry<ArchiveFile<F>>> {
+        let file = match self.fs.read(path)? {
+            VirtualFsInnerEntry::File(file) => file,
+            VirtualFsInnerEntry::Directory(entries) => {
+                return Ok(VirtualFsInnerEntry::Directory(entries))
+            }
+        };
+
+        let record = self
+            .files
+            .get(path.trim_start_matches('/'))
+            .ok_or(anyhow!("BA2 file not found"))?;
+        match record {
+            Ba2File::General(chunk) if chunk.packed_size == 0 => {
+                Ok(VirtualFsInnerEntry::File(ArchiveFile::Inner(file)))
+            }
+            Ba2File::General(chunk) => Ok(VirtualFsInnerEntry::File(ArchiveFile::memory(
+                self.read_chunk(chunk)?,
+            ))),
+            Ba2File::Texture {
+                width,
+                height,
+                mipmap_count,
+                format,
+                cubemap,
+                chunks,
+            } => {
+                // Textures are reassembled into a regular DDS file.
+                let mut header = DdsHeader::new(
+                    *width as u32,
+                    *height as u32,
+                    DxgiFormat::from(*format as u32)?,
+                );
+                header.mipmap_count = *mipmap_count as u32;
+                header.cubemap = *cubemap;
+                let mut data = Vec::new();
+                header.write(&mut data)?;
+                for chunk in chunks {
+                    data.extend(self.read_chunk(chunk)?);
+                }
+                Ok(VirtualFsInnerEntry::File(ArchiveFile::memory(data)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::{Cursor, Write};
+    use util::texture::dds::Dds;
+
+    /// Builds a file record from its offset.
+    type RecordFn<'a> = &'a dyn Fn(u64) -> Vec<u8>;
+
+    /// Version 1 archive, files are `(name, contents, record)`.
+    fn ba2(archive_type: &[u8; 4], files: &[(&str, Vec<u8>, RecordFn)]) -> Vec<u8> {
+        let records_size = files.iter().map(|file| file.2(0).len()).sum::<usize>();
+        let mut offset = (24 + records_size) as u64;
+        let mut records = Vec::new();
+        for (_, contents, record) in files {
+            records.extend(record(offset));
+            offset += contents.len() as u64;
+        }
+
+        let mut data = b"BTDX".to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(archive_type);
+        data.extend_from_slice(&(files.len() as u32).to_le_bytes());
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend(records);
+        for (_, contents, _) in files {
+            data.extend_from_slice(contents);
+        }
+        for (name, _, _) in files {
+            data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            data.extend_from_slice(name.as_bytes());
+        }
+        data
+    }
+
+    fn general_record(offset: u64, packed_size: u32, unpacked_size: u32) -> Vec<u8> {
+        let mut record = vec![0; 12];
+        record.extend_from_slice(&0u32.to_le_bytes());
+        record.extend_from_slice(&offset.to_le_bytes());
+        record.extend_from_slice(&packed_size.to_le_bytes());
+        record.extend_from_slice(&unpacked_size.to_le_bytes());
+        record.extend_from_slice(&0xBAADF00Du32.to_le_bytes());
+        record
+    }
+
+    fn read_file<F: Read + Seek>(ba2: &mut Ba2Archive<F>, path: &str) -> Result<Vec<u8>> {
+        let VirtualFsInnerEntry::File(mut file) = ba2.read(path)? else {
+            return Err(anyhow!("{} is not a file", path));
+        };
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn general() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[7; 100]).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let compressed_size = compressed.len() as u32;
+
+        let data = ba2(
+            b"GNRL",
+            &[
+                ("meshes\\a.nif", b"stored".to_vec(), &|offset| {
+                    general_record(offset, 0, 6)
+                }),
+                ("meshes\\b.nif", compressed.clone(), &|offset| {
+                    general_record(offset, compressed_size, 100)
+                }),
+                ("c.txt", compressed, &|offset| {
+                    general_record(offset, compressed_size, u32::MAX)
+                }),
+            ],
+        );
+        let mut ba2 = Ba2Archive::load(Cursor::new(data)).unwrap();
+        assert_eq!(ba2.version(), 1);
+        assert_eq!(ba2.archive_type(), Ba2Type::General);
+
+        let VirtualFsInnerEntry::Directory(mut entries) = ba2.read("meshes").unwrap() else {
+            panic!("meshes is not a directory");
+        };
+        entries.sort();
+        assert_eq!(entries, ["a.nif", "b.nif"]);
+        assert_eq!(read_file(&mut ba2, "meshes/a.nif").unwrap(), b"stored");
+        assert_eq!(read_file(&mut ba2, "meshes/b.nif").unwrap(), [7; 100]);
+        assert!(read_file(&mut ba2, "c.txt").is_err());
+    }
+
+    #[test]
+    fn unsupported_texture_format() {
+        let texture_record = |format: u8| {
+            move |offset: u64| {
+                let mut record = vec![0; 12];
+                record.extend_from_slice(&[0, 1]);
+                record.extend_from_slice(&24u16.to_le_bytes());
+                record.extend_from_slice(&4u16.to_le_bytes());
+                record.extend_from_slice(&4u16.to_le_bytes());
+                record.extend_from_slice(&[1, format, 0, 0]);
+                record.extend_from_slice(&offset.to_le_bytes());
+                record.extend_from_slice(&0u32.to_le_bytes());
+                record.extend_from_slice(&64u32.to_le_bytes());
+                record.extend_from_slice(&0u16.to_le_bytes());
+                record.extend_from_slice(&0u16.to_le_bytes());
+                record.extend_from_slice(&0xBAADF00Du32.to_le_bytes());
+                record
+            }
+        };
+        let data = ba2(
+            b"DX10",
+            &[
+                ("a.dds", vec![0x40; 64], &texture_record(28)),
+                // R11G11B10_FLOAT
+                ("b.dds", vec![0x40; 64], &texture_record(26)),
+            ],
+        );
+        let mut ba2 = Ba2Archive::load(Cursor::new(data)).unwrap();
+        assert_eq!(ba2.archive_type(), Ba2Type::Texture);
+
+        let dds = read_file(&mut ba2, "a.dds").unwrap();
+        assert_eq!(&dds[..4], b"DDS ");
+        assert_eq!(&dds[dds.len() - 64..], [0x40; 64]);
+        let texture = Dds::load(Cursor::new(&dds)).unwrap();
+        assert_eq!((texture.width(), texture.height()), (4, 4));
+        assert!(read_file(&mut ba2, "b.dds").is_err());
+    }
+}
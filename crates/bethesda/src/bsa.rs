@@ -0,0 +1,316 @@
+// https://en.uesp.net/wiki/Skyrim_Mod:Archive_File_Format
+// https://en.uesp.net/wiki/Oblivion_Mod:BSA_File_Format
+
+use anyhow::{anyhow, Result};
+use bitflags::bitflags;
+use std::{
+    collections::HashMap,
+    io::{Read, Seek},
+    sync::{Arc, Mutex},
+};
+use util::{
+    file_utils::{ArchiveFile, InnerFile},
+    tree_fs::TreeFs,
+    virtual_fs::{VirtualFsInner, VirtualFsInnerEntry},
+};
+
+bitflags! {
+    #[derive(Debug, Clone, Copy)]
+    struct BsaArchiveFlags: u32 {
+        const INCLUDE_DIRECTORY_NAMES = 0x1;
+        const INCLUDE_FILE_NAMES = 0x2;
+        const COMPRESSED = 0x4;
+        const RETAIN_DIRECTORY_NAMES = 0x8;
+        const RETAIN_FILE_NAMES = 0x10;
+        const RETAIN_FILE_NAME_OFFSETS = 0x20;
+        const XBOX360 = 0x40;
+        const RETAIN_STRINGS = 0x80;
+        const EMBED_FILE_NAMES = 0x100;
+        const XMEM_CODEC = 0x200;
+    }
+}
+
+/// Set on a file's size when its compression is the opposite of the archive default.
+const FILE_SIZE_COMPRESSION_TOGGLE: u32 = 0x40000000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BsaVersion {
+    /// Oblivion
+    V103,
+    /// Fallout 3, Fallout: New Vegas & Skyrim
+    V104,
+    /// Skyrim Special Edition
+    V105,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BsaFileInfo {
+    compressed: bool,
+    embedded_name: bool,
+}
+
+pub struct BsaArchive<F: Read + Seek> {
+    version: BsaVersion,
+    size: u64,
+    fs: TreeFs<InnerFile<F>>,
+    files: HashMap<String, BsaFileInfo>,
+}
+
+impl<F: Read + Seek> BsaArchive<F> {
+    pub fn version(&self) -> BsaVersion {
+        self.version
+    }
+
+    pub fn load(mut data: F) -> Result<Self> {
+        data.rewind()?;
+        let mut reader = crate::util::reader::Reader::new_le(&mut data);
+
+        if &reader.read::<[u8; 4]>()? != b"BSA\0" {
+            return Err(anyhow!("BSA identifier doesn't match"));
+        }
+
+        let version = match reader.read::<u32>()? {
+            103 => BsaVersion::V103,
+            104 => BsaVersion::V104,
+            105 => BsaVersion::V105,
+            version => return Err(anyhow!("BSA version {} not supported", version)),
+        };
+        let _folder_records_offset = reader.read::<u32>()?;
+        let flags = BsaArchiveFlags::from_bits_retain(reader.read::<u32>()?);
+        let folder_count = reader.read::<u32>()?;
+        let file_count = reader.read::<u32>()?;
+        let _total_folder_name_length = reader.read::<u32>()?;
+        let _total_file_name_length = reader.read::<u32>()?;
+        let _file_flags = reader.read::<u16>()?;
+        reader.skip(2)?;
+
+        if !flags.contains(BsaArchiveFlags::INCLUDE_DIRECTORY_NAMES)
+            || !flags.contains(BsaArchiveFlags::INCLUDE_FILE_NAMES)
+        {
+            return Err(anyhow!("BSA without file names not supported"));
+        }
+        if flags.contains(BsaArchiveFlags::XBOX360) {
+            return Err(anyhow!("BSA Xbox 360 archive not supported"));
+        }
+        // Oblivion reuses this flag for something else.
+        let embedded_name =
+            version != BsaVersion::V103 && flags.contains(BsaArchiveFlags::EMBED_FILE_NAMES);
+
+        let mut folder_file_counts: Vec<u32> = Vec::new();
+        for _ in 0..folder_count {
+            let _hash = reader.read::<u64>()?;
+            let count = reader.read::<u32>()?;
+            match version {
+                BsaVersion::V103 | BsaVersion::V104 => {
+                    let _offset = reader.read::<u32>()?;
+                }
+                BsaVersion::V105 => {
+                    reader.skip(4)?;
+                    let _offset = reader.read::<u64>()?;
+                }
+            }
+            folder_file_counts.push(count);
+        }
+
+        // (folder, offset, size, compressed)
+        let mut records: Vec<(String, u64, u64, bool)> = Vec::new();
+        for count in folder_file_counts {
+            let folder = reader.read_length_string::<u8>()?;
+            let folder = folder.trim_end_matches('\0').replace('\\', "/");
+            for _ in 0..count {
+                let _hash = reader.read::<u64>()?;
+                let size = reader.read::<u32>()?;
+                let offset = reader.read::<u32>()?;
+                let compressed = flags.contains(BsaArchiveFlags::COMPRESSED)
+                    != (size & FILE_SIZE_COMPRESSION_TOGGLE != 0);
+                records.push((
+                    folder.clone(),
+                    offset as u64,
+                    (size & !FILE_SIZE_COMPRESSION_TOGGLE) as u64,
+                    compressed,
+                ));
+            }
+        }
+
+        if records.len() != file_count as usize {
+            return Err(anyhow!("BSA file count mismatch"));
+        }
+
+        // File names are stored in the same order as the file records, right after them.
+        let mut names: Vec<String> = Vec::new();
+        for _ in 0..file_count {
+            names.push(reader.read_terminated_string(0)?);
+        }
+        let size = reader.size()?;
+
+        let file = Arc::new(Mutex::new(data));
+
+        let mut entries = Vec::new();
+        let mut files = HashMap::new();
+        for ((folder, offset, size, compressed), name) in records.into_iter().zip(names) {
+            let path = match folder.as_str() {
+                "" | "." => name,
+                folder => format!("{}/{}", folder, name),
+            };
+            entries.push((
+                path.clone(),
+                InnerFile::new(Arc::clone(&file), offset, size),
+            ));
+            files.insert(
+                path,
+                BsaFileInfo {
+                    compressed,
+                    embedded_name,
+                },
+            );
+        }
+
+        Ok(Self {
+            version,
+            size,
+            fs: TreeFs::new(entries)?,
+            files,
+        })
+    }
+}
+
+impl<F: Read + Seek> VirtualFsInner<ArchiveFile<F>> for BsaArchive<F> {
+    fn read(&mut self, path: &str) -> Result<VirtualFsInnerEntry<ArchiveFile<F>>> {
+        let mut file = match self.fs.read(path)? {
+            VirtualFsInnerEntry::File(file) => file,
+            VirtualFsInnerEntry::Directory(entries) => {
+                return Ok(VirtualFsInnerEntry::Directory(entries))
+            }
+        };
+
+        let info = *self
+            .files
+            .get(path.trim_start_matches('/'))
+            .ok_or(anyhow!("BSA file not found"))?;
+        if !info.compressed && !info.embedded_name {
+            return Ok(VirtualFsInnerEntry::File(ArchiveFile::Inner(file)));
+        }
+
+        let mut reader = crate::util::reader::Reader::new_le(&mut file);
+        if reader.size()? > self.size {
+            return Err(anyhow!("BSA file out of bounds"));
+        }
+        if info.embedded_name {
+            let _name = reader.read_length_string::<u8>()?;
+        }
+        if !info.compressed {
+            let remaining = reader.bytes_remaining()?;
+            let data = reader.read_buf(remaining as usize)?;
+            return Ok(VirtualFsInnerEntry::File(ArchiveFile::memory(data)));
+        }
+
+        let original_size = reader.read::<u32>()? as usize;
+        let remaining = reader.bytes_remaining()?;
+        let compressed = reader.read_buf(remaining as usize)?;
+        let mut data = Vec::with_capacity(original_size.min(self.size as usize));
+        match self.version {
+            BsaVersion::V103 | BsaVersion::V104 => {
+                flate2::read::ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+            }
+            BsaVersion::V105 => {
+                lz4_flex::frame::FrameDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+            }
+        }
+        if data.len() != original_size {
+            return Err(anyhow!("BSA decompressed file size mismatch"));
+        }
+        Ok(VirtualFsInnerEntry::File(ArchiveFile::memory(data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Uncompressed v105 archive with a single folder, files are `(name, contents, size)`.
+    fn bsa105(flags: u32, folder: &str, files: &[(&str, &[u8], u32)]) -> Vec<u8> {
+        let folder_name = format!("{}\0", folder);
+        let file_names_length = files.iter().map(|file| file.0.len() + 1).sum::<usize>();
+
+        let mut data = b"BSA\0".to_vec();
+        for value in [
+            105,
+            36,
+            flags,
+            1,
+            files.len() as u32,
+            folder_name.len() as u32,
+            file_names_length as u32,
+        ] {
+            data.extend_from_slice(&u32::to_le_bytes(value));
+        }
+        data.extend_from_slice(&[0; 4]);
+
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&(files.len() as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&0u64.to_le_bytes());
+
+        data.push(folder_name.len() as u8);
+        data.extend_from_slice(folder_name.as_bytes());
+        let mut offset = data.len() + files.len() * 16 + file_names_length;
+        for (_, contents, size) in files {
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data.extend_from_slice(&size.to_le_bytes());
+            data.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += contents.len();
+        }
+        for (name, _, _) in files {
+            data.extend_from_slice(name.as_bytes());
+            data.push(0);
+        }
+        for (_, contents, _) in files {
+            data.extend_from_slice(contents);
+        }
+        data
+    }
+
+    fn read_file<F: Read + Seek>(bsa: &mut BsaArchive<F>, path: &str) -> Result<Vec<u8>> {
+        let VirtualFsInnerEntry::File(mut file) = bsa.read(path)? else {
+            return Err(anyhow!("{} is not a file", path));
+        };
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn uncompressed_v105() {
+        let data = bsa105(
+            0x3,
+            "meshes\\armor",
+            &[("a.nif", b"first", 5), ("b.txt", b"second file", 11)],
+        );
+        let mut bsa = BsaArchive::load(Cursor::new(data)).unwrap();
+        assert_eq!(bsa.version(), BsaVersion::V105);
+
+        let VirtualFsInnerEntry::Directory(entries) = bsa.read("meshes").unwrap() else {
+            panic!("meshes is not a directory");
+        };
+        assert_eq!(entries, ["armor"]);
+        assert_eq!(read_file(&mut bsa, "meshes/armor/a.nif").unwrap(), b"first");
+        assert_eq!(
+            read_file(&mut bsa, "meshes/armor/b.txt").unwrap(),
+            b"second file"
+        );
+    }
+
+    #[test]
+    fn oversized_file() {
+        // The embedded name makes the file get read into memory.
+        let contents = b"\x05a.txtdata";
+        let data = bsa105(0x103, "", &[("a.txt", contents, 0x3FFFFFFF)]);
+        let mut bsa = BsaArchive::load(Cursor::new(data)).unwrap();
+        assert!(read_file(&mut bsa, "a.txt").is_err());
+
+        let data = bsa105(0x103, "", &[("a.txt", contents, contents.len() as u32)]);
+        let mut bsa = BsaArchive::load(Cursor::new(data)).unwrap();
+        assert_eq!(read_file(&mut bsa, "a.txt").unwrap(), b"data");
+    }
+}
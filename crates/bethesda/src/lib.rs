@@ -0,0 +1,8 @@
+extern crate anyhow;
+extern crate bitflags;
+extern crate flate2;
+extern crate lz4_flex;
+extern crate util;
+
+pub mod ba2;
+pub mod bsa;
@@ -1,7 +1,12 @@
 use anyhow::{anyhow, Result};
 use bitflags::bitflags;
-use image::DynamicImage;
+use image::{DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Rgb, RgbImage, Rgba};
 use std::io::{Read, Seek};
+use util::texture::{
+    astc,
+    dds::{self, DxgiFormat},
+    etc, pvrtc,
+};
 
 bitflags! {
     #[derive(Debug, Clone, Copy)]
@@ -31,22 +36,332 @@ impl DataFormatBits {
     }
 }
 
+/// Raw image formats, Godot 3 & Godot 4 number these differently.
+///
+/// https://github.com/godotengine/godot/blob/3.6-stable/core/image.h#L73
+/// https://github.com/godotengine/godot/blob/4.3-stable/core/io/image.h#L77
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageFormat {
+    L8,
+    LA8,
+    R8,
+    RG8,
+    RGB8,
+    RGBA8,
+    RGBA4444,
+    RGBA5551,
+    RGB565,
+    RF,
+    RGF,
+    RGBF,
+    RGBAF,
+    RH,
+    RGH,
+    RGBH,
+    RGBAH,
+    RGBE9995,
+    DXT1,
+    DXT3,
+    DXT5,
+    RGTC_R,
+    RGTC_RG,
+    BPTC_RGBA,
+    BPTC_RGBF,
+    BPTC_RGBFU,
+    PVRTC2,
+    PVRTC2A,
+    PVRTC4,
+    PVRTC4A,
+    ETC,
+    ETC2_R11,
+    ETC2_R11S,
+    ETC2_RG11,
+    ETC2_RG11S,
+    ETC2_RGB8,
+    ETC2_RGBA8,
+    ETC2_RGB8A1,
+    ETC2_RA_AS_RG,
+    DXT5_RA_AS_RG,
+    ASTC_4x4,
+    ASTC_4x4_HDR,
+    ASTC_8x8,
+    ASTC_8x8_HDR,
+}
+
+impl ImageFormat {
+    const COMMON: [ImageFormat; 25] = [
+        ImageFormat::L8,
+        ImageFormat::LA8,
+        ImageFormat::R8,
+        ImageFormat::RG8,
+        ImageFormat::RGB8,
+        ImageFormat::RGBA8,
+        ImageFormat::RGBA4444,
+        ImageFormat::RGBA5551, // RGB565 in Godot 4
+        ImageFormat::RF,
+        ImageFormat::RGF,
+        ImageFormat::RGBF,
+        ImageFormat::RGBAF,
+        ImageFormat::RH,
+        ImageFormat::RGH,
+        ImageFormat::RGBH,
+        ImageFormat::RGBAH,
+        ImageFormat::RGBE9995,
+        ImageFormat::DXT1,
+        ImageFormat::DXT3,
+        ImageFormat::DXT5,
+        ImageFormat::RGTC_R,
+        ImageFormat::RGTC_RG,
+        ImageFormat::BPTC_RGBA,
+        ImageFormat::BPTC_RGBF,
+        ImageFormat::BPTC_RGBFU,
+    ];
+
+    fn from_godot3(value: u32) -> Result<ImageFormat> {
+        const FORMATS: [ImageFormat; 12] = [
+            ImageFormat::PVRTC2,
+            ImageFormat::PVRTC2A,
+            ImageFormat::PVRTC4,
+            ImageFormat::PVRTC4A,
+            ImageFormat::ETC,
+            ImageFormat::ETC2_R11,
+            ImageFormat::ETC2_R11S,
+            ImageFormat::ETC2_RG11,
+            ImageFormat::ETC2_RG11S,
+            ImageFormat::ETC2_RGB8,
+            ImageFormat::ETC2_RGBA8,
+            ImageFormat::ETC2_RGB8A1,
+        ];
+        ImageFormat::COMMON
+            .iter()
+            .chain(FORMATS.iter())
+            .nth(value as usize)
+            .copied()
+            .ok_or(anyhow!("Godot 3 image format {} invalid", value))
+    }
+
+    fn from_godot4(value: u32) -> Result<ImageFormat> {
+        const FORMATS: [ImageFormat; 14] = [
+            ImageFormat::ETC,
+            ImageFormat::ETC2_R11,
+            ImageFormat::ETC2_R11S,
+            ImageFormat::ETC2_RG11,
+            ImageFormat::ETC2_RG11S,
+            ImageFormat::ETC2_RGB8,
+            ImageFormat::ETC2_RGBA8,
+            ImageFormat::ETC2_RGB8A1,
+            ImageFormat::ETC2_RA_AS_RG,
+            ImageFormat::DXT5_RA_AS_RG,
+            ImageFormat::ASTC_4x4,
+            ImageFormat::ASTC_4x4_HDR,
+            ImageFormat::ASTC_8x8,
+            ImageFormat::ASTC_8x8_HDR,
+        ];
+        match ImageFormat::COMMON
+            .iter()
+            .chain(FORMATS.iter())
+            .nth(value as usize)
+            .copied()
+        {
+            Some(ImageFormat::RGBA5551) => Ok(ImageFormat::RGB565),
+            Some(format) => Ok(format),
+            None => Err(anyhow!("Godot 4 image format {} invalid", value)),
+        }
+    }
+
+    /// Formats that are the same as a DDS format.
+    fn dxgi_format(&self) -> Option<DxgiFormat> {
+        Some(match self {
+            ImageFormat::R8 => DxgiFormat::R8_UNORM,
+            ImageFormat::RG8 => DxgiFormat::R8G8_UNORM,
+            ImageFormat::RGBA8 => DxgiFormat::R8G8B8A8_UNORM,
+            ImageFormat::RGB565 => DxgiFormat::B5G6R5_UNORM,
+            ImageFormat::RF => DxgiFormat::R32_FLOAT,
+            ImageFormat::RGF => DxgiFormat::R32G32_FLOAT,
+            ImageFormat::RGBF => DxgiFormat::R32G32B32_FLOAT,
+            ImageFormat::RGBAF => DxgiFormat::R32G32B32A32_FLOAT,
+            ImageFormat::RH => DxgiFormat::R16_FLOAT,
+            ImageFormat::RGH => DxgiFormat::R16G16_FLOAT,
+            ImageFormat::RGBAH => DxgiFormat::R16G16B16A16_FLOAT,
+            ImageFormat::DXT1 => DxgiFormat::BC1_UNORM,
+            ImageFormat::DXT3 => DxgiFormat::BC2_UNORM,
+            ImageFormat::DXT5 => DxgiFormat::BC3_UNORM,
+            ImageFormat::RGTC_R => DxgiFormat::BC4_UNORM,
+            ImageFormat::RGTC_RG => DxgiFormat::BC5_UNORM,
+            ImageFormat::BPTC_RGBA => DxgiFormat::BC7_UNORM,
+            ImageFormat::BPTC_RGBF => DxgiFormat::BC6H_SF16,
+            ImageFormat::BPTC_RGBFU => DxgiFormat::BC6H_UF16,
+            _ => return None,
+        })
+    }
+
+    fn decode(&self, data: &[u8], width: u32, height: u32) -> Result<DynamicImage> {
+        if let Some(format) = self.dxgi_format() {
+            return dds::decode_surface(format, data, width, height);
+        }
+
+        fn raw<const S: usize>(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+            let size = (width as usize) * (height as usize) * S;
+            data.get(..size)
+                .map(|data| data.to_vec())
+                .ok_or(anyhow!("Godot image data is too small"))
+        }
+
+        fn pixels<const S: usize>(data: &[u8], width: u32, height: u32) -> Result<Vec<[u8; S]>> {
+            Ok(raw::<S>(data, width, height)?
+                .chunks_exact(S)
+                .map(|c| c.try_into().unwrap())
+                .collect())
+        }
+
+        fn from_pixels<P: image::Pixel>(
+            width: u32,
+            height: u32,
+            pixels: impl Iterator<Item = P>,
+        ) -> ImageBuffer<P, Vec<P::Subpixel>> {
+            let mut image = ImageBuffer::new(width, height);
+            for (pixel, new) in image.pixels_mut().zip(pixels) {
+                *pixel = new;
+            }
+            image
+        }
+
+        let expand = |v: u16, bits: u32| -> u8 {
+            let max = (1 << bits) - 1;
+            ((v as u32) * 255 / max) as u8
+        };
+
+        Ok(match self {
+            ImageFormat::L8 => DynamicImage::ImageLuma8(
+                GrayImage::from_raw(width, height, raw::<1>(data, width, height)?).unwrap(),
+            ),
+            ImageFormat::LA8 => DynamicImage::ImageLumaA8(
+                GrayAlphaImage::from_raw(width, height, raw::<2>(data, width, height)?).unwrap(),
+            ),
+            ImageFormat::RGB8 => DynamicImage::ImageRgb8(
+                RgbImage::from_raw(width, height, raw::<3>(data, width, height)?).unwrap(),
+            ),
+            ImageFormat::RGBA4444 => DynamicImage::ImageRgba8(from_pixels(
+                width,
+                height,
+                pixels::<2>(data, width, height)?.into_iter().map(|c| {
+                    let v = u16::from_le_bytes(c);
+                    Rgba([
+                        expand(v >> 12, 4),
+                        expand((v >> 8) & 0xF, 4),
+                        expand((v >> 4) & 0xF, 4),
+                        expand(v & 0xF, 4),
+                    ])
+                }),
+            )),
+            ImageFormat::RGBA5551 => DynamicImage::ImageRgba8(from_pixels(
+                width,
+                height,
+                pixels::<2>(data, width, height)?.into_iter().map(|c| {
+                    let v = u16::from_le_bytes(c);
+                    Rgba([
+                        expand(v >> 11, 5),
+                        expand((v >> 6) & 0x1F, 5),
+                        expand((v >> 1) & 0x1F, 5),
+                        expand(v & 1, 1),
+                    ])
+                }),
+            )),
+            ImageFormat::RGBH => {
+                // Pad to RGBA so the DDS decoder can be used.
+                let data: Vec<u8> = pixels::<6>(data, width, height)?
+                    .into_iter()
+                    .flat_map(|c| [c[0], c[1], c[2], c[3], c[4], c[5], 0x00, 0x3C])
+                    .collect();
+                dds::decode_surface(DxgiFormat::R16G16B16A16_FLOAT, &data, width, height)?
+            }
+            ImageFormat::RGBE9995 => DynamicImage::ImageRgb32F(from_pixels(
+                width,
+                height,
+                pixels::<4>(data, width, height)?.into_iter().map(|c| {
+                    let v = u32::from_le_bytes(c);
+                    let exponent = 2f32.powi(((v >> 27) as i32) - 15 - 9);
+                    Rgb([
+                        (v & 0x1FF) as f32 * exponent,
+                        ((v >> 9) & 0x1FF) as f32 * exponent,
+                        ((v >> 18) & 0x1FF) as f32 * exponent,
+                    ])
+                }),
+            )),
+            ImageFormat::PVRTC2 | ImageFormat::PVRTC2A => {
+                DynamicImage::ImageRgba8(pvrtc::decode_pvrtc(data, width, height, true))
+            }
+            ImageFormat::PVRTC4 | ImageFormat::PVRTC4A => {
+                DynamicImage::ImageRgba8(pvrtc::decode_pvrtc(data, width, height, false))
+            }
+            ImageFormat::ETC => DynamicImage::ImageRgb8(etc::decode_etc1(data, width, height)),
+            ImageFormat::ETC2_R11 => {
+                DynamicImage::ImageLuma16(etc::decode_eac_r11(data, width, height, false))
+            }
+            ImageFormat::ETC2_R11S => {
+                DynamicImage::ImageLuma16(etc::decode_eac_r11(data, width, height, true))
+            }
+            ImageFormat::ETC2_RG11 => {
+                DynamicImage::ImageRgb16(etc::decode_eac_rg11(data, width, height, false))
+            }
+            ImageFormat::ETC2_RG11S => {
+                DynamicImage::ImageRgb16(etc::decode_eac_rg11(data, width, height, true))
+            }
+            ImageFormat::ETC2_RGB8 => {
+                DynamicImage::ImageRgb8(etc::decode_etc2_rgb(data, width, height))
+            }
+            ImageFormat::ETC2_RGBA8 => {
+                DynamicImage::ImageRgba8(etc::decode_etc2_rgba8(data, width, height))
+            }
+            ImageFormat::ETC2_RGB8A1 => {
+                DynamicImage::ImageRgba8(etc::decode_etc2_rgba1(data, width, height))
+            }
+            // Red & green are stored in the red & alpha channels, usually for normal maps.
+            ImageFormat::ETC2_RA_AS_RG | ImageFormat::DXT5_RA_AS_RG => {
+                let image = if *self == ImageFormat::ETC2_RA_AS_RG {
+                    etc::decode_etc2_rgba8(data, width, height)
+                } else {
+                    util::texture::bc::decode_bc3(data, width, height)
+                };
+                DynamicImage::ImageRgb8(from_pixels(
+                    width,
+                    height,
+                    image.pixels().map(|p| Rgb([p[0], p[3], 0])),
+                ))
+            }
+            // HDR blocks decode to the error color.
+            ImageFormat::ASTC_4x4 | ImageFormat::ASTC_4x4_HDR => {
+                DynamicImage::ImageRgba8(astc::decode_astc(data, width, height, 4, 4))
+            }
+            ImageFormat::ASTC_8x8 | ImageFormat::ASTC_8x8_HDR => {
+                DynamicImage::ImageRgba8(astc::decode_astc(data, width, height, 8, 8))
+            }
+            _ => unreachable!(),
+        })
+    }
+}
+
 pub fn godot_extract_texture(mut file: impl Read + Seek) -> Result<DynamicImage> {
     file.rewind()?;
     let mut reader = crate::util::reader::Reader::new_le(file);
 
     match &reader.read::<[u8; 4]>()? {
         b"GDST" => {
-            let _texture_width = reader.read::<u16>()?;
+            let texture_width = reader.read::<u16>()?;
             let _image_width = reader.read::<u16>()?;
-            let _texture_height = reader.read::<u16>()?;
+            let texture_height = reader.read::<u16>()?;
             let _image_height = reader.read::<u16>()?;
             let _flags = reader.read::<u32>()?;
             let data_format = DataFormatBits::from_bits_retain(reader.read::<u32>()?);
 
-            let image_format = data_format.image_format().ok_or(anyhow!(
-                "Godot texture that isn't PNG or WEBP data format not supported"
-            ))?;
+            let Some(image_format) = data_format.image_format() else {
+                // Raw image data, the lower bits are the image format.
+                let format = ImageFormat::from_godot3(data_format.bits() & ((1 << 20) - 1))?;
+                let size = reader.bytes_remaining()?;
+                let data = reader.read_buf(size as usize)?;
+                return format.decode(&data, texture_width as u32, texture_height as u32);
+            };
 
             if data_format.intersects(DataFormatBits::HAS_MIPMAPS) {
                 println!("Godot texture with extra mipmaps ignored.");
@@ -110,13 +425,20 @@ pub fn godot_extract_texture(mut file: impl Read + Seek) -> Result<DynamicImage>
             reader.skip(12)?;
 
             let data_format = DataFormat::from(reader.read::<u32>()?)?;
-            let _width = reader.read::<u16>()?;
-            let _height = reader.read::<u16>()?;
+            let width = reader.read::<u16>()?;
+            let height = reader.read::<u16>()?;
             let _mipmaps = reader.read::<u32>()?;
-            let _format = reader.read::<u32>()?; // https://github.com/godotengine/godot/blob/3504c98c1233bbd2506e89ce46509bc79afaec17/core/io/image.h#L77
+            let format = reader.read::<u32>()?;
+
+            if data_format == DataFormat::IMAGE {
+                let format = ImageFormat::from_godot4(format)?;
+                let size = reader.bytes_remaining()?;
+                let data = reader.read_buf(size as usize)?;
+                return format.decode(&data, width as u32, height as u32);
+            }
 
             let image_format = data_format.image_format().ok_or(anyhow!(
-                "Godot texture that isn't PNG or WEBP data format not supported"
+                "Godot texture that isn't image, PNG or WEBP data format not supported"
             ))?;
 
             // Read first mipmap
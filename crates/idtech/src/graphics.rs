@@ -0,0 +1,298 @@
+// https://doomwiki.org/wiki/Picture_format
+// https://doomwiki.org/wiki/Flat
+// https://twhl.info/wiki/page/Specification:_WAD3
+
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::io::Cursor;
+
+#[derive(Debug, Clone)]
+pub struct Palette([[u8; 3]; 256]);
+
+impl Palette {
+    pub fn from_bytes(data: &[u8]) -> Result<Palette> {
+        if data.len() < 256 * 3 {
+            return Err(anyhow!("Palette must have 256 colors"));
+        }
+        let mut colors = [[0u8; 3]; 256];
+        for (i, color) in colors.iter_mut().enumerate() {
+            color.copy_from_slice(&data[(i * 3)..(i * 3 + 3)]);
+        }
+        Ok(Palette(colors))
+    }
+
+    /// Fallback for when an archive doesn't contain its own palette.
+    pub fn grayscale() -> Palette {
+        let mut colors = [[0u8; 3]; 256];
+        for (i, color) in colors.iter_mut().enumerate() {
+            *color = [i as u8; 3];
+        }
+        Palette(colors)
+    }
+
+    #[inline]
+    pub fn color(&self, index: u8) -> Rgba<u8> {
+        let [r, g, b] = self.0[index as usize];
+        Rgba([r, g, b, 255])
+    }
+}
+
+fn indexed_image(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    palette: &Palette,
+    transparent: Option<u8>,
+) -> Result<RgbaImage> {
+    let num_pixels = (width as usize) * (height as usize);
+    if data.len() < num_pixels {
+        return Err(anyhow!("Indexed image data is too small"));
+    }
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, index) in image.pixels_mut().zip(data) {
+        *pixel = if Some(*index) == transparent {
+            Rgba([0, 0, 0, 0])
+        } else {
+            palette.color(*index)
+        };
+    }
+    Ok(image)
+}
+
+/// Doom flats are raw palette indices, usually 64x64.
+pub fn decode_flat(data: &[u8], palette: &Palette) -> Result<DynamicImage> {
+    let (width, height) = match data.len() {
+        0 => return Err(anyhow!("Doom flat is empty")),
+        4096 => (64, 64),
+        size => {
+            let side = (size as f64).sqrt() as u32;
+            if (side as usize) * (side as usize) == size {
+                (side, side)
+            } else {
+                (64, (size / 64) as u32)
+            }
+        }
+    };
+    Ok(DynamicImage::ImageRgba8(indexed_image(
+        data, width, height, palette, None,
+    )?))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..(offset + 2))?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..(offset + 4))?.try_into().ok()?,
+    ))
+}
+
+/// Longest header [`is_patch_header`] needs, the size & a column offset for every column.
+pub const MAX_PATCH_HEADER_SIZE: usize = 8 + 4096 * 4;
+
+/// If the data looks like a valid Doom picture.
+pub fn is_patch(data: &[u8]) -> bool {
+    is_patch_header(data, data.len())
+}
+
+/// Like [`is_patch`], with only the start of a picture that is `size` bytes long.
+pub fn is_patch_header(header: &[u8], size: usize) -> bool {
+    let (Some(width), Some(height)) = (read_u16(header, 0), read_u16(header, 2)) else {
+        return false;
+    };
+    if width == 0 || height == 0 || width > 4096 || height > 4096 {
+        return false;
+    }
+    let columns_start = 8 + (width as usize) * 4;
+    if columns_start > size {
+        return false;
+    }
+    (0..(width as usize)).all(|column| {
+        read_u32(header, 8 + column * 4)
+            .map(|offset| (offset as usize) >= columns_start && (offset as usize) < size)
+            .unwrap_or(false)
+    })
+}
+
+/// ZDoom ports allow PNGs anywhere a Doom picture or flat can be.
+pub fn is_png(data: &[u8]) -> bool {
+    data.starts_with(b"\x89PNG\r\n\x1A\n")
+}
+
+/// Doom pictures are stored as columns of posts, anything not covered by a post is transparent.
+pub fn decode_patch(data: &[u8], palette: &Palette) -> Result<DynamicImage> {
+    if !is_patch(data) {
+        return Err(anyhow!("Invalid Doom picture"));
+    }
+
+    let width = read_u16(data, 0).unwrap() as u32;
+    let height = read_u16(data, 2).unwrap() as u32;
+    let mut image = RgbaImage::new(width, height);
+
+    for x in 0..width {
+        let mut offset = read_u32(data, 8 + (x as usize) * 4).unwrap() as usize;
+        let mut top: i32 = -1;
+        while let Some(&top_delta) = data.get(offset) {
+            if top_delta == 0xFF {
+                break;
+            }
+            // Tall patches use relative offsets once the top delta stops increasing.
+            if (top_delta as i32) <= top {
+                top += top_delta as i32;
+            } else {
+                top = top_delta as i32;
+            }
+            let length = *data
+                .get(offset + 1)
+                .ok_or(anyhow!("Doom picture post out of bounds"))?
+                as usize;
+            let pixels = data
+                .get((offset + 3)..(offset + 3 + length))
+                .ok_or(anyhow!("Doom picture post out of bounds"))?;
+            for (i, index) in pixels.iter().enumerate() {
+                let y = (top as u32) + (i as u32);
+                if y < height {
+                    image.put_pixel(x, y, palette.color(*index));
+                }
+            }
+            offset += length + 4;
+        }
+    }
+
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+/// Quake & Half-Life mip texture, only the full size mipmap is decoded.
+///
+/// Half-Life textures have their palette after the last mipmap, Quake textures do not.
+pub fn decode_miptex(data: &[u8], palette: Option<&Palette>) -> Result<DynamicImage> {
+    let name = data.get(0..16).ok_or(anyhow!("Mip texture is too small"))?;
+    let width = read_u32(data, 16).ok_or(anyhow!("Mip texture is too small"))?;
+    let height = read_u32(data, 20).ok_or(anyhow!("Mip texture is too small"))?;
+    let offset = read_u32(data, 24).ok_or(anyhow!("Mip texture is too small"))? as usize;
+    let last_offset = read_u32(data, 36).ok_or(anyhow!("Mip texture is too small"))? as usize;
+
+    let embedded_palette;
+    let palette = match palette {
+        Some(palette) => palette,
+        None => {
+            let palette_offset = ((width / 8) as usize)
+                .checked_mul((height / 8) as usize)
+                .and_then(|pixels| pixels.checked_add(last_offset))
+                .and_then(|offset| offset.checked_add(2))
+                .filter(|offset| *offset <= data.len())
+                .ok_or(anyhow!("Mip texture palette out of bounds"))?;
+            embedded_palette = Palette::from_bytes(&data[palette_offset..])?;
+            &embedded_palette
+        }
+    };
+
+    // Textures prefixed with '{' use the last palette color as transparency.
+    let transparent = if name[0] == b'{' { Some(255) } else { None };
+
+    Ok(DynamicImage::ImageRgba8(indexed_image(
+        data.get(offset..)
+            .ok_or(anyhow!("Mip texture data out of bounds"))?,
+        width,
+        height,
+        palette,
+        transparent,
+    )?))
+}
+
+/// Quake & Half-Life `qpic`, Half-Life pictures have their palette after the image data.
+pub fn decode_qpic(data: &[u8], palette: Option<&Palette>) -> Result<DynamicImage> {
+    let width = read_u32(data, 0).ok_or(anyhow!("Picture is too small"))?;
+    let height = read_u32(data, 4).ok_or(anyhow!("Picture is too small"))?;
+
+    let embedded_palette;
+    let palette = match palette {
+        Some(palette) => palette,
+        None => {
+            let palette_offset = (width as usize)
+                .checked_mul(height as usize)
+                .and_then(|pixels| pixels.checked_add(8 + 2))
+                .filter(|offset| *offset <= data.len())
+                .ok_or(anyhow!("Picture palette out of bounds"))?;
+            embedded_palette = Palette::from_bytes(&data[palette_offset..])?;
+            &embedded_palette
+        }
+    };
+
+    Ok(DynamicImage::ImageRgba8(indexed_image(
+        &data[8..],
+        width,
+        height,
+        palette,
+        None,
+    )?))
+}
+
+/// Decoded graphics are exposed as PNG files so they can be viewed & extracted like any other image.
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut data = Cursor::new(Vec::new());
+    image.write_to(&mut data, image::ImageFormat::Png)?;
+    Ok(data.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    #[test]
+    fn patch_posts() {
+        // 2x3 picture, the first column has a single post of 2 pixels, the second is empty.
+        let mut data = vec![2, 0, 3, 0, 0, 0, 0, 0];
+        data.extend_from_slice(&16u32.to_le_bytes());
+        data.extend_from_slice(&22u32.to_le_bytes());
+        data.extend_from_slice(&[1, 2, 0, 10, 20, 0, 0xFF]);
+        data.extend_from_slice(&[0xFF]);
+        assert!(is_patch(&data));
+
+        let image = decode_patch(&data, &Palette::grayscale()).unwrap();
+        assert_eq!(image.dimensions(), (2, 3));
+        assert_eq!(image.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(image.get_pixel(0, 1), Rgba([10, 10, 10, 255]));
+        assert_eq!(image.get_pixel(0, 2), Rgba([20, 20, 20, 255]));
+        assert_eq!(image.get_pixel(1, 1), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn patch_column_out_of_bounds() {
+        let mut data = vec![1, 0, 1, 0, 0, 0, 0, 0];
+        data.extend_from_slice(&100u32.to_le_bytes());
+        assert!(!is_patch(&data));
+        assert!(decode_patch(&data, &Palette::grayscale()).is_err());
+    }
+
+    #[test]
+    fn qpic_embedded_palette() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[0, 1]);
+        data.extend_from_slice(&256u16.to_le_bytes());
+        for i in 0..256 {
+            data.extend_from_slice(&[i as u8, 0, 255 - i as u8]);
+        }
+
+        let image = decode_qpic(&data, None).unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+        assert_eq!(image.get_pixel(1, 0), Rgba([1, 0, 254, 255]));
+    }
+
+    #[test]
+    fn qpic_size_overflow() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&[0; 16]);
+        assert!(decode_qpic(&data, None).is_err());
+        assert!(decode_qpic(&data, Some(&Palette::grayscale())).is_err());
+    }
+}
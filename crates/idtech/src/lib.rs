@@ -0,0 +1,8 @@
+extern crate anyhow;
+extern crate image;
+extern crate util;
+
+pub mod graphics;
+pub mod pak;
+pub mod wad;
+pub mod wad3;
@@ -0,0 +1,64 @@
+// https://quakewiki.org/wiki/.pak
+
+use anyhow::{anyhow, Result};
+use std::{
+    io::{Read, Seek},
+    sync::{Arc, Mutex},
+};
+use util::{file_utils::InnerFile, tree_fs::TreeFs};
+
+pub struct QuakePak<F: Read + Seek> {
+    fs: TreeFs<InnerFile<F>>,
+}
+
+impl<F: Read + Seek> QuakePak<F> {
+    pub fn load(mut data: F) -> Result<Self> {
+        data.rewind()?;
+        let mut reader = crate::util::reader::Reader::new_le(&mut data);
+
+        if &reader.read::<[u8; 4]>()? != b"PACK" {
+            return Err(anyhow!("Quake PAK identifier doesn't match"));
+        }
+
+        let directory_offset = reader.read::<u32>()?;
+        let directory_size = reader.read::<u32>()?;
+        if directory_size % 64 != 0 {
+            return Err(anyhow!("Quake PAK malformed directory"));
+        }
+
+        reader.seek(std::io::SeekFrom::Start(directory_offset as u64))?;
+
+        let mut entries: Vec<(String, u64, u64)> = Vec::new();
+
+        for _ in 0..(directory_size / 64) {
+            let name = reader.read::<[u8; 56]>()?;
+            let length = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+            let path = String::from_utf8_lossy(&name[..length]).into_owned();
+            let offset = reader.read::<u32>()?;
+            let size = reader.read::<u32>()?;
+            entries.push((path, offset as u64, size as u64));
+        }
+
+        let file = Arc::new(Mutex::new(data));
+
+        Ok(Self {
+            fs: TreeFs::new(
+                entries
+                    .into_iter()
+                    .map(|(path, offset, size)| {
+                        (path, InnerFile::new(Arc::clone(&file), offset, size))
+                    })
+                    .collect(),
+            )?,
+        })
+    }
+}
+
+impl<F: Read + Seek> crate::util::virtual_fs::VirtualFsInner<InnerFile<F>> for QuakePak<F> {
+    fn read(
+        &mut self,
+        path: &str,
+    ) -> Result<crate::util::virtual_fs::VirtualFsInnerEntry<InnerFile<F>>> {
+        self.fs.read(path)
+    }
+}
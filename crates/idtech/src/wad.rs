@@ -0,0 +1,416 @@
+// https://doomwiki.org/wiki/WAD
+
+use crate::graphics::{self, Palette};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::HashMap,
+    io::{Read, Seek},
+    sync::{Arc, Mutex},
+};
+use util::{
+    file_utils::{ArchiveFile, InnerFile},
+    tree_fs::TreeFs,
+    virtual_fs::{VirtualFsInner, VirtualFsInnerEntry},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WadType {
+    Iwad,
+    Pwad,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LumpKind {
+    Raw,
+    Flat,
+    Patch,
+    /// ZDoom pictures & flats can be PNGs, which are listed as they are.
+    Png,
+}
+
+const MAP_LUMPS: &[&str] = &[
+    "THINGS", "LINEDEFS", "SIDEDEFS", "VERTEXES", "SEGS", "SSECTORS", "NODES", "SECTORS", "REJECT",
+    "BLOCKMAP", "BEHAVIOR", "SCRIPTS", "TEXTMAP", "ZNODES", "DIALOGUE", "ENDMAP",
+];
+
+/// Lumps that are never pictures, even if they happen to look like one.
+const NON_PICTURE_LUMPS: &[&str] = &[
+    "PLAYPAL", "COLORMAP", "ENDOOM", "TEXTURE1", "TEXTURE2", "PNAMES", "GENMIDI", "DMXGUS",
+    "DMXGUSC",
+];
+
+/// `ExMy` & `MAPxx`, or anything followed by the first lump of a map.
+fn is_map_marker(name: &str, next: Option<&str>) -> bool {
+    let bytes = name.as_bytes();
+    let episode = bytes.len() == 4
+        && bytes[0] == b'E'
+        && bytes[1].is_ascii_digit()
+        && bytes[2] == b'M'
+        && bytes[3].is_ascii_digit();
+    let map =
+        bytes.len() == 5 && name.starts_with("MAP") && bytes[3..].iter().all(u8::is_ascii_digit);
+    episode || map || matches!(next, Some("THINGS" | "TEXTMAP"))
+}
+
+fn lump_name(name: [u8; 8]) -> String {
+    let length = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+    String::from_utf8_lossy(&name[..length]).into_owned()
+}
+
+/// At most `max_size` bytes from the start of the lump.
+fn read_lump<F: Read + Seek>(
+    file: &Arc<Mutex<F>>,
+    offset: u64,
+    size: u64,
+    max_size: u64,
+) -> Result<Vec<u8>> {
+    let mut data = vec![0u8; size.min(max_size) as usize];
+    InnerFile::new(Arc::clone(file), offset, size).read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Doom engine IWAD & PWAD.
+///
+/// Every lump is listed as a raw `.lmp` file, map lumps are grouped into a directory per map.
+/// Flats & lumps whose header looks like a picture get a `.png` decoded through `PLAYPAL` next to
+/// them, only the headers are read while loading.
+pub struct DoomWad<F: Read + Seek> {
+    wad_type: WadType,
+    palette: Palette,
+    fs: TreeFs<InnerFile<F>>,
+    kinds: HashMap<String, LumpKind>,
+}
+
+impl<F: Read + Seek> DoomWad<F> {
+    pub fn wad_type(&self) -> WadType {
+        self.wad_type
+    }
+
+    pub fn load(mut data: F) -> Result<Self> {
+        data.rewind()?;
+        let mut reader = util::reader::Reader::new_le(&mut data);
+
+        let wad_type = match &reader.read::<[u8; 4]>()? {
+            b"IWAD" => WadType::Iwad,
+            b"PWAD" => WadType::Pwad,
+            _ => return Err(anyhow!("Doom WAD identifier doesn't match")),
+        };
+        let num_lumps = reader.read::<i32>()?;
+        let directory_offset = reader.read::<i32>()?;
+        if num_lumps < 0 || directory_offset < 0 {
+            return Err(anyhow!("Doom WAD malformed header"));
+        }
+
+        reader.seek(std::io::SeekFrom::Start(directory_offset as u64))?;
+        let mut lumps: Vec<(String, u64, u64)> = Vec::new();
+        for _ in 0..num_lumps {
+            let offset = reader.read::<i32>()?;
+            let size = reader.read::<i32>()?;
+            let name = lump_name(reader.read::<[u8; 8]>()?);
+            if offset < 0 || size < 0 {
+                return Err(anyhow!("Doom WAD malformed lump \"{}\"", name));
+            }
+            lumps.push((name, offset as u64, size as u64));
+        }
+
+        let file = Arc::new(Mutex::new(data));
+
+        let palette = match lumps.iter().rev().find(|(name, _, _)| name == "PLAYPAL") {
+            // Only the first of the palettes is used.
+            Some((_, offset, size)) => {
+                Palette::from_bytes(&read_lump(&file, *offset, *size, 256 * 3)?)?
+            }
+            None => Palette::grayscale(),
+        };
+        // Unreadable lumps are listed raw, reading them reports the error.
+        let picture_kind = |offset: u64, size: u64| {
+            let header =
+                read_lump(&file, offset, size, graphics::MAX_PATCH_HEADER_SIZE as u64).ok()?;
+            if graphics::is_png(&header) {
+                Some(LumpKind::Png)
+            } else if graphics::is_patch_header(&header, size as usize) {
+                Some(LumpKind::Patch)
+            } else {
+                None
+            }
+        };
+
+        // Later lumps override earlier lumps with the same name, so keep only the last.
+        let mut entries: Vec<(String, InnerFile<F>)> = Vec::new();
+        let mut indices: HashMap<String, usize> = HashMap::new();
+        let mut kinds: HashMap<String, LumpKind> = HashMap::new();
+        let mut add = |path: String, kind: LumpKind, offset: u64, size: u64| {
+            let entry = (
+                path.clone(),
+                InnerFile::new(Arc::clone(&file), offset, size),
+            );
+            match indices.get(&path) {
+                Some(index) => entries[*index] = entry,
+                None => {
+                    indices.insert(path.clone(), entries.len());
+                    entries.push(entry);
+                }
+            }
+            kinds.insert(path, kind);
+        };
+
+        let mut namespace: Option<&str> = None;
+        let mut map: Option<&str> = None;
+        for (i, (name, offset, size)) in lumps.iter().enumerate() {
+            let next = lumps.get(i + 1).map(|(name, _, _)| name.as_str());
+            if is_map_marker(name, next) {
+                map = Some(name);
+                continue;
+            }
+            if let Some(map_name) = map {
+                if MAP_LUMPS.contains(&name.as_str()) {
+                    add(
+                        format!("maps/{}/{}.lmp", map_name, name),
+                        LumpKind::Raw,
+                        *offset,
+                        *size,
+                    );
+                    continue;
+                }
+            }
+            map = None;
+
+            match name.as_str() {
+                "F_START" | "FF_START" => namespace = Some("flats"),
+                "P_START" | "PP_START" => namespace = Some("patches"),
+                "S_START" | "SS_START" => namespace = Some("sprites"),
+                "F_END" | "FF_END" | "P_END" | "PP_END" | "S_END" | "SS_END" => namespace = None,
+                _ => {}
+            }
+            // Markers.
+            if *size == 0 {
+                continue;
+            }
+
+            // Pictures can also be outside of a namespace, like TITLEPIC & STBAR.
+            let directory = namespace.map_or(String::new(), |namespace| format!("{}/", namespace));
+            add(
+                format!("{}{}.lmp", directory, name),
+                LumpKind::Raw,
+                *offset,
+                *size,
+            );
+            let picture = match namespace {
+                _ if NON_PICTURE_LUMPS.contains(&name.as_str()) => None,
+                Some("flats") => picture_kind(*offset, *size)
+                    .filter(|kind| *kind == LumpKind::Png)
+                    .or(Some(LumpKind::Flat)),
+                _ => picture_kind(*offset, *size),
+            };
+            if let Some(kind) = picture {
+                add(format!("{}{}.png", directory, name), kind, *offset, *size);
+            }
+        }
+
+        Ok(Self {
+            wad_type,
+            palette,
+            fs: TreeFs::new(entries)?,
+            kinds,
+        })
+    }
+}
+
+impl<F: Read + Seek> VirtualFsInner<ArchiveFile<F>> for DoomWad<F> {
+    fn read(&mut self, path: &str) -> Result<VirtualFsInnerEntry<ArchiveFile<F>>> {
+        let mut file = match self.fs.read(path)? {
+            VirtualFsInnerEntry::File(file) => file,
+            VirtualFsInnerEntry::Directory(entries) => {
+                return Ok(VirtualFsInnerEntry::Directory(entries))
+            }
+        };
+
+        let kind = self
+            .kinds
+            .get(path.trim_start_matches('/'))
+            .copied()
+            .unwrap_or(LumpKind::Raw);
+        if kind == LumpKind::Raw || kind == LumpKind::Png {
+            return Ok(VirtualFsInnerEntry::File(ArchiveFile::Inner(file)));
+        }
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        let image = match kind {
+            LumpKind::Flat => graphics::decode_flat(&data, &self.palette)?,
+            LumpKind::Patch => graphics::decode_patch(&data, &self.palette)?,
+            LumpKind::Raw | LumpKind::Png => unreachable!(),
+        };
+        Ok(VirtualFsInnerEntry::File(ArchiveFile::memory(
+            graphics::encode_png(&image)?,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn wad(lumps: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut data = b"PWAD".to_vec();
+        data.extend_from_slice(&(lumps.len() as i32).to_le_bytes());
+        let directory_offset = 12 + lumps.iter().map(|(_, lump)| lump.len()).sum::<usize>();
+        data.extend_from_slice(&(directory_offset as i32).to_le_bytes());
+        let mut directory = Vec::new();
+        for (name, lump) in lumps {
+            directory.extend_from_slice(&(data.len() as i32).to_le_bytes());
+            directory.extend_from_slice(&(lump.len() as i32).to_le_bytes());
+            let mut name_bytes = [0u8; 8];
+            name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+            directory.extend_from_slice(&name_bytes);
+            data.extend_from_slice(lump);
+        }
+        data.extend_from_slice(&directory);
+        data
+    }
+
+    fn list(wad: &mut DoomWad<Cursor<Vec<u8>>>, path: &str) -> Vec<String> {
+        match wad.read(path).unwrap() {
+            VirtualFsInnerEntry::Directory(entries) => entries,
+            VirtualFsInnerEntry::File(_) => panic!("{} is not a directory", path),
+        }
+    }
+
+    #[test]
+    fn map_lumps_are_grouped() {
+        let data = wad(&[
+            ("MAP01", &[]),
+            ("THINGS", &[1; 10]),
+            ("LINEDEFS", &[2; 14]),
+            ("REJECT", &[]),
+            ("BLOCKMAP", &[]),
+            ("SECTORS", &[3; 26]),
+            ("DEMO1", &[4; 8]),
+            ("MYMAP", &[]),
+            ("TEXTMAP", &[5; 4]),
+            ("ENDMAP", &[]),
+        ]);
+        let mut wad = DoomWad::load(Cursor::new(data)).unwrap();
+        assert_eq!(wad.wad_type(), WadType::Pwad);
+
+        let mut map = list(&mut wad, "maps/MAP01");
+        map.sort();
+        assert_eq!(
+            map,
+            [
+                "BLOCKMAP.lmp",
+                "LINEDEFS.lmp",
+                "REJECT.lmp",
+                "SECTORS.lmp",
+                "THINGS.lmp"
+            ]
+        );
+        let mut map = list(&mut wad, "maps/MYMAP");
+        map.sort();
+        assert_eq!(map, ["ENDMAP.lmp", "TEXTMAP.lmp"]);
+        assert!(list(&mut wad, "").contains(&"DEMO1.lmp".to_owned()));
+    }
+
+    #[test]
+    fn later_lumps_override() {
+        let data = wad(&[("DEMO1", &[1; 4]), ("DEMO1", &[2; 8])]);
+        let mut wad = DoomWad::load(Cursor::new(data)).unwrap();
+        assert_eq!(list(&mut wad, ""), ["DEMO1.lmp"]);
+        let VirtualFsInnerEntry::File(mut file) = wad.read("DEMO1.lmp").unwrap() else {
+            panic!("DEMO1.lmp is not a file");
+        };
+        let mut lump = Vec::new();
+        file.read_to_end(&mut lump).unwrap();
+        assert_eq!(lump, [2; 8]);
+    }
+
+    fn read(wad: &mut DoomWad<Cursor<Vec<u8>>>, path: &str) -> Vec<u8> {
+        let VirtualFsInnerEntry::File(mut file) = wad.read(path).unwrap() else {
+            panic!("{} is not a file", path);
+        };
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn pictures_are_listed_next_to_raw_lumps() {
+        // 1x1 picture, a single post of one pixel.
+        let patch: &[u8] = &[1, 0, 1, 0, 0, 0, 0, 0, 12, 0, 0, 0, 0, 1, 0, 5, 0, 0xFF];
+        let png: &[u8] = b"\x89PNG\r\n\x1A\nnot really";
+        let data = wad(&[
+            ("TITLEPIC", patch),
+            ("DEMO1", &[4; 8]),
+            ("P_START", &[]),
+            ("WALL00_1", patch),
+            ("ZWALL", png),
+            ("NOTPIC", &[0xFF; 8]),
+            ("P_END", &[]),
+            ("F_START", &[]),
+            ("FLOOR0_1", &[7; 4096]),
+            ("ZFLAT", png),
+            ("F_END", &[]),
+        ]);
+        let mut wad = DoomWad::load(Cursor::new(data)).unwrap();
+
+        let mut root = list(&mut wad, "");
+        root.sort();
+        assert_eq!(
+            root,
+            [
+                "DEMO1.lmp",
+                "TITLEPIC.lmp",
+                "TITLEPIC.png",
+                "flats",
+                "patches"
+            ]
+        );
+        let mut patches = list(&mut wad, "patches");
+        patches.sort();
+        assert_eq!(
+            patches,
+            [
+                "NOTPIC.lmp",
+                "WALL00_1.lmp",
+                "WALL00_1.png",
+                "ZWALL.lmp",
+                "ZWALL.png"
+            ]
+        );
+        let mut flats = list(&mut wad, "flats");
+        flats.sort();
+        assert_eq!(
+            flats,
+            ["FLOOR0_1.lmp", "FLOOR0_1.png", "ZFLAT.lmp", "ZFLAT.png"]
+        );
+
+        assert_eq!(read(&mut wad, "TITLEPIC.lmp"), patch);
+        assert!(graphics::is_png(&read(&mut wad, "TITLEPIC.png")));
+        assert!(graphics::is_png(&read(&mut wad, "flats/FLOOR0_1.png")));
+        assert_eq!(read(&mut wad, "patches/ZWALL.png"), png);
+        assert_eq!(read(&mut wad, "flats/ZFLAT.png"), png);
+    }
+
+    #[test]
+    fn malformed_lumps() {
+        let mut data = wad(&[("PLAYPAL", &[1; 768 * 2])]);
+        let size = data.len() - 12;
+        // Only the first palette is read, whatever size the lump claims to be.
+        data[size..(size + 4)].copy_from_slice(&i32::MAX.to_le_bytes());
+        assert!(DoomWad::load(Cursor::new(data.clone())).is_ok());
+        data[size..(size + 4)].copy_from_slice(&(-1i32).to_le_bytes());
+        assert!(DoomWad::load(Cursor::new(data.clone())).is_err());
+        data[size..(size + 4)].copy_from_slice(&100i32.to_le_bytes());
+        assert!(DoomWad::load(Cursor::new(data)).is_err());
+    }
+
+    #[test]
+    fn truncated_directory() {
+        let mut data = wad(&[("DEMO1", &[1; 4]), ("DEMO2", &[2; 4])]);
+        data.truncate(data.len() - 4);
+        assert!(DoomWad::load(Cursor::new(data)).is_err());
+        assert!(DoomWad::load(Cursor::new(b"PWAD".to_vec())).is_err());
+        assert!(DoomWad::load(Cursor::new(b"NOTAWAD!".to_vec())).is_err());
+    }
+}
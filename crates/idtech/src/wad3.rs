@@ -0,0 +1,238 @@
+// https://twhl.info/wiki/page/Specification:_WAD3
+// https://www.gamers.org/dEngine/quake/spec/quake-spec34/qkspec_7.htm
+
+use crate::graphics::{self, Palette};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::HashMap,
+    io::{Read, Seek},
+    sync::{Arc, Mutex},
+};
+use util::{
+    file_utils::{ArchiveFile, InnerFile},
+    tree_fs::TreeFs,
+    virtual_fs::{VirtualFsInner, VirtualFsInnerEntry},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureWadVersion {
+    /// Quake
+    Wad2,
+    /// Half-Life
+    Wad3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LumpKind {
+    Raw,
+    Picture,
+    MipTexture,
+}
+
+const TYPE_PALETTE: u8 = 0x40;
+const TYPE_PICTURE: u8 = 0x42;
+const TYPE_MIPTEX_WAD3: u8 = 0x43;
+const TYPE_MIPTEX_WAD2: u8 = 0x44;
+
+fn lump_name(name: [u8; 16]) -> String {
+    let length = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+    String::from_utf8_lossy(&name[..length]).into_owned()
+}
+
+/// Quake WAD2 & Half-Life WAD3 texture archives.
+///
+/// Mip textures & pictures are decoded & listed as `.png` files. Half-Life textures have their own
+/// palette, Quake textures use the `PALETTE` lump if the archive has one.
+pub struct TextureWad<F: Read + Seek> {
+    version: TextureWadVersion,
+    palette: Option<Palette>,
+    fs: TreeFs<InnerFile<F>>,
+    kinds: HashMap<String, LumpKind>,
+}
+
+impl<F: Read + Seek> TextureWad<F> {
+    pub fn version(&self) -> TextureWadVersion {
+        self.version
+    }
+
+    pub fn load(mut data: F) -> Result<Self> {
+        data.rewind()?;
+        let mut reader = util::reader::Reader::new_le(&mut data);
+
+        let version = match &reader.read::<[u8; 4]>()? {
+            b"WAD2" => TextureWadVersion::Wad2,
+            b"WAD3" => TextureWadVersion::Wad3,
+            _ => return Err(anyhow!("Texture WAD identifier doesn't match")),
+        };
+        let num_entries = reader.read::<u32>()?;
+        let directory_offset = reader.read::<u32>()?;
+
+        reader.seek(std::io::SeekFrom::Start(directory_offset as u64))?;
+        let mut lumps: Vec<(String, u8, u64, u64)> = Vec::new();
+        for _ in 0..num_entries {
+            let offset = reader.read::<u32>()?;
+            let disk_size = reader.read::<u32>()?;
+            let _size = reader.read::<u32>()?;
+            let lump_type = reader.read::<u8>()?;
+            let compression = reader.read::<u8>()?;
+            reader.skip(2)?;
+            let name = lump_name(reader.read::<[u8; 16]>()?);
+            // Compression was never used by any tool, so those lumps are left out.
+            if compression != 0 {
+                continue;
+            }
+            lumps.push((name, lump_type, offset as u64, disk_size as u64));
+        }
+
+        let file = Arc::new(Mutex::new(data));
+
+        let mut palette = None;
+        if version == TextureWadVersion::Wad2 {
+            if let Some((_, _, offset, size)) = lumps
+                .iter()
+                .find(|(_, lump_type, _, _)| *lump_type == TYPE_PALETTE)
+            {
+                // Only the colors are read, whatever size the lump claims to be.
+                let mut data = vec![0u8; (*size).min(256 * 3) as usize];
+                InnerFile::new(Arc::clone(&file), *offset, *size).read_exact(&mut data)?;
+                palette = Some(Palette::from_bytes(&data)?);
+            }
+        }
+
+        let mut entries = Vec::new();
+        let mut kinds = HashMap::new();
+        for (name, lump_type, offset, size) in lumps {
+            let (path, kind) = match lump_type {
+                TYPE_PICTURE => (format!("{}.png", name), LumpKind::Picture),
+                TYPE_MIPTEX_WAD2 | TYPE_MIPTEX_WAD3 => {
+                    (format!("{}.png", name), LumpKind::MipTexture)
+                }
+                _ => (format!("{}.lmp", name), LumpKind::Raw),
+            };
+            if kinds.contains_key(&path) {
+                continue;
+            }
+            entries.push((
+                path.clone(),
+                InnerFile::new(Arc::clone(&file), offset, size),
+            ));
+            kinds.insert(path, kind);
+        }
+
+        Ok(Self {
+            version,
+            palette,
+            fs: TreeFs::new(entries)?,
+            kinds,
+        })
+    }
+
+    fn palette(&self) -> Option<&Palette> {
+        match self.version {
+            TextureWadVersion::Wad2 => Some(self.palette.as_ref().unwrap_or(&GRAYSCALE)),
+            TextureWadVersion::Wad3 => None,
+        }
+    }
+}
+
+static GRAYSCALE: std::sync::LazyLock<Palette> = std::sync::LazyLock::new(Palette::grayscale);
+
+impl<F: Read + Seek> VirtualFsInner<ArchiveFile<F>> for TextureWad<F> {
+    fn read(&mut self, path: &str) -> Result<VirtualFsInnerEntry<ArchiveFile<F>>> {
+        let mut file = match self.fs.read(path)? {
+            VirtualFsInnerEntry::File(file) => file,
+            VirtualFsInnerEntry::Directory(entries) => {
+                return Ok(VirtualFsInnerEntry::Directory(entries))
+            }
+        };
+
+        let kind = self
+            .kinds
+            .get(path.trim_start_matches('/'))
+            .copied()
+            .unwrap_or(LumpKind::Raw);
+        if kind == LumpKind::Raw {
+            return Ok(VirtualFsInnerEntry::File(ArchiveFile::Inner(file)));
+        }
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        let image = match kind {
+            LumpKind::Picture => graphics::decode_qpic(&data, self.palette())?,
+            LumpKind::MipTexture => graphics::decode_miptex(&data, self.palette())?,
+            LumpKind::Raw => unreachable!(),
+        };
+        Ok(VirtualFsInnerEntry::File(ArchiveFile::memory(
+            graphics::encode_png(&image)?,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// `(name, type, compression, data)`
+    fn wad2(lumps: &[(&str, u8, u8, &[u8])], palette_size: u32) -> Vec<u8> {
+        let mut data = b"WAD2".to_vec();
+        data.extend_from_slice(&(lumps.len() as u32).to_le_bytes());
+        let directory_offset = 12 + lumps.iter().map(|lump| lump.3.len()).sum::<usize>();
+        data.extend_from_slice(&(directory_offset as u32).to_le_bytes());
+        let mut directory = Vec::new();
+        for (name, lump_type, compression, lump) in lumps {
+            let size = if *lump_type == TYPE_PALETTE {
+                palette_size
+            } else {
+                lump.len() as u32
+            };
+            directory.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&size.to_le_bytes());
+            directory.extend_from_slice(&size.to_le_bytes());
+            directory.extend_from_slice(&[*lump_type, *compression, 0, 0]);
+            let mut name_bytes = [0u8; 16];
+            name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+            directory.extend_from_slice(&name_bytes);
+            data.extend_from_slice(lump);
+        }
+        data.extend_from_slice(&directory);
+        data
+    }
+
+    #[test]
+    fn lumps() {
+        // 1x1 picture.
+        let qpic: &[u8] = &[1, 0, 0, 0, 1, 0, 0, 0, 3];
+        let data = wad2(
+            &[
+                ("PALETTE", TYPE_PALETTE, 0, &[9; 768]),
+                ("CONCHARS", TYPE_PICTURE, 0, qpic),
+                ("PACKED", TYPE_PICTURE, 1, qpic),
+                ("OTHER", 0x41, 0, &[1, 2, 3]),
+            ],
+            // Bigger than the file, only the colors are read.
+            u32::MAX,
+        );
+        let mut wad = TextureWad::load(Cursor::new(data)).unwrap();
+        assert_eq!(wad.version(), TextureWadVersion::Wad2);
+
+        let VirtualFsInnerEntry::Directory(mut entries) = wad.read("").unwrap() else {
+            panic!("Root is not a directory");
+        };
+        entries.sort();
+        assert_eq!(entries, ["CONCHARS.png", "OTHER.lmp", "PALETTE.lmp"]);
+        let VirtualFsInnerEntry::File(mut file) = wad.read("CONCHARS.png").unwrap() else {
+            panic!("CONCHARS.png is not a file");
+        };
+        let mut png = Vec::new();
+        file.read_to_end(&mut png).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(image.get_pixel(0, 0).0, [9, 9, 9, 255]);
+    }
+
+    #[test]
+    fn truncated_palette() {
+        let data = wad2(&[("PALETTE", TYPE_PALETTE, 0, &[9; 100])], 768);
+        assert!(TextureWad::load(Cursor::new(data)).is_err());
+    }
+}
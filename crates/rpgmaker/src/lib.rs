@@ -0,0 +1,6 @@
+extern crate anyhow;
+extern crate serde_json;
+extern crate util;
+
+pub mod mv;
+pub mod rgss;
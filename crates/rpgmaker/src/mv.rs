@@ -0,0 +1,309 @@
+// https://github.com/Petschko/Java-RPG-Maker-MV-Decrypter
+
+use anyhow::{anyhow, Result};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek},
+    path::{Path, PathBuf},
+};
+
+pub type RpgMakerKey = [u8; 16];
+
+/// Header every encrypted RPG Maker MV & MZ asset starts with.
+pub const ENCRYPTED_HEADER: [u8; 16] = *b"RPGMV\0\0\0\0\x03\x01\0\0\0\0\0";
+const ENCRYPTED_HEADER_SIZE: u64 = ENCRYPTED_HEADER.len() as u64;
+
+const PNG_HEADER: [u8; 16] = *b"\x89PNG\r\n\x1A\n\0\0\0\x0DIHDR";
+
+/// (Encrypted extension, Decrypted extension)
+const EXTENSIONS: &[(&str, &str)] = &[
+    // RPG Maker MV
+    ("rpgmvp", "png"),
+    ("rpgmvo", "ogg"),
+    ("rpgmvm", "m4a"),
+    // RPG Maker MZ
+    ("png_", "png"),
+    ("ogg_", "ogg"),
+    ("m4a_", "m4a"),
+];
+
+pub fn decrypted_extension(extension: &str) -> Option<&'static str> {
+    EXTENSIONS
+        .iter()
+        .find(|(encrypted, _)| encrypted.eq_ignore_ascii_case(extension))
+        .map(|(_, decrypted)| *decrypted)
+}
+
+/// Extension of the file after decryption, if the filename is an encrypted asset.
+pub fn decrypted_filename_extension(filename: &str) -> Option<&'static str> {
+    Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(decrypted_extension)
+}
+
+pub fn parse_key(key: &str) -> Result<RpgMakerKey> {
+    util::decode_hex(key)?
+        .try_into()
+        .map_err(|_| anyhow!("RPG Maker encryption key must be 16 bytes"))
+}
+
+/// Images are always PNG, so the key can be recovered from the known PNG header.
+pub fn recover_png_key(mut data: impl Read + Seek) -> Result<RpgMakerKey> {
+    data.rewind()?;
+    let mut reader = util::reader::Reader::new_le(&mut data);
+
+    if reader.read::<[u8; 16]>()? != ENCRYPTED_HEADER {
+        return Err(anyhow!("RPG Maker encrypted file header doesn't match"));
+    }
+
+    let encrypted = reader.read::<[u8; 16]>()?;
+    let mut key = [0u8; 16];
+    for i in 0..key.len() {
+        key[i] = encrypted[i] ^ PNG_HEADER[i];
+    }
+    Ok(key)
+}
+
+/// A possibly encrypted RPG Maker MV & MZ asset.
+///
+/// Encrypted assets have a 16 byte header, followed by the original file with the first 16 bytes
+/// XOR'd with the key.
+pub struct RpgMakerFile<F: Read + Seek> {
+    file: F,
+    key: Option<RpgMakerKey>,
+    pointer: u64,
+}
+
+impl<F: Read + Seek> RpgMakerFile<F> {
+    pub fn new(file: F) -> Self {
+        Self {
+            file,
+            key: None,
+            pointer: 0,
+        }
+    }
+
+    pub fn new_encrypted(file: F, key: RpgMakerKey) -> Self {
+        Self {
+            file,
+            key: Some(key),
+            pointer: 0,
+        }
+    }
+
+    fn header_size(&self) -> u64 {
+        if self.key.is_some() {
+            ENCRYPTED_HEADER_SIZE
+        } else {
+            0
+        }
+    }
+}
+
+impl<F: Read + Seek> Read for RpgMakerFile<F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file
+            .seek(io::SeekFrom::Start(self.pointer + self.header_size()))?;
+        let bytes_read = self.file.read(buf)?;
+
+        if let Some(key) = &self.key {
+            for (i, byte) in buf[..bytes_read].iter_mut().enumerate() {
+                let position = (self.pointer as usize) + i;
+                if position >= key.len() {
+                    break;
+                }
+                *byte ^= key[position];
+            }
+        }
+
+        self.pointer += bytes_read as u64;
+        Ok(bytes_read)
+    }
+}
+
+impl<F: Read + Seek> Seek for RpgMakerFile<F> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let size = self
+            .file
+            .seek(io::SeekFrom::End(0))?
+            .saturating_sub(self.header_size());
+
+        let new_pointer = (match pos {
+            io::SeekFrom::Start(offset) => Some(offset),
+            io::SeekFrom::End(offset) => size.checked_add_signed(offset),
+            io::SeekFrom::Current(offset) => self.pointer.checked_add_signed(offset),
+        })
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "seek u64 overflow",
+        ))?;
+
+        self.pointer = new_pointer;
+        Ok(self.pointer)
+    }
+}
+
+impl<F: Read + Seek + Clone> Clone for RpgMakerFile<F> {
+    fn clone(&self) -> Self {
+        Self {
+            file: self.file.clone(),
+            key: self.key,
+            pointer: self.pointer,
+        }
+    }
+}
+
+/// Decrypt a standalone encrypted image, without needing to know the game's key.
+pub fn decrypt_image<F: Read + Seek>(mut data: F) -> Result<RpgMakerFile<F>> {
+    let key = recover_png_key(&mut data)?;
+    Ok(RpgMakerFile::new_encrypted(data, key))
+}
+
+/// A deployed RPG Maker MV or MZ game directory.
+///
+/// Encrypted assets are listed with their decrypted extension & are decrypted when read.
+pub struct RpgMakerGame {
+    root: PathBuf,
+    key: Option<RpgMakerKey>,
+}
+
+impl RpgMakerGame {
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn key(&self) -> Option<&RpgMakerKey> {
+        self.key.as_ref()
+    }
+
+    /// Find the game directory, this is the directory that contains `data/System.json`.
+    pub fn locate<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+        let path = path.as_ref();
+        for root in [path.to_path_buf(), path.join("www")] {
+            if root.join("data").join("System.json").is_file() {
+                return Ok(root);
+            }
+        }
+        Err(anyhow!("Failed to locate RPG Maker game directory"))
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let root = RpgMakerGame::locate(path)?;
+
+        let system: serde_json::Value =
+            serde_json::from_reader(File::open(root.join("data").join("System.json"))?)?;
+
+        let key = match system.get("encryptionKey").and_then(|k| k.as_str()) {
+            Some(key) => Some(parse_key(key)?),
+            None => None,
+        };
+
+        let mut game = Self { root, key };
+        if game.key.is_none() {
+            // Some games strip the key from System.json, try to recover it from an image instead.
+            game.key = game.find_png_key()?;
+        }
+
+        Ok(game)
+    }
+
+    fn find_png_key(&self) -> Result<Option<RpgMakerKey>> {
+        fn find_recursive(directory: &Path) -> Result<Option<RpgMakerKey>> {
+            for entry in fs::read_dir(directory)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    if let Some(key) = find_recursive(&path)? {
+                        return Ok(Some(key));
+                    }
+                } else if path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .and_then(decrypted_extension)
+                    == Some("png")
+                {
+                    if let Ok(key) = recover_png_key(File::open(&path)?) {
+                        return Ok(Some(key));
+                    }
+                }
+            }
+            Ok(None)
+        }
+
+        let img = self.root.join("img");
+        if img.is_dir() {
+            find_recursive(&img)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn real_path(&self, path: &str) -> Option<(PathBuf, bool)> {
+        let path = self.root.join(path);
+        if path.exists() {
+            return Some((path, false));
+        }
+
+        let extension = path.extension()?.to_str()?;
+        EXTENSIONS
+            .iter()
+            .filter(|(_, decrypted)| *decrypted == extension)
+            .map(|(encrypted, _)| path.with_extension(encrypted))
+            .find(|path| path.is_file())
+            .map(|path| (path, true))
+    }
+}
+
+impl util::virtual_fs::VirtualFsInner<RpgMakerFile<File>> for RpgMakerGame {
+    fn read(
+        &mut self,
+        path: &str,
+    ) -> Result<util::virtual_fs::VirtualFsInnerEntry<RpgMakerFile<File>>> {
+        let (real_path, encrypted) = self
+            .real_path(path)
+            .ok_or(anyhow!("RPG Maker game file \"{}\" doesn't exist", path))?;
+
+        if real_path.is_dir() {
+            let mut entries = Vec::new();
+            for entry in fs::read_dir(&real_path)? {
+                let entry_path = entry?.path();
+                let Some(name) = entry_path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                let name = match entry_path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .and_then(decrypted_extension)
+                {
+                    Some(extension) if entry_path.is_file() => entry_path
+                        .with_extension(extension)
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or(name)
+                        .to_owned(),
+                    _ => name.to_owned(),
+                };
+                if !entries.contains(&name) {
+                    entries.push(name);
+                }
+            }
+            entries.sort();
+            return Ok(util::virtual_fs::VirtualFsInnerEntry::Directory(entries));
+        }
+
+        let file = File::open(&real_path)?;
+        if !encrypted {
+            return Ok(util::virtual_fs::VirtualFsInnerEntry::File(
+                RpgMakerFile::new(file),
+            ));
+        }
+
+        let key = match self.key {
+            Some(key) => key,
+            None => recover_png_key(&file)?,
+        };
+        Ok(util::virtual_fs::VirtualFsInnerEntry::File(
+            RpgMakerFile::new_encrypted(file, key),
+        ))
+    }
+}
@@ -0,0 +1,198 @@
+// https://github.com/uuksu/RPGMakerDecrypter/tree/master/RPGMakerDecrypter.Decrypter
+
+use anyhow::{anyhow, Result};
+use std::{
+    io::{self, Read, Seek},
+    sync::{Arc, Mutex},
+};
+use util::{file_utils::InnerFile, tree_fs::TreeFs};
+
+const RGSSAD_V1_KEY: u32 = 0xDEADCAFE;
+
+#[inline(always)]
+fn advance_key(key: u32) -> u32 {
+    key.wrapping_mul(7).wrapping_add(3)
+}
+
+/// A file inside of a RGSS archive.
+///
+/// File data is XOR encrypted 4 bytes at a time, with the key advancing after each 4 bytes.
+pub struct RgssFile<F: Read + Seek> {
+    inner: InnerFile<F>,
+    key: u32,
+    pointer: u64,
+    // Cached key for the current 4 byte chunk, so sequential reads don't recompute the whole key chain.
+    chunk: u64,
+    chunk_key: u32,
+}
+
+impl<F: Read + Seek> RgssFile<F> {
+    pub fn new(file: Arc<Mutex<F>>, offset: u64, size: u64, key: u32) -> Self {
+        Self {
+            inner: InnerFile::new(file, offset, size),
+            key,
+            pointer: 0,
+            chunk: 0,
+            chunk_key: key,
+        }
+    }
+
+    fn key_for_chunk(&mut self, chunk: u64) -> u32 {
+        if chunk < self.chunk {
+            self.chunk = 0;
+            self.chunk_key = self.key;
+        }
+        while self.chunk < chunk {
+            self.chunk_key = advance_key(self.chunk_key);
+            self.chunk += 1;
+        }
+        self.chunk_key
+    }
+}
+
+impl<F: Read + Seek> Read for RgssFile<F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.seek(io::SeekFrom::Start(self.pointer))?;
+        let bytes_read = self.inner.read(buf)?;
+
+        for (i, byte) in buf[..bytes_read].iter_mut().enumerate() {
+            let position = self.pointer + (i as u64);
+            let key = self.key_for_chunk(position >> 2);
+            *byte ^= (key >> ((position & 0b11) << 3)) as u8;
+        }
+
+        self.pointer += bytes_read as u64;
+        Ok(bytes_read)
+    }
+}
+
+impl<F: Read + Seek> Seek for RgssFile<F> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.pointer = self.inner.seek(pos)?;
+        Ok(self.pointer)
+    }
+}
+
+impl<F: Read + Seek> Clone for RgssFile<F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            key: self.key,
+            pointer: self.pointer,
+            chunk: self.chunk,
+            chunk_key: self.chunk_key,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RgssVersion {
+    /// RPG Maker XP `.rgssad` & RPG Maker VX `.rgss2a`
+    V1,
+    /// RPG Maker VX Ace `.rgss3a`
+    V3,
+}
+
+pub struct RgssArchive<F: Read + Seek> {
+    version: RgssVersion,
+    fs: TreeFs<RgssFile<F>>,
+}
+
+impl<F: Read + Seek> RgssArchive<F> {
+    pub fn version(&self) -> RgssVersion {
+        self.version
+    }
+
+    pub fn load(mut data: F) -> Result<Self> {
+        data.rewind()?;
+        let mut reader = crate::util::reader::Reader::new_le(&mut data);
+
+        if &reader.read::<[u8; 7]>()? != b"RGSSAD\0" {
+            return Err(anyhow!("RGSS archive identifier doesn't match"));
+        }
+
+        let version = match reader.read::<u8>()? {
+            1 => RgssVersion::V1,
+            3 => RgssVersion::V3,
+            version => return Err(anyhow!("RGSS archive version {} not supported", version)),
+        };
+
+        let mut entries: Vec<(String, u64, u64, u32)> = Vec::new();
+
+        match version {
+            RgssVersion::V1 => {
+                let size = reader.size()?;
+                let mut key = RGSSAD_V1_KEY;
+
+                while reader.position()? < size {
+                    let name_length = reader.read::<u32>()? ^ key;
+                    key = advance_key(key);
+
+                    let mut name = reader.read_buf(name_length as usize)?;
+                    for byte in name.iter_mut() {
+                        *byte ^= key as u8;
+                        key = advance_key(key);
+                    }
+
+                    let file_size = reader.read::<u32>()? ^ key;
+                    key = advance_key(key);
+
+                    let offset = reader.position()?;
+                    entries.push((String::from_utf8(name)?, offset, file_size as u64, key));
+                    reader.seek(std::io::SeekFrom::Current(file_size as i64))?;
+                }
+            }
+            RgssVersion::V3 => {
+                let key = reader.read::<u32>()?.wrapping_mul(9).wrapping_add(3);
+
+                loop {
+                    let offset = reader.read::<u32>()? ^ key;
+                    if offset == 0 {
+                        break;
+                    }
+                    let file_size = reader.read::<u32>()? ^ key;
+                    let file_key = reader.read::<u32>()? ^ key;
+                    let name_length = reader.read::<u32>()? ^ key;
+
+                    let mut name = reader.read_buf(name_length as usize)?;
+                    for (i, byte) in name.iter_mut().enumerate() {
+                        *byte ^= (key >> ((i & 0b11) << 3)) as u8;
+                    }
+
+                    entries.push((
+                        String::from_utf8(name)?,
+                        offset as u64,
+                        file_size as u64,
+                        file_key,
+                    ));
+                }
+            }
+        }
+
+        let file = Arc::new(Mutex::new(data));
+
+        Ok(Self {
+            version,
+            fs: TreeFs::new(
+                entries
+                    .into_iter()
+                    .map(|(path, offset, size, key)| {
+                        (
+                            path.replace('\\', "/"),
+                            RgssFile::new(Arc::clone(&file), offset, size, key),
+                        )
+                    })
+                    .collect(),
+            )?,
+        })
+    }
+}
+
+impl<F: Read + Seek> crate::util::virtual_fs::VirtualFsInner<RgssFile<F>> for RgssArchive<F> {
+    fn read(
+        &mut self,
+        path: &str,
+    ) -> Result<crate::util::virtual_fs::VirtualFsInnerEntry<RgssFile<F>>> {
+        self.fs.read(path)
+    }
+}
@@ -0,0 +1,227 @@
+// https://developer.valvesoftware.com/wiki/Closed_Captions
+//
+// Compiled caption dictionary. Entries are keyed by the CRC of the lowercase token name, so the
+// names have to come from elsewhere, usually the source .txt next to the .dat.
+
+use crate::keyvalues::KeyValues;
+use anyhow::{anyhow, Result};
+use std::{collections::HashMap, io::Read};
+
+#[derive(Debug, Clone)]
+pub struct CloseCaptionEntry {
+    pub hash: u32,
+    /// May contain formatting tags like <clr:255,0,0> & <I>.
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CloseCaptions {
+    version: u32,
+    entries: Vec<CloseCaptionEntry>,
+}
+
+const ENTRY_SIZE: usize = 12;
+
+impl CloseCaptions {
+    pub fn parse(data: &[u8]) -> Result<CloseCaptions> {
+        let int = |offset: usize| -> Result<u32> {
+            Ok(u32::from_le_bytes(
+                data.get(offset..(offset + 4))
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or(anyhow!("Close captions offset {} out of range", offset))?,
+            ))
+        };
+        if data.get(0..4) != Some(b"VCCD") {
+            return Err(anyhow!("Invalid close captions identifier"));
+        }
+        let version = int(4)?;
+        if version != 1 {
+            return Err(anyhow!("Close captions version {} not supported", version));
+        }
+        let block_size = int(12)? as usize;
+        let num_entries = int(16)? as usize;
+        let data_offset = int(20)? as usize;
+
+        let entries = (0..num_entries)
+            .map(|i| {
+                let entry = 24 + i * ENTRY_SIZE;
+                let block = int(entry + 4)? as usize;
+                let [o0, o1, l0, l1] = int(entry + 8)?.to_le_bytes();
+                let offset =
+                    data_offset + block * block_size + u16::from_le_bytes([o0, o1]) as usize;
+                let length = u16::from_le_bytes([l0, l1]) as usize;
+                let text = data
+                    .get(offset..(offset + length))
+                    .ok_or(anyhow!("Close captions entry out of range"))?;
+                Ok(CloseCaptionEntry {
+                    hash: int(entry)?,
+                    text: decode_ucs2(text),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CloseCaptions { version, entries })
+    }
+
+    pub fn load(mut data: impl Read) -> Result<CloseCaptions> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf)?;
+        CloseCaptions::parse(&buf)
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+    pub fn entries(&self) -> &[CloseCaptionEntry] {
+        &self.entries
+    }
+
+    /// Token names are case insensitive.
+    pub fn hash(token: &str) -> u32 {
+        crc32fast::hash(token.to_lowercase().as_bytes())
+    }
+
+    pub fn get(&self, token: &str) -> Option<&str> {
+        let hash = CloseCaptions::hash(token);
+        self.entries
+            .iter()
+            .find(|entry| entry.hash == hash)
+            .map(|entry| entry.text.as_str())
+    }
+
+    /// Maps hashes to names, names that aren't in the dictionary are skipped.
+    pub fn resolve_names<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> HashMap<u32, String> {
+        let mut resolved = HashMap::new();
+        for name in names {
+            let hash = CloseCaptions::hash(name);
+            if self.entries.iter().any(|entry| entry.hash == hash) {
+                resolved.insert(hash, name.to_owned());
+            }
+        }
+        resolved
+    }
+}
+
+/// Token names from the caption source file, `"lang" { "Tokens" { "name" "text" } }`.
+pub fn caption_source_tokens(source: &[u8]) -> Result<Vec<String>> {
+    let key_values = KeyValues::parse(&decode_text(source))?;
+    let tokens = key_values
+        .get_section("lang")
+        .and_then(|lang| lang.get_section("Tokens"))
+        .ok_or(anyhow!("Close captions source has no tokens"))?;
+    Ok(tokens.iter().map(|(key, _)| key.to_owned()).collect())
+}
+
+fn decode_ucs2(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Caption sources & other localization files are usually UCS-2 with a BOM.
+pub fn decode_text(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => decode_ucs2(rest),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        bytes => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ucs2(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .chain([0])
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    /// Two blocks of 64 bytes, the second entry is in the second block.
+    fn vccd(entries: &[(&str, &str)]) -> Vec<u8> {
+        const BLOCK_SIZE: usize = 64;
+        let data_offset = 24 + entries.len() * ENTRY_SIZE;
+        let mut data = Vec::new();
+        data.extend_from_slice(b"VCCD");
+        for int in [
+            1,
+            entries.len() as u32,
+            BLOCK_SIZE as u32,
+            entries.len() as u32,
+        ] {
+            data.extend_from_slice(&int.to_le_bytes());
+        }
+        data.extend_from_slice(&(data_offset as u32).to_le_bytes());
+
+        let mut blocks = vec![0u8; BLOCK_SIZE * entries.len()];
+        for (block, (token, text)) in entries.iter().enumerate() {
+            let text = ucs2(text);
+            let offset = 4u16;
+            let start = block * BLOCK_SIZE + offset as usize;
+            blocks[start..(start + text.len())].copy_from_slice(&text);
+            data.extend_from_slice(&CloseCaptions::hash(token).to_le_bytes());
+            data.extend_from_slice(&(block as u32).to_le_bytes());
+            data.extend_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(&(text.len() as u16).to_le_bytes());
+        }
+        data.extend_from_slice(&blocks);
+        data
+    }
+
+    #[test]
+    fn parse() {
+        let captions = CloseCaptions::parse(&vccd(&[
+            ("NPC_Citizen.Hello", "<clr:255,255,255>Hello."),
+            ("npc_citizen.bye", "Bye!"),
+        ]))
+        .unwrap();
+        assert_eq!(captions.version(), 1);
+        assert_eq!(captions.entries().len(), 2);
+        assert_eq!(
+            captions.get("npc_citizen.hello"),
+            Some("<clr:255,255,255>Hello.")
+        );
+        assert_eq!(captions.get("NPC_Citizen.Bye"), Some("Bye!"));
+        assert_eq!(captions.get("npc_citizen.unknown"), None);
+
+        let names = captions.resolve_names(["NPC_Citizen.Bye", "npc_citizen.unknown"]);
+        assert_eq!(names.len(), 1);
+        assert_eq!(
+            names
+                .get(&CloseCaptions::hash("npc_citizen.bye"))
+                .map(String::as_str),
+            Some("NPC_Citizen.Bye")
+        );
+    }
+
+    #[test]
+    fn corrupt() {
+        let mut data = vccd(&[("a", "Text")]);
+        assert!(CloseCaptions::parse(&data[..30]).is_err());
+        // Entry length past the end of the file.
+        data[24 + 10..24 + 12].copy_from_slice(&1000u16.to_le_bytes());
+        assert!(CloseCaptions::parse(&data).is_err());
+        data[4] = 2;
+        assert!(CloseCaptions::parse(&data).is_err());
+        assert!(CloseCaptions::parse(b"RIFF").is_err());
+    }
+
+    #[test]
+    fn source_tokens() {
+        let mut source = vec![0xFF, 0xFE];
+        source.extend(
+            "\"lang\" { \"Language\" \"english\" \"Tokens\" { \"a.b\" \"Text\" \"c\" \"More\" } }"
+                .encode_utf16()
+                .flat_map(|unit| unit.to_le_bytes()),
+        );
+        assert_eq!(caption_source_tokens(&source).unwrap(), vec!["a.b", "c"]);
+        assert!(caption_source_tokens(b"\"lang\" { }").is_err());
+    }
+}
@@ -0,0 +1,325 @@
+// https://developer.valvesoftware.com/wiki/KeyValues
+//
+// KeyValues1 text format. Keys are case insensitive & may be repeated, so entries are kept in
+// order instead of in a map.
+
+use anyhow::{anyhow, Result};
+use std::{iter::Peekable, str::Chars};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyValue {
+    String(String),
+    Section(KeyValues),
+}
+
+impl KeyValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            KeyValue::String(value) => Some(value),
+            KeyValue::Section(_) => None,
+        }
+    }
+
+    pub fn as_section(&self) -> Option<&KeyValues> {
+        match self {
+            KeyValue::String(_) => None,
+            KeyValue::Section(section) => Some(section),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyValueEntry {
+    pub key: String,
+    pub value: KeyValue,
+    /// Platform condition like `$X360` or `!$X360`, without the brackets.
+    pub condition: Option<String>,
+}
+
+impl KeyValueEntry {
+    /// Whether the entry applies on PC, unknown conditions are false like in Source.
+    pub fn is_active(&self) -> bool {
+        let Some(condition) = &self.condition else {
+            return true;
+        };
+        let (negated, define) = match condition.trim().strip_prefix('!') {
+            Some(define) => (true, define.trim()),
+            None => (false, condition.trim()),
+        };
+        let defined = ["$WIN32", "$WINDOWS"]
+            .iter()
+            .any(|pc| define.eq_ignore_ascii_case(pc));
+        defined != negated
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct KeyValues {
+    pub entries: Vec<KeyValueEntry>,
+}
+
+impl KeyValues {
+    pub fn parse(text: &str) -> Result<KeyValues> {
+        let mut tokens = Tokenizer {
+            chars: text.chars().peekable(),
+            line: 1,
+        };
+        KeyValues::parse_section(&mut tokens, false)
+    }
+
+    fn parse_section(tokens: &mut Tokenizer, nested: bool) -> Result<KeyValues> {
+        let mut entries = Vec::new();
+        loop {
+            let key = match tokens.next()? {
+                Some(Token::String(key)) => key,
+                Some(Token::Close) if nested => break,
+                None if !nested => break,
+                Some(Token::Open) | Some(Token::Close) => {
+                    return Err(anyhow!(
+                        "KeyValues unexpected brace on line {}",
+                        tokens.line
+                    ))
+                }
+                None => return Err(anyhow!("KeyValues unclosed section")),
+                Some(Token::Condition(_)) => {
+                    return Err(anyhow!(
+                        "KeyValues unexpected condition on line {}",
+                        tokens.line
+                    ))
+                }
+            };
+
+            let mut condition = None;
+            let value = match tokens.next()? {
+                Some(Token::String(value)) => KeyValue::String(value),
+                Some(Token::Open) => KeyValue::Section(KeyValues::parse_section(tokens, true)?),
+                Some(Token::Condition(section_condition)) => match tokens.next()? {
+                    Some(Token::Open) => {
+                        condition = Some(section_condition);
+                        KeyValue::Section(KeyValues::parse_section(tokens, true)?)
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "KeyValues expected section on line {}",
+                            tokens.line
+                        ))
+                    }
+                },
+                _ => return Err(anyhow!("KeyValues expected value on line {}", tokens.line)),
+            };
+
+            // Conditions like [$X360] after a value.
+            if matches!(value, KeyValue::String(_)) && tokens.peek_condition() {
+                if let Some(Token::Condition(value_condition)) = tokens.next()? {
+                    condition = Some(value_condition);
+                }
+            }
+
+            entries.push(KeyValueEntry {
+                key,
+                value,
+                condition,
+            });
+        }
+        Ok(KeyValues { entries })
+    }
+
+    /// Every entry in order, including ones whose condition is false on PC.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &KeyValue)> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.key.as_str(), &entry.value))
+    }
+
+    /// First value with the key that applies on PC, case insensitive.
+    pub fn get(&self, key: &str) -> Option<&KeyValue> {
+        self.entries
+            .iter()
+            .find(|entry| entry.key.eq_ignore_ascii_case(key) && entry.is_active())
+            .map(|entry| &entry.value)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|value| value.as_str())
+    }
+
+    pub fn get_section(&self, key: &str) -> Option<&KeyValues> {
+        self.get(key).and_then(|value| value.as_section())
+    }
+}
+
+enum Token {
+    String(String),
+    Open,
+    Close,
+    /// Platform condition like [$WIN32].
+    Condition(String),
+}
+
+struct Tokenizer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '\n' {
+                self.line += 1;
+                self.chars.next();
+            } else if c.is_whitespace() {
+                self.chars.next();
+            } else if c == '/' {
+                let mut lookahead = self.chars.clone();
+                lookahead.next();
+                if lookahead.peek() != Some(&'/') {
+                    break;
+                }
+                while self.chars.next_if(|&c| c != '\n').is_some() {}
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek_condition(&mut self) -> bool {
+        // Conditions are only on the same line as the value.
+        while self
+            .chars
+            .next_if(|&c| c.is_whitespace() && c != '\n')
+            .is_some()
+        {}
+        self.chars.peek() == Some(&'[')
+    }
+
+    fn next(&mut self) -> Result<Option<Token>> {
+        self.skip_whitespace();
+        let Some(c) = self.chars.next() else {
+            return Ok(None);
+        };
+        Ok(Some(match c {
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '[' => {
+                let mut condition = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some(']') => break,
+                        Some('\n') | None => {
+                            return Err(anyhow!("KeyValues unclosed condition"));
+                        }
+                        Some(c) => condition.push(c),
+                    }
+                }
+                Token::Condition(condition)
+            }
+            // Escape sequences are disabled for materials, paths often have backslashes.
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                self.line += 1;
+                            }
+                            string.push(c);
+                        }
+                        None => return Err(anyhow!("KeyValues unclosed string")),
+                    }
+                }
+                Token::String(string)
+            }
+            c => {
+                let mut string = String::from(c);
+                while let Some(c) = self
+                    .chars
+                    .next_if(|&c| !c.is_whitespace() && !matches!(c, '{' | '}' | '"' | '['))
+                {
+                    string.push(c);
+                }
+                Token::String(string)
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_sections() {
+        let key_values = KeyValues::parse(
+            r#"
+            "LightmappedGeneric" // Shader
+            {
+                "$basetexture" "brick\wall01"
+                $surfaceprop concrete
+                "$translucent" "1" [$WIN32]
+                "Proxies"
+                {
+                    "AnimatedTexture" { "animatedtexturevar" "$basetexture" }
+                }
+                "$envmap" "env_cubemap"
+                "$ENVMAP" "duplicate"
+            }
+            "#,
+        )
+        .unwrap();
+
+        let material = key_values.get_section("lightmappedgeneric").unwrap();
+        assert_eq!(material.get_str("$basetexture"), Some("brick\\wall01"));
+        assert_eq!(material.get_str("$SurfaceProp"), Some("concrete"));
+        assert_eq!(material.get_str("$translucent"), Some("1"));
+        assert_eq!(material.get_str("$envmap"), Some("env_cubemap"));
+        assert_eq!(material.iter().count(), 6);
+        let proxy = material
+            .get_section("proxies")
+            .and_then(|proxies| proxies.get_section("AnimatedTexture"))
+            .unwrap();
+        assert_eq!(proxy.get_str("animatedtexturevar"), Some("$basetexture"));
+    }
+
+    #[test]
+    fn conditional_section() {
+        let key_values = KeyValues::parse("\"a\" [$WIN32] { \"b\" \"c\" }").unwrap();
+        assert_eq!(
+            key_values.get_section("a").and_then(|a| a.get_str("b")),
+            Some("c")
+        );
+        let key_values = KeyValues::parse("\"a\" [$X360] { \"b\" \"c\" }").unwrap();
+        assert_eq!(key_values.get_section("a"), None);
+        assert_eq!(key_values.iter().count(), 1);
+    }
+
+    #[test]
+    fn conditional_values() {
+        let key_values = KeyValues::parse(
+            r#"
+            "$x" "a" [$X360]
+            "$x" "b" [!$X360]
+            "$y" "c" [$PS3]
+            "$y" "d" [$OSX]
+            "$y" "e" [$WIN32]
+            "$z" "f" [!$WIN32]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(key_values.get_str("$x"), Some("b"));
+        assert_eq!(key_values.get_str("$y"), Some("e"));
+        assert_eq!(key_values.get_str("$z"), None);
+        assert_eq!(key_values.entries[0].condition.as_deref(), Some("$X360"));
+        assert_eq!(key_values.iter().count(), 6);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(KeyValues::parse("\"a\" { \"b\" \"c\"").is_err());
+        assert!(KeyValues::parse("\"a\" \"b\" }").is_err());
+        assert!(KeyValues::parse("\"a\" \"b").is_err());
+        assert!(KeyValues::parse("\"a\"").is_err());
+        assert!(KeyValues::parse("\"a\" \"b\" [$WIN32").is_err());
+        assert_eq!(KeyValues::parse("").unwrap(), KeyValues::default());
+    }
+}
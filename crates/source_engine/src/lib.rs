@@ -2,10 +2,19 @@
 
 extern crate anyhow;
 extern crate bitflags;
+extern crate crc32fast;
+extern crate half;
 extern crate image;
 extern crate rayon;
 extern crate regex;
 extern crate util;
 
+pub mod closecaption;
+pub mod keyvalues;
+pub mod mdl;
+pub mod sound;
+pub mod vmt;
 pub mod vpk;
 pub mod vtf;
+pub mod vtx;
+pub mod vvd;
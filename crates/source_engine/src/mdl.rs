@@ -0,0 +1,598 @@
+// https://developer.valvesoftware.com/wiki/MDL_(Source)
+// https://github.com/ValveSoftware/source-sdk-2013/blob/master/sp/src/public/studio.h
+//
+// Only the parts needed for the mesh & metadata are read, animations & physics are skipped.
+// The mesh data is in the .vvd & .dx90.vtx files next to the .mdl.
+
+use crate::{
+    vtx::Vtx,
+    vvd::{Vvd, VvdVertex},
+};
+use anyhow::{anyhow, Result};
+use std::{collections::HashMap, io::Read};
+use util::mesh::{Mesh, Primitive};
+
+/// Random access into a whole file, model files are full of offsets.
+pub(crate) struct ByteSlice<'a>(pub &'a [u8]);
+
+impl<'a> ByteSlice<'a> {
+    pub fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N]> {
+        self.0
+            .get(offset..(offset + N))
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(anyhow!("Model data offset {} out of range", offset))
+    }
+    pub fn u8(&self, offset: usize) -> Result<u8> {
+        Ok(self.bytes::<1>(offset)?[0])
+    }
+    pub fn u16(&self, offset: usize) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(offset)?))
+    }
+    pub fn i32(&self, offset: usize) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(offset)?))
+    }
+    pub fn f32(&self, offset: usize) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes(offset)?))
+    }
+    pub fn vector<const N: usize>(&self, offset: usize) -> Result<[f32; N]> {
+        let mut vector = [0.0; N];
+        for (i, v) in vector.iter_mut().enumerate() {
+            *v = self.f32(offset + i * 4)?;
+        }
+        Ok(vector)
+    }
+    /// Offsets & counts are stored as signed ints.
+    pub fn offset(&self, offset: usize) -> Result<usize> {
+        usize::try_from(self.i32(offset)?).map_err(|_| anyhow!("Model data negative offset"))
+    }
+    /// Offset relative to `base`.
+    pub fn relative(&self, base: usize, offset: usize) -> Result<usize> {
+        base.checked_add_signed(self.i32(offset)? as isize)
+            .ok_or(anyhow!("Model data negative offset"))
+    }
+    pub fn string(&self, offset: usize) -> Result<String> {
+        let bytes = self
+            .0
+            .get(offset..)
+            .ok_or(anyhow!("Model data offset {} out of range", offset))?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MdlBone {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub position: [f32; 3],
+    pub rotation: [f32; 4],
+}
+
+#[derive(Debug, Clone)]
+pub struct MdlMesh {
+    /// Index into the skin table.
+    pub material: usize,
+    /// Relative to the model's first vertex.
+    pub vertex_offset: usize,
+    pub num_vertices: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct MdlModel {
+    pub name: String,
+    /// Index of the model's first vertex in the .vvd.
+    pub vertex_index: usize,
+    pub num_vertices: usize,
+    pub meshes: Vec<MdlMesh>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MdlBodyPart {
+    pub name: String,
+    pub models: Vec<MdlModel>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mdl {
+    version: u32,
+    checksum: u32,
+    name: String,
+    flags: u32,
+    eye_position: [f32; 3],
+    hull: ([f32; 3], [f32; 3]),
+    mass: f32,
+    surface_prop: String,
+    key_values: Option<String>,
+    bones: Vec<MdlBone>,
+    textures: Vec<String>,
+    texture_directories: Vec<String>,
+    skins: Vec<Vec<usize>>,
+    body_parts: Vec<MdlBodyPart>,
+}
+
+const VERTEX_SIZE: usize = 48;
+
+impl Mdl {
+    pub fn parse(data: &[u8]) -> Result<Mdl> {
+        let data = ByteSlice(data);
+        if &data.bytes::<4>(0)? != b"IDST" {
+            return Err(anyhow!("Invalid MDL identifier"));
+        }
+        let version = data.i32(4)? as u32;
+        // 52+ moved things around in the header.
+        if !(44..=49).contains(&version) {
+            return Err(anyhow!("MDL version {} not supported", version));
+        }
+
+        let bone_index = data.offset(160)?;
+        let bones = (0..data.offset(156)?)
+            .map(|i| {
+                let bone = bone_index + i * 216;
+                let parent = data.i32(bone + 4)?;
+                Ok(MdlBone {
+                    name: data.string(data.relative(bone, bone)?)?,
+                    parent: usize::try_from(parent).ok(),
+                    position: data.vector(bone + 32)?,
+                    rotation: data.vector(bone + 44)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let texture_index = data.offset(208)?;
+        let textures = (0..data.offset(204)?)
+            .map(|i| {
+                let texture = texture_index + i * 64;
+                data.string(data.relative(texture, texture)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let texture_directory_index = data.offset(216)?;
+        let texture_directories = (0..data.offset(212)?)
+            .map(|i| data.string(data.offset(texture_directory_index + i * 4)?))
+            .collect::<Result<Vec<_>>>()?;
+
+        let num_skin_references = data.offset(220)?;
+        let skin_index = data.offset(228)?;
+        let num_skin_families = data.offset(224)?;
+        // Families without references take no space, so bound the count by the file instead.
+        let max_skin_families =
+            data.0.len().saturating_sub(skin_index) / (num_skin_references * 2).max(1);
+        if num_skin_families > max_skin_families {
+            return Err(anyhow!("MDL skin table out of range"));
+        }
+        let skins = (0..num_skin_families)
+            .map(|family| {
+                (0..num_skin_references)
+                    .map(|i| {
+                        Ok(data.u16(skin_index + (family * num_skin_references + i) * 2)? as usize)
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let body_part_index = data.offset(236)?;
+        let body_parts = (0..data.offset(232)?)
+            .map(|i| {
+                let body_part = body_part_index + i * 16;
+                let model_index = data.relative(body_part, body_part + 12)?;
+                let models = (0..data.offset(body_part + 4)?)
+                    .map(|i| {
+                        let model = model_index + i * 148;
+                        let mesh_index = data.relative(model, model + 76)?;
+                        let meshes = (0..data.offset(model + 72)?)
+                            .map(|i| {
+                                let mesh = mesh_index + i * 116;
+                                Ok(MdlMesh {
+                                    material: data.offset(mesh)?,
+                                    num_vertices: data.offset(mesh + 8)?,
+                                    vertex_offset: data.offset(mesh + 12)?,
+                                })
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        Ok(MdlModel {
+                            name: data.string(model)?.chars().take(64).collect(),
+                            num_vertices: data.offset(model + 80)?,
+                            vertex_index: data.offset(model + 84)? / VERTEX_SIZE,
+                            meshes,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(MdlBodyPart {
+                    name: data.string(data.relative(body_part, body_part)?)?,
+                    models,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let key_values_size = data.offset(316)?;
+        let key_values = if key_values_size > 0 {
+            Some(data.string(data.offset(312)?)?)
+        } else {
+            None
+        };
+
+        Ok(Mdl {
+            version,
+            checksum: data.i32(8)? as u32,
+            name: data.string(12)?.chars().take(64).collect(),
+            flags: data.i32(152)? as u32,
+            eye_position: data.vector(80)?,
+            hull: (data.vector(104)?, data.vector(116)?),
+            mass: data.f32(328)?,
+            surface_prop: data.string(data.offset(308)?)?,
+            key_values,
+            bones,
+            textures,
+            texture_directories,
+            skins,
+            body_parts,
+        })
+    }
+
+    pub fn load(mut data: impl Read) -> Result<Mdl> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf)?;
+        Mdl::parse(&buf)
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+    /// The .vvd & .vtx must have the same checksum.
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+    pub fn eye_position(&self) -> [f32; 3] {
+        self.eye_position
+    }
+    /// Minimum & maximum.
+    pub fn hull(&self) -> ([f32; 3], [f32; 3]) {
+        self.hull
+    }
+    pub fn mass(&self) -> f32 {
+        self.mass
+    }
+    pub fn surface_prop(&self) -> &str {
+        &self.surface_prop
+    }
+    /// KeyValues text, see [`crate::keyvalues::KeyValues`].
+    pub fn key_values(&self) -> Option<&str> {
+        self.key_values.as_deref()
+    }
+    pub fn bones(&self) -> &[MdlBone] {
+        &self.bones
+    }
+    /// Material names, they're found by searching the texture directories.
+    pub fn textures(&self) -> &[String] {
+        &self.textures
+    }
+    pub fn texture_directories(&self) -> &[String] {
+        &self.texture_directories
+    }
+    /// Each skin family maps skin references to textures.
+    pub fn skins(&self) -> &[Vec<usize>] {
+        &self.skins
+    }
+    pub fn body_parts(&self) -> &[MdlBodyPart] {
+        &self.body_parts
+    }
+
+    /// Possible paths of a material, one for each texture directory.
+    pub fn material_paths(&self, texture: usize) -> Vec<String> {
+        let Some(name) = self.textures.get(texture) else {
+            return Vec::new();
+        };
+        self.texture_directories
+            .iter()
+            .map(|directory| {
+                let path = format!("{}/{}", directory, name)
+                    .replace('\\', "/")
+                    .to_lowercase();
+                format!(
+                    "materials/{}.vmt",
+                    path.trim_start_matches('/').replace("//", "/")
+                )
+            })
+            .collect()
+    }
+
+    /// Builds the mesh for the picked model of each body part, materials are the first of
+    /// [`Mdl::material_paths`].
+    ///
+    /// Converted from Source's Z up to Y up & meshes are wound counter-clockwise.
+    pub fn mesh(
+        &self,
+        vvd: &Vvd,
+        vtx: &Vtx,
+        body_part_models: &[usize],
+        skin: usize,
+        lod: usize,
+    ) -> Result<Mesh> {
+        if vvd.checksum() != self.checksum || vtx.checksum() != self.checksum {
+            return Err(anyhow!("MDL, VVD & VTX checksums don't match"));
+        }
+        let skin_family = self
+            .skins
+            .get(skin)
+            .ok_or(anyhow!("MDL skin {} out of range", skin))?;
+
+        let mut mesh = Mesh::default();
+        let mut remap: HashMap<usize, u32> = HashMap::new();
+        let mut primitives: Vec<Primitive> = Vec::new();
+
+        for ((body_part, vtx_body_part), &model_index) in self
+            .body_parts
+            .iter()
+            .zip(vtx.body_parts())
+            .zip(body_part_models)
+        {
+            let (Some(model), Some(vtx_model)) = (
+                body_part.models.get(model_index),
+                vtx_body_part.models.get(model_index),
+            ) else {
+                continue;
+            };
+            let Some(vtx_lod) = vtx_model.lods.get(lod).or(vtx_model.lods.last()) else {
+                continue;
+            };
+
+            for (mdl_mesh, vtx_mesh) in model.meshes.iter().zip(&vtx_lod.meshes) {
+                let material = skin_family
+                    .get(mdl_mesh.material)
+                    .and_then(|&texture| self.material_paths(texture).into_iter().next());
+                let primitive = match primitives.iter().position(|p| p.material == material) {
+                    Some(index) => &mut primitives[index],
+                    None => {
+                        primitives.push(Primitive {
+                            material,
+                            indices: Vec::new(),
+                        });
+                        primitives.last_mut().unwrap()
+                    }
+                };
+
+                for strip_group in &vtx_mesh.strip_groups {
+                    let mut vertex = |index: u16| -> Result<u32> {
+                        let vtx_vertex = *strip_group
+                            .vertices
+                            .get(index as usize)
+                            .ok_or(anyhow!("VTX index out of range"))?;
+                        let vertex =
+                            model.vertex_index + mdl_mesh.vertex_offset + (vtx_vertex as usize);
+                        if let Some(&index) = remap.get(&vertex) {
+                            return Ok(index);
+                        }
+                        let VvdVertex {
+                            position: [x, y, z],
+                            normal: [nx, ny, nz],
+                            uv,
+                            ..
+                        } = *vvd
+                            .vertices()
+                            .get(vertex)
+                            .ok_or(anyhow!("VVD vertex out of range"))?;
+                        let index = mesh.positions.len() as u32;
+                        mesh.positions.push([x, z, -y]);
+                        mesh.normals.push([nx, nz, -ny]);
+                        mesh.uvs.push(uv);
+                        remap.insert(vertex, index);
+                        Ok(index)
+                    };
+                    for triangle in strip_group.triangles() {
+                        let [a, b, c] = triangle;
+                        // Source is wound clockwise.
+                        primitive
+                            .indices
+                            .extend([vertex(a)?, vertex(c)?, vertex(b)?]);
+                    }
+                }
+            }
+        }
+
+        mesh.primitives = primitives
+            .into_iter()
+            .filter(|primitive| !primitive.indices.is_empty())
+            .collect();
+        Ok(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKSUM: i32 = 1234;
+
+    fn put_i32(data: &mut [u8], offset: usize, value: i32) {
+        data[offset..(offset + 4)].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_f32(data: &mut [u8], offset: usize, value: f32) {
+        data[offset..(offset + 4)].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put_str(data: &mut [u8], offset: usize, value: &str) {
+        data[offset..(offset + value.len())].copy_from_slice(value.as_bytes());
+    }
+
+    /// One body part with one model & mesh of 3 vertices.
+    fn mdl() -> Vec<u8> {
+        let mut data = vec![0u8; 1024];
+        put_str(&mut data, 0, "IDST");
+        put_i32(&mut data, 4, 48);
+        put_i32(&mut data, 8, CHECKSUM);
+        put_str(&mut data, 12, "props/crate.mdl");
+        put_f32(&mut data, 328, 10.0);
+
+        // Texture & texture directory.
+        put_i32(&mut data, 204, 1);
+        put_i32(&mut data, 208, 400);
+        put_i32(&mut data, 400, 80);
+        put_str(&mut data, 480, "Crate");
+        put_i32(&mut data, 212, 1);
+        put_i32(&mut data, 216, 500);
+        put_i32(&mut data, 500, 510);
+        put_str(&mut data, 510, "models\\props\\");
+
+        // Single skin family.
+        put_i32(&mut data, 220, 1);
+        put_i32(&mut data, 224, 1);
+        put_i32(&mut data, 228, 540);
+
+        // Body part, model & mesh.
+        put_i32(&mut data, 232, 1);
+        put_i32(&mut data, 236, 560);
+        put_i32(&mut data, 560, 40);
+        put_str(&mut data, 600, "body");
+        put_i32(&mut data, 564, 1);
+        put_i32(&mut data, 572, 80);
+        put_str(&mut data, 640, "crate_ref");
+        put_i32(&mut data, 640 + 72, 1);
+        put_i32(&mut data, 640 + 76, 160);
+        put_i32(&mut data, 640 + 80, 3);
+        put_i32(&mut data, 800 + 8, 3);
+
+        put_i32(&mut data, 308, 920);
+        put_str(&mut data, 920, "wood_crate");
+        data
+    }
+
+    fn vvd() -> Vec<u8> {
+        let mut data = vec![0u8; 64 + 3 * VERTEX_SIZE];
+        put_str(&mut data, 0, "IDSV");
+        put_i32(&mut data, 4, 4);
+        put_i32(&mut data, 8, CHECKSUM);
+        put_i32(&mut data, 12, 1);
+        put_i32(&mut data, 16, 3);
+        put_i32(&mut data, 56, 64);
+        for (i, position) in [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 2.0]]
+            .iter()
+            .enumerate()
+        {
+            let vertex = 64 + i * VERTEX_SIZE;
+            for (j, v) in position.iter().enumerate() {
+                put_f32(&mut data, vertex + 16 + j * 4, *v);
+            }
+            put_f32(&mut data, vertex + 36, 1.0);
+            put_f32(&mut data, vertex + 40, i as f32);
+        }
+        data
+    }
+
+    fn vtx() -> Vec<u8> {
+        let mut data = vec![0u8; 158];
+        put_i32(&mut data, 0, 7);
+        put_i32(&mut data, 16, CHECKSUM);
+        put_i32(&mut data, 20, 1);
+        put_i32(&mut data, 28, 1);
+        put_i32(&mut data, 32, 36);
+        // Body part, model, LOD & mesh, each pointing to the next.
+        for (offset, next) in [(36, 44), (44, 52), (52, 64), (64, 73)] {
+            put_i32(&mut data, offset, 1);
+            put_i32(&mut data, offset + 4, next - offset as i32);
+        }
+        // Strip group, with its vertices, indices & strip.
+        put_i32(&mut data, 73, 3);
+        put_i32(&mut data, 77, 98 - 73);
+        put_i32(&mut data, 81, 3);
+        put_i32(&mut data, 85, 125 - 73);
+        put_i32(&mut data, 89, 1);
+        put_i32(&mut data, 93, 131 - 73);
+        for i in 0..3 {
+            data[98 + i * 9 + 4] = i as u8;
+            data[125 + i * 2] = i as u8;
+        }
+        put_i32(&mut data, 131, 3);
+        data[131 + 18] = 0x01;
+        data
+    }
+
+    #[test]
+    fn parse() {
+        let mdl = Mdl::parse(&mdl()).unwrap();
+        assert_eq!(mdl.version(), 48);
+        assert_eq!(mdl.name(), "props/crate.mdl");
+        assert_eq!(mdl.surface_prop(), "wood_crate");
+        assert_eq!(mdl.mass(), 10.0);
+        assert_eq!(mdl.skins(), [vec![0]]);
+        assert_eq!(mdl.material_paths(0), ["materials/models/props/crate.vmt"]);
+        assert_eq!(mdl.body_parts()[0].name, "body");
+        assert_eq!(mdl.body_parts()[0].models[0].name, "crate_ref");
+        assert_eq!(mdl.body_parts()[0].models[0].meshes[0].num_vertices, 3);
+    }
+
+    #[test]
+    fn mesh() {
+        let mdl = Mdl::parse(&mdl()).unwrap();
+        let vvd = Vvd::parse(&vvd()).unwrap();
+        let vtx = Vtx::parse(&vtx()).unwrap();
+        assert_eq!(vvd.vertices().len(), 3);
+
+        let mesh = mdl.mesh(&vvd, &vtx, &[0], 0, 0).unwrap();
+        // Z up to Y up, vertices are added as the clockwise triangles are flipped.
+        assert_eq!(
+            mesh.positions,
+            [[0.0, 0.0, 0.0], [0.0, 2.0, -1.0], [1.0, 0.0, 0.0]]
+        );
+        assert_eq!(mesh.normals[1], [0.0, 1.0, 0.0]);
+        assert_eq!(mesh.uvs[1], [2.0, 0.0]);
+        assert_eq!(mesh.primitives.len(), 1);
+        assert_eq!(
+            mesh.primitives[0].material.as_deref(),
+            Some("materials/models/props/crate.vmt")
+        );
+        assert_eq!(mesh.primitives[0].indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn mismatched_checksum() {
+        let mdl = Mdl::parse(&mdl()).unwrap();
+        let mut vvd = vvd();
+        put_i32(&mut vvd, 8, CHECKSUM + 1);
+        let vvd = Vvd::parse(&vvd).unwrap();
+        let vtx = Vtx::parse(&vtx()).unwrap();
+        assert!(mdl.mesh(&vvd, &vtx, &[0], 0, 0).is_err());
+    }
+
+    #[test]
+    fn truncated() {
+        let mdl = mdl();
+        assert!(Mdl::parse(&mdl[..300]).is_err());
+        assert!(Mdl::parse(&mdl[..850]).is_err());
+        let vvd = vvd();
+        assert!(Vvd::parse(&vvd[..(vvd.len() - 8)]).is_err());
+        let vtx = vtx();
+        assert!(Vtx::parse(&vtx[..130]).is_err());
+    }
+
+    #[test]
+    fn huge_counts() {
+        let mut mdl = mdl();
+        put_i32(&mut mdl, 220, 0);
+        put_i32(&mut mdl, 224, i32::MAX);
+        assert!(Mdl::parse(&mdl).is_err());
+
+        // Fixup table after the vertices, with far more root vertices than the file holds.
+        let mut vvd = vvd();
+        let fixup_start = vvd.len();
+        vvd.resize(fixup_start + 12, 0);
+        put_i32(&mut vvd, 16, i32::MAX);
+        put_i32(&mut vvd, 48, 1);
+        put_i32(&mut vvd, 52, fixup_start as i32);
+        put_i32(&mut vvd, fixup_start + 8, 3);
+        assert_eq!(Vvd::parse(&vvd).unwrap().vertices().len(), 3);
+    }
+
+    #[test]
+    fn vtx_index_out_of_range() {
+        let mut vtx = vtx();
+        vtx[125 + 4] = 3;
+        assert!(Vtx::parse(&vtx).is_err());
+    }
+}
@@ -0,0 +1,491 @@
+// https://developer.valvesoftware.com/wiki/Looping_a_Sound
+//
+// Metadata of the sound formats Source uses. Sounds loop from the first WAV cue point to the
+// end, or between the points of a sampler loop.
+
+use anyhow::{anyhow, Result};
+use std::io::{Read, Seek, SeekFrom};
+use util::reader::Reader;
+
+#[derive(Debug, Clone, Copy)]
+pub struct WavFormat {
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub byte_rate: u32,
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+}
+
+impl WavFormat {
+    pub fn format_name(&self) -> &'static str {
+        match self.format_tag {
+            0x0001 => "PCM",
+            0x0002 => "Microsoft ADPCM",
+            0x0003 => "IEEE float",
+            0x0011 => "IMA ADPCM",
+            0x0055 => "MP3",
+            0x0069 => "Xbox ADPCM",
+            0xFFFE => "Extensible",
+            _ => "Unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WavCue {
+    pub id: u32,
+    pub position: u32,
+    /// In samples, this is where Source starts the loop.
+    pub sample_offset: u32,
+    /// From the adtl list.
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WavLoop {
+    pub id: u32,
+    /// 0 forward, 1 ping-pong, 2 backward.
+    pub loop_type: u32,
+    pub start: u32,
+    pub end: u32,
+    /// 0 loops forever.
+    pub play_count: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct WavInfo {
+    pub format: WavFormat,
+    pub data_size: u32,
+    /// From the fact chunk, compressed formats need it to know the length.
+    pub fact_samples: Option<u32>,
+    pub cues: Vec<WavCue>,
+    pub loops: Vec<WavLoop>,
+    /// Every chunk identifier & size, in file order.
+    pub chunks: Vec<(String, u32)>,
+}
+
+impl WavInfo {
+    /// Only reads the chunk headers & metadata chunks, the sample data is skipped.
+    pub fn load<R: Read + Seek>(data: R) -> Result<WavInfo> {
+        let mut reader = Reader::new_le(data);
+        reader.rewind()?;
+        if reader.read::<[u8; 4]>()? != *b"RIFF" {
+            return Err(anyhow!("Invalid WAV identifier"));
+        }
+        let riff_end = (reader.read::<u32>()? as u64).saturating_add(8);
+        if reader.read::<[u8; 4]>()? != *b"WAVE" {
+            return Err(anyhow!("RIFF file is not WAVE"));
+        }
+        let riff_end = riff_end.min(reader.size()?);
+
+        let mut format = None;
+        let mut data_size = None;
+        let mut fact_samples = None;
+        let mut cues = Vec::new();
+        let mut loops = Vec::new();
+        let mut labels = Vec::new();
+        let mut chunks = Vec::new();
+
+        while reader.position()? + 8 <= riff_end {
+            let id = reader.read::<[u8; 4]>()?;
+            let size = reader.read::<u32>()?;
+            let start = reader.position()?;
+            chunks.push((String::from_utf8_lossy(&id).into_owned(), size));
+
+            match &id {
+                b"fmt " => {
+                    format = Some(WavFormat {
+                        format_tag: reader.read()?,
+                        channels: reader.read()?,
+                        sample_rate: reader.read()?,
+                        byte_rate: reader.read()?,
+                        block_align: reader.read()?,
+                        bits_per_sample: reader.read()?,
+                    });
+                }
+                b"data" => data_size = Some(size),
+                b"fact" => fact_samples = Some(reader.read::<u32>()?),
+                b"cue " => {
+                    for _ in 0..reader.read::<u32>()? {
+                        let id = reader.read::<u32>()?;
+                        let position = reader.read::<u32>()?;
+                        reader.read::<[u32; 3]>()?;
+                        cues.push(WavCue {
+                            id,
+                            position,
+                            sample_offset: reader.read()?,
+                            label: None,
+                        });
+                    }
+                }
+                b"smpl" => {
+                    reader.seek(SeekFrom::Current(28))?;
+                    let num_loops = reader.read::<u32>()?;
+                    reader.read::<u32>()?;
+                    for _ in 0..num_loops {
+                        let [id, loop_type, start, end, _fraction, play_count] =
+                            reader.read::<[u32; 6]>()?;
+                        loops.push(WavLoop {
+                            id,
+                            loop_type,
+                            start,
+                            end,
+                            play_count,
+                        });
+                    }
+                }
+                b"LIST" if size >= 4 && reader.read::<[u8; 4]>()? == *b"adtl" => {
+                    let end = start + size as u64;
+                    while reader.position()? + 8 <= end {
+                        let sub_id = reader.read::<[u8; 4]>()?;
+                        let sub_size = reader.read::<u32>()?;
+                        let sub_start = reader.position()?;
+                        if &sub_id == b"labl" && sub_size >= 4 {
+                            let cue = reader.read::<u32>()?;
+                            let text = reader.read_buf(sub_size as usize - 4)?;
+                            let end = text.iter().position(|&b| b == 0).unwrap_or(text.len());
+                            labels.push((cue, String::from_utf8_lossy(&text[..end]).into_owned()));
+                        }
+                        reader.seek(SeekFrom::Start(
+                            sub_start + sub_size as u64 + (sub_size as u64 & 1),
+                        ))?;
+                    }
+                }
+                _ => {}
+            }
+
+            // Chunks are padded to even sizes.
+            reader.seek(SeekFrom::Start(start + size as u64 + (size as u64 & 1)))?;
+        }
+
+        for (id, label) in labels {
+            if let Some(cue) = cues.iter_mut().find(|cue| cue.id == id) {
+                cue.label = Some(label);
+            }
+        }
+
+        Ok(WavInfo {
+            format: format.ok_or(anyhow!("WAV has no format chunk"))?,
+            data_size: data_size.ok_or(anyhow!("WAV has no data chunk"))?,
+            fact_samples,
+            cues,
+            loops,
+            chunks,
+        })
+    }
+
+    pub fn num_samples(&self) -> Option<u32> {
+        if let Some(samples) = self.fact_samples {
+            return Some(samples);
+        }
+        match self.format.format_tag {
+            0x0001 | 0x0003 | 0xFFFE if self.format.block_align > 0 => {
+                Some(self.data_size / self.format.block_align as u32)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn duration(&self) -> Option<f64> {
+        if self.format.sample_rate == 0 {
+            return None;
+        }
+        Some(self.num_samples()? as f64 / self.format.sample_rate as f64)
+    }
+
+    /// Sample Source starts looping from.
+    pub fn loop_start(&self) -> Option<u32> {
+        self.cues.first().map(|cue| cue.sample_offset)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mp3Info {
+    /// 1.0, 2.0 or 2.5.
+    pub version: &'static str,
+    pub layer: u8,
+    pub sample_rate: u32,
+    pub channels: u8,
+    /// Of the first frame in kbit/s, variable bitrate files change it per frame.
+    pub bitrate: u32,
+    pub num_frames: u32,
+    pub num_samples: u64,
+    /// Size of the ID3v2 tag at the start, if any.
+    pub id3v2_size: Option<u32>,
+    pub id3v1: bool,
+}
+
+struct Mp3Frame {
+    version: &'static str,
+    layer: u8,
+    bitrate: u32,
+    sample_rate: u32,
+    channels: u8,
+    samples: u32,
+    length: usize,
+}
+
+fn mp3_frame(header: [u8; 4]) -> Option<Mp3Frame> {
+    let header = u32::from_be_bytes(header);
+    if header >> 21 != 0x7FF {
+        return None;
+    }
+    let version = (header >> 19) & 0b11;
+    let layer = match (header >> 17) & 0b11 {
+        0b11 => 1,
+        0b10 => 2,
+        0b01 => 3,
+        _ => return None,
+    };
+    let bitrate_index = ((header >> 12) & 0b1111) as usize;
+    let sample_rate_index = ((header >> 10) & 0b11) as usize;
+    let padding = (header >> 9) & 1;
+    let channels = if (header >> 6) & 0b11 == 0b11 { 1 } else { 2 };
+
+    const BITRATES_V1: [[u32; 15]; 3] = [
+        [
+            0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+        ],
+        [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+        ],
+        [
+            0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+        ],
+    ];
+    const BITRATES_V2: [[u32; 15]; 2] = [
+        [
+            0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+        ],
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+    ];
+    const SAMPLE_RATES: [u32; 3] = [44100, 48000, 32000];
+
+    if bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+        return None;
+    }
+    let (version, bitrate, sample_rate) = match version {
+        0b11 => (
+            "1.0",
+            BITRATES_V1[layer as usize - 1][bitrate_index],
+            SAMPLE_RATES[sample_rate_index],
+        ),
+        0b10 => (
+            "2.0",
+            BITRATES_V2[(layer != 1) as usize][bitrate_index],
+            SAMPLE_RATES[sample_rate_index] / 2,
+        ),
+        0b00 => (
+            "2.5",
+            BITRATES_V2[(layer != 1) as usize][bitrate_index],
+            SAMPLE_RATES[sample_rate_index] / 4,
+        ),
+        _ => return None,
+    };
+
+    let samples = match (layer, version) {
+        (1, _) => 384,
+        (3, "2.0" | "2.5") => 576,
+        _ => 1152,
+    };
+    let length = if layer == 1 {
+        (12 * bitrate * 1000 / sample_rate + padding) * 4
+    } else {
+        samples / 8 * bitrate * 1000 / sample_rate + padding
+    };
+
+    Some(Mp3Frame {
+        version,
+        layer,
+        bitrate,
+        sample_rate,
+        channels,
+        samples,
+        length: length as usize,
+    })
+}
+
+impl Mp3Info {
+    /// Counts every frame, so the length is exact for variable bitrate files too.
+    pub fn load(mut data: impl Read) -> Result<Mp3Info> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf)?;
+
+        let mut offset = 0;
+        let mut id3v2_size = None;
+        if buf.starts_with(b"ID3") && buf.len() >= 10 {
+            // Syncsafe integer, 7 bits per byte.
+            let size = buf[6..10]
+                .iter()
+                .fold(0u32, |size, &b| (size << 7) | (b & 0x7F) as u32);
+            let footer = if buf[5] & 0x10 != 0 { 10 } else { 0 };
+            offset = 10 + size as usize + footer;
+            id3v2_size = Some(offset as u32);
+        }
+        let id3v1 = buf.len() >= 128 && buf[buf.len() - 128..].starts_with(b"TAG");
+
+        let mut first: Option<Mp3Frame> = None;
+        let mut num_frames = 0;
+        let mut num_samples = 0;
+        while let Some(header) = buf.get(offset..(offset + 4)) {
+            let frame = mp3_frame(header.try_into().unwrap()).filter(|frame| {
+                // Before the first frame, the next frame must be valid too so junk isn't matched.
+                first.is_some()
+                    || buf
+                        .get((offset + frame.length)..(offset + frame.length + 4))
+                        .is_none_or(|next| mp3_frame(next.try_into().unwrap()).is_some())
+            });
+            let Some(frame) = frame else {
+                // Junk is only searched through before the first frame.
+                if first.is_none() {
+                    offset += 1;
+                    continue;
+                }
+                break;
+            };
+            offset += frame.length.max(1);
+            num_frames += 1;
+            num_samples += frame.samples as u64;
+            first.get_or_insert(frame);
+        }
+        let first = first.ok_or(anyhow!("No MP3 frames found"))?;
+
+        Ok(Mp3Info {
+            version: first.version,
+            layer: first.layer,
+            sample_rate: first.sample_rate,
+            channels: first.channels,
+            bitrate: first.bitrate,
+            num_frames,
+            num_samples,
+            id3v2_size,
+            id3v1,
+        })
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.num_samples as f64 / self.sample_rate as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn ints(ints: &[u32]) -> Vec<u8> {
+        ints.iter().flat_map(|int| int.to_le_bytes()).collect()
+    }
+
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut wav = b"RIFF".to_vec();
+        wav.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(&body);
+        wav
+    }
+
+    fn fmt() -> Vec<u8> {
+        // PCM, mono, 22050 Hz, 16 bit.
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&ints(&[22050, 44100]));
+        fmt.extend_from_slice(&2u16.to_le_bytes());
+        fmt.extend_from_slice(&16u16.to_le_bytes());
+        chunk(b"fmt ", &fmt)
+    }
+
+    #[test]
+    fn cue_and_loop() {
+        let cue = ints(&[7, 0, u32::from_le_bytes(*b"data"), 0, 0, 11025]);
+        let mut smpl = ints(&[0; 7]);
+        smpl.extend(ints(&[1, 0, 3, 0, 100, 2000, 0, 0]));
+        let mut adtl = b"adtl".to_vec();
+        let mut labl = ints(&[7]);
+        labl.extend_from_slice(b"Loop\0");
+        adtl.extend(chunk(b"labl", &labl));
+
+        let data = wav(&[
+            fmt(),
+            chunk(b"cue ", &[ints(&[1]), cue].concat()),
+            chunk(b"smpl", &smpl),
+            chunk(b"LIST", &adtl),
+            chunk(b"data", &[0; 44100]),
+        ]);
+        let info = WavInfo::load(Cursor::new(data)).unwrap();
+
+        assert_eq!(info.format.format_name(), "PCM");
+        assert_eq!(info.num_samples(), Some(22050));
+        assert_eq!(info.duration(), Some(1.0));
+        assert_eq!(info.loop_start(), Some(11025));
+        assert_eq!(info.cues[0].label.as_deref(), Some("Loop"));
+        assert_eq!(info.loops.len(), 1);
+        assert_eq!(info.loops[0].id, 3);
+        assert_eq!((info.loops[0].start, info.loops[0].end), (100, 2000));
+        let ids: Vec<&str> = info.chunks.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["fmt ", "cue ", "smpl", "LIST", "data"]);
+    }
+
+    #[test]
+    fn compressed_length() {
+        let data = wav(&[
+            fmt(),
+            chunk(b"fact", &ints(&[1234])),
+            chunk(b"data", &[0; 10]),
+        ]);
+        let mut info = WavInfo::load(Cursor::new(data)).unwrap();
+        assert_eq!(info.num_samples(), Some(1234));
+        info.fact_samples = None;
+        info.format.format_tag = 0x0002;
+        assert_eq!(info.num_samples(), None);
+    }
+
+    #[test]
+    fn invalid_wav() {
+        assert!(WavInfo::load(Cursor::new(wav(&[chunk(b"data", &[0; 4])]))).is_err());
+        assert!(WavInfo::load(Cursor::new(wav(&[fmt()]))).is_err());
+        let mut truncated = wav(&[fmt(), chunk(b"cue ", &ints(&[100])), chunk(b"data", &[])]);
+        truncated.truncate(48);
+        assert!(WavInfo::load(Cursor::new(truncated)).is_err());
+        assert!(WavInfo::load(Cursor::new(b"RIFF\0\0\0\0AVI ".to_vec())).is_err());
+    }
+
+    #[test]
+    fn mp3_frames() {
+        // MPEG 1 layer 3, 128 kbit/s, 44100 Hz, joint stereo, 417 bytes per frame.
+        let frame: Vec<u8> = [0xFF, 0xFB, 0x90, 0x64]
+            .into_iter()
+            .chain(std::iter::repeat_n(0, 413))
+            .collect();
+        let mut data = b"ID3\x04\0\0\0\0\0\x02\0\0".to_vec();
+        data.extend_from_slice(b"junk");
+        for _ in 0..3 {
+            data.extend_from_slice(&frame);
+        }
+        let info = Mp3Info::load(data.as_slice()).unwrap();
+
+        assert_eq!(info.id3v2_size, Some(12));
+        assert!(!info.id3v1);
+        assert_eq!((info.version, info.layer), ("1.0", 3));
+        assert_eq!(
+            (info.sample_rate, info.channels, info.bitrate),
+            (44100, 2, 128)
+        );
+        assert_eq!(info.num_frames, 3);
+        assert_eq!(info.num_samples, 3 * 1152);
+        assert!(Mp3Info::load([0u8; 64].as_slice()).is_err());
+    }
+}
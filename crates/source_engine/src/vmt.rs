@@ -0,0 +1,126 @@
+// https://developer.valvesoftware.com/wiki/Material
+
+use crate::keyvalues::{KeyValue, KeyValueEntry, KeyValues};
+use anyhow::{anyhow, Result};
+use std::io::Read;
+
+/// Parameters that are paths to textures, relative to the materials directory.
+pub const TEXTURE_PARAMETERS: [&str; 18] = [
+    "$basetexture",
+    "$basetexture2",
+    "$bumpmap",
+    "$bumpmap2",
+    "$normalmap",
+    "$detail",
+    "$envmap",
+    "$envmapmask",
+    "$selfillummask",
+    "$phongexponenttexture",
+    "$lightwarptexture",
+    "$blendmodulatetexture",
+    "$dudvmap",
+    "$refracttexture",
+    "$ambientoccltexture",
+    "$iris",
+    "$corneatexture",
+    "$hdrcompressedtexture",
+];
+
+#[derive(Debug, Clone)]
+pub struct Vmt {
+    shader: String,
+    parameters: KeyValues,
+}
+
+impl Vmt {
+    pub fn parse(text: &str) -> Result<Vmt> {
+        let key_values = KeyValues::parse(text)?;
+        let KeyValueEntry {
+            key: shader,
+            value: parameters,
+            ..
+        } = key_values
+            .entries
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("VMT has no shader"))?;
+        match parameters {
+            KeyValue::Section(parameters) => Ok(Vmt { shader, parameters }),
+            KeyValue::String(_) => Err(anyhow!("VMT shader has no parameters")),
+        }
+    }
+
+    pub fn load(mut data: impl Read) -> Result<Vmt> {
+        let mut text = String::new();
+        data.read_to_string(&mut text)?;
+        // Some editors save with a byte order mark.
+        Vmt::parse(text.trim_start_matches('\u{FEFF}'))
+    }
+
+    pub fn shader(&self) -> &str {
+        &self.shader
+    }
+
+    pub fn parameters(&self) -> &KeyValues {
+        &self.parameters
+    }
+
+    /// Texture parameters & the path of the VTF they reference.
+    pub fn textures(&self) -> impl Iterator<Item = (&str, String)> {
+        self.parameters.iter().filter_map(|(key, value)| {
+            let value = value.as_str()?;
+            TEXTURE_PARAMETERS
+                .iter()
+                .any(|parameter| parameter.eq_ignore_ascii_case(key))
+                .then(|| (key, texture_path(value)))
+        })
+    }
+}
+
+/// Full path of a texture referenced by a material, paths are case insensitive so it's lowercase.
+pub fn texture_path(texture: &str) -> String {
+    let texture = texture.replace('\\', "/").to_lowercase();
+    let texture = texture.trim_start_matches('/');
+    let texture = texture.strip_prefix("materials/").unwrap_or(texture);
+    if texture.ends_with(".vtf") {
+        format!("materials/{}", texture)
+    } else {
+        format!("materials/{}.vtf", texture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_paths() {
+        let vmt = Vmt::load(
+            "\u{FEFF}VertexLitGeneric { $basetexture \"Models\\Props/Crate\" $bumpmap \
+            \"materials/models/props/crate_normal.vtf\" $color \"[1 1 1]\" }"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(vmt.shader(), "VertexLitGeneric");
+        let textures: Vec<(&str, String)> = vmt.textures().collect();
+        assert_eq!(
+            textures,
+            [
+                (
+                    "$basetexture",
+                    "materials/models/props/crate.vtf".to_owned()
+                ),
+                (
+                    "$bumpmap",
+                    "materials/models/props/crate_normal.vtf".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_parameters() {
+        assert!(Vmt::parse("").is_err());
+        assert!(Vmt::parse("\"LightmappedGeneric\" \"oops\"").is_err());
+    }
+}
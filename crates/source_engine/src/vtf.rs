@@ -1,14 +1,14 @@
 use anyhow::{anyhow, Result};
 use bitflags::bitflags;
 use image::{
-    DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, LumaA, Pixel, Rgb, RgbImage, Rgba,
-    RgbaImage,
+    imageops::FilterType, DynamicImage, GenericImageView, GrayAlphaImage, GrayImage, ImageBuffer,
+    LumaA, Pixel, Rgb, RgbImage, Rgba, RgbaImage,
 };
 use std::{
     convert::TryInto,
-    io::{Read, Seek},
+    io::{Read, Seek, SeekFrom, Write},
 };
-use util::image_utils::SizeHint;
+use util::{image_utils::SizeHint, texture::bc};
 
 bitflags! {
     #[derive(Debug, Clone, Copy)]
@@ -72,7 +72,7 @@ pub enum TextureFormat {
     RGB565 = 4,
     I8 = 5,
     IA88 = 6,
-    P8 = 7, // ?????
+    P8 = 7,
     A8 = 8,
     RGB888_BLUESCREEN = 9,
     BGR888_BLUESCREEN = 10,
@@ -92,11 +92,23 @@ pub enum TextureFormat {
     RGBA16161616F = 24,
     RGBA16161616 = 25,
     UVLX8888 = 26,
+    R32F = 27,
+    RGB323232F = 28,
+    RGBA32323232F = 29,
+    NV_DST16 = 30,
+    NV_DST24 = 31,
+    NV_INTZ = 32,
+    NV_RAWZ = 33,
+    ATI_DST16 = 34,
+    ATI_DST24 = 35,
+    NV_NULL = 36,
+    ATI2N = 37,
+    ATI1N = 38,
 }
 
 impl TextureFormat {
     fn try_from(value: i32) -> Result<TextureFormat> {
-        if value < (TextureFormat::NONE as i32) || value > (TextureFormat::UVLX8888 as i32) {
+        if value < (TextureFormat::NONE as i32) || value > (TextureFormat::ATI1N as i32) {
             return Err(anyhow!("Texture with format invalid {}", value));
         }
         Ok(unsafe { std::mem::transmute(value) })
@@ -130,7 +142,7 @@ impl TextureFormat {
             TextureFormat::RGB565 => width * height * 2,
             TextureFormat::I8 => width * height,
             TextureFormat::IA88 => width * height * 2,
-            TextureFormat::P8 => 256 * 4 + width * height, // ?????
+            TextureFormat::P8 => width * height,
             TextureFormat::A8 => width * height,
             TextureFormat::RGB888_BLUESCREEN => width * height * 3,
             TextureFormat::BGR888_BLUESCREEN => width * height * 3,
@@ -150,6 +162,19 @@ impl TextureFormat {
             TextureFormat::RGBA16161616F => width * height * 8,
             TextureFormat::RGBA16161616 => width * height * 8,
             TextureFormat::UVLX8888 => width * height * 4,
+            TextureFormat::R32F => width * height * 4,
+            TextureFormat::RGB323232F => width * height * 12,
+            TextureFormat::RGBA32323232F => width * height * 16,
+            // Depth formats only exist on the GPU, they never have any data.
+            TextureFormat::NV_DST16
+            | TextureFormat::NV_DST24
+            | TextureFormat::NV_INTZ
+            | TextureFormat::NV_RAWZ
+            | TextureFormat::ATI_DST16
+            | TextureFormat::ATI_DST24
+            | TextureFormat::NV_NULL => 0,
+            TextureFormat::ATI2N => width.div_ceil(4) * height.div_ceil(4) * 16,
+            TextureFormat::ATI1N => width.div_ceil(4) * height.div_ceil(4) * 8,
         }
     }
 }
@@ -292,7 +317,10 @@ impl VtfTexture {
             TextureFormat::IA88 => DynamicImage::ImageLumaA8(
                 GrayAlphaImage::from_raw(self.width, self.height, self.data.clone()).unwrap(),
             ),
-            TextureFormat::P8 => unsupported_format_image(), // ?????
+            // The palette is never stored, so the indices are shown as grayscale.
+            TextureFormat::P8 => DynamicImage::ImageLuma8(
+                GrayImage::from_raw(self.width, self.height, self.data.clone()).unwrap(),
+            ),
             // There's no such thing as ImageA8, So we just use ImageLumaA8 & set Luma to be 0.
             TextureFormat::A8 => DynamicImage::ImageLumaA8(swizzle_image(
                 &self.data,
@@ -318,7 +346,7 @@ impl VtfTexture {
                 self.height,
                 |c: &[u8; 4]| Rgba([c[3], c[0], c[1], c[2]]),
             )),
-            // FIXME: This may also be an HDR texture based on texture flags.
+            // May also be compressed HDR, see VtfTexture::to_compressed_hdr_image
             TextureFormat::BGRA8888 => DynamicImage::ImageRgba8(swizzle_image(
                 &self.data,
                 self.width,
@@ -416,17 +444,19 @@ impl VtfTexture {
             TextureFormat::UVWQ8888 => DynamicImage::ImageRgba8(
                 RgbaImage::from_raw(self.width, self.height, self.data.clone()).unwrap(),
             ),
-            TextureFormat::RGBA16161616F => DynamicImage::ImageRgba16(
-                // Is this supposed to be the same as RGBA16161616 ???
-                swizzle_image(&self.data, self.width, self.height, |c: &[u8; 8]| {
+            TextureFormat::RGBA16161616F => DynamicImage::ImageRgba32F(swizzle_image(
+                &self.data,
+                self.width,
+                self.height,
+                |c: &[u8; 8]| {
                     Rgba([
-                        u16::from_le_bytes([c[0], c[1]]),
-                        u16::from_le_bytes([c[2], c[3]]),
-                        u16::from_le_bytes([c[4], c[5]]),
-                        u16::from_le_bytes([c[6], c[7]]),
+                        half::f16::from_le_bytes([c[0], c[1]]).to_f32(),
+                        half::f16::from_le_bytes([c[2], c[3]]).to_f32(),
+                        half::f16::from_le_bytes([c[4], c[5]]).to_f32(),
+                        half::f16::from_le_bytes([c[6], c[7]]).to_f32(),
                     ])
-                }),
-            ),
+                },
+            )),
             TextureFormat::RGBA16161616 => DynamicImage::ImageRgba16(swizzle_image(
                 &self.data,
                 self.width,
@@ -443,12 +473,205 @@ impl VtfTexture {
             TextureFormat::UVLX8888 => DynamicImage::ImageRgba8(
                 RgbaImage::from_raw(self.width, self.height, self.data.clone()).unwrap(),
             ), // TODO: Probably warn for malformed because X component.
+            TextureFormat::R32F => DynamicImage::ImageRgb32F(swizzle_image(
+                &self.data,
+                self.width,
+                self.height,
+                |c: &[u8; 4]| {
+                    let r = f32::from_le_bytes(*c);
+                    Rgb([r, r, r])
+                },
+            )),
+            TextureFormat::RGB323232F => DynamicImage::ImageRgb32F(swizzle_image(
+                &self.data,
+                self.width,
+                self.height,
+                |c: &[u8; 12]| {
+                    Rgb([
+                        f32::from_le_bytes([c[0], c[1], c[2], c[3]]),
+                        f32::from_le_bytes([c[4], c[5], c[6], c[7]]),
+                        f32::from_le_bytes([c[8], c[9], c[10], c[11]]),
+                    ])
+                },
+            )),
+            TextureFormat::RGBA32323232F => DynamicImage::ImageRgba32F(swizzle_image(
+                &self.data,
+                self.width,
+                self.height,
+                |c: &[u8; 16]| {
+                    Rgba([
+                        f32::from_le_bytes([c[0], c[1], c[2], c[3]]),
+                        f32::from_le_bytes([c[4], c[5], c[6], c[7]]),
+                        f32::from_le_bytes([c[8], c[9], c[10], c[11]]),
+                        f32::from_le_bytes([c[12], c[13], c[14], c[15]]),
+                    ])
+                },
+            )),
+            TextureFormat::NV_DST16
+            | TextureFormat::NV_DST24
+            | TextureFormat::NV_INTZ
+            | TextureFormat::NV_RAWZ
+            | TextureFormat::ATI_DST16
+            | TextureFormat::ATI_DST24
+            | TextureFormat::NV_NULL => unsupported_format_image(),
+            TextureFormat::ATI2N => DynamicImage::ImageRgb8(crate::util::texture::bc::decode_bc5(
+                &self.data,
+                self.width(),
+                self.height(),
+                false,
+            )),
+            TextureFormat::ATI1N => DynamicImage::ImageLuma8(crate::util::texture::bc::decode_bc4(
+                &self.data,
+                self.width(),
+                self.height(),
+                false,
+            )),
         }
     }
+
+    /// Decodes BGRA8888 as compressed HDR, where alpha is a scale for the color.
+    ///
+    /// Nothing in the VTF marks a texture as compressed HDR, Source decides by the texture name
+    /// (usually ending in `_hdr`) & the material using it.
+    pub fn to_compressed_hdr_image(&self) -> Option<DynamicImage> {
+        if !matches!(self.format, TextureFormat::BGRA8888) {
+            return None;
+        }
+        let image: ImageBuffer<Rgba<f32>, Vec<f32>> =
+            ImageBuffer::from_fn(self.width, self.height, |x, y| {
+                let offset = ((y as usize) * (self.width as usize) + (x as usize)) * 4;
+                let c = &self.data[offset..(offset + 4)];
+                let scale = (c[3] as f32) * 16.0 / (255.0 * 255.0);
+                Rgba([
+                    (c[2] as f32) * scale,
+                    (c[1] as f32) * scale,
+                    (c[0] as f32) * scale,
+                    1.0,
+                ])
+            });
+        Some(DynamicImage::ImageRgba32F(image))
+    }
+
+    /// Encodes an image, not every format can be encoded.
+    pub fn from_image(image: &DynamicImage, format: TextureFormat) -> Result<VtfTexture> {
+        fn swizzle<const S: usize>(
+            image: &DynamicImage,
+            swizzle: fn(&Rgba<u8>) -> [u8; S],
+        ) -> Vec<u8> {
+            image.to_rgba8().pixels().flat_map(swizzle).collect()
+        }
+
+        // Inverse of extract in VtfTexture::to_image
+        #[inline]
+        fn compress(v: u8, offset: u8, length: u8) -> u16 {
+            let max = (1u32 << length) - 1;
+            ((((v as u32) * max + 127) / 255) as u16) << offset
+        }
+
+        #[inline]
+        fn bluescreen(c: &Rgba<u8>) -> [u8; 3] {
+            if c[3] < 128 {
+                [0, 0, 255]
+            } else {
+                [c[0], c[1], c[2]]
+            }
+        }
+
+        let data = match format {
+            TextureFormat::RGBA8888 | TextureFormat::UVWQ8888 | TextureFormat::UVLX8888 => {
+                image.to_rgba8().into_raw()
+            }
+            TextureFormat::ABGR8888 => swizzle(image, |c| [c[3], c[2], c[1], c[0]]),
+            TextureFormat::RGB888 => image.to_rgb8().into_raw(),
+            TextureFormat::BGR888 => swizzle(image, |c| [c[2], c[1], c[0]]),
+            TextureFormat::RGB565 => swizzle(image, |c| {
+                (compress(c[0], 0, 5) | compress(c[1], 5, 6) | compress(c[2], 11, 5)).to_le_bytes()
+            }),
+            TextureFormat::I8 => image.to_luma8().into_raw(),
+            TextureFormat::IA88 => image.to_luma_alpha8().into_raw(),
+            TextureFormat::A8 => swizzle(image, |c| [c[3]]),
+            TextureFormat::RGB888_BLUESCREEN => swizzle(image, bluescreen),
+            TextureFormat::BGR888_BLUESCREEN => swizzle(image, |c| {
+                let [r, g, b] = bluescreen(c);
+                [b, g, r]
+            }),
+            TextureFormat::ARGB8888 => swizzle(image, |c| [c[1], c[2], c[3], c[0]]),
+            TextureFormat::BGRA8888 => swizzle(image, |c| [c[2], c[1], c[0], c[3]]),
+            TextureFormat::BGRX8888 => swizzle(image, |c| [c[2], c[1], c[0], 0]),
+            TextureFormat::BGR565 => swizzle(image, |c| {
+                (compress(c[0], 11, 5) | compress(c[1], 5, 6) | compress(c[2], 0, 5)).to_le_bytes()
+            }),
+            TextureFormat::BGRX5551 => swizzle(image, |c| {
+                (compress(c[0], 10, 5) | compress(c[1], 5, 5) | compress(c[2], 0, 5)).to_le_bytes()
+            }),
+            TextureFormat::BGRA4444 => swizzle(image, |c| {
+                (compress(c[0], 8, 4)
+                    | compress(c[1], 4, 4)
+                    | compress(c[2], 0, 4)
+                    | compress(c[3], 12, 4))
+                .to_le_bytes()
+            }),
+            TextureFormat::BGRA5551 => swizzle(image, |c| {
+                (compress(c[0], 10, 5)
+                    | compress(c[1], 5, 5)
+                    | compress(c[2], 0, 5)
+                    | compress(c[3], 15, 1))
+                .to_le_bytes()
+            }),
+            TextureFormat::UV88 => swizzle(image, |c| [c[0], c[1]]),
+            TextureFormat::RGBA16161616F => image
+                .to_rgba32f()
+                .into_raw()
+                .into_iter()
+                .flat_map(|c| half::f16::from_f32(c).to_le_bytes())
+                .collect(),
+            TextureFormat::DXT1 => bc::encode_bc1(&image.to_rgba8(), false),
+            TextureFormat::DXT1_ONEBITALPHA => bc::encode_bc1(&image.to_rgba8(), true),
+            TextureFormat::DXT3 => bc::encode_bc2(&image.to_rgba8()),
+            TextureFormat::DXT5 => bc::encode_bc3(&image.to_rgba8()),
+            TextureFormat::ATI1N => bc::encode_bc4(&image.to_rgba8()),
+            TextureFormat::ATI2N => bc::encode_bc5(&image.to_rgb8()),
+            TextureFormat::RGBA16161616 => image
+                .to_rgba16()
+                .into_raw()
+                .into_iter()
+                .flat_map(u16::to_le_bytes)
+                .collect(),
+            TextureFormat::R32F => image
+                .to_rgb32f()
+                .pixels()
+                .flat_map(|c| c[0].to_le_bytes())
+                .collect(),
+            TextureFormat::RGB323232F => image
+                .to_rgb32f()
+                .into_raw()
+                .into_iter()
+                .flat_map(f32::to_le_bytes)
+                .collect(),
+            TextureFormat::RGBA32323232F => image
+                .to_rgba32f()
+                .into_raw()
+                .into_iter()
+                .flat_map(f32::to_le_bytes)
+                .collect(),
+            format => return Err(anyhow!("VTF format {:?} encoding not supported", format)),
+        };
+
+        Ok(VtfTexture::new(
+            image.width(),
+            image.height(),
+            format,
+            &data,
+        ))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Vtf {
+    version: [u32; 2],
+    flags: TextureFlags,
+    reflectivity: [f32; 3],
+    bumpmap_scale: f32,
     thumbnail: Option<VtfTexture>,
     format: TextureFormat,
     width: u32,
@@ -459,9 +682,37 @@ pub struct Vtf {
     faces: u8,
     slices: u16,
     textures: Vec<VtfTexture>,
+    resources: VtfResources,
 }
 
 impl Vtf {
+    pub fn version(&self) -> [u32; 2] {
+        self.version
+    }
+    pub fn flags(&self) -> TextureFlags {
+        self.flags
+    }
+    pub fn reflectivity(&self) -> [f32; 3] {
+        self.reflectivity
+    }
+    pub fn bumpmap_scale(&self) -> f32 {
+        self.bumpmap_scale
+    }
+    /// Only VTF 7.3+ has resources.
+    pub fn resources(&self) -> &VtfResources {
+        &self.resources
+    }
+
+    /// CRC32 of the high-res image data, used for the CRC resource of written textures.
+    ///
+    /// vtex stores the CRC of the source image instead, so this can't be used to verify textures.
+    pub fn compute_crc(&self) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        for texture in &self.textures {
+            hasher.update(&texture.data);
+        }
+        hasher.finalize()
+    }
     /// May have different format than Vtf::format()
     pub fn thumbnail(&self) -> Option<&VtfTexture> {
         self.thumbnail.as_ref()
@@ -481,6 +732,10 @@ impl Vtf {
     pub fn frames(&self) -> u32 {
         self.frames as u32
     }
+    /// Frame animations start on, frames are indexed in the order they are stored.
+    pub fn first_frame(&self) -> u32 {
+        self.first_frame as u32
+    }
     pub fn faces(&self) -> u32 {
         self.faces as u32
     }
@@ -498,7 +753,7 @@ impl Vtf {
         }
 
         let mipmap = (self.mipmaps() as usize) - 1 - (mipmap as usize);
-        let frame = ((frame + (self.first_frame as u32)) % self.frames()) as usize;
+        let frame = frame as usize;
         let face = face as usize;
         let slice = slice as usize;
 
@@ -530,44 +785,131 @@ impl Vtf {
         self.textures.into_iter().nth(index)
     }
 
-    pub fn load(mut data: impl Read + Seek) -> Result<Vtf> {
-        data.rewind()?;
-        let header = VtfHeader::load(&mut data)?;
+    pub fn load(data: impl Read + Seek) -> Result<Vtf> {
+        LazyVtf::load(data)?.into_vtf()
+    }
 
-        let mut thumbnail = None;
-        if let Some(lowres_offset) = header.lowres_offset {
-            data.seek(std::io::SeekFrom::Start(lowres_offset as u64))?;
-            thumbnail = Vtf::read_thumbnail(
-                &mut data,
-                header.lowres_format,
-                header.lowres_width as u32,
-                header.lowres_height as u32,
-            )?;
+    /// Writes the VTF, only versions 7.2 to 7.5 can be written.
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if !([7, 2]..=[7, 5]).contains(&self.version) {
+            return Err(anyhow!(
+                "VTF version {}.{} writing not supported",
+                self.version[0],
+                self.version[1]
+            ));
         }
-        data.seek(std::io::SeekFrom::Start(header.highres_offset as u64))?;
-        let textures = Vtf::read_textures(
-            &mut data,
-            header.highres_format,
-            header.width as u32,
-            header.height as u32,
-            header.mipmaps as u32,
-            header.frames as u32,
-            header.faces as u32,
-            header.slices as u32,
-        )?;
+        // Older versions mark 7 face cubemaps (with a spheremap) using the first frame.
+        let first_frame = match self.faces {
+            7 if self.version < [7, 5] => 0xFFFF,
+            7 => return Err(anyhow!("VTF 7.5 doesn't support 7 face cubemaps")),
+            _ => self.first_frame,
+        };
 
-        Ok(Vtf {
-            format: header.highres_format,
-            width: header.width as u32,
-            height: header.height as u32,
-            thumbnail,
-            mipmaps: header.mipmaps,
-            frames: header.frames,
-            first_frame: header.first_frame,
-            faces: header.faces,
-            slices: header.slices,
-            textures,
-        })
+        let resource_format = self.version > [7, 2];
+        let key_values = self
+            .resources
+            .key_values
+            .as_ref()
+            .filter(|_| resource_format)
+            .map(|key_values| {
+                let mut chunk = (key_values.len() as u32).to_le_bytes().to_vec();
+                chunk.extend_from_slice(key_values.as_bytes());
+                chunk
+            });
+
+        // Tag, flags & offset, or the data itself for resources without a data chunk.
+        let mut resources: Vec<([u8; 3], u8, u32)> = Vec::new();
+        if resource_format {
+            if self.thumbnail.is_some() {
+                resources.push((*b"\x01\0\0", 0, 0));
+            }
+            resources.push((*b"\x30\0\0", 0, 0));
+            if let Some(crc) = self.resources.crc {
+                resources.push((*b"CRC", RESOURCE_NO_DATA, crc));
+            }
+            if let Some((u, v)) = self.resources.lod {
+                resources.push((*b"LOD", RESOURCE_NO_DATA, u32::from_le_bytes([u, v, 0, 0])));
+            }
+            if let Some(texture_settings) = self.resources.texture_settings {
+                resources.push((*b"TSO", RESOURCE_NO_DATA, texture_settings));
+            }
+            if key_values.is_some() {
+                resources.push((*b"KVD", 0, 0));
+            }
+        }
+
+        let header_size: u32 = 80 + (resources.len() as u32) * 8;
+        let key_values_offset = header_size;
+        let lowres_offset =
+            key_values_offset + key_values.as_ref().map(|kv| kv.len() as u32).unwrap_or(0);
+        let highres_offset = lowres_offset
+            + self
+                .thumbnail
+                .as_ref()
+                .map(|thumbnail| thumbnail.data.len() as u32)
+                .unwrap_or(0);
+        for (tag, _, offset) in resources.iter_mut() {
+            match &*tag {
+                b"\x01\0\0" => *offset = lowres_offset,
+                b"\x30\0\0" => *offset = highres_offset,
+                b"KVD" => *offset = key_values_offset,
+                _ => {}
+            }
+        }
+
+        let mut header = Vec::with_capacity(header_size as usize);
+        header.extend_from_slice(b"VTF\0");
+        header.extend_from_slice(&self.version[0].to_le_bytes());
+        header.extend_from_slice(&self.version[1].to_le_bytes());
+        header.extend_from_slice(&header_size.to_le_bytes());
+        header.extend_from_slice(&(self.width as u16).to_le_bytes());
+        header.extend_from_slice(&(self.height as u16).to_le_bytes());
+        header.extend_from_slice(&self.flags.bits().to_le_bytes());
+        header.extend_from_slice(&self.frames.to_le_bytes());
+        header.extend_from_slice(&first_frame.to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        for reflectivity in self.reflectivity {
+            header.extend_from_slice(&reflectivity.to_le_bytes());
+        }
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&self.bumpmap_scale.to_le_bytes());
+        header.extend_from_slice(&(self.format as i32).to_le_bytes());
+        header.push(self.mipmaps);
+        match &self.thumbnail {
+            Some(thumbnail) => {
+                header.extend_from_slice(&(thumbnail.format as i32).to_le_bytes());
+                header.push(thumbnail.width as u8);
+                header.push(thumbnail.height as u8);
+            }
+            None => {
+                header.extend_from_slice(&(TextureFormat::NONE as i32).to_le_bytes());
+                header.extend_from_slice(&[0, 0]);
+            }
+        }
+        header.extend_from_slice(&self.slices.to_le_bytes());
+        if resource_format {
+            header.extend_from_slice(&[0; 3]);
+            header.extend_from_slice(&(resources.len() as u32).to_le_bytes());
+            header.extend_from_slice(&[0; 8]);
+            for (tag, flags, offset) in &resources {
+                header.extend_from_slice(tag);
+                header.push(*flags);
+                header.extend_from_slice(&offset.to_le_bytes());
+            }
+        }
+        header.resize(header_size as usize, 0);
+
+        writer.write_all(&header)?;
+        if let Some(key_values) = &key_values {
+            writer.write_all(key_values)?;
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            writer.write_all(&thumbnail.data)?;
+        }
+        for texture in &self.textures {
+            writer.write_all(&texture.data)?;
+        }
+        Ok(())
     }
 
     fn read_texture(
@@ -621,59 +963,11 @@ impl Vtf {
         Ok(textures)
     }
 
-    fn read_specific_texture(
-        mut data: impl Read + Seek,
-        format: TextureFormat,
-        width: u32,
-        height: u32,
-        mipmaps: u32,
-        frames: u32,
-        faces: u32,
-        slices: u32,
-        target_mipmap: u32,
-        target_frame: u32,
-        target_face: u32,
-        target_slice: u32,
-    ) -> Result<VtfTexture> {
-        let mut offset: u64 = 0;
-        for mipmap in (0..mipmaps).rev() {
-            let mip_width = (width >> mipmap).max(1);
-            let mip_height = (height >> mipmap).max(1);
-            for frame in 0..frames {
-                for face in 0..faces {
-                    for slice in 0..slices {
-                        let size = format.texture_byte_size(mip_width, mip_height);
-
-                        if mipmap != target_mipmap
-                            || frame != target_frame
-                            || face != target_face
-                            || slice != target_slice
-                        {
-                            offset += size;
-                            continue;
-                        }
-
-                        data.seek_relative(offset as i64)?;
-                        return Ok(Vtf::read_texture(&mut data, format, mip_width, mip_height)?);
-                    }
-                }
-            }
-        }
-        Err(anyhow!("Failed to read specific texture"))
-    }
-
-    pub fn load_thumbnail(
-        mut data: impl Read + Seek,
-        hint: SizeHint,
-    ) -> Result<Option<VtfTexture>> {
-        data.rewind()?;
-        if let Ok(header) = VtfHeader::load(&mut data) {
+    pub fn load_thumbnail(data: impl Read + Seek, hint: SizeHint) -> Result<Option<VtfTexture>> {
+        if let Ok(mut vtf) = LazyVtf::load(data) {
             let mut mipmap = 0;
-            while mipmap < header.mipmaps {
-                if hint.satisfies(
-                    (header.width as u32) >> mipmap,
-                    (header.height as u32) >> mipmap,
-                ) {
+            while mipmap < vtf.mipmaps() {
+                if hint.satisfies(vtf.width() >> mipmap, vtf.height() >> mipmap) {
                     if mipmap > 0 {
                         // Go to previous mipmap so scaling is a bit more clean.
                         mipmap -= 1;
@@ -682,28 +976,461 @@ impl Vtf {
                 }
                 mipmap += 1;
             }
+            // Files with no mipmaps never satisfy the hint.
+            let mipmap = mipmap.min(vtf.mipmaps().saturating_sub(1));
 
-            data.seek(std::io::SeekFrom::Start(header.highres_offset as u64))?;
-            Ok(Some(Vtf::read_specific_texture(
-                data,
-                header.highres_format,
-                header.width as u32,
-                header.height as u32,
-                header.mipmaps as u32,
-                header.frames as u32,
-                header.faces as u32,
-                header.slices as u32,
-                mipmap as u32,
-                0,
-                0,
-                0,
-            )?))
+            let frame = if vtf.first_frame() < vtf.frames() {
+                vtf.first_frame()
+            } else {
+                0
+            };
+            Ok(Some(vtf.texture(mipmap, frame, 0, 0)?))
         } else {
             Ok(None)
         }
     }
 }
 
+/// A [`Vtf`] that keeps the reader & only reads textures when they are requested.
+///
+/// Huge animated textures & cubemaps may be hundreds of MB, most of which is never looked at.
+pub struct LazyVtf<R: Read + Seek> {
+    reader: R,
+    header: VtfHeader,
+    thumbnail: Option<VtfTexture>,
+    resources: VtfResources,
+}
+
+impl<R: Read + Seek> LazyVtf<R> {
+    pub fn load(mut reader: R) -> Result<LazyVtf<R>> {
+        reader.rewind()?;
+        let header = VtfHeader::load(&mut reader)?;
+
+        let mut thumbnail = None;
+        if let Some(lowres_offset) = header.lowres_offset {
+            reader.seek(std::io::SeekFrom::Start(lowres_offset as u64))?;
+            thumbnail = Vtf::read_thumbnail(
+                &mut reader,
+                header.lowres_format,
+                header.lowres_width as u32,
+                header.lowres_height as u32,
+            )?;
+        }
+        let resources = VtfResources::load(&mut reader, &header.resources)?;
+
+        Ok(LazyVtf {
+            reader,
+            header,
+            thumbnail,
+            resources,
+        })
+    }
+
+    pub fn version(&self) -> [u32; 2] {
+        self.header.version
+    }
+    pub fn flags(&self) -> TextureFlags {
+        self.header.flags
+    }
+    pub fn reflectivity(&self) -> [f32; 3] {
+        self.header.reflectivity
+    }
+    pub fn bumpmap_scale(&self) -> f32 {
+        self.header.bumpmap_scale
+    }
+    /// Only VTF 7.3+ has resources.
+    pub fn resources(&self) -> &VtfResources {
+        &self.resources
+    }
+    /// May have different format than LazyVtf::format()
+    pub fn thumbnail(&self) -> Option<&VtfTexture> {
+        self.thumbnail.as_ref()
+    }
+    pub fn format(&self) -> TextureFormat {
+        self.header.highres_format
+    }
+    pub fn width(&self) -> u32 {
+        self.header.width as u32
+    }
+    pub fn height(&self) -> u32 {
+        self.header.height as u32
+    }
+    pub fn mipmaps(&self) -> u32 {
+        self.header.mipmaps as u32
+    }
+    pub fn frames(&self) -> u32 {
+        self.header.frames as u32
+    }
+    /// See [`Vtf::first_frame`].
+    pub fn first_frame(&self) -> u32 {
+        self.header.first_frame as u32
+    }
+    pub fn faces(&self) -> u32 {
+        self.header.faces as u32
+    }
+    pub fn slices(&self) -> u32 {
+        self.header.slices as u32
+    }
+
+    pub fn total_num_textures(&self) -> usize {
+        (self.mipmaps() as usize)
+            * (self.frames() as usize)
+            * (self.faces() as usize)
+            * (self.slices() as usize)
+    }
+
+    fn mipmap_size(&self, mipmap: u32) -> (u32, u32) {
+        (
+            (self.width() >> mipmap).max(1),
+            (self.height() >> mipmap).max(1),
+        )
+    }
+
+    /// Offset of a texture in the file, mipmaps are stored smallest first.
+    fn texture_offset(&self, mipmap: u32, frame: u32, face: u32, slice: u32) -> Option<u64> {
+        if mipmap >= self.mipmaps()
+            || frame >= self.frames()
+            || face >= self.faces()
+            || slice >= self.slices()
+        {
+            return None;
+        }
+
+        let textures_per_mipmap =
+            (self.frames() as u64) * (self.faces() as u64) * (self.slices() as u64);
+        let smaller_mipmaps: u64 = ((mipmap + 1)..self.mipmaps())
+            .map(|mipmap| {
+                let (width, height) = self.mipmap_size(mipmap);
+                self.format().texture_byte_size(width, height) * textures_per_mipmap
+            })
+            .sum();
+
+        let (width, height) = self.mipmap_size(mipmap);
+        let index = (slice as u64)
+            + (face as u64) * (self.slices() as u64)
+            + (frame as u64) * (self.faces() as u64) * (self.slices() as u64);
+
+        Some(
+            (self.header.highres_offset as u64)
+                + smaller_mipmaps
+                + index * self.format().texture_byte_size(width, height),
+        )
+    }
+
+    pub fn texture(
+        &mut self,
+        mipmap: u32,
+        frame: u32,
+        face: u32,
+        slice: u32,
+    ) -> Result<VtfTexture> {
+        let offset = self
+            .texture_offset(mipmap, frame, face, slice)
+            .ok_or(anyhow!("VTF texture out of range"))?;
+        let (width, height) = self.mipmap_size(mipmap);
+        let format = self.format();
+        self.reader.seek(std::io::SeekFrom::Start(offset))?;
+        Vtf::read_texture(&mut self.reader, format, width, height)
+    }
+
+    /// Reads all textures.
+    pub fn into_vtf(mut self) -> Result<Vtf> {
+        self.reader
+            .seek(std::io::SeekFrom::Start(self.header.highres_offset as u64))?;
+        let textures = Vtf::read_textures(
+            &mut self.reader,
+            self.header.highres_format,
+            self.header.width as u32,
+            self.header.height as u32,
+            self.header.mipmaps as u32,
+            self.header.frames as u32,
+            self.header.faces as u32,
+            self.header.slices as u32,
+        )?;
+
+        Ok(Vtf {
+            version: self.header.version,
+            flags: self.header.flags,
+            reflectivity: self.header.reflectivity,
+            bumpmap_scale: self.header.bumpmap_scale,
+            format: self.header.highres_format,
+            width: self.header.width as u32,
+            height: self.header.height as u32,
+            thumbnail: self.thumbnail,
+            mipmaps: self.header.mipmaps,
+            frames: self.header.frames,
+            first_frame: self.header.first_frame,
+            faces: self.header.faces,
+            slices: self.header.slices,
+            textures,
+            resources: self.resources,
+        })
+    }
+}
+
+/// Creates a [`Vtf`] from images, every image is a frame & they must all be the same size.
+#[derive(Debug, Clone)]
+pub struct VtfBuilder {
+    frames: Vec<DynamicImage>,
+    format: Option<TextureFormat>,
+    version: [u32; 2],
+    flags: TextureFlags,
+    mipmaps: bool,
+    thumbnail: bool,
+    resources: VtfResources,
+}
+
+impl VtfBuilder {
+    pub fn new(frames: Vec<DynamicImage>) -> VtfBuilder {
+        VtfBuilder {
+            frames,
+            format: None,
+            version: [7, 2],
+            flags: TextureFlags::empty(),
+            mipmaps: true,
+            thumbnail: true,
+            resources: VtfResources::default(),
+        }
+    }
+
+    /// Defaults to DXT5 if any frame has transparency, DXT1 otherwise.
+    pub fn format(mut self, format: TextureFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Defaults to 7.2, which every Source engine game can load.
+    pub fn version(mut self, version: [u32; 2]) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Flags that depend on the texture data (alpha & mipmap flags) are added automatically.
+    pub fn flags(mut self, flags: TextureFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    pub fn thumbnail(mut self, thumbnail: bool) -> Self {
+        self.thumbnail = thumbnail;
+        self
+    }
+
+    /// Only written for 7.3+, the CRC is always computed from the image data & unknown resources
+    /// are never written.
+    pub fn resources(mut self, resources: VtfResources) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    pub fn build(self) -> Result<Vtf> {
+        let first = self
+            .frames
+            .first()
+            .ok_or(anyhow!("VTF needs at least 1 frame"))?;
+        let (width, height) = first.dimensions();
+        if self
+            .frames
+            .iter()
+            .any(|frame| frame.dimensions() != (width, height))
+        {
+            return Err(anyhow!("VTF frames must all be the same size"));
+        }
+        if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(anyhow!("VTF size {}x{} invalid", width, height));
+        }
+        if self.frames.len() > u16::MAX as usize {
+            return Err(anyhow!("VTF has too many frames"));
+        }
+
+        let has_alpha = self.frames.iter().any(|frame| {
+            frame.color().has_alpha() && frame.to_rgba8().pixels().any(|pixel| pixel[3] < 255)
+        });
+        let format = self.format.unwrap_or(if has_alpha {
+            TextureFormat::DXT5
+        } else {
+            TextureFormat::DXT1
+        });
+
+        let frame_mipmaps: Vec<Vec<DynamicImage>> = self
+            .frames
+            .iter()
+            .map(|frame| {
+                if self.mipmaps {
+                    util::texture::generate_mipmaps(frame)
+                } else {
+                    vec![frame.clone()]
+                }
+            })
+            .collect();
+        let mipmaps = frame_mipmaps[0].len();
+
+        // Same order as the file, smallest mipmap first.
+        let mut textures = Vec::with_capacity(mipmaps * self.frames.len());
+        for mipmap in (0..mipmaps).rev() {
+            for frame in &frame_mipmaps {
+                textures.push(VtfTexture::from_image(&frame[mipmap], format)?);
+            }
+        }
+
+        let thumbnail = if self.thumbnail {
+            let (mut thumbnail_width, mut thumbnail_height) = (width, height);
+            while thumbnail_width > 16 || thumbnail_height > 16 {
+                thumbnail_width = (thumbnail_width / 2).max(1);
+                thumbnail_height = (thumbnail_height / 2).max(1);
+            }
+            Some(VtfTexture::from_image(
+                &first.resize_exact(thumbnail_width, thumbnail_height, FilterType::Triangle),
+                TextureFormat::DXT1,
+            )?)
+        } else {
+            None
+        };
+
+        let mut flags = self.flags;
+        if !self.mipmaps {
+            flags |= TextureFlags::NOMIP | TextureFlags::NOLOD;
+        }
+        match format {
+            TextureFormat::DXT1_ONEBITALPHA | TextureFormat::BGRA5551 => {
+                flags |= TextureFlags::ONEBITALPHA
+            }
+            TextureFormat::RGBA8888
+            | TextureFormat::ABGR8888
+            | TextureFormat::ARGB8888
+            | TextureFormat::BGRA8888
+            | TextureFormat::BGRA4444
+            | TextureFormat::IA88
+            | TextureFormat::A8
+            | TextureFormat::DXT3
+            | TextureFormat::DXT5
+            | TextureFormat::RGBA16161616
+            | TextureFormat::RGBA32323232F
+                if has_alpha =>
+            {
+                flags |= TextureFlags::EIGHTBITALPHA
+            }
+            _ => {}
+        }
+
+        // Average linear color of the first frame, used by vrad for bounced light.
+        let average = frame_mipmaps[0]
+            .last()
+            .unwrap()
+            .resize_exact(1, 1, FilterType::Triangle)
+            .to_rgb32f();
+        let reflectivity = average.get_pixel(0, 0).0.map(|c| c.powf(2.2));
+
+        let mut vtf = Vtf {
+            version: self.version,
+            flags,
+            reflectivity,
+            bumpmap_scale: 1.0,
+            thumbnail,
+            format,
+            width,
+            height,
+            mipmaps: mipmaps as u8,
+            frames: self.frames.len() as u16,
+            first_frame: 0,
+            faces: 1,
+            slices: 1,
+            textures,
+            resources: VtfResources {
+                unknown: Vec::new(),
+                ..self.resources
+            },
+        };
+        if self.version > [7, 2] {
+            vtf.resources.crc = Some(vtf.compute_crc());
+        }
+        Ok(vtf)
+    }
+}
+
+/// Resources that have their data stored in place of the offset.
+const RESOURCE_NO_DATA: u8 = 0x02;
+
+/// Extra resources from the resource directory of VTF 7.3+.
+#[derive(Debug, Clone, Default)]
+pub struct VtfResources {
+    pub crc: Option<u32>,
+    /// Highest mipmap size used in-game, as powers of 2 for the width & height.
+    pub lod: Option<(u8, u8)>,
+    /// Extended texture flags, no known game uses these.
+    pub texture_settings: Option<u32>,
+    /// KeyValues text, may be anything.
+    pub key_values: Option<String>,
+    /// Tag, flags & offset of resources that aren't decoded.
+    pub unknown: Vec<([u8; 3], u8, u32)>,
+}
+
+impl VtfResources {
+    /// Data of a resource that isn't stored in place of its offset.
+    fn read_data(mut data: impl Read + Seek, offset: u32) -> Result<Vec<u8>> {
+        let mut reader = crate::util::reader::Reader::new_le(&mut data);
+        let stream_length = reader.size()?;
+        reader.seek(SeekFrom::Start(offset as u64))?;
+        let size = reader.read::<u32>()? as u64;
+        if (offset as u64) + 4 + size > stream_length {
+            return Err(anyhow!("VTF resource data out of bounds"));
+        }
+        reader.read_buf(size as usize)
+    }
+
+    /// Only the resources that are understood are read, anything else is kept as is. Resources
+    /// that fail to read are treated as unknown, so they don't fail the whole texture.
+    fn load(mut data: impl Read + Seek, resources: &[VtfResource]) -> Result<VtfResources> {
+        let mut loaded = VtfResources::default();
+        for resource in resources {
+            let VtfResource::Unknown(tag, flags, offset) = resource else {
+                continue;
+            };
+            if !matches!(tag, b"CRC" | b"LOD" | b"TSO" | b"KVD") {
+                loaded.unknown.push((*tag, *flags, *offset));
+                continue;
+            }
+
+            let value = if flags & RESOURCE_NO_DATA != 0 {
+                offset.to_le_bytes().to_vec()
+            } else {
+                match VtfResources::read_data(&mut data, *offset) {
+                    Ok(value) => value,
+                    Err(_) => {
+                        loaded.unknown.push((*tag, *flags, *offset));
+                        continue;
+                    }
+                }
+            };
+
+            match tag {
+                b"CRC" if value.len() >= 4 => {
+                    loaded.crc = Some(u32::from_le_bytes(value[0..4].try_into().unwrap()))
+                }
+                b"LOD" if value.len() >= 2 => loaded.lod = Some((value[0], value[1])),
+                b"TSO" if value.len() >= 4 => {
+                    loaded.texture_settings =
+                        Some(u32::from_le_bytes(value[0..4].try_into().unwrap()))
+                }
+                b"KVD" => {
+                    loaded.key_values = Some(
+                        String::from_utf8_lossy(&value)
+                            .trim_end_matches('\0')
+                            .to_owned(),
+                    )
+                }
+                _ => loaded.unknown.push((*tag, *flags, *offset)),
+            }
+        }
+        Ok(loaded)
+    }
+}
+
 enum VtfResource {
     Unknown([u8; 3], u8, u32),
     LowRes(u32),
@@ -857,3 +1584,114 @@ impl VtfHeader {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use std::io::Cursor;
+
+    fn write_vtf() -> Vec<u8> {
+        let image = RgbaImage::from_fn(8, 8, |x, y| {
+            image::Rgba([x as u8 * 32, y as u8 * 32, 0, 255])
+        });
+        let vtf = VtfBuilder::new(vec![DynamicImage::ImageRgba8(image)])
+            .format(TextureFormat::RGBA8888)
+            .version([7, 4])
+            .resources(VtfResources {
+                lod: Some((2, 3)),
+                texture_settings: Some(0x1234),
+                key_values: Some("\"settings\" { \"a\" \"1\" }".to_owned()),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let mut data = Vec::new();
+        vtf.save(&mut data).unwrap();
+        data
+    }
+
+    /// Offset of the resource directory entry with the tag.
+    fn find_resource(data: &[u8], tag: &[u8; 3]) -> usize {
+        (80..data.len())
+            .step_by(8)
+            .find(|offset| &data[*offset..(*offset + 3)] == tag)
+            .unwrap()
+    }
+
+    #[test]
+    fn resources_round_trip() {
+        let vtf = Vtf::load(Cursor::new(write_vtf())).unwrap();
+        let resources = vtf.resources();
+        assert_eq!(resources.crc, Some(vtf.compute_crc()));
+        assert_eq!(resources.lod, Some((2, 3)));
+        assert_eq!(resources.texture_settings, Some(0x1234));
+        assert_eq!(
+            resources.key_values.as_deref(),
+            Some("\"settings\" { \"a\" \"1\" }")
+        );
+        assert!(resources.unknown.is_empty());
+    }
+
+    #[test]
+    fn unknown_resources_are_skipped() {
+        // Unknown tag with data past the end of the file.
+        let mut data = write_vtf();
+        let entry = find_resource(&data, b"CRC");
+        data[entry..(entry + 8)].copy_from_slice(&[b'X', b'Y', b'Z', 0, 0xF0, 0xFF, 0xFF, 0xFF]);
+
+        let vtf = Vtf::load(Cursor::new(data)).unwrap();
+        assert_eq!(vtf.resources().crc, None);
+        assert_eq!(vtf.resources().lod, Some((2, 3)));
+        assert_eq!(vtf.resources().unknown, [(*b"XYZ", 0, 0xFFFFFFF0)]);
+    }
+
+    #[test]
+    fn corrupt_resource_size() {
+        let mut data = write_vtf();
+        let entry = find_resource(&data, b"KVD");
+        let offset = u32::from_le_bytes(data[(entry + 4)..(entry + 8)].try_into().unwrap());
+        data[(offset as usize)..(offset as usize + 4)].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let vtf = LazyVtf::load(Cursor::new(data)).unwrap();
+        assert_eq!(vtf.resources().key_values, None);
+        assert_eq!(vtf.resources().lod, Some((2, 3)));
+        assert_eq!(vtf.resources().unknown.len(), 1);
+    }
+
+    #[test]
+    fn half_float_decode() {
+        // 1.0, -2.0, 0.5, 65504.0 (largest half).
+        let data = [0x00, 0x3C, 0x00, 0xC0, 0x00, 0x38, 0xFF, 0x7B];
+        let texture = VtfTexture::new(1, 1, TextureFormat::RGBA16161616F, &data);
+        let DynamicImage::ImageRgba32F(image) = texture.to_image() else {
+            panic!("expected an RGBA32F image");
+        };
+        assert_eq!(image.get_pixel(0, 0).0, [1.0, -2.0, 0.5, 65504.0]);
+    }
+
+    #[test]
+    fn compressed_hdr_decode() {
+        // BGRA, alpha scales the color by up to 16.
+        let data = [0, 51, 255, 255, 255, 255, 255, 0];
+        let texture = VtfTexture::new(2, 1, TextureFormat::BGRA8888, &data);
+        let DynamicImage::ImageRgba32F(image) = texture.to_compressed_hdr_image().unwrap() else {
+            panic!("expected an RGBA32F image");
+        };
+        for (value, expected) in image.get_pixel(0, 0).0.iter().zip([16.0, 3.2, 0.0, 1.0]) {
+            assert!((value - expected).abs() < 1e-5, "{value} != {expected}");
+        }
+        assert_eq!(image.get_pixel(1, 0).0, [0.0, 0.0, 0.0, 1.0]);
+
+        let texture = VtfTexture::new(1, 1, TextureFormat::RGBA8888, &data[..4]);
+        assert!(texture.to_compressed_hdr_image().is_none());
+    }
+
+    #[test]
+    fn truncated() {
+        let data = write_vtf();
+        assert!(Vtf::load(Cursor::new(data[..60].to_vec())).is_err());
+        // Inside the resource directory.
+        assert!(LazyVtf::load(Cursor::new(data[..90].to_vec())).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+// https://developer.valvesoftware.com/wiki/VTX
+//
+// Triangles for a .mdl, grouped the same way as the model: body parts, models, LODs & meshes.
+// Only the .dx90.vtx variant is needed, the others are identical for modern games.
+
+use crate::mdl::ByteSlice;
+use anyhow::{anyhow, Result};
+use std::io::Read;
+
+const STRIP_FLAG_TRI_STRIP: u8 = 0x02;
+
+#[derive(Debug, Clone)]
+pub struct VtxStrip {
+    /// Into the strip group's indices.
+    pub index_offset: usize,
+    pub num_indices: usize,
+    pub is_tri_strip: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct VtxStripGroup {
+    /// Vertex index relative to the .mdl mesh for each strip group vertex.
+    pub vertices: Vec<u16>,
+    /// Into vertices.
+    pub indices: Vec<u16>,
+    pub strips: Vec<VtxStrip>,
+}
+
+impl VtxStripGroup {
+    /// Triangles as indices into vertices, as they're wound in the file.
+    pub fn triangles(&self) -> Vec<[u16; 3]> {
+        let mut triangles = Vec::new();
+        for strip in &self.strips {
+            let Some(indices) = self
+                .indices
+                .get(strip.index_offset..(strip.index_offset + strip.num_indices))
+            else {
+                continue;
+            };
+            if strip.is_tri_strip {
+                for (i, window) in indices.windows(3).enumerate() {
+                    // Every other triangle of a strip is flipped.
+                    if i % 2 == 0 {
+                        triangles.push([window[0], window[1], window[2]]);
+                    } else {
+                        triangles.push([window[1], window[0], window[2]]);
+                    }
+                }
+            } else {
+                triangles.extend(indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]));
+            }
+        }
+        triangles
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VtxMesh {
+    pub strip_groups: Vec<VtxStripGroup>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VtxLod {
+    pub switch_point: f32,
+    pub meshes: Vec<VtxMesh>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VtxModel {
+    pub lods: Vec<VtxLod>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VtxBodyPart {
+    pub models: Vec<VtxModel>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Vtx {
+    version: u32,
+    checksum: u32,
+    num_lods: usize,
+    body_parts: Vec<VtxBodyPart>,
+}
+
+impl Vtx {
+    pub fn parse(data: &[u8]) -> Result<Vtx> {
+        let data = ByteSlice(data);
+        let version = data.i32(0)? as u32;
+        if version != 7 {
+            return Err(anyhow!("VTX version {} not supported", version));
+        }
+        // Some newer games added 8 bytes of topology info to strip groups & strips, the header
+        // doesn't say which is used.
+        let body_parts = Vtx::parse_body_parts(&data, 25, 27)
+            .or_else(|_| Vtx::parse_body_parts(&data, 33, 35))?;
+        Ok(Vtx {
+            version,
+            checksum: data.i32(16)? as u32,
+            num_lods: data.offset(20)?,
+            body_parts,
+        })
+    }
+
+    fn parse_body_parts(
+        data: &ByteSlice,
+        strip_group_size: usize,
+        strip_size: usize,
+    ) -> Result<Vec<VtxBodyPart>> {
+        let body_part_index = data.offset(32)?;
+        (0..data.offset(28)?)
+            .map(|i| {
+                let body_part = body_part_index + i * 8;
+                let model_index = data.relative(body_part, body_part + 4)?;
+                let models = (0..data.offset(body_part)?)
+                    .map(|i| {
+                        let model = model_index + i * 8;
+                        let lod_index = data.relative(model, model + 4)?;
+                        let lods = (0..data.offset(model)?)
+                            .map(|i| {
+                                let lod = lod_index + i * 12;
+                                let mesh_index = data.relative(lod, lod + 4)?;
+                                let meshes = (0..data.offset(lod)?)
+                                    .map(|i| {
+                                        let mesh = mesh_index + i * 9;
+                                        let strip_group_index = data.relative(mesh, mesh + 4)?;
+                                        let strip_groups = (0..data.offset(mesh)?)
+                                            .map(|i| {
+                                                Vtx::parse_strip_group(
+                                                    data,
+                                                    strip_group_index + i * strip_group_size,
+                                                    strip_size,
+                                                )
+                                            })
+                                            .collect::<Result<Vec<_>>>()?;
+                                        Ok(VtxMesh { strip_groups })
+                                    })
+                                    .collect::<Result<Vec<_>>>()?;
+                                Ok(VtxLod {
+                                    switch_point: data.f32(lod + 8)?,
+                                    meshes,
+                                })
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        Ok(VtxModel { lods })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(VtxBodyPart { models })
+            })
+            .collect()
+    }
+
+    fn parse_strip_group(
+        data: &ByteSlice,
+        strip_group: usize,
+        strip_size: usize,
+    ) -> Result<VtxStripGroup> {
+        let vertex_index = data.relative(strip_group, strip_group + 4)?;
+        let vertices = (0..data.offset(strip_group)?)
+            .map(|i| data.u16(vertex_index + i * 9 + 4))
+            .collect::<Result<Vec<_>>>()?;
+
+        let index_index = data.relative(strip_group, strip_group + 12)?;
+        let indices = (0..data.offset(strip_group + 8)?)
+            .map(|i| data.u16(index_index + i * 2))
+            .collect::<Result<Vec<_>>>()?;
+        if indices
+            .iter()
+            .any(|&index| index as usize >= vertices.len())
+        {
+            return Err(anyhow!("VTX index out of range"));
+        }
+
+        let strip_index = data.relative(strip_group, strip_group + 20)?;
+        let strips = (0..data.offset(strip_group + 16)?)
+            .map(|i| {
+                let strip = strip_index + i * strip_size;
+                let strip = VtxStrip {
+                    num_indices: data.offset(strip)?,
+                    index_offset: data.offset(strip + 4)?,
+                    is_tri_strip: data.u8(strip + 18)? & STRIP_FLAG_TRI_STRIP != 0,
+                };
+                if strip.index_offset + strip.num_indices > indices.len() {
+                    return Err(anyhow!("VTX strip out of range"));
+                }
+                Ok(strip)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(VtxStripGroup {
+            vertices,
+            indices,
+            strips,
+        })
+    }
+
+    pub fn load(mut data: impl Read) -> Result<Vtx> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf)?;
+        Vtx::parse(&buf)
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+    pub fn num_lods(&self) -> usize {
+        self.num_lods
+    }
+    pub fn body_parts(&self) -> &[VtxBodyPart] {
+        &self.body_parts
+    }
+}
@@ -0,0 +1,103 @@
+// https://developer.valvesoftware.com/wiki/VVD
+//
+// Vertex data for a .mdl, only the root LOD vertices are kept. Lower LODs reuse them.
+
+use crate::mdl::ByteSlice;
+use anyhow::{anyhow, Result};
+use std::io::Read;
+
+#[derive(Debug, Clone, Copy)]
+pub struct VvdVertex {
+    pub bone_weights: [f32; 3],
+    pub bones: [u8; 3],
+    pub num_bones: u8,
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+#[derive(Debug, Clone)]
+pub struct Vvd {
+    version: u32,
+    checksum: u32,
+    num_lods: usize,
+    vertices: Vec<VvdVertex>,
+}
+
+const VERTEX_SIZE: usize = 48;
+
+impl Vvd {
+    pub fn parse(data: &[u8]) -> Result<Vvd> {
+        let data = ByteSlice(data);
+        if &data.bytes::<4>(0)? != b"IDSV" {
+            return Err(anyhow!("Invalid VVD identifier"));
+        }
+        let version = data.i32(4)? as u32;
+        if version != 4 {
+            return Err(anyhow!("VVD version {} not supported", version));
+        }
+
+        let vertex_start = data.offset(56)?;
+        let vertex = |index: usize| -> Result<VvdVertex> {
+            let offset = vertex_start + index * VERTEX_SIZE;
+            let [b0, b1, b2, num_bones] = data.bytes(offset + 12)?;
+            Ok(VvdVertex {
+                bone_weights: data.vector(offset)?,
+                bones: [b0, b1, b2],
+                num_bones,
+                position: data.vector(offset + 16)?,
+                normal: data.vector(offset + 28)?,
+                uv: data.vector(offset + 40)?,
+            })
+        };
+
+        let num_root_vertices = data.offset(16)?;
+        let num_fixups = data.offset(48)?;
+        let vertices = if num_fixups == 0 {
+            (0..num_root_vertices)
+                .map(vertex)
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            // Fixups reorder the vertices per LOD, the root LOD uses every fixup.
+            let fixup_start = data.offset(52)?;
+            // The count isn't trusted for the allocation, each vertex has to fit in the file.
+            let max_vertices = data.0.len().saturating_sub(vertex_start) / VERTEX_SIZE;
+            let mut vertices = Vec::with_capacity(num_root_vertices.min(max_vertices));
+            for i in 0..num_fixups {
+                let fixup = fixup_start + i * 12;
+                let source = data.offset(fixup + 4)?;
+                let count = data.offset(fixup + 8)?;
+                for index in source..(source + count) {
+                    vertices.push(vertex(index)?);
+                }
+            }
+            vertices
+        };
+
+        Ok(Vvd {
+            version,
+            checksum: data.i32(8)? as u32,
+            num_lods: data.offset(12)?,
+            vertices,
+        })
+    }
+
+    pub fn load(mut data: impl Read) -> Result<Vvd> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf)?;
+        Vvd::parse(&buf)
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+    pub fn num_lods(&self) -> usize {
+        self.num_lods
+    }
+    pub fn vertices(&self) -> &[VvdVertex] {
+        &self.vertices
+    }
+}
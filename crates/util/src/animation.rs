@@ -0,0 +1,99 @@
+use anyhow::{anyhow, Result};
+use image::{
+    codecs::{
+        gif::{GifDecoder, GifEncoder, Repeat},
+        png::PngDecoder,
+        webp::WebPDecoder,
+    },
+    AnimationDecoder, Delay, DynamicImage, Frame, Frames, ImageFormat,
+};
+use std::io::{BufRead, Seek, Write};
+
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    /// Already composited onto the previous frames, so every frame is the full size.
+    pub image: DynamicImage,
+    /// In milliseconds.
+    pub delay: f32,
+}
+
+/// Every frame of a GIF, APNG or animated WebP. Errors if the image isn't animated, single frame
+/// GIFs are still returned.
+pub fn decode_animation<R: BufRead + Seek>(
+    reader: R,
+    format: ImageFormat,
+) -> Result<Vec<AnimationFrame>> {
+    fn collect(frames: Frames) -> Result<Vec<AnimationFrame>> {
+        frames
+            .map(|frame| {
+                let frame = frame?;
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                Ok(AnimationFrame {
+                    delay: numer as f32 / denom.max(1) as f32,
+                    image: DynamicImage::ImageRgba8(frame.into_buffer()),
+                })
+            })
+            .collect()
+    }
+
+    match format {
+        ImageFormat::Gif => collect(GifDecoder::new(reader)?.into_frames()),
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(reader)?;
+            if !decoder.is_apng()? {
+                return Err(anyhow!("PNG is not animated"));
+            }
+            collect(decoder.apng()?.into_frames())
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(reader)?;
+            if !decoder.has_animation() {
+                return Err(anyhow!("WebP is not animated"));
+            }
+            collect(decoder.into_frames())
+        }
+        format => Err(anyhow!("{:?} images can't be animated", format)),
+    }
+}
+
+/// Frame delay in milliseconds, GIF & APNG both store delays as fractions.
+fn frame_delay_ms(fps: f32) -> u32 {
+    (1000.0 / fps.max(0.001)).round() as u32
+}
+
+/// Looping GIF, colors are quantized to 256 per frame.
+pub fn encode_gif<W: Write>(frames: &[DynamicImage], fps: f32, writer: W) -> Result<()> {
+    let mut encoder = GifEncoder::new_with_speed(writer, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_numer_denom_ms(frame_delay_ms(fps), 1);
+    encoder.encode_frames(
+        frames
+            .iter()
+            .map(|frame| Frame::from_parts(frame.to_rgba8(), 0, 0, delay)),
+    )?;
+    Ok(())
+}
+
+/// Looping APNG, every frame must be the same size.
+pub fn encode_apng<W: Write>(frames: &[DynamicImage], fps: f32, writer: W) -> Result<()> {
+    let first = frames.first().ok_or(anyhow!("Animation has no frames"))?;
+    let (width, height) = (first.width(), first.height());
+    if frames
+        .iter()
+        .any(|frame| frame.width() != width || frame.height() != height)
+    {
+        return Err(anyhow!("Animation frames must be the same size"));
+    }
+
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(frame_delay_ms(fps).min(u16::MAX as u32) as u16, 1000)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame.to_rgba8().as_raw())?;
+    }
+    writer.finish()?;
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use std::{
-    io::{self, Read, Seek},
+    io::{self, Cursor, Read, Seek},
     path::PathBuf,
     sync::{Arc, Mutex},
 };
@@ -12,6 +12,10 @@ pub fn filename<P: Into<PathBuf>>(path: P) -> Option<String> {
         .flatten()
 }
 
+/// [`Read`] + [`Seek`] that can be made into a trait object.
+pub trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
 pub struct InnerFile<F: Read + Seek> {
     file: Arc<Mutex<F>>,
     offset: u64,
@@ -78,6 +82,46 @@ impl<F: Read + Seek> Clone for InnerFile<F> {
     }
 }
 
+/// A file inside of an archive that is either read directly from the archive, or has been
+/// decompressed or decoded into memory.
+pub enum ArchiveFile<F: Read + Seek> {
+    Inner(InnerFile<F>),
+    Memory(Cursor<Vec<u8>>),
+}
+
+impl<F: Read + Seek> ArchiveFile<F> {
+    pub fn memory(data: Vec<u8>) -> Self {
+        Self::Memory(Cursor::new(data))
+    }
+}
+
+impl<F: Read + Seek> Read for ArchiveFile<F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ArchiveFile::Inner(inner) => inner.read(buf),
+            ArchiveFile::Memory(memory) => memory.read(buf),
+        }
+    }
+}
+
+impl<F: Read + Seek> Seek for ArchiveFile<F> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match self {
+            ArchiveFile::Inner(inner) => inner.seek(pos),
+            ArchiveFile::Memory(memory) => memory.seek(pos),
+        }
+    }
+}
+
+impl<F: Read + Seek> Clone for ArchiveFile<F> {
+    fn clone(&self) -> Self {
+        match self {
+            ArchiveFile::Inner(inner) => ArchiveFile::Inner(inner.clone()),
+            ArchiveFile::Memory(memory) => ArchiveFile::Memory(memory.clone()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct FileSize(u64);
 
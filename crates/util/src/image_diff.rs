@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use image::{Rgba, RgbaImage};
+
+/// Black through red & yellow to white, `t` from 0.0 to 1.0.
+fn heat_color(t: f32) -> Rgba<u8> {
+    let channel = |offset: f32| ((t * 3.0 - offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgba([channel(0.0), channel(1.0), channel(2.0), 255])
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    /// Pixels where any channel differs.
+    pub changed_pixels: u64,
+    pub total_pixels: u64,
+    /// Largest difference of any channel.
+    pub max_difference: u8,
+    /// Mean squared error over every channel.
+    pub mean_squared_error: f64,
+    /// Largest channel difference of every pixel, scaled so the largest difference is white.
+    pub heatmap: RgbaImage,
+}
+
+impl ImageDiff {
+    /// Both images must be the same size.
+    pub fn new(a: &RgbaImage, b: &RgbaImage) -> Result<ImageDiff> {
+        if a.dimensions() != b.dimensions() {
+            return Err(anyhow!("Images must be the same size to compare"));
+        }
+
+        let differences: Vec<u8> = a
+            .pixels()
+            .zip(b.pixels())
+            .map(|(a, b)| {
+                a.0.iter()
+                    .zip(b.0.iter())
+                    .map(|(a, b)| a.abs_diff(*b))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let squared_error: u64 = a
+            .as_raw()
+            .iter()
+            .zip(b.as_raw().iter())
+            .map(|(a, b)| (a.abs_diff(*b) as u64).pow(2))
+            .sum();
+
+        let max_difference = differences.iter().copied().max().unwrap_or(0);
+        let heatmap = RgbaImage::from_fn(a.width(), a.height(), |x, y| {
+            let difference = differences[(y * a.width() + x) as usize];
+            if max_difference == 0 {
+                heat_color(0.0)
+            } else {
+                heat_color(difference as f32 / max_difference as f32)
+            }
+        });
+
+        Ok(ImageDiff {
+            changed_pixels: differences.iter().filter(|&&d| d > 0).count() as u64,
+            total_pixels: differences.len() as u64,
+            max_difference,
+            mean_squared_error: squared_error as f64 / a.as_raw().len().max(1) as f64,
+            heatmap,
+        })
+    }
+
+    /// Peak signal to noise ratio in decibels, none if the images are identical.
+    pub fn psnr(&self) -> Option<f64> {
+        if self.mean_squared_error == 0.0 {
+            None
+        } else {
+            Some(10.0 * (255.0 * 255.0 / self.mean_squared_error).log10())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics() {
+        let a = RgbaImage::from_pixel(4, 2, Rgba([10, 20, 30, 255]));
+        let mut b = a.clone();
+        b.put_pixel(0, 0, Rgba([10, 20, 40, 255]));
+        b.put_pixel(3, 1, Rgba([15, 20, 30, 250]));
+
+        let diff = ImageDiff::new(&a, &b).unwrap();
+        assert_eq!(diff.changed_pixels, 2);
+        assert_eq!(diff.total_pixels, 8);
+        assert_eq!(diff.max_difference, 10);
+        // (10² + 5² + 5²) / 32 channels.
+        assert_eq!(diff.mean_squared_error, 150.0 / 32.0);
+        let psnr = diff.psnr().unwrap();
+        assert!((psnr - 41.42).abs() < 0.01);
+
+        assert_eq!(diff.heatmap.dimensions(), (4, 2));
+        assert_eq!(*diff.heatmap.get_pixel(0, 0), Rgba([255, 255, 255, 255]));
+        assert_eq!(*diff.heatmap.get_pixel(1, 0), Rgba([0, 0, 0, 255]));
+        // Half the largest difference.
+        assert_eq!(*diff.heatmap.get_pixel(3, 1), Rgba([255, 128, 0, 255]));
+    }
+
+    #[test]
+    fn identical() {
+        let a = RgbaImage::from_pixel(3, 3, Rgba([1, 2, 3, 4]));
+        let diff = ImageDiff::new(&a, &a).unwrap();
+        assert_eq!(diff.changed_pixels, 0);
+        assert_eq!(diff.max_difference, 0);
+        assert_eq!(diff.psnr(), None);
+        assert!(diff.heatmap.pixels().all(|p| *p == Rgba([0, 0, 0, 255])));
+    }
+
+    #[test]
+    fn size_mismatch() {
+        assert!(ImageDiff::new(&RgbaImage::new(2, 2), &RgbaImage::new(2, 3)).is_err());
+        let empty = ImageDiff::new(&RgbaImage::new(0, 0), &RgbaImage::new(0, 0)).unwrap();
+        assert_eq!(empty.total_pixels, 0);
+    }
+}
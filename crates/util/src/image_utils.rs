@@ -1,4 +1,4 @@
-use image::DynamicImage;
+use image::{DynamicImage, Rgba, Rgba32FImage, RgbaImage};
 use std::path::PathBuf;
 
 pub fn filename_hint<P: Into<PathBuf>>(path: Option<P>) -> Option<String> {
@@ -93,3 +93,91 @@ impl SizeHint {
         }
     }
 }
+
+/// Curve used to fit HDR colors into 0.0 to 1.0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMap {
+    /// Colors above 1.0 are clipped.
+    #[default]
+    Clamp,
+    Reinhard,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+impl ToneMap {
+    pub const ALL: [ToneMap; 3] = [ToneMap::Clamp, ToneMap::Reinhard, ToneMap::Aces];
+
+    #[inline]
+    pub fn apply(&self, v: f32) -> f32 {
+        match self {
+            ToneMap::Clamp => v,
+            ToneMap::Reinhard => v / (1.0 + v),
+            ToneMap::Aces => (v * (2.51 * v + 0.03)) / (v * (2.43 * v + 0.59) + 0.14),
+        }
+        .clamp(0.0, 1.0)
+    }
+}
+
+/// Converts linear HDR colors to displayable sRGB colors, `exposure` is in stops.
+pub fn tone_map(image: &Rgba32FImage, exposure: f32, tone_map: ToneMap) -> RgbaImage {
+    #[inline]
+    fn linear_to_srgb(v: f32) -> f32 {
+        if v <= 0.0031308 {
+            v * 12.92
+        } else {
+            1.055 * v.powf(1.0 / 2.4) - 0.055
+        }
+    }
+
+    let scale = 2f32.powf(exposure);
+    let mut output = RgbaImage::new(image.width(), image.height());
+    for (pixel, hdr) in output.pixels_mut().zip(image.pixels()) {
+        let color = |v: f32| (linear_to_srgb(tone_map.apply(v * scale)) * 255.0).round() as u8;
+        *pixel = Rgba([
+            color(hdr[0]),
+            color(hdr[1]),
+            color(hdr[2]),
+            (hdr[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+        ]);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tone_map_curves() {
+        let image = Rgba32FImage::from_raw(
+            4,
+            1,
+            vec![
+                0.0, 0.5, 1.0, 1.0, //
+                2.0, 0.0031308, -1.0, 0.5, //
+                0.25, 0.25, 0.25, 2.0, //
+                1.0, 1.0, 1.0, -1.0,
+            ],
+        )
+        .unwrap();
+
+        let clamp = tone_map(&image, 0.0, ToneMap::Clamp);
+        assert_eq!(clamp.get_pixel(0, 0).0, [0, 188, 255, 255]);
+        assert_eq!(clamp.get_pixel(1, 0).0, [255, 10, 0, 128]);
+        assert_eq!(clamp.get_pixel(2, 0).0, [137, 137, 137, 255]);
+        assert_eq!(clamp.get_pixel(3, 0).0, [255, 255, 255, 0]);
+
+        // One stop brighter, 0.25 becomes 0.5.
+        let exposed = tone_map(&image, 1.0, ToneMap::Clamp);
+        assert_eq!(exposed.get_pixel(2, 0).0, [188, 188, 188, 255]);
+
+        // 1.0 maps to 0.5.
+        let reinhard = tone_map(&image, 0.0, ToneMap::Reinhard);
+        assert_eq!(reinhard.get_pixel(3, 0).0, [188, 188, 188, 0]);
+
+        // 1.0 maps to about 0.8038.
+        let aces = tone_map(&image, 0.0, ToneMap::Aces);
+        assert_eq!(aces.get_pixel(3, 0).0, [232, 232, 232, 0]);
+    }
+}
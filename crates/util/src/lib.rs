@@ -1,14 +1,22 @@
 extern crate anyhow;
 extern crate flate2;
+extern crate half;
 extern crate image;
+extern crate png;
 extern crate rayon;
+extern crate resvg;
 extern crate serde;
 extern crate serde_json;
 
+pub mod animation;
 pub mod file_utils;
+pub mod image_diff;
 pub mod image_utils;
+pub mod mesh;
 pub mod pickle;
+pub mod rasterizer;
 pub mod reader;
+pub mod svg;
 pub mod texture;
 pub mod tree_fs;
 pub mod virtual_fs;
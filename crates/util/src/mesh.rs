@@ -0,0 +1,473 @@
+// Generic triangle mesh that model formats convert to, so they can share exporting & previewing.
+//
+// Uses glTF conventions: Y up, right-handed, counter-clockwise front faces & UV origin at the top
+// left.
+
+use anyhow::{anyhow, Result};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Primitive {
+    /// Material name or path, how it's found depends on the format.
+    pub material: Option<String>,
+    /// Triangle list.
+    pub indices: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    /// Empty or the same length as positions.
+    pub normals: Vec<[f32; 3]>,
+    /// Empty or the same length as positions.
+    pub uvs: Vec<[f32; 2]>,
+    pub primitives: Vec<Primitive>,
+}
+
+impl Mesh {
+    pub fn num_triangles(&self) -> usize {
+        self.primitives
+            .iter()
+            .map(|primitive| primitive.indices.len() / 3)
+            .sum()
+    }
+
+    /// Unique materials in order of use.
+    pub fn materials(&self) -> Vec<&str> {
+        let mut materials: Vec<&str> = Vec::new();
+        for material in self.primitives.iter().filter_map(|p| p.material.as_deref()) {
+            if !materials.contains(&material) {
+                materials.push(material);
+            }
+        }
+        materials
+    }
+
+    /// Minimum & maximum of positions, None if there are no positions.
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        let first = *self.positions.first()?;
+        Some(
+            self.positions
+                .iter()
+                .fold((first, first), |(min, max), position| {
+                    (
+                        std::array::from_fn(|i| min[i].min(position[i])),
+                        std::array::from_fn(|i| max[i].max(position[i])),
+                    )
+                }),
+        )
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.normals.is_empty() && self.normals.len() != self.positions.len() {
+            return Err(anyhow!("Mesh normals count doesn't match positions"));
+        }
+        if !self.uvs.is_empty() && self.uvs.len() != self.positions.len() {
+            return Err(anyhow!("Mesh UVs count doesn't match positions"));
+        }
+        if self
+            .primitives
+            .iter()
+            .flat_map(|primitive| primitive.indices.iter())
+            .any(|&index| index as usize >= self.positions.len())
+        {
+            return Err(anyhow!("Mesh index out of range"));
+        }
+        Ok(())
+    }
+
+    /// Wavefront OBJ, polygons are triangulated as fans & materials are the usemtl names.
+    pub fn read_obj<R: BufRead>(reader: R) -> Result<Mesh> {
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut normals: Vec<[f32; 3]> = Vec::new();
+        let mut uvs: Vec<[f32; 2]> = Vec::new();
+
+        let mut mesh = Mesh::default();
+        let mut vertex_normals: Vec<Option<[f32; 3]>> = Vec::new();
+        let mut vertex_uvs: Vec<Option<[f32; 2]>> = Vec::new();
+        // OBJ indexes each attribute separately.
+        let mut vertices: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+        let mut primitive = Primitive::default();
+
+        // 1-based, negative is relative to the end.
+        fn index(index: &str, len: usize) -> Result<usize> {
+            let index: isize = index.parse()?;
+            let index = if index < 0 {
+                len as isize + index
+            } else {
+                index - 1
+            };
+            usize::try_from(index)
+                .ok()
+                .filter(|&index| index < len)
+                .ok_or(anyhow!("OBJ index out of range"))
+        }
+        fn floats<const N: usize>(values: &[&str]) -> Result<[f32; N]> {
+            let mut floats = [0.0; N];
+            for (i, float) in floats.iter_mut().enumerate() {
+                *float = values.get(i).ok_or(anyhow!("OBJ missing value"))?.parse()?;
+            }
+            Ok(floats)
+        }
+
+        for line in reader.lines() {
+            let line = line?;
+            let mut parts = line.split_whitespace();
+            let Some(keyword) = parts.next() else {
+                continue;
+            };
+            let values: Vec<&str> = parts.collect();
+            match keyword {
+                "v" => positions.push(floats(&values)?),
+                "vn" => normals.push(floats(&values)?),
+                "vt" => {
+                    let [u, v] = floats(&values)?;
+                    uvs.push([u, 1.0 - v]);
+                }
+                "usemtl" => {
+                    let material = Some(values.join(" "));
+                    if primitive.material != material {
+                        mesh.primitives.push(std::mem::take(&mut primitive));
+                        primitive.material = material;
+                    }
+                }
+                "f" => {
+                    let face = values
+                        .iter()
+                        .map(|vertex| {
+                            let mut indices = vertex.split('/');
+                            let position = index(indices.next().unwrap_or(""), positions.len())?;
+                            let uv = match indices.next() {
+                                Some("") | None => None,
+                                Some(uv) => Some(index(uv, uvs.len())?),
+                            };
+                            let normal = match indices.next() {
+                                Some("") | None => None,
+                                Some(normal) => Some(index(normal, normals.len())?),
+                            };
+                            let key = (position, uv, normal);
+                            if let Some(&index) = vertices.get(&key) {
+                                return Ok(index);
+                            }
+                            let index = mesh.positions.len() as u32;
+                            mesh.positions.push(positions[position]);
+                            vertex_uvs.push(uv.map(|uv| uvs[uv]));
+                            vertex_normals.push(normal.map(|normal| normals[normal]));
+                            vertices.insert(key, index);
+                            Ok(index)
+                        })
+                        .collect::<Result<Vec<u32>>>()?;
+                    for i in 2..face.len() {
+                        primitive.indices.extend([face[0], face[i - 1], face[i]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        mesh.primitives.push(primitive);
+        mesh.primitives
+            .retain(|primitive| !primitive.indices.is_empty());
+
+        // Vertices without an attribute get zeroes if any other vertex has it.
+        if vertex_normals.iter().any(|normal| normal.is_some()) {
+            mesh.normals = vertex_normals
+                .into_iter()
+                .map(|normal| normal.unwrap_or_default())
+                .collect();
+        }
+        if vertex_uvs.iter().any(|uv| uv.is_some()) {
+            mesh.uvs = vertex_uvs
+                .into_iter()
+                .map(|uv| uv.unwrap_or_default())
+                .collect();
+        }
+        Ok(mesh)
+    }
+
+    /// Wavefront OBJ, `mtl_filename` is referenced with mtllib if materials are written with
+    /// [`Mesh::write_mtl`].
+    pub fn write_obj<W: Write>(&self, mut writer: W, mtl_filename: Option<&str>) -> Result<()> {
+        self.validate()?;
+        if let Some(mtl_filename) = mtl_filename {
+            writeln!(writer, "mtllib {}", mtl_filename)?;
+        }
+        for [x, y, z] in &self.positions {
+            writeln!(writer, "v {} {} {}", x, y, z)?;
+        }
+        for [x, y, z] in &self.normals {
+            writeln!(writer, "vn {} {} {}", x, y, z)?;
+        }
+        for [u, v] in &self.uvs {
+            // OBJ UV origin is the bottom left.
+            writeln!(writer, "vt {} {}", u, 1.0 - v)?;
+        }
+
+        let vertex = |index: u32| -> String {
+            let index = index + 1;
+            match (self.uvs.is_empty(), self.normals.is_empty()) {
+                (true, true) => format!("{}", index),
+                (false, true) => format!("{}/{}", index, index),
+                (true, false) => format!("{}//{}", index, index),
+                (false, false) => format!("{}/{}/{}", index, index, index),
+            }
+        };
+        for primitive in &self.primitives {
+            if let Some(material) = &primitive.material {
+                writeln!(writer, "usemtl {}", material)?;
+            }
+            for triangle in primitive.indices.chunks_exact(3) {
+                writeln!(
+                    writer,
+                    "f {} {} {}",
+                    vertex(triangle[0]),
+                    vertex(triangle[1]),
+                    vertex(triangle[2])
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Material library with an empty material for each used material, so they keep their names.
+    pub fn write_mtl<W: Write>(&self, mut writer: W) -> Result<()> {
+        for material in self.materials() {
+            writeln!(writer, "newmtl {}", material)?;
+            writeln!(writer, "Kd 1 1 1")?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Binary glTF, materials only have names.
+    pub fn write_glb<W: Write>(&self, mut writer: W) -> Result<()> {
+        self.validate()?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut buffer_views: Vec<serde_json::Value> = Vec::new();
+        let mut accessors: Vec<serde_json::Value> = Vec::new();
+        // Returns the accessor index.
+        let mut push_accessor =
+            |data: Vec<u8>, target: u32, accessor: serde_json::Value| -> usize {
+                while !buffer.len().is_multiple_of(4) {
+                    buffer.push(0);
+                }
+                buffer_views.push(serde_json::json!({
+                    "buffer": 0,
+                    "byteOffset": buffer.len(),
+                    "byteLength": data.len(),
+                    "target": target,
+                }));
+                buffer.extend_from_slice(&data);
+                let mut accessor = accessor;
+                accessor["bufferView"] = (buffer_views.len() - 1).into();
+                accessors.push(accessor);
+                accessors.len() - 1
+            };
+        const ARRAY_BUFFER: u32 = 34962;
+        const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+        const FLOAT: u32 = 5126;
+        const UNSIGNED_INT: u32 = 5125;
+
+        let (min, max) = self.bounds().unwrap_or(([0.0; 3], [0.0; 3]));
+        let mut attributes = serde_json::json!({
+            "POSITION": push_accessor(
+                self.positions.iter().flatten().flat_map(|v| v.to_le_bytes()).collect(),
+                ARRAY_BUFFER,
+                serde_json::json!({
+                    "componentType": FLOAT,
+                    "count": self.positions.len(),
+                    "type": "VEC3",
+                    "min": min,
+                    "max": max,
+                }),
+            ),
+        });
+        if !self.normals.is_empty() {
+            attributes["NORMAL"] = push_accessor(
+                self.normals
+                    .iter()
+                    .flatten()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect(),
+                ARRAY_BUFFER,
+                serde_json::json!({
+                    "componentType": FLOAT,
+                    "count": self.normals.len(),
+                    "type": "VEC3",
+                }),
+            )
+            .into();
+        }
+        if !self.uvs.is_empty() {
+            attributes["TEXCOORD_0"] = push_accessor(
+                self.uvs
+                    .iter()
+                    .flatten()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect(),
+                ARRAY_BUFFER,
+                serde_json::json!({
+                    "componentType": FLOAT,
+                    "count": self.uvs.len(),
+                    "type": "VEC2",
+                }),
+            )
+            .into();
+        }
+
+        let materials = self.materials();
+        let primitives: Vec<serde_json::Value> = self
+            .primitives
+            .iter()
+            .filter(|primitive| !primitive.indices.is_empty())
+            .map(|primitive| {
+                let mut json = serde_json::json!({
+                    "attributes": attributes,
+                    "indices": push_accessor(
+                        primitive.indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+                        ELEMENT_ARRAY_BUFFER,
+                        serde_json::json!({
+                            "componentType": UNSIGNED_INT,
+                            "count": primitive.indices.len(),
+                            "type": "SCALAR",
+                        }),
+                    ),
+                });
+                if let Some(material) = &primitive.material {
+                    json["material"] = materials.iter().position(|m| m == material).unwrap().into();
+                }
+                json
+            })
+            .collect();
+        while !buffer.len().is_multiple_of(4) {
+            buffer.push(0);
+        }
+
+        let mut json = serde_json::json!({
+            "asset": { "version": "2.0", "generator": "universal-explorer" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": primitives }],
+            "buffers": [{ "byteLength": buffer.len() }],
+            "bufferViews": buffer_views,
+            "accessors": accessors,
+        });
+        // glTF arrays can't be empty.
+        if !materials.is_empty() {
+            json["materials"] = materials
+                .iter()
+                .map(|material| serde_json::json!({ "name": material }))
+                .collect();
+        }
+        let mut json = serde_json::to_vec(&json)?;
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+
+        // https://registry.khronos.org/glTF/specs/2.0/glTF-2.0.html#binary-gltf-layout
+        let length = 12 + 8 + json.len() + 8 + buffer.len();
+        writer.write_all(b"glTF")?;
+        writer.write_all(&2u32.to_le_bytes())?;
+        writer.write_all(&(length as u32).to_le_bytes())?;
+        writer.write_all(&(json.len() as u32).to_le_bytes())?;
+        writer.write_all(b"JSON")?;
+        writer.write_all(&json)?;
+        writer.write_all(&(buffer.len() as u32).to_le_bytes())?;
+        writer.write_all(b"BIN\0")?;
+        writer.write_all(&buffer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> Mesh {
+        Mesh {
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            normals: vec![[0.0, 0.0, 1.0]; 4],
+            uvs: vec![[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]],
+            primitives: vec![
+                Primitive {
+                    material: Some("first".to_owned()),
+                    indices: vec![0, 1, 2],
+                },
+                Primitive {
+                    material: Some("second".to_owned()),
+                    indices: vec![0, 2, 3],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn obj_round_trip() {
+        let mesh = quad();
+        let mut obj: Vec<u8> = Vec::new();
+        mesh.write_obj(&mut obj, Some("quad.mtl")).unwrap();
+        let read = Mesh::read_obj(obj.as_slice()).unwrap();
+
+        assert_eq!(read.num_triangles(), 2);
+        assert_eq!(read.materials(), vec!["first", "second"]);
+        for primitive in &read.primitives {
+            for &index in &primitive.indices {
+                let position = read.positions[index as usize];
+                let original = mesh.positions.iter().position(|&p| p == position).unwrap();
+                assert_eq!(read.uvs[index as usize], mesh.uvs[original]);
+                assert_eq!(read.normals[index as usize], mesh.normals[original]);
+            }
+        }
+        assert_eq!(read.bounds(), Some(([0.0, 0.0, 0.0], [1.0, 1.0, 0.0])));
+    }
+
+    #[test]
+    fn obj_negative_indices() {
+        let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf -4 -3 -2 -1\n";
+        let mesh = Mesh::read_obj(obj.as_bytes()).unwrap();
+        assert_eq!(mesh.num_triangles(), 2);
+        assert!(Mesh::read_obj("v 0 0 0\nf 1 2 3\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn glb_layout() {
+        let mut glb: Vec<u8> = Vec::new();
+        quad().write_glb(&mut glb).unwrap();
+
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap());
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(u32_at(4), 2);
+        assert_eq!(u32_at(8) as usize, glb.len());
+        let json_length = u32_at(12) as usize;
+        assert_eq!(json_length % 4, 0);
+        assert_eq!(&glb[16..20], b"JSON");
+        let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        assert_eq!(json["materials"][1]["name"], "second");
+        let bin = 20 + json_length;
+        assert_eq!(&glb[bin + 4..bin + 8], b"BIN\0");
+        assert_eq!(
+            json["buffers"][0]["byteLength"].as_u64().unwrap() as usize,
+            glb.len() - bin - 8
+        );
+    }
+
+    #[test]
+    fn invalid_mesh() {
+        let mut mesh = quad();
+        mesh.primitives[0].indices.push(4);
+        assert!(mesh.write_glb(Vec::new()).is_err());
+        mesh.primitives[0].indices.pop();
+        mesh.uvs.pop();
+        assert!(mesh.write_obj(Vec::new(), None).is_err());
+    }
+}
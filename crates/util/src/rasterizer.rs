@@ -0,0 +1,249 @@
+// Software renderer for previewing meshes without a GPU, also used for thumbnails.
+//
+// Flat shaded with a z-buffer, lit from the camera. Faces are drawn from both sides because
+// winding isn't reliable across formats.
+
+use crate::mesh::Mesh;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+type Vec3 = [f32; 3];
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(a: Vec3) -> Vec3 {
+    let length = dot(a, a).sqrt();
+    if length == 0.0 {
+        return a;
+    }
+    [a[0] / length, a[1] / length, a[2] / length]
+}
+
+/// Orbits around the center of the mesh bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// Radians around the up axis.
+    pub yaw: f32,
+    /// Radians, positive looks down on the mesh.
+    pub pitch: f32,
+    /// Distance multiplier, 1.0 fits the whole mesh in view.
+    pub zoom: f32,
+    /// Vertical field of view in radians.
+    pub fov: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            yaw: std::f32::consts::FRAC_PI_4,
+            pitch: 0.4,
+            zoom: 1.0,
+            fov: 50f32.to_radians(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: u32,
+    pub camera: Camera,
+    pub background: Rgba<u8>,
+    pub color: Rgba<u8>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            width: 256,
+            height: 256,
+            camera: Camera::default(),
+            background: Rgba([0, 0, 0, 0]),
+            color: Rgba([200, 200, 200, 255]),
+        }
+    }
+}
+
+const AMBIENT: f32 = 0.25;
+
+pub fn render(mesh: &Mesh, options: &RenderOptions) -> DynamicImage {
+    let (width, height) = (options.width.max(1), options.height.max(1));
+    let mut image = RgbaImage::from_pixel(width, height, options.background);
+    let Some((min, max)) = mesh.bounds() else {
+        return DynamicImage::ImageRgba8(image);
+    };
+
+    let center: Vec3 = std::array::from_fn(|i| (min[i] + max[i]) / 2.0);
+    let radius = (dot(sub(max, min), sub(max, min)).sqrt() / 2.0).max(f32::EPSILON);
+    let camera = options.camera;
+    let tan_half_fov = (camera.fov / 2.0).tan();
+    let distance = radius / (camera.fov / 2.0).sin() * camera.zoom.max(f32::EPSILON);
+    let near = distance * 0.001;
+    let focal = (width.min(height) as f32 / 2.0) / tan_half_fov;
+
+    let (yaw_sin, yaw_cos) = camera.yaw.sin_cos();
+    let (pitch_sin, pitch_cos) = camera.pitch.sin_cos();
+    // View space, camera looking down -Z.
+    let view = |position: Vec3| -> Vec3 {
+        let [x, y, z] = sub(position, center);
+        let (x, z) = (x * yaw_cos - z * yaw_sin, x * yaw_sin + z * yaw_cos);
+        let (y, z) = (y * pitch_cos - z * pitch_sin, y * pitch_sin + z * pitch_cos);
+        [x, y, z - distance]
+    };
+    let positions: Vec<Vec3> = mesh.positions.iter().map(|&p| view(p)).collect();
+    let light = normalize([0.3, 0.5, 1.0]);
+
+    let mut depth_buffer = vec![0.0f32; (width * height) as usize];
+    let half = (width as f32 / 2.0, height as f32 / 2.0);
+
+    for primitive in &mesh.primitives {
+        for triangle in primitive.indices.chunks_exact(3) {
+            // Out of range indices skip the triangle, meshes aren't validated before previewing.
+            let vertex = |i: usize| positions.get(triangle[i] as usize).copied();
+            let (Some(a), Some(b), Some(c)) = (vertex(0), vertex(1), vertex(2)) else {
+                continue;
+            };
+            // Triangles crossing the near plane are dropped instead of clipped.
+            if -a[2] < near || -b[2] < near || -c[2] < near {
+                continue;
+            }
+
+            let normal = normalize(cross(sub(b, a), sub(c, a)));
+            let intensity = AMBIENT + (1.0 - AMBIENT) * dot(normal, light).abs();
+            let color = Rgba([
+                (options.color[0] as f32 * intensity) as u8,
+                (options.color[1] as f32 * intensity) as u8,
+                (options.color[2] as f32 * intensity) as u8,
+                options.color[3],
+            ]);
+
+            // Screen space x & y, with 1/depth that interpolates linearly.
+            let [a, b, c] = [a, b, c].map(|[x, y, z]| {
+                let inverse_depth = -1.0 / z;
+                [
+                    half.0 + x * focal * inverse_depth,
+                    half.1 - y * focal * inverse_depth,
+                    inverse_depth,
+                ]
+            });
+            let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+
+            let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+            let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+            let max_x = (a[0].max(b[0]).max(c[0]).ceil() as i64).clamp(0, width as i64) as u32;
+            let max_y = (a[1].max(b[1]).max(c[1]).ceil() as i64).clamp(0, height as i64) as u32;
+
+            for y in min_y..max_y {
+                for x in min_x..max_x {
+                    let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                    let edge = |p: Vec3, q: Vec3| {
+                        (q[0] - p[0]) * (py - p[1]) - (q[1] - p[1]) * (px - p[0])
+                    };
+                    let (w0, w1, w2) = (edge(b, c) / area, edge(c, a) / area, edge(a, b) / area);
+                    if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                        continue;
+                    }
+                    let inverse_depth = w0 * a[2] + w1 * b[2] + w2 * c[2];
+                    let index = (y * width + x) as usize;
+                    if inverse_depth > depth_buffer[index] {
+                        depth_buffer[index] = inverse_depth;
+                        image.put_pixel(x, y, color);
+                    }
+                }
+            }
+        }
+    }
+
+    DynamicImage::ImageRgba8(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::Primitive;
+
+    /// Small quad facing the camera at z = 1, in front of a larger tilted quad around z = -2.
+    fn overlapping_quads(indices: Vec<u32>) -> Mesh {
+        Mesh {
+            positions: vec![
+                [-0.5, -0.5, 1.0],
+                [0.5, -0.5, 1.0],
+                [0.5, 0.5, 1.0],
+                [-0.5, 0.5, 1.0],
+                [-1.0, -1.0, -1.0],
+                [1.0, -1.0, -1.0],
+                [1.0, 1.0, -3.0],
+                [-1.0, 1.0, -3.0],
+            ],
+            primitives: vec![Primitive {
+                material: None,
+                indices,
+            }],
+            ..Default::default()
+        }
+    }
+
+    const NEAR: [u32; 6] = [0, 1, 2, 0, 2, 3];
+    const FAR: [u32; 6] = [4, 5, 6, 4, 6, 7];
+
+    fn options() -> RenderOptions {
+        RenderOptions {
+            width: 32,
+            height: 32,
+            camera: Camera {
+                yaw: 0.0,
+                pitch: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn center(mesh: &Mesh) -> Rgba<u8> {
+        *render(mesh, &options()).to_rgba8().get_pixel(16, 16)
+    }
+
+    #[test]
+    fn depth_test() {
+        let near = center(&overlapping_quads(NEAR.to_vec()));
+        let far = center(&overlapping_quads(FAR.to_vec()));
+        assert_ne!(near, options().background);
+        assert_ne!(far, options().background);
+        assert_ne!(near, far);
+
+        // The nearer quad wins whichever is drawn last.
+        assert_eq!(center(&overlapping_quads([NEAR, FAR].concat())), near);
+        assert_eq!(center(&overlapping_quads([FAR, NEAR].concat())), near);
+    }
+
+    #[test]
+    fn invalid_indices() {
+        let near = center(&overlapping_quads(NEAR.to_vec()));
+        assert_eq!(
+            center(&overlapping_quads([&NEAR[..], &[0, 1, 99]].concat())),
+            near
+        );
+    }
+
+    #[test]
+    fn empty_mesh() {
+        let image = render(&Mesh::default(), &options()).to_rgba8();
+        assert!(image.pixels().all(|pixel| *pixel == options().background));
+    }
+}
@@ -0,0 +1,90 @@
+// Text isn't rendered, no fonts are loaded.
+
+use anyhow::{anyhow, Result};
+use image::RgbaImage;
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{self, TreeParsing},
+};
+use std::io::Read;
+
+pub struct Svg {
+    tree: resvg::Tree,
+}
+
+impl Svg {
+    pub fn parse(data: &[u8]) -> Result<Svg> {
+        let tree = usvg::Tree::from_data(data, &usvg::Options::default())?;
+        Ok(Svg {
+            tree: resvg::Tree::from_usvg(&tree),
+        })
+    }
+
+    pub fn load(mut data: impl Read) -> Result<Svg> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf)?;
+        Svg::parse(&buf)
+    }
+
+    /// Size the SVG is meant to be shown at, in pixels.
+    pub fn width(&self) -> f32 {
+        self.tree.size.width()
+    }
+    pub fn height(&self) -> f32 {
+        self.tree.size.height()
+    }
+
+    fn render_transform(&self, width: u32, height: u32, transform: Transform) -> Result<RgbaImage> {
+        let mut pixmap = Pixmap::new(width, height).ok_or(anyhow!(
+            "Invalid SVG render size {}x{}",
+            width,
+            height
+        ))?;
+        self.tree.render(transform, &mut pixmap.as_mut());
+        let pixels = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+        RgbaImage::from_raw(width, height, pixels).ok_or(anyhow!("Failed to convert SVG render"))
+    }
+
+    /// Stretched to `width` & `height`, so keep the aspect ratio to not distort it.
+    pub fn render(&self, width: u32, height: u32) -> Result<RgbaImage> {
+        self.render_transform(
+            width,
+            height,
+            Transform::from_scale(width as f32 / self.width(), height as f32 / self.height()),
+        )
+    }
+
+    /// Part of the SVG scaled by `scale`, `x` & `y` are the top left of the part in scaled pixels.
+    ///
+    /// Lets zoomed in views only render what's visible.
+    pub fn render_region(
+        &self,
+        width: u32,
+        height: u32,
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> Result<RgbaImage> {
+        self.render_transform(
+            width,
+            height,
+            Transform::from_row(scale, 0.0, 0.0, scale, -x, -y),
+        )
+    }
+
+    /// Largest size that fits inside `width` & `height` while keeping the aspect ratio.
+    pub fn fit_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scale = (width as f32 / self.width()).min(height as f32 / self.height());
+        (
+            ((self.width() * scale).round() as u32).max(1),
+            ((self.height() * scale).round() as u32).max(1),
+        )
+    }
+}
@@ -0,0 +1,1008 @@
+// https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html#ASTC
+//
+// Only the LDR profile is supported, HDR endpoints decode to the error color like LDR hardware.
+
+use image::{Rgba, RgbaImage};
+
+/// Decoders are required to output magenta for invalid blocks.
+const ERROR_COLOR: Rgba<u8> = Rgba([255, 0, 255, 255]);
+
+/// Largest block footprint is 12x12.
+const MAX_TEXELS: usize = 144;
+
+/// Reads bits of a 128 bit block, lowest bit first. Bits past `end` read as 0.
+struct BlockBits {
+    data: u128,
+    position: u32,
+    end: u32,
+}
+
+impl BlockBits {
+    fn new(data: u128, position: u32, end: u32) -> Self {
+        Self {
+            data,
+            position,
+            end,
+        }
+    }
+
+    #[inline(always)]
+    fn read(&mut self, bits: u32) -> u32 {
+        let bits_left = self.end.saturating_sub(self.position).min(bits);
+        let value = if bits_left == 0 {
+            0
+        } else {
+            ((self.data >> self.position) as u32) & (((1u64 << bits_left) - 1) as u32)
+        };
+        self.position += bits;
+        value
+    }
+}
+
+#[inline(always)]
+fn bits(data: u128, low: u32, count: u32) -> u32 {
+    ((data >> low) as u32) & (((1u64 << count) - 1) as u32)
+}
+
+/// Integer sequence encoding ranges, in the order the block mode & color quantization use them.
+///
+/// (trits, quints, bits)
+const ISE_RANGES: [(u32, u32, u32); 21] = [
+    (0, 0, 1), // 2
+    (1, 0, 0), // 3
+    (0, 0, 2), // 4
+    (0, 1, 0), // 5
+    (1, 0, 1), // 6
+    (0, 0, 3), // 8
+    (0, 1, 1), // 10
+    (1, 0, 2), // 12
+    (0, 0, 4), // 16
+    (0, 1, 2), // 20
+    (1, 0, 3), // 24
+    (0, 0, 5), // 32
+    (0, 1, 3), // 40
+    (1, 0, 4), // 48
+    (0, 0, 6), // 64
+    (0, 1, 4), // 80
+    (1, 0, 5), // 96
+    (0, 0, 7), // 128
+    (0, 1, 5), // 160
+    (1, 0, 6), // 192
+    (0, 0, 8), // 256
+];
+
+fn ise_bit_count(count: u32, range: usize) -> u32 {
+    let (trits, quints, bits) = ISE_RANGES[range];
+    count * bits + trits * (count * 8).div_ceil(5) + quints * (count * 7).div_ceil(3)
+}
+
+/// Integer sequence value, the trit or quint part & the bits part are kept separate for
+/// unquantization.
+#[derive(Clone, Copy, Default)]
+struct IseValue {
+    tq: u32,
+    bits: u32,
+}
+
+fn decode_trits(t: u32) -> [u32; 5] {
+    let bit = |v: u32, i: u32| (v >> i) & 1;
+    let (c, t4, t3);
+    if (t >> 2) & 0b111 == 0b111 {
+        c = (((t >> 5) & 0b111) << 2) | (t & 0b11);
+        t4 = 2;
+        t3 = 2;
+    } else {
+        c = t & 0b11111;
+        if (t >> 5) & 0b11 == 0b11 {
+            t4 = 2;
+            t3 = bit(t, 7);
+        } else {
+            t4 = bit(t, 7);
+            t3 = (t >> 5) & 0b11;
+        }
+    }
+    let (t2, t1, t0);
+    if c & 0b11 == 0b11 {
+        t2 = 2;
+        t1 = bit(c, 4);
+        t0 = (bit(c, 3) << 1) | (bit(c, 2) & !bit(c, 3) & 1);
+    } else if (c >> 2) & 0b11 == 0b11 {
+        t2 = 2;
+        t1 = 2;
+        t0 = c & 0b11;
+    } else {
+        t2 = bit(c, 4);
+        t1 = (c >> 2) & 0b11;
+        t0 = (bit(c, 1) << 1) | (bit(c, 0) & !bit(c, 1) & 1);
+    }
+    [t0, t1, t2, t3, t4]
+}
+
+fn decode_quints(q: u32) -> [u32; 3] {
+    let bit = |v: u32, i: u32| (v >> i) & 1;
+    if (q >> 1) & 0b11 == 0b11 && (q >> 5) & 0b11 == 0 {
+        let not_q0 = !bit(q, 0) & 1;
+        let q2 = (bit(q, 0) << 2) | ((bit(q, 4) & not_q0) << 1) | (bit(q, 3) & not_q0);
+        return [4, 4, q2];
+    }
+    let (q2, c);
+    if (q >> 1) & 0b11 == 0b11 {
+        q2 = 4;
+        c = (((q >> 3) & 0b11) << 3) | ((!(q >> 5) & 0b11) << 1) | bit(q, 0);
+    } else {
+        q2 = (q >> 5) & 0b11;
+        c = q & 0b11111;
+    }
+    if c & 0b111 == 0b101 {
+        [(c >> 3) & 0b11, 4, q2]
+    } else {
+        [c & 0b111, (c >> 3) & 0b11, q2]
+    }
+}
+
+/// Decode `count` values of an integer sequence, bits past the end of the sequence read as 0.
+fn decode_ise(data: u128, start: u32, count: usize, range: usize, out: &mut [IseValue]) {
+    let (trits, quints, bits) = ISE_RANGES[range];
+    let reader = &mut BlockBits::new(data, start, start + ise_bit_count(count as u32, range));
+    if trits == 1 {
+        for chunk in out[..count].chunks_mut(5) {
+            let mut values = [0; 5];
+            let mut t = 0;
+            for (i, (value, t_bits)) in values.iter_mut().zip([2, 2, 1, 2, 1]).enumerate() {
+                *value = reader.read(bits);
+                t |= reader.read(t_bits) << [0, 2, 4, 5, 7][i];
+            }
+            for ((out, value), tq) in chunk.iter_mut().zip(values).zip(decode_trits(t)) {
+                *out = IseValue { tq, bits: value };
+            }
+        }
+    } else if quints == 1 {
+        for chunk in out[..count].chunks_mut(3) {
+            let mut values = [0; 3];
+            let mut q = 0;
+            for (i, (value, q_bits)) in values.iter_mut().zip([3, 2, 2]).enumerate() {
+                *value = reader.read(bits);
+                q |= reader.read(q_bits) << [0, 3, 5][i];
+            }
+            for ((out, value), tq) in chunk.iter_mut().zip(values).zip(decode_quints(q)) {
+                *out = IseValue { tq, bits: value };
+            }
+        }
+    } else {
+        for out in out[..count].iter_mut() {
+            *out = IseValue {
+                tq: 0,
+                bits: reader.read(bits),
+            };
+        }
+    }
+}
+
+/// Unquantize a color endpoint value to 0..=255.
+fn unquantize_color(value: IseValue, range: usize) -> u8 {
+    let (trits, quints, bits) = ISE_RANGES[range];
+    if trits == 0 && quints == 0 {
+        // Bit replication.
+        let mut result = value.bits << (8 - bits);
+        let mut filled = bits;
+        while filled < 8 {
+            result |= result >> filled;
+            filled *= 2;
+        }
+        return result as u8;
+    }
+
+    let a = if value.bits & 1 == 1 { 0x1FF } else { 0 };
+    let (b, c) = {
+        let bit = |i: u32| (value.bits >> i) & 1;
+        let (b1, b2, b3, b4, b5) = (bit(1), bit(2), bit(3), bit(4), bit(5));
+        match (trits, bits) {
+            (1, 1) => (0, 204),
+            (1, 2) => ((b1 << 8) | (b1 << 4) | (b1 << 2) | (b1 << 1), 93),
+            (1, 3) => {
+                let cb = (b2 << 1) | b1;
+                ((cb << 7) | (cb << 2) | cb, 44)
+            }
+            (1, 4) => {
+                let dcb = (b3 << 2) | (b2 << 1) | b1;
+                ((dcb << 6) | dcb, 22)
+            }
+            (1, 5) => {
+                let edcb = (b4 << 3) | (b3 << 2) | (b2 << 1) | b1;
+                ((edcb << 5) | (edcb >> 2), 11)
+            }
+            (1, _) => {
+                let fedcb = (b5 << 4) | (b4 << 3) | (b3 << 2) | (b2 << 1) | b1;
+                ((fedcb << 4) | (fedcb >> 4), 5)
+            }
+            (_, 1) => (0, 113),
+            (_, 2) => ((b1 << 8) | (b1 << 3) | (b1 << 2), 54),
+            (_, 3) => {
+                let cb = (b2 << 1) | b1;
+                ((cb << 7) | (cb << 1) | (cb >> 1), 26)
+            }
+            (_, 4) => {
+                let dcb = (b3 << 2) | (b2 << 1) | b1;
+                ((dcb << 6) | (dcb >> 1), 13)
+            }
+            (_, _) => {
+                let edcb = (b4 << 3) | (b3 << 2) | (b2 << 1) | b1;
+                ((edcb << 5) | (edcb >> 3), 6)
+            }
+        }
+    };
+    let t = (value.tq * c + b) ^ a;
+    ((a & 0x80) | (t >> 2)) as u8
+}
+
+/// Unquantize a weight value to 0..=64.
+fn unquantize_weight(value: IseValue, range: usize) -> u8 {
+    let (trits, quints, bits) = ISE_RANGES[range];
+    let result = if trits == 0 && quints == 0 {
+        // Bit replication.
+        let mut result = value.bits << (6 - bits);
+        let mut filled = bits;
+        while filled < 6 {
+            result |= result >> filled;
+            filled *= 2;
+        }
+        result
+    } else if bits == 0 {
+        match (trits, value.tq) {
+            (1, tq) => [0, 32, 63][tq as usize],
+            (_, tq) => [0, 16, 32, 47, 63][tq as usize],
+        }
+    } else {
+        let a = if value.bits & 1 == 1 { 0x7F } else { 0 };
+        let (b1, b2) = ((value.bits >> 1) & 1, (value.bits >> 2) & 1);
+        let (b, c) = match (trits, bits) {
+            (1, 1) => (0, 50),
+            (1, 2) => ((b1 << 6) | (b1 << 2) | b1, 23),
+            (1, _) => {
+                let cb = (b2 << 1) | b1;
+                ((cb << 5) | cb, 11)
+            }
+            (_, 1) => (0, 28),
+            (_, _) => ((b1 << 6) | (b1 << 1), 13),
+        };
+        let t = (value.tq * c + b) ^ a;
+        (a & 0x20) | (t >> 2)
+    };
+    if result > 32 {
+        (result + 1) as u8
+    } else {
+        result as u8
+    }
+}
+
+struct BlockMode {
+    grid_width: u32,
+    grid_height: u32,
+    dual_plane: bool,
+    weight_range: usize,
+}
+
+fn decode_block_mode(mode: u32) -> Option<BlockMode> {
+    let mut quant = (mode >> 4) & 1;
+    let mut high_precision = (mode >> 9) & 1;
+    let mut dual_plane = (mode >> 10) & 1;
+    let a = (mode >> 5) & 0b11;
+
+    let (grid_width, grid_height);
+    if mode & 0b11 != 0 {
+        quant |= (mode & 0b11) << 1;
+        let b = (mode >> 7) & 0b11;
+        (grid_width, grid_height) = match (mode >> 2) & 0b11 {
+            0 => (b + 4, a + 2),
+            1 => (b + 8, a + 2),
+            2 => (a + 2, b + 8),
+            _ if mode & 0x100 != 0 => ((b & 1) + 2, a + 2),
+            _ => (a + 2, (b & 1) + 6),
+        };
+    } else {
+        quant |= ((mode >> 2) & 0b11) << 1;
+        if (mode >> 2) & 0b11 == 0 {
+            return None;
+        }
+        let b = (mode >> 9) & 0b11;
+        (grid_width, grid_height) = match (mode >> 7) & 0b11 {
+            0 => (12, a + 2),
+            1 => (a + 2, 12),
+            2 => {
+                high_precision = 0;
+                dual_plane = 0;
+                (a + 6, b + 6)
+            }
+            _ => match a {
+                0 => (6, 10),
+                1 => (10, 6),
+                _ => return None,
+            },
+        };
+    }
+
+    let weight_range = (quant - 2 + 6 * high_precision) as usize;
+    let weight_count = grid_width * grid_height * (dual_plane + 1);
+    let weight_bits = ise_bit_count(weight_count, weight_range);
+    if weight_count > 64 || !(24..=96).contains(&weight_bits) {
+        return None;
+    }
+
+    Some(BlockMode {
+        grid_width,
+        grid_height,
+        dual_plane: dual_plane == 1,
+        weight_range,
+    })
+}
+
+fn hash52(mut p: u32) -> u32 {
+    p ^= p >> 15;
+    p = p.wrapping_sub(p << 17);
+    p = p.wrapping_add(p << 7);
+    p = p.wrapping_add(p << 4);
+    p ^= p >> 5;
+    p = p.wrapping_add(p << 16);
+    p ^= p >> 7;
+    p ^= p >> 3;
+    p ^= p << 6;
+    p ^= p >> 17;
+    p
+}
+
+fn select_partition(seed: u32, x: u32, y: u32, partition_count: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block {
+        (x << 1, y << 1)
+    } else {
+        (x, y)
+    };
+    let seed = seed + (partition_count - 1) * 1024;
+    let rnum = hash52(seed);
+
+    let mut seeds = [
+        rnum,
+        rnum >> 4,
+        rnum >> 8,
+        rnum >> 12,
+        rnum >> 16,
+        rnum >> 20,
+        rnum >> 24,
+        rnum >> 28,
+    ]
+    .map(|s| {
+        let s = s & 0xF;
+        s * s
+    });
+
+    let (sh1, sh2) = if seed & 1 != 0 {
+        (
+            if seed & 2 != 0 { 4 } else { 5 },
+            if partition_count == 3 { 6 } else { 5 },
+        )
+    } else {
+        (
+            if partition_count == 3 { 6 } else { 5 },
+            if seed & 2 != 0 { 4 } else { 5 },
+        )
+    };
+    for (i, seed) in seeds.iter_mut().enumerate() {
+        *seed >>= if i % 2 == 0 { sh1 } else { sh2 };
+    }
+
+    // The z seeds aren't needed, only 2D textures are supported.
+    let a = (seeds[0] * x + seeds[1] * y + (rnum >> 14)) & 0x3F;
+    let b = (seeds[2] * x + seeds[3] * y + (rnum >> 10)) & 0x3F;
+    let c = if partition_count >= 3 {
+        (seeds[4] * x + seeds[5] * y + (rnum >> 6)) & 0x3F
+    } else {
+        0
+    };
+    let d = if partition_count >= 4 {
+        (seeds[6] * x + seeds[7] * y + (rnum >> 2)) & 0x3F
+    } else {
+        0
+    };
+
+    if a >= b && a >= c && a >= d {
+        0
+    } else if b >= c && b >= d {
+        1
+    } else if c >= d {
+        2
+    } else {
+        3
+    }
+}
+
+#[inline(always)]
+fn bit_transfer_signed(a: i32, b: i32) -> (i32, i32) {
+    let b = (b >> 1) | (a & 0x80);
+    let a = (a >> 1) & 0x3F;
+    let a = if a & 0x20 != 0 { a - 0x40 } else { a };
+    (a, b)
+}
+
+#[inline(always)]
+fn blue_contract(r: i32, g: i32, b: i32, a: i32) -> [i32; 4] {
+    [(r + b) >> 1, (g + b) >> 1, b, a]
+}
+
+/// Decode endpoints of a color endpoint mode, `None` if it's an HDR mode.
+fn decode_endpoints(mode: u32, v: &[i32]) -> Option<[[u8; 4]; 2]> {
+    let endpoints = match mode {
+        0 => [[v[0], v[0], v[0], 255], [v[1], v[1], v[1], 255]],
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xC0);
+            let l1 = (l0 + (v[1] & 0x3F)).min(255);
+            [[l0, l0, l0, 255], [l1, l1, l1, 255]]
+        }
+        4 => [[v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]],
+        5 => {
+            let (v1, v0) = bit_transfer_signed(v[1], v[0]);
+            let (v3, v2) = bit_transfer_signed(v[3], v[2]);
+            let l1 = v0 + v1;
+            [[v0, v0, v0, v2], [l1, l1, l1, v2 + v3]]
+        }
+        6 => [
+            [
+                (v[0] * v[3]) >> 8,
+                (v[1] * v[3]) >> 8,
+                (v[2] * v[3]) >> 8,
+                255,
+            ],
+            [v[0], v[1], v[2], 255],
+        ],
+        8 | 12 => {
+            let (a0, a1) = if mode == 12 { (v[6], v[7]) } else { (255, 255) };
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                [[v[0], v[2], v[4], a0], [v[1], v[3], v[5], a1]]
+            } else {
+                [
+                    blue_contract(v[1], v[3], v[5], a1),
+                    blue_contract(v[0], v[2], v[4], a0),
+                ]
+            }
+        }
+        9 | 13 => {
+            let (v1, v0) = bit_transfer_signed(v[1], v[0]);
+            let (v3, v2) = bit_transfer_signed(v[3], v[2]);
+            let (v5, v4) = bit_transfer_signed(v[5], v[4]);
+            let (v7, v6) = if mode == 13 {
+                bit_transfer_signed(v[7], v[6])
+            } else {
+                (0, 255)
+            };
+            if v1 + v3 + v5 >= 0 {
+                [[v0, v2, v4, v6], [v0 + v1, v2 + v3, v4 + v5, v6 + v7]]
+            } else {
+                [
+                    blue_contract(v0 + v1, v2 + v3, v4 + v5, v6 + v7),
+                    blue_contract(v0, v2, v4, v6),
+                ]
+            }
+        }
+        10 => [
+            [
+                (v[0] * v[3]) >> 8,
+                (v[1] * v[3]) >> 8,
+                (v[2] * v[3]) >> 8,
+                v[4],
+            ],
+            [v[0], v[1], v[2], v[5]],
+        ],
+        _ => return None,
+    };
+    Some(endpoints.map(|endpoint| endpoint.map(|c| c.clamp(0, 255) as u8)))
+}
+
+fn decode_void_extent(block: u128) -> Rgba<u8> {
+    // HDR void extents store half floats, which can't be shown on LDR.
+    if bits(block, 9, 1) == 1 {
+        return ERROR_COLOR;
+    }
+    Rgba([
+        (bits(block, 64, 16) >> 8) as u8,
+        (bits(block, 80, 16) >> 8) as u8,
+        (bits(block, 96, 16) >> 8) as u8,
+        (bits(block, 112, 16) >> 8) as u8,
+    ])
+}
+
+fn decode_astc_block(
+    data: &[u8; 16],
+    block_width: u32,
+    block_height: u32,
+) -> [Rgba<u8>; MAX_TEXELS] {
+    let mut texels = [ERROR_COLOR; MAX_TEXELS];
+    let num_texels = (block_width * block_height) as usize;
+
+    let block = u128::from_le_bytes(*data);
+    if bits(block, 0, 9) == 0x1FC {
+        texels[..num_texels].fill(decode_void_extent(block));
+        return texels;
+    }
+
+    let Some(mode) = decode_block_mode(bits(block, 0, 11)) else {
+        return texels;
+    };
+    if mode.grid_width > block_width || mode.grid_height > block_height {
+        return texels;
+    }
+    let partition_count = bits(block, 11, 2) + 1;
+    if mode.dual_plane && partition_count == 4 {
+        return texels;
+    }
+
+    let num_weights =
+        (mode.grid_width * mode.grid_height) as usize * (mode.dual_plane as usize + 1);
+    let weight_bits = ise_bit_count(num_weights as u32, mode.weight_range);
+
+    // Color endpoint modes.
+    let mut endpoint_modes = [0u32; 4];
+    let (color_start, extra_mode_bits) = if partition_count == 1 {
+        endpoint_modes[0] = bits(block, 13, 4);
+        (17, 0)
+    } else {
+        let selector = bits(block, 23, 2);
+        if selector == 0 {
+            endpoint_modes.fill(bits(block, 25, 4));
+            (29, 0)
+        } else {
+            // The rest of the mode bits are stored below the weights.
+            let extra_bits = 3 * partition_count - 4;
+            let extra = bits(block, 128 - weight_bits - extra_bits, extra_bits);
+            let mode_bits = bits(block, 25, 4) | (extra << 4);
+            let base_class = selector - 1;
+            for (i, endpoint_mode) in endpoint_modes
+                .iter_mut()
+                .take(partition_count as usize)
+                .enumerate()
+            {
+                let class = base_class + ((mode_bits >> i) & 1);
+                let modifier = (mode_bits >> (partition_count as usize + i * 2)) & 0b11;
+                *endpoint_mode = (class << 2) | modifier;
+            }
+            (29, extra_bits)
+        }
+    };
+    let partition_seed = bits(block, 13, 10);
+    let color_component = bits(block, 128 - weight_bits - extra_mode_bits - 2, 2) as usize;
+
+    // Color endpoint values.
+    let num_values: usize = endpoint_modes
+        .iter()
+        .take(partition_count as usize)
+        .map(|mode| ((mode >> 2) + 1) as usize * 2)
+        .sum();
+    if num_values > 18 {
+        return texels;
+    }
+    let color_bits = 128i32
+        - weight_bits as i32
+        - extra_mode_bits as i32
+        - if mode.dual_plane { 2 } else { 0 }
+        - color_start as i32;
+    let Some(color_range) = (0..ISE_RANGES.len())
+        .rev()
+        .find(|range| ise_bit_count(num_values as u32, *range) as i32 <= color_bits)
+    else {
+        return texels;
+    };
+    // The smallest allowed range for color endpoints is 0..=5.
+    if color_range < 4 {
+        return texels;
+    }
+    let mut values = [IseValue::default(); 18];
+    decode_ise(block, color_start, num_values, color_range, &mut values);
+    let values = values.map(|value| unquantize_color(value, color_range) as i32);
+
+    let mut endpoints = [[[0u8; 4]; 2]; 4];
+    let mut offset = 0;
+    for (endpoint_mode, endpoints) in endpoint_modes
+        .iter()
+        .zip(endpoints.iter_mut())
+        .take(partition_count as usize)
+    {
+        let Some(decoded) = decode_endpoints(*endpoint_mode, &values[offset..]) else {
+            return texels;
+        };
+        *endpoints = decoded;
+        offset += ((endpoint_mode >> 2) + 1) as usize * 2;
+    }
+
+    // Weights are stored backwards from the end of the block.
+    let mut weights = [IseValue::default(); 64];
+    decode_ise(
+        block.reverse_bits(),
+        0,
+        num_weights,
+        mode.weight_range,
+        &mut weights,
+    );
+    let weights = weights.map(|weight| unquantize_weight(weight, mode.weight_range) as u32);
+
+    // Infill weights from the weight grid to the block texels.
+    let planes = mode.dual_plane as usize + 1;
+    let grid_width = mode.grid_width as usize;
+    let grid_weight = |plane: usize, index: usize| -> u32 {
+        weights
+            .get(index * planes + plane)
+            .copied()
+            .unwrap_or_default()
+    };
+    let scale_s = (1024 + block_width / 2) / (block_width - 1);
+    let scale_t = (1024 + block_height / 2) / (block_height - 1);
+
+    let small_block = num_texels < 31;
+    for (i, texel) in texels.iter_mut().take(num_texels).enumerate() {
+        let (s, t) = (i as u32 % block_width, i as u32 / block_width);
+
+        let gs = (scale_s * s * (mode.grid_width - 1) + 32) >> 6;
+        let gt = (scale_t * t * (mode.grid_height - 1) + 32) >> 6;
+        let (js, fs) = ((gs >> 4) as usize, gs & 0xF);
+        let (jt, ft) = ((gt >> 4) as usize, gt & 0xF);
+        let w11 = (fs * ft + 8) >> 4;
+        let w10 = ft - w11;
+        let w01 = fs - w11;
+        let w00 = 16 + w11 - fs - ft;
+        let v0 = js + jt * grid_width;
+
+        let weight = |plane: usize| -> u32 {
+            (grid_weight(plane, v0) * w00
+                + grid_weight(plane, v0 + 1) * w01
+                + grid_weight(plane, v0 + grid_width) * w10
+                + grid_weight(plane, v0 + grid_width + 1) * w11
+                + 8)
+                >> 4
+        };
+        let plane_weights = [weight(0), if mode.dual_plane { weight(1) } else { 0 }];
+
+        let partition = if partition_count > 1 {
+            select_partition(partition_seed, s, t, partition_count, small_block)
+        } else {
+            0
+        };
+        let [e0, e1] = endpoints[partition];
+
+        *texel = Rgba(std::array::from_fn(|c| {
+            let weight = if mode.dual_plane && c == color_component {
+                plane_weights[1]
+            } else {
+                plane_weights[0]
+            };
+            // Interpolation is done on 16 bit values, the endpoints are expanded by replication.
+            let (c0, c1) = ((e0[c] as u32) * 257, (e1[c] as u32) * 257);
+            let c = (c0 * (64 - weight) + c1 * weight + 32) >> 6;
+            ((c * 255 + 32768) >> 16) as u8
+        }));
+    }
+
+    texels
+}
+
+/// Blocks are always 16 bytes, but their footprint is anywhere from 4x4 to 12x12 texels.
+pub fn decode_astc(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    block_width: u32,
+    block_height: u32,
+) -> RgbaImage {
+    super::decode_blocks(
+        data,
+        width,
+        height,
+        (block_width, block_height),
+        |block: &[u8; 16]| decode_astc_block(block, block_width, block_height),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn void_extent(color: [u16; 4]) -> [u8; 16] {
+        // LDR, with every extent coordinate set so the color applies to the whole block.
+        let mut block: u128 = 0x1FC | (0b11 << 10) | (((1u128 << 52) - 1) << 12);
+        for (i, channel) in color.iter().enumerate() {
+            block |= (*channel as u128) << (64 + i * 16);
+        }
+        block.to_le_bytes()
+    }
+
+    #[test]
+    fn void_extent_blocks() {
+        let block = void_extent([0x1200, 0x3400, 0x5600, 0x7800]);
+        // 10x10 doesn't divide evenly into 6x6 blocks.
+        let image = decode_astc(&block.repeat(4), 10, 10, 6, 6);
+        assert_eq!(image.dimensions(), (10, 10));
+        assert!(image
+            .pixels()
+            .all(|pixel| pixel.0 == [0x12, 0x34, 0x56, 0x78]));
+    }
+
+    #[test]
+    fn invalid_blocks() {
+        // HDR void extent.
+        let mut block = u128::from_le_bytes(void_extent([0; 4]));
+        block |= 1 << 9;
+        let image = decode_astc(&block.to_le_bytes(), 4, 4, 4, 4);
+        assert!(image.pixels().all(|pixel| *pixel == ERROR_COLOR));
+
+        // Reserved block mode.
+        let image = decode_astc(&[0; 16], 4, 4, 4, 4);
+        assert!(image.pixels().all(|pixel| *pixel == ERROR_COLOR));
+    }
+    /// Packs `(value, bit count)` fields lowest bit first, starting at bit `start`.
+    fn pack(start: u32, fields: &[(u32, u32)]) -> u128 {
+        let mut data = 0;
+        let mut position = start;
+        for (value, bits) in fields {
+            data |= (*value as u128) << position;
+            position += bits;
+        }
+        data
+    }
+
+    /// `(trit or quint, bits)` of each value.
+    fn ise(data: u128, start: u32, count: usize, range: usize) -> Vec<(u32, u32)> {
+        let mut values = [IseValue::default(); 8];
+        decode_ise(data, start, count, range, &mut values);
+        values[..count]
+            .iter()
+            .map(|value| (value.tq, value.bits))
+            .collect()
+    }
+
+    fn assert_block(block: u128, size: u32, expected: &[u32]) {
+        let image = decode_astc(&block.to_le_bytes(), size, size, size, size);
+        let texels: Vec<u32> = image
+            .pixels()
+            .map(|pixel| u32::from_be_bytes(pixel.0))
+            .collect();
+        assert_eq!(texels, expected);
+    }
+
+    #[test]
+    fn trits_and_quints() {
+        // Every packed value was checked against the specification's lookup tables.
+        assert_eq!(decode_trits(0x00), [0, 0, 0, 0, 0]);
+        assert_eq!(decode_trits(0x1F), [0, 0, 2, 2, 2]);
+        assert_eq!(decode_trits(0x3C), [0, 1, 0, 2, 2]);
+        assert_eq!(decode_trits(0x7C), [0, 2, 2, 2, 2]);
+        assert_eq!(decode_trits(0xE3), [0, 0, 2, 1, 2]);
+        assert_eq!(decode_trits(0xFF), [2, 1, 2, 2, 2]);
+        assert_eq!(decode_trits(0x9B), [2, 1, 2, 0, 1]);
+        assert_eq!(decode_trits(0x5A), [2, 2, 1, 2, 0]);
+
+        assert_eq!(decode_quints(0x00), [0, 0, 0]);
+        assert_eq!(decode_quints(0x06), [4, 4, 0]);
+        assert_eq!(decode_quints(0x1E), [4, 4, 3]);
+        assert_eq!(decode_quints(0x66), [0, 0, 4]);
+        assert_eq!(decode_quints(0x7F), [1, 3, 4]);
+        assert_eq!(decode_quints(0x15), [2, 4, 0]);
+        assert_eq!(decode_quints(0x4B), [3, 1, 2]);
+    }
+
+    #[test]
+    fn integer_sequences() {
+        // 6 levels, 1 bit & a trit each. The trits 0x9B are split up between the bits.
+        let data = pack(
+            17,
+            &[
+                (1, 1),
+                (0b11, 2),
+                (0, 1),
+                (0b10, 2),
+                (1, 1),
+                (0b1, 1),
+                (1, 1),
+                (0b00, 2),
+                (0, 1),
+                (0b1, 1),
+            ],
+        );
+        assert_eq!(
+            ise(data, 17, 5, 4),
+            [(2, 1), (1, 0), (2, 1), (0, 1), (1, 0)]
+        );
+
+        // 20 levels, 2 bits & a quint each, the quints are 0x4B.
+        let data = pack(
+            0,
+            &[(2, 2), (0b011, 3), (0, 2), (0b01, 2), (3, 2), (0b10, 2)],
+        );
+        assert_eq!(ise(data, 0, 3, 9), [(3, 2), (1, 0), (2, 3)]);
+
+        // 8 levels, only bits.
+        let data = pack(3, &[(5, 3), (2, 3), (7, 3)]);
+        assert_eq!(ise(data, 3, 3, 5), [(0, 5), (0, 2), (0, 7)]);
+
+        // A partial trit block only has 4 bits for 2 values, the rest of the trits read as 0
+        // even if there's data after the sequence.
+        let data = pack(40, &[(0b0111, 4), (0b1111, 4)]);
+        assert_eq!(ise(data, 40, 2, 1), [(1, 0), (0, 0)]);
+    }
+
+    #[test]
+    fn partitions() {
+        let pattern = |seed, partition_count, size: u32| -> Vec<String> {
+            (0..size)
+                .map(|y| {
+                    (0..size)
+                        .map(|x| {
+                            select_partition(seed, x, y, partition_count, size * size < 31)
+                                .to_string()
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+
+        assert_eq!(pattern(0x2A, 2, 4), ["1100", "1111", "1111", "0000"]);
+        assert_eq!(
+            pattern(0x156, 3, 5),
+            ["00000", "11111", "22222", "22222", "11222"]
+        );
+        assert_eq!(
+            pattern(0x3A0, 4, 8),
+            [
+                "20000010", "00000003", "00000300", "00000000", "31111112", "21111112", "21111112",
+                "21111113",
+            ]
+        );
+        assert_eq!(
+            pattern(0x00D, 2, 12)[..4],
+            [
+                "111111111100",
+                "111111111100",
+                "111111111000",
+                "111111110000"
+            ]
+        );
+    }
+
+    #[test]
+    fn endpoints() {
+        let endpoints = |mode, values: &[i32]| {
+            let mut v = [0; 8];
+            v[..values.len()].copy_from_slice(values);
+            decode_endpoints(mode, &v)
+        };
+
+        // Luminance.
+        assert_eq!(
+            endpoints(0, &[10, 200]),
+            Some([[10, 10, 10, 255], [200, 200, 200, 255]])
+        );
+        assert_eq!(
+            endpoints(1, &[0x84, 0xC5]),
+            Some([[225, 225, 225, 255], [230, 230, 230, 255]])
+        );
+        assert_eq!(
+            endpoints(1, &[0xFC, 0xFF]),
+            Some([[255, 255, 255, 255], [255, 255, 255, 255]])
+        );
+        assert_eq!(
+            endpoints(4, &[10, 20, 30, 40]),
+            Some([[10, 10, 10, 30], [20, 20, 20, 40]])
+        );
+        // The deltas are 6 bit signed values, with their top bit moved to the base.
+        assert_eq!(
+            endpoints(5, &[100, 0x84, 50, 0x06]),
+            Some([[178, 178, 178, 25], [180, 180, 180, 28]])
+        );
+        assert_eq!(
+            endpoints(5, &[100, 0x7E, 50, 0]),
+            Some([[50, 50, 50, 25], [49, 49, 49, 25]])
+        );
+
+        // RGB.
+        assert_eq!(
+            endpoints(6, &[200, 100, 50, 128]),
+            Some([[100, 50, 25, 255], [200, 100, 50, 255]])
+        );
+        assert_eq!(
+            endpoints(8, &[10, 200, 20, 210, 30, 220]),
+            Some([[10, 20, 30, 255], [200, 210, 220, 255]])
+        );
+        // Swapped endpoints use blue contraction.
+        assert_eq!(
+            endpoints(8, &[200, 10, 210, 20, 220, 30]),
+            Some([[20, 25, 30, 255], [210, 215, 220, 255]])
+        );
+        assert_eq!(
+            endpoints(9, &[100, 0x84, 50, 0x06, 60, 0x04]),
+            Some([[178, 25, 30, 255], [180, 28, 32, 255]])
+        );
+
+        // RGBA.
+        assert_eq!(
+            endpoints(10, &[200, 100, 50, 128, 10, 250]),
+            Some([[100, 50, 25, 10], [200, 100, 50, 250]])
+        );
+        assert_eq!(
+            endpoints(12, &[10, 200, 20, 210, 30, 220, 40, 230]),
+            Some([[10, 20, 30, 40], [200, 210, 220, 230]])
+        );
+
+        for hdr_mode in [2, 3, 7, 11, 14, 15] {
+            assert_eq!(endpoints(hdr_mode, &[0; 8]), None);
+        }
+    }
+
+    // The expected texels of the following blocks were produced by an independent decoder.
+
+    #[test]
+    fn single_partition_blocks() {
+        // 6x6 RGB block with a 3x3 weight grid.
+        assert_block(
+            0x254bf04e8007348490f78effe25302c1,
+            6,
+            &[
+                0xD686CEFF, 0xDA84C9FF, 0xCB89DDFF, 0xCA8ADFFF, 0xD487D2FF, 0xC68BE4FF, 0xCC89DBFF,
+                0xDA84C9FF, 0xD586CFFF, 0xD287D4FF, 0xD187D5FF, 0xC48CE7FF, 0xCB8ADEFF, 0xDB84C7FF,
+                0xDC84C6FF, 0xD885CBFF, 0xCF88D8FF, 0xC18DECFF, 0xDA84C9FF, 0xDC84C5FF, 0xDD83C4FF,
+                0xD985CAFF, 0xCF88D8FF, 0xBD8EF2FF, 0xD487D2FF, 0xD785CCFF, 0xDC84C6FF, 0xDB84C8FF,
+                0xD088D6FF, 0xBB8FF4FF, 0xC38CE9FF, 0xCC89DBFF, 0xD885CBFF, 0xDC84C5FF, 0xD486D1FF,
+                0xBB8FF4FF,
+            ],
+        );
+    }
+
+    #[test]
+    fn dual_plane_blocks() {
+        // RGBA endpoints, red uses the second plane of weights.
+        assert_block(
+            0x90dfa96eb1b99319bf91143df8918451,
+            4,
+            &[
+                0xF1CC817E, 0x91F7106E, 0xF1F7106E, 0xF1CC817E, 0xF1CC817E, 0xC1CC817E, 0x91CC817E,
+                0xF1CC817E, 0xC1CC817E, 0xF1F7106E, 0xF1CC817E, 0x91A1F38D, 0xC1CC817E, 0xC1F7106E,
+                0xF1A1F38D, 0xF1CC817E,
+            ],
+        );
+        // 2 partitions.
+        assert_block(
+            0x38b3895a22db2e53e4d9b47373392f4d,
+            4,
+            &[
+                0x989929D6, 0x989729D6, 0x989529D6, 0x989329D6, 0xA6652DD2, 0xA4672DD2, 0xA1692CD3,
+                0x9F6B2BD3, 0xB49931CE, 0xB68A32CD, 0xB97932CD, 0xBB6B33CC, 0xAD822FD0, 0xB18230CF,
+                0xB78232CD, 0xBB8233CC,
+            ],
+        );
+    }
+
+    #[test]
+    fn multiple_partition_blocks() {
+        assert_block(
+            0x0f2039f2db32ebe246f04b8b4e87a832,
+            4,
+            &[
+                0x12121297, 0x393939B8, 0x20202055, 0x29292948, 0x1F1F1FA2, 0x1E1E1EA1, 0x29292947,
+                0x2525254C, 0x313131B2, 0x27272749, 0x29292947, 0x23232351, 0x20202055, 0x1B1B1B5C,
+                0x20202055, 0x20202055,
+            ],
+        );
+        assert_block(
+            0x1309156d68afeccce5d8c96aaa8371ef,
+            5,
+            &[
+                0xC2C2C2A3, 0xBDBDBDA4, 0xB8B8B8A5, 0xB9B9B9A5, 0xB7B7B77E, 0xC2C2C2A3, 0xC1C1C1A3,
+                0xC0C0C0A3, 0xBFBFBFA4, 0xB4B4B4BE, 0xC2C2C2A3, 0xBDBDBDA4, 0xB8B8B8A5, 0xBABABAA5,
+                0xB5B5B59C, 0xC0C0C0A3, 0xBEBEBEA4, 0xBCBCBCA4, 0xBCBCBCA4, 0xB5B5B59C, 0xC0C0C0A3,
+                0xBEBEBEA4, 0xBCBCBCA4, 0xBFBFBFA4, 0xB2B2B2FF,
+            ],
+        );
+        assert_block(
+            0x50d85c0a46cbe4379cf27876023f7a03,
+            4,
+            &[
+                0xDFDFDFFF, 0xDBDBDBFF, 0xEAEAEAFF, 0x232323FF, 0xE4E4E4FF, 0xDADADAFF, 0xEEEEEEFF,
+                0xE5E5E5FF, 0x2A2A2AFF, 0xDEDEDEFF, 0xE7E7E7FF, 0x2D2D2DFF, 0x2C2C2CFF, 0xDEDEDEFF,
+                0xE8E8E8FF, 0x313131FF,
+            ],
+        );
+    }
+}
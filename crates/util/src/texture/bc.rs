@@ -5,7 +5,7 @@
 //     4096x4096 bc3 texture decode in ~17.2ms.
 //     (Can comfortably decode an animated 1920x1080 bc1 texture in real time.)
 
-use image::{ImageBuffer, Rgb, Rgba, RgbaImage};
+use image::{GrayImage, ImageBuffer, Luma, Rgb, RgbImage, Rgba, Rgba32FImage, RgbaImage};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::sync::atomic::AtomicU32;
 
@@ -204,11 +204,10 @@ fn decode_bc2_alpha_block(data: &[u8], out: &UnsafeImageWriter, block_x: u32, bl
     }
 }
 
-fn decode_bc3_alpha_block(data: &[u8], out: &UnsafeImageWriter, block_x: u32, block_y: u32) {
-    let a0 = data[0];
-    let a1 = data[1];
-
-    let palette: [u8; 8] = if a0 > a1 {
+/// BC3 alpha & BC4 endpoints are interpolated the same way.
+#[inline(always)]
+fn bc4_palette_unorm(a0: u8, a1: u8) -> [u8; 8] {
+    if a0 > a1 {
         [
             lerp_u8::<0, 7>(a0, a1),
             lerp_u8::<7, 7>(a0, a1),
@@ -230,17 +229,65 @@ fn decode_bc3_alpha_block(data: &[u8], out: &UnsafeImageWriter, block_x: u32, bl
             0,
             255,
         ]
+    }
+}
+
+/// Signed endpoints are interpolated in -127..=127, then remapped to 0..=255 so they can be
+/// displayed.
+#[inline(always)]
+fn bc4_palette_snorm(a0: u8, a1: u8) -> [u8; 8] {
+    let a0 = (a0 as i8).max(-127) as i32;
+    let a1 = (a1 as i8).max(-127) as i32;
+    let lerp = |n: i32, d: i32| (a0 * (d - n) + a1 * n) / d;
+    let palette: [i32; 8] = if a0 > a1 {
+        [
+            a0,
+            a1,
+            lerp(1, 7),
+            lerp(2, 7),
+            lerp(3, 7),
+            lerp(4, 7),
+            lerp(5, 7),
+            lerp(6, 7),
+        ]
+    } else {
+        [
+            a0,
+            a1,
+            lerp(1, 5),
+            lerp(2, 5),
+            lerp(3, 5),
+            lerp(4, 5),
+            -127,
+            127,
+        ]
+    };
+    palette.map(|v| ((v + 127) * 255 / 254) as u8)
+}
+
+#[inline(always)]
+fn decode_bc4_block<const SIGNED: bool>(data: &[u8]) -> [u8; 16] {
+    let palette = if SIGNED {
+        bc4_palette_snorm(data[0], data[1])
+    } else {
+        bc4_palette_unorm(data[0], data[1])
     };
 
     let indices = join_le_bytes!(u64; data[2], data[3], data[4], data[5], data[6], data[7], 0, 0);
-    for pi in 0..16 {
-        let index = (indices >> (pi * 3)) & 0b111;
-        let alpha = palette[index as usize];
+    let mut values = [0u8; 16];
+    for (pi, value) in values.iter_mut().enumerate() {
+        *value = palette[((indices >> (pi * 3)) & 0b111) as usize];
+    }
+    values
+}
 
+fn decode_bc3_alpha_block(data: &[u8], out: &UnsafeImageWriter, block_x: u32, block_y: u32) {
+    let alphas = decode_bc4_block::<false>(data);
+    for pi in 0..16 {
         let x = (block_x << 2) | (pi & 0b11);
         let y = (block_y << 2) | (pi >> 2);
 
-        out.or_alpha(x, y, alpha);
+        out.or_alpha(x, y, alphas[pi as usize]);
     }
 }
 
@@ -308,3 +355,999 @@ pub fn decode_bc3(data: &[u8], width: u32, height: u32) -> RgbaImage {
 
     img.into_image()
 }
+
+pub fn decode_bc4(data: &[u8], width: u32, height: u32, signed: bool) -> GrayImage {
+    super::decode_blocks(data, width, height, (4, 4), |block: &[u8; 8]| {
+        let values = if signed {
+            decode_bc4_block::<true>(block)
+        } else {
+            decode_bc4_block::<false>(block)
+        };
+        values.map(|v| Luma([v]))
+    })
+}
+
+/// Red & green channels are each stored as a BC4 block, blue is left empty.
+pub fn decode_bc5(data: &[u8], width: u32, height: u32, signed: bool) -> RgbImage {
+    super::decode_blocks(
+        data,
+        width,
+        height,
+        (4, 4),
+        |block: &[u8; 16]| -> [Rgb<u8>; 16] {
+            let (red, green) = if signed {
+                (
+                    decode_bc4_block::<true>(&block[0..8]),
+                    decode_bc4_block::<true>(&block[8..16]),
+                )
+            } else {
+                (
+                    decode_bc4_block::<false>(&block[0..8]),
+                    decode_bc4_block::<false>(&block[8..16]),
+                )
+            };
+            std::array::from_fn(|pi| Rgb([red[pi], green[pi], 0]))
+        },
+    )
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/direct3d11/bc6h-format
+// https://learn.microsoft.com/en-us/windows/win32/direct3d11/bc7-format
+
+/// Reads & writes bits of a 128 bit block, lowest bit first.
+struct BlockBits {
+    data: u128,
+    position: u32,
+}
+
+impl BlockBits {
+    fn new(block: &[u8; 16]) -> Self {
+        Self {
+            data: u128::from_le_bytes(*block),
+            position: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn write(&mut self, bits: u32, value: u32) {
+        self.data |= ((value as u128) & ((1u128 << bits) - 1)) << self.position;
+        self.position += bits;
+    }
+
+    #[inline(always)]
+    fn read(&mut self, bits: u32) -> u32 {
+        let value = ((self.data >> self.position) as u32) & ((1u64 << bits) - 1) as u32;
+        self.position += bits;
+        value
+    }
+
+    #[inline(always)]
+    fn read_reversed(&mut self, bits: u32) -> u32 {
+        self.read(bits).reverse_bits() >> (32 - bits)
+    }
+}
+
+/// Subset of each pixel for 2 subset partitions, 1 bit per pixel.
+const PARTITIONS_2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80, 0xC800, 0xFFEC, 0xFE80, 0xE800,
+    0xFFE8, 0xFF00, 0xFFF0, 0xF000, 0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+    0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C, 0xAAAA, 0xF0F0, 0x5A5A, 0x33CC,
+    0x3C3C, 0x55AA, 0x9696, 0xA55A, 0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C, 0x9336, 0x9CC6, 0x817E, 0xE718,
+    0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+/// Subset of each pixel for 3 subset partitions, 2 bits per pixel.
+const PARTITIONS_3: [u32; 64] = [
+    0xAA685050, 0x6A5A5040, 0x5A5A4200, 0x5450A0A8, 0xA5A50000, 0xA0A05050, 0x5555A0A0, 0x5A5A5050,
+    0xAA550000, 0xAA555500, 0xAAAA5500, 0x90909090, 0x94949494, 0xA4A4A4A4, 0xA9A59450, 0x2A0A4250,
+    0xA5945040, 0x0A425054, 0xA5A5A500, 0x55A0A0A0, 0xA8A85454, 0x6A6A4040, 0xA4A45000, 0x1A1A0500,
+    0x0050A4A4, 0xAAA59090, 0x14696914, 0x69691400, 0xA08585A0, 0xAA821414, 0x50A4A450, 0x6A5A0200,
+    0xA9A58000, 0x5090A0A8, 0xA8A09050, 0x24242424, 0x00AA5500, 0x24924924, 0x24499224, 0x50A50A50,
+    0x500AA550, 0xAAAA4444, 0x66660000, 0xA5A0A5A0, 0x50A050A0, 0x69286928, 0x44AAAA44, 0x66666600,
+    0xAA444444, 0x54A854A8, 0x95809580, 0x96969600, 0xA85454A8, 0x80959580, 0xAA141414, 0x96960000,
+    0xAAAA1414, 0xA05050A0, 0xA0A5A5A0, 0x96000000, 0x40804080, 0xA9A8A9A8, 0xAAAAAA44, 0x2A4A5254,
+];
+
+/// Anchor pixel of the second subset for 2 subset partitions.
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 2, 8, 2, 2, 8, 8, 15, 2, 8,
+    2, 2, 8, 8, 2, 2, 15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6, 6, 2, 6, 8, 15, 15, 2,
+    2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// Anchor pixel of the second subset for 3 subset partitions.
+const ANCHORS_3_SECOND: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15, 8, 8, 6, 6, 6, 5, 3, 3, 3, 3, 8, 15, 3, 3, 6, 10, 5, 8, 8, 6, 8, 5,
+    15, 15, 8, 15, 3, 5, 6, 10, 8, 15, 15, 3, 15, 5, 15, 15, 15, 15, 3, 15, 5, 5, 5, 8, 5, 10, 5,
+    10, 8, 13, 15, 12, 3, 3,
+];
+
+/// Anchor pixel of the third subset for 3 subset partitions.
+const ANCHORS_3_THIRD: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8, 15, 15, 15, 15, 15, 15, 15, 8, 15, 8, 15, 3, 15, 8, 15, 8, 3, 15, 6,
+    10, 15, 15, 10, 8, 15, 3, 15, 10, 10, 8, 9, 10, 6, 15, 8, 15, 3, 6, 6, 8, 15, 3, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+#[inline(always)]
+fn weights(index_bits: u32) -> &'static [u32] {
+    match index_bits {
+        2 => &WEIGHTS_2,
+        3 => &WEIGHTS_3,
+        _ => &WEIGHTS_4,
+    }
+}
+
+/// Subset of a pixel & if it's an anchor pixel, which is stored with one less index bit.
+#[inline(always)]
+fn subset_and_anchor(subsets: u32, partition: usize, pi: usize) -> (usize, bool) {
+    match subsets {
+        2 => {
+            let subset = ((PARTITIONS_2[partition] >> pi) & 1) as usize;
+            let anchor = pi == 0 || pi == ANCHORS_2[partition] as usize;
+            (subset, anchor)
+        }
+        3 => {
+            let subset = ((PARTITIONS_3[partition] >> (pi * 2)) & 0b11) as usize;
+            let anchor = pi == 0
+                || pi == ANCHORS_3_SECOND[partition] as usize
+                || pi == ANCHORS_3_THIRD[partition] as usize;
+            (subset, anchor)
+        }
+        _ => (0, pi == 0),
+    }
+}
+
+#[inline(always)]
+fn read_indices(bits: &mut BlockBits, subsets: u32, partition: usize, index_bits: u32) -> [u8; 16] {
+    std::array::from_fn(|pi| {
+        let (_, anchor) = subset_and_anchor(subsets, partition, pi);
+        bits.read(if anchor { index_bits - 1 } else { index_bits }) as u8
+    })
+}
+
+struct Bc6hMode {
+    transformed: bool,
+    subsets: u32,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    /// Fields in the order they're stored: (channel, endpoint, lowest bit, number of bits).
+    layout: &'static [(u8, u8, u8, u8)],
+    /// Some modes store the highest bits of the first endpoint reversed.
+    reversed: bool,
+}
+
+const R: u8 = 0;
+const G: u8 = 1;
+const B: u8 = 2;
+
+#[rustfmt::skip]
+const BC6H_MODES: [(u32, Bc6hMode); 14] = [
+    (0b00, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 10, delta_bits: [5, 5, 5], reversed: false, layout: &[
+        (G, 2, 4, 1), (B, 2, 4, 1), (B, 3, 4, 1), (R, 0, 0, 10), (G, 0, 0, 10), (B, 0, 0, 10),
+        (R, 1, 0, 5), (G, 3, 4, 1), (G, 2, 0, 4), (G, 1, 0, 5), (B, 3, 0, 1), (G, 3, 0, 4),
+        (B, 1, 0, 5), (B, 3, 1, 1), (B, 2, 0, 4), (R, 2, 0, 5), (B, 3, 2, 1), (R, 3, 0, 5),
+        (B, 3, 3, 1),
+    ] }),
+    (0b01, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 7, delta_bits: [6, 6, 6], reversed: false, layout: &[
+        (G, 2, 5, 1), (G, 3, 4, 1), (G, 3, 5, 1), (R, 0, 0, 7), (B, 3, 0, 1), (B, 3, 1, 1),
+        (B, 2, 4, 1), (G, 0, 0, 7), (B, 2, 5, 1), (B, 3, 2, 1), (G, 2, 4, 1), (B, 0, 0, 7),
+        (B, 3, 3, 1), (B, 3, 5, 1), (B, 3, 4, 1), (R, 1, 0, 6), (G, 2, 0, 4), (G, 1, 0, 6),
+        (G, 3, 0, 4), (B, 1, 0, 6), (B, 2, 0, 4), (R, 2, 0, 6), (R, 3, 0, 6),
+    ] }),
+    (0b00010, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 11, delta_bits: [5, 4, 4], reversed: false, layout: &[
+        (R, 0, 0, 10), (G, 0, 0, 10), (B, 0, 0, 10), (R, 1, 0, 5), (R, 0, 10, 1), (G, 2, 0, 4),
+        (G, 1, 0, 4), (G, 0, 10, 1), (B, 3, 0, 1), (G, 3, 0, 4), (B, 1, 0, 4), (B, 0, 10, 1),
+        (B, 3, 1, 1), (B, 2, 0, 4), (R, 2, 0, 5), (B, 3, 2, 1), (R, 3, 0, 5), (B, 3, 3, 1),
+    ] }),
+    (0b00110, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 11, delta_bits: [4, 5, 4], reversed: false, layout: &[
+        (R, 0, 0, 10), (G, 0, 0, 10), (B, 0, 0, 10), (R, 1, 0, 4), (R, 0, 10, 1), (G, 3, 4, 1),
+        (G, 2, 0, 4), (G, 1, 0, 5), (G, 0, 10, 1), (G, 3, 0, 4), (B, 1, 0, 4), (B, 0, 10, 1),
+        (B, 3, 1, 1), (B, 2, 0, 4), (R, 2, 0, 4), (B, 3, 0, 1), (B, 3, 2, 1), (R, 3, 0, 4),
+        (G, 2, 4, 1), (B, 3, 3, 1),
+    ] }),
+    (0b01010, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 11, delta_bits: [4, 4, 5], reversed: false, layout: &[
+        (R, 0, 0, 10), (G, 0, 0, 10), (B, 0, 0, 10), (R, 1, 0, 4), (R, 0, 10, 1), (B, 2, 4, 1),
+        (G, 2, 0, 4), (G, 1, 0, 4), (G, 0, 10, 1), (B, 3, 0, 1), (G, 3, 0, 4), (B, 1, 0, 5),
+        (B, 0, 10, 1), (B, 2, 0, 4), (R, 2, 0, 4), (B, 3, 1, 1), (B, 3, 2, 1), (R, 3, 0, 4),
+        (B, 3, 4, 1), (B, 3, 3, 1),
+    ] }),
+    (0b01110, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 9, delta_bits: [5, 5, 5], reversed: false, layout: &[
+        (R, 0, 0, 9), (B, 2, 4, 1), (G, 0, 0, 9), (G, 2, 4, 1), (B, 0, 0, 9), (B, 3, 4, 1),
+        (R, 1, 0, 5), (G, 3, 4, 1), (G, 2, 0, 4), (G, 1, 0, 5), (B, 3, 0, 1), (G, 3, 0, 4),
+        (B, 1, 0, 5), (B, 3, 1, 1), (B, 2, 0, 4), (R, 2, 0, 5), (B, 3, 2, 1), (R, 3, 0, 5),
+        (B, 3, 3, 1),
+    ] }),
+    (0b10010, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 8, delta_bits: [6, 5, 5], reversed: false, layout: &[
+        (R, 0, 0, 8), (G, 3, 4, 1), (B, 2, 4, 1), (G, 0, 0, 8), (B, 3, 2, 1), (G, 2, 4, 1),
+        (B, 0, 0, 8), (B, 3, 3, 1), (B, 3, 4, 1), (R, 1, 0, 6), (G, 2, 0, 4), (G, 1, 0, 5),
+        (B, 3, 0, 1), (G, 3, 0, 4), (B, 1, 0, 5), (B, 3, 1, 1), (B, 2, 0, 4), (R, 2, 0, 6),
+        (R, 3, 0, 6),
+    ] }),
+    (0b10110, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 8, delta_bits: [5, 6, 5], reversed: false, layout: &[
+        (R, 0, 0, 8), (B, 3, 0, 1), (B, 2, 4, 1), (G, 0, 0, 8), (G, 2, 5, 1), (G, 2, 4, 1),
+        (B, 0, 0, 8), (G, 3, 5, 1), (B, 3, 4, 1), (R, 1, 0, 5), (G, 3, 4, 1), (G, 2, 0, 4),
+        (G, 1, 0, 6), (G, 3, 0, 4), (B, 1, 0, 5), (B, 3, 1, 1), (B, 2, 0, 4), (R, 2, 0, 5),
+        (B, 3, 2, 1), (R, 3, 0, 5), (B, 3, 3, 1),
+    ] }),
+    (0b11010, Bc6hMode { transformed: true, subsets: 2, endpoint_bits: 8, delta_bits: [5, 5, 6], reversed: false, layout: &[
+        (R, 0, 0, 8), (B, 3, 1, 1), (B, 2, 4, 1), (G, 0, 0, 8), (B, 2, 5, 1), (G, 2, 4, 1),
+        (B, 0, 0, 8), (B, 3, 5, 1), (B, 3, 4, 1), (R, 1, 0, 5), (G, 3, 4, 1), (G, 2, 0, 4),
+        (G, 1, 0, 5), (B, 3, 0, 1), (G, 3, 0, 4), (B, 1, 0, 6), (B, 2, 0, 4), (R, 2, 0, 5),
+        (B, 3, 2, 1), (R, 3, 0, 5), (B, 3, 3, 1),
+    ] }),
+    (0b11110, Bc6hMode { transformed: false, subsets: 2, endpoint_bits: 6, delta_bits: [6, 6, 6], reversed: false, layout: &[
+        (R, 0, 0, 6), (G, 3, 4, 1), (B, 3, 0, 1), (B, 3, 1, 1), (B, 2, 4, 1), (G, 0, 0, 6),
+        (G, 2, 5, 1), (B, 2, 5, 1), (B, 3, 2, 1), (G, 2, 4, 1), (B, 0, 0, 6), (G, 3, 5, 1),
+        (B, 3, 3, 1), (B, 3, 5, 1), (B, 3, 4, 1), (R, 1, 0, 6), (G, 2, 0, 4), (G, 1, 0, 6),
+        (G, 3, 0, 4), (B, 1, 0, 6), (B, 2, 0, 4), (R, 2, 0, 6), (R, 3, 0, 6),
+    ] }),
+    (0b00011, Bc6hMode { transformed: false, subsets: 1, endpoint_bits: 10, delta_bits: [10, 10, 10], reversed: false, layout: &[
+        (R, 0, 0, 10), (G, 0, 0, 10), (B, 0, 0, 10), (R, 1, 0, 10), (G, 1, 0, 10), (B, 1, 0, 10),
+    ] }),
+    (0b00111, Bc6hMode { transformed: true, subsets: 1, endpoint_bits: 11, delta_bits: [9, 9, 9], reversed: false, layout: &[
+        (R, 0, 0, 10), (G, 0, 0, 10), (B, 0, 0, 10), (R, 1, 0, 9), (R, 0, 10, 1), (G, 1, 0, 9),
+        (G, 0, 10, 1), (B, 1, 0, 9), (B, 0, 10, 1),
+    ] }),
+    (0b01011, Bc6hMode { transformed: true, subsets: 1, endpoint_bits: 12, delta_bits: [8, 8, 8], reversed: true, layout: &[
+        (R, 0, 0, 10), (G, 0, 0, 10), (B, 0, 0, 10), (R, 1, 0, 8), (R, 0, 10, 2), (G, 1, 0, 8),
+        (G, 0, 10, 2), (B, 1, 0, 8), (B, 0, 10, 2),
+    ] }),
+    (0b01111, Bc6hMode { transformed: true, subsets: 1, endpoint_bits: 16, delta_bits: [4, 4, 4], reversed: true, layout: &[
+        (R, 0, 0, 10), (G, 0, 0, 10), (B, 0, 0, 10), (R, 1, 0, 4), (R, 0, 10, 6), (G, 1, 0, 4),
+        (G, 0, 10, 6), (B, 1, 0, 4), (B, 0, 10, 6),
+    ] }),
+];
+
+#[inline(always)]
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+#[inline(always)]
+fn bc6h_unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if signed {
+        if bits >= 16 || value == 0 {
+            return value;
+        }
+        let (negative, magnitude) = (value < 0, value.abs());
+        let unquantized = if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7FFF
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        if negative {
+            -unquantized
+        } else {
+            unquantized
+        }
+    } else if bits >= 15 || value == 0 {
+        value
+    } else if value == (1 << bits) - 1 {
+        0xFFFF
+    } else {
+        ((value << 16) + 0x8000) >> bits
+    }
+}
+
+#[inline(always)]
+fn bc6h_finish_unquantize(value: i32, signed: bool) -> f32 {
+    let bits = if signed {
+        if value < 0 {
+            0x8000 | (((-value) * 31) >> 5) as u16
+        } else {
+            ((value * 31) >> 5) as u16
+        }
+    } else {
+        ((value * 31) >> 6) as u16
+    };
+    half::f16::from_bits(bits).to_f32()
+}
+
+fn decode_bc6h_block(block: &[u8; 16], signed: bool) -> [Rgba<f32>; 16] {
+    let mut bits = BlockBits::new(block);
+
+    let mode_bits = match bits.read(2) {
+        mode @ (0b00 | 0b01) => mode,
+        mode => mode | (bits.read(3) << 2),
+    };
+    let Some((_, mode)) = BC6H_MODES.iter().find(|(bits, _)| *bits == mode_bits) else {
+        // Reserved modes decode to black.
+        return [Rgba([0.0, 0.0, 0.0, 1.0]); 16];
+    };
+
+    // [channel][endpoint]
+    let mut endpoints = [[0i32; 4]; 3];
+    for &(channel, endpoint, lowest_bit, count) in mode.layout {
+        let value = if mode.reversed && endpoint == 0 && lowest_bit >= 10 {
+            bits.read_reversed(count as u32)
+        } else {
+            bits.read(count as u32)
+        };
+        endpoints[channel as usize][endpoint as usize] |= (value << lowest_bit) as i32;
+    }
+    let partition = if mode.subsets == 2 {
+        bits.read(5) as usize
+    } else {
+        0
+    };
+
+    let num_endpoints = (mode.subsets * 2) as usize;
+    for (channel, endpoints) in endpoints.iter_mut().enumerate() {
+        if signed {
+            endpoints[0] = sign_extend(endpoints[0], mode.endpoint_bits);
+        }
+        for endpoint in endpoints.iter_mut().take(num_endpoints).skip(1) {
+            if mode.transformed || signed {
+                *endpoint = sign_extend(*endpoint, mode.delta_bits[channel]);
+            }
+        }
+        if mode.transformed {
+            let mask = (1 << mode.endpoint_bits) - 1;
+            for i in 1..num_endpoints {
+                endpoints[i] = (endpoints[0] + endpoints[i]) & mask;
+                if signed {
+                    endpoints[i] = sign_extend(endpoints[i], mode.endpoint_bits);
+                }
+            }
+        }
+        for endpoint in endpoints.iter_mut().take(num_endpoints) {
+            *endpoint = bc6h_unquantize(*endpoint, mode.endpoint_bits, signed);
+        }
+    }
+
+    let index_bits = if mode.subsets == 2 { 3 } else { 4 };
+    let indices = read_indices(&mut bits, mode.subsets, partition, index_bits);
+    let weights = weights(index_bits);
+
+    std::array::from_fn(|pi| {
+        let (subset, _) = subset_and_anchor(mode.subsets, partition, pi);
+        let weight = weights[indices[pi] as usize] as i32;
+        let channel = |c: usize| {
+            let e0 = endpoints[c][subset * 2];
+            let e1 = endpoints[c][subset * 2 + 1];
+            bc6h_finish_unquantize(((64 - weight) * e0 + weight * e1 + 32) >> 6, signed)
+        };
+        Rgba([channel(0), channel(1), channel(2), 1.0])
+    })
+}
+
+/// BC6H is always HDR, so is decoded as floating point.
+pub fn decode_bc6h(data: &[u8], width: u32, height: u32, signed: bool) -> Rgba32FImage {
+    super::decode_blocks(data, width, height, (4, 4), |block: &[u8; 16]| {
+        decode_bc6h_block(block, signed)
+    })
+}
+
+struct Bc7Mode {
+    subsets: u32,
+    partition_bits: u32,
+    rotation_bits: u32,
+    index_selection_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    secondary_index_bits: u32,
+}
+
+#[rustfmt::skip]
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_pbits: false, shared_pbits: true, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_pbits: false, shared_pbits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_pbits: false, shared_pbits: false, index_bits: 2, secondary_index_bits: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_pbits: false, shared_pbits: false, index_bits: 2, secondary_index_bits: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_pbits: true, shared_pbits: false, index_bits: 4, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_pbits: true, shared_pbits: false, index_bits: 2, secondary_index_bits: 0 },
+];
+
+#[inline(always)]
+fn bc7_interpolate(e0: u8, e1: u8, weight: u32) -> u8 {
+    (((64 - weight) * (e0 as u32) + weight * (e1 as u32) + 32) >> 6) as u8
+}
+
+fn decode_bc7_block(block: &[u8; 16]) -> [Rgba<u8>; 16] {
+    if block[0] == 0 {
+        // Reserved mode decodes to transparent black.
+        return [Rgba([0, 0, 0, 0]); 16];
+    }
+    let mode_index = block[0].trailing_zeros();
+    let mode = &BC7_MODES[mode_index as usize];
+
+    let mut bits = BlockBits::new(block);
+    bits.read(mode_index + 1);
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let index_selection = bits.read(mode.index_selection_bits);
+
+    let num_endpoints = (mode.subsets * 2) as usize;
+    // [endpoint][channel]
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(num_endpoints) {
+            endpoint[channel] = bits.read(mode.color_bits);
+        }
+    }
+    for endpoint in endpoints.iter_mut().take(num_endpoints) {
+        endpoint[3] = bits.read(mode.alpha_bits);
+    }
+
+    let (mut color_bits, mut alpha_bits) = (mode.color_bits, mode.alpha_bits);
+    if mode.endpoint_pbits || mode.shared_pbits {
+        let pbits: Vec<u32> = if mode.endpoint_pbits {
+            (0..num_endpoints).map(|_| bits.read(1)).collect()
+        } else {
+            (0..mode.subsets)
+                .flat_map(|_| {
+                    let pbit = bits.read(1);
+                    [pbit, pbit]
+                })
+                .collect()
+        };
+        for (endpoint, pbit) in endpoints.iter_mut().zip(pbits) {
+            for channel in endpoint.iter_mut() {
+                *channel = (*channel << 1) | pbit;
+            }
+        }
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+
+    let expand = |value: u32, bits: u32| -> u8 {
+        let value = value << (8 - bits);
+        (value | (value >> bits)) as u8
+    };
+    let endpoints: [[u8; 4]; 6] = endpoints.map(|endpoint| {
+        [
+            expand(endpoint[0], color_bits),
+            expand(endpoint[1], color_bits),
+            expand(endpoint[2], color_bits),
+            if alpha_bits > 0 {
+                expand(endpoint[3], alpha_bits)
+            } else {
+                255
+            },
+        ]
+    });
+
+    let indices = read_indices(&mut bits, mode.subsets, partition, mode.index_bits);
+    let secondary_indices = if mode.secondary_index_bits > 0 {
+        Some(read_indices(&mut bits, 1, 0, mode.secondary_index_bits))
+    } else {
+        None
+    };
+
+    std::array::from_fn(|pi| {
+        let (subset, _) = subset_and_anchor(mode.subsets, partition, pi);
+        let (e0, e1) = (endpoints[subset * 2], endpoints[subset * 2 + 1]);
+
+        let (color_weight, alpha_weight) = match secondary_indices {
+            None => {
+                let weight = weights(mode.index_bits)[indices[pi] as usize];
+                (weight, weight)
+            }
+            Some(secondary_indices) => {
+                let primary = weights(mode.index_bits)[indices[pi] as usize];
+                let secondary = weights(mode.secondary_index_bits)[secondary_indices[pi] as usize];
+                if index_selection == 0 {
+                    (primary, secondary)
+                } else {
+                    (secondary, primary)
+                }
+            }
+        };
+
+        let mut color = Rgba([
+            bc7_interpolate(e0[0], e1[0], color_weight),
+            bc7_interpolate(e0[1], e1[1], color_weight),
+            bc7_interpolate(e0[2], e1[2], color_weight),
+            bc7_interpolate(e0[3], e1[3], alpha_weight),
+        ]);
+        if rotation > 0 {
+            color.0.swap(3, (rotation - 1) as usize);
+        }
+        color
+    })
+}
+
+pub fn decode_bc7(data: &[u8], width: u32, height: u32) -> RgbaImage {
+    super::decode_blocks(data, width, height, (4, 4), decode_bc7_block)
+}
+
+// Encoding
+//
+// Endpoints are picked along the principal axis of the block, then refined once with least
+// squares. Nowhere near as good as a dedicated texture compressor, but fast & good enough for
+// exporting textures.
+
+type Vector<const N: usize> = [f32; N];
+
+#[inline(always)]
+fn distance_squared<const N: usize>(a: &Vector<N>, b: &[u8; N]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - (*b as f32)) * (a - (*b as f32)))
+        .sum()
+}
+
+/// Index & distance of the closest color in the palette.
+#[inline(always)]
+fn nearest<const N: usize>(value: &Vector<N>, palette: &[[u8; N]]) -> (usize, f32) {
+    palette
+        .iter()
+        .map(|color| distance_squared(value, color))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap()
+}
+
+/// Endpoints along the principal axis of the values, at the furthest values on that axis.
+fn principal_endpoints<const N: usize>(values: &[Vector<N>]) -> (Vector<N>, Vector<N>) {
+    let count = values.len().max(1) as f32;
+    let mean: Vector<N> =
+        std::array::from_fn(|c| values.iter().map(|value| value[c]).sum::<f32>() / count);
+
+    let covariance: [Vector<N>; N] = std::array::from_fn(|i| {
+        std::array::from_fn(|j| {
+            values
+                .iter()
+                .map(|value| (value[i] - mean[i]) * (value[j] - mean[j]))
+                .sum()
+        })
+    });
+
+    // Power iteration
+    let mut axis = [1.0 / (N as f32).sqrt(); N];
+    for _ in 0..8 {
+        let next: Vector<N> = std::array::from_fn(|i| {
+            covariance[i]
+                .iter()
+                .zip(axis)
+                .map(|(covariance, axis)| covariance * axis)
+                .sum()
+        });
+        let length = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length < 1e-6 {
+            break;
+        }
+        axis = next.map(|v| v / length);
+    }
+
+    let (mut min, mut max) = (0.0f32, 0.0f32);
+    for value in values {
+        let t: f32 = (0..N).map(|c| (value[c] - mean[c]) * axis[c]).sum();
+        min = min.min(t);
+        max = max.max(t);
+    }
+    (
+        std::array::from_fn(|c| mean[c] + axis[c] * min),
+        std::array::from_fn(|c| mean[c] + axis[c] * max),
+    )
+}
+
+/// Endpoints that best fit the values, `weights` are how far each value is from the first to the
+/// second endpoint.
+fn least_squares_endpoints<const N: usize>(
+    values: &[Vector<N>],
+    weights: &[f32],
+) -> Option<(Vector<N>, Vector<N>)> {
+    let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+    let (mut ax, mut bx) = ([0.0; N], [0.0; N]);
+    for (value, weight) in values.iter().zip(weights) {
+        let (a, b) = (1.0 - weight, *weight);
+        aa += a * a;
+        ab += a * b;
+        bb += b * b;
+        for (c, value) in value.iter().enumerate() {
+            ax[c] += a * value;
+            bx[c] += b * value;
+        }
+    }
+
+    let determinant = aa * bb - ab * ab;
+    if determinant.abs() < 1e-6 {
+        return None;
+    }
+    Some((
+        std::array::from_fn(|c| (bb * ax[c] - ab * bx[c]) / determinant),
+        std::array::from_fn(|c| (aa * bx[c] - ab * ax[c]) / determinant),
+    ))
+}
+
+#[inline(always)]
+fn encode_rgb565(color: &Vector<3>) -> u16 {
+    let quantize = |value: f32, max: f32| (value.clamp(0.0, 255.0) * max / 255.0).round() as u16;
+    (quantize(color[0], 31.0) << 11) | (quantize(color[1], 63.0) << 5) | quantize(color[2], 31.0)
+}
+
+/// Indices & total error of a BC1 color block, pixels in `transparent` always use index 3.
+fn bc1_indices(values: &[Vector<3>; 16], transparent: u16, q0: u16, q1: u16) -> (u32, f32) {
+    let rgb0 = decode_rgb565(q0);
+    let rgb1 = decode_rgb565(q1);
+    let palette = if q0 > q1 {
+        vec![
+            rgb0.0,
+            rgb1.0,
+            rgb888_lerp::<1, 3>(rgb0, rgb1).0,
+            rgb888_lerp::<2, 3>(rgb0, rgb1).0,
+        ]
+    } else {
+        vec![rgb0.0, rgb1.0, rgb888_lerp::<1, 2>(rgb0, rgb1).0]
+    };
+
+    let mut indices = 0;
+    let mut error = 0.0;
+    for (pi, value) in values.iter().enumerate() {
+        let index = if (transparent >> pi) & 1 == 1 {
+            3
+        } else {
+            let (index, distance) = nearest(value, &palette);
+            error += distance;
+            index as u32
+        };
+        indices |= index << (pi * 2);
+    }
+    (indices, error)
+}
+
+fn encode_bc1_block(pixels: &[Rgba<u8>; 16], alpha: bool) -> [u8; 8] {
+    let transparent = if alpha {
+        pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, pixel)| pixel.0[3] < 128)
+            .fold(0u16, |mask, (pi, _)| mask | (1 << pi))
+    } else {
+        0
+    };
+    if transparent == 0xFFFF {
+        return [0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+    }
+    // Blocks with transparency need the 3 color palette, which has the endpoints swapped.
+    let order = |q0: u16, q1: u16| {
+        if transparent != 0 {
+            (q0.min(q1), q0.max(q1))
+        } else {
+            (q0.max(q1), q0.min(q1))
+        }
+    };
+
+    let values: [Vector<3>; 16] =
+        std::array::from_fn(|pi| std::array::from_fn(|c| pixels[pi].0[c] as f32));
+    let opaque: Vec<Vector<3>> = (0..16)
+        .filter(|pi| (transparent >> pi) & 1 == 0)
+        .map(|pi| values[pi])
+        .collect();
+
+    let (e0, e1) = principal_endpoints(&opaque);
+    let (q0, q1) = order(encode_rgb565(&e0), encode_rgb565(&e1));
+    let mut best = (q0, q1, bc1_indices(&values, transparent, q0, q1));
+
+    let palette_weights: &[f32] = if q0 > q1 {
+        &[0.0, 1.0, 1.0 / 3.0, 2.0 / 3.0]
+    } else {
+        &[0.0, 1.0, 0.5, 0.0]
+    };
+    let weights: Vec<f32> = (0..16)
+        .filter(|pi| (transparent >> pi) & 1 == 0)
+        .map(|pi| palette_weights[((best.2 .0 >> (pi * 2)) & 0b11) as usize])
+        .collect();
+    if let Some((e0, e1)) = least_squares_endpoints(&opaque, &weights) {
+        let (q0, q1) = order(encode_rgb565(&e0), encode_rgb565(&e1));
+        let candidate = bc1_indices(&values, transparent, q0, q1);
+        if candidate.1 < best.2 .1 {
+            best = (q0, q1, candidate);
+        }
+    }
+
+    let (q0, q1, (indices, _)) = best;
+    let mut block = [0u8; 8];
+    block[0..2].copy_from_slice(&q0.to_le_bytes());
+    block[2..4].copy_from_slice(&q1.to_le_bytes());
+    block[4..8].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+fn encode_bc2_alpha_block(pixels: &[Rgba<u8>; 16]) -> [u8; 8] {
+    let alphas = pixels.iter().enumerate().fold(0u64, |alphas, (pi, pixel)| {
+        let alpha = ((pixel.0[3] as u32 * 15 + 127) / 255) as u64;
+        alphas | (alpha << (pi * 4))
+    });
+    alphas.to_le_bytes()
+}
+
+fn encode_bc4_block(values: &[u8; 16]) -> [u8; 8] {
+    let indices = |a0: u8, a1: u8| -> (u64, f32) {
+        let palette = bc4_palette_unorm(a0, a1).map(|v| [v]);
+        let mut indices = 0;
+        let mut error = 0.0;
+        for (pi, value) in values.iter().enumerate() {
+            let (index, distance) = nearest(&[*value as f32], &palette);
+            indices |= (index as u64) << (pi * 3);
+            error += distance;
+        }
+        (indices, error)
+    };
+
+    let min = *values.iter().min().unwrap();
+    let max = *values.iter().max().unwrap();
+    let mut best = (max, min, indices(max, min));
+
+    // 6 interpolated values with an explicit 0 & 255, for blocks that have those as outliers.
+    let inner = values.iter().filter(|v| **v != 0 && **v != 255);
+    if let (Some(min), Some(max)) = (inner.clone().min(), inner.max()) {
+        let candidate = indices(*min, *max);
+        if candidate.1 < best.2 .1 {
+            best = (*min, *max, candidate);
+        }
+    }
+
+    let (a0, a1, (indices, _)) = best;
+    let mut block = [0u8; 8];
+    block[0] = a0;
+    block[1] = a1;
+    block[2..8].copy_from_slice(&indices.to_le_bytes()[0..6]);
+    block
+}
+
+/// Only uses mode 6, a single subset with 7 bit RGBA endpoints & 4 bit indices.
+fn encode_bc7_block(pixels: &[Rgba<u8>; 16]) -> [u8; 16] {
+    let values: [Vector<4>; 16] = pixels.map(|pixel| pixel.0.map(|c| c as f32));
+
+    // Each endpoint has a shared lowest bit for all channels.
+    let quantize = |endpoint: Vector<4>| -> [u8; 4] {
+        let candidates = [0u8, 1u8].map(|pbit| {
+            endpoint
+                .map(|v| ((((v - pbit as f32) / 2.0).round().clamp(0.0, 127.0) as u8) << 1) | pbit)
+        });
+        if distance_squared(&endpoint, &candidates[0])
+            <= distance_squared(&endpoint, &candidates[1])
+        {
+            candidates[0]
+        } else {
+            candidates[1]
+        }
+    };
+    let indices = |e0: [u8; 4], e1: [u8; 4]| -> ([u8; 16], f32) {
+        let palette: [[u8; 4]; 16] = std::array::from_fn(|i| {
+            std::array::from_fn(|c| bc7_interpolate(e0[c], e1[c], WEIGHTS_4[i]))
+        });
+        let mut error = 0.0;
+        let indices = values.map(|value| {
+            let (index, distance) = nearest(&value, &palette);
+            error += distance;
+            index as u8
+        });
+        (indices, error)
+    };
+
+    let (e0, e1) = principal_endpoints(&values);
+    let (e0, e1) = (quantize(e0), quantize(e1));
+    let mut best = (e0, e1, indices(e0, e1));
+
+    let weights = best
+        .2
+         .0
+        .map(|index| WEIGHTS_4[index as usize] as f32 / 64.0);
+    if let Some((e0, e1)) = least_squares_endpoints(&values, &weights) {
+        let (e0, e1) = (quantize(e0), quantize(e1));
+        let candidate = indices(e0, e1);
+        if candidate.1 < best.2 .1 {
+            best = (e0, e1, candidate);
+        }
+    }
+
+    let (mut e0, mut e1, (mut indices, _)) = best;
+    // The highest bit of the anchor index is implicitly 0.
+    if indices[0] >= 8 {
+        std::mem::swap(&mut e0, &mut e1);
+        indices = indices.map(|index| 15 - index);
+    }
+
+    let mut bits = BlockBits {
+        data: 0,
+        position: 0,
+    };
+    bits.write(7, 1 << 6);
+    for (c0, c1) in e0.iter().zip(e1) {
+        bits.write(7, (c0 >> 1) as u32);
+        bits.write(7, (c1 >> 1) as u32);
+    }
+    bits.write(1, (e0[0] & 1) as u32);
+    bits.write(1, (e1[0] & 1) as u32);
+    for (pi, index) in indices.iter().enumerate() {
+        bits.write(if pi == 0 { 3 } else { 4 }, *index as u32);
+    }
+    bits.data.to_le_bytes()
+}
+
+/// With `alpha`, pixels that are less than half transparent are stored as transparent.
+pub fn encode_bc1(image: &RgbaImage, alpha: bool) -> Vec<u8> {
+    super::encode_blocks(image, |pixels| encode_bc1_block(pixels, alpha))
+}
+
+pub fn encode_bc2(image: &RgbaImage) -> Vec<u8> {
+    super::encode_blocks(image, |pixels| {
+        let mut block = [0u8; 16];
+        block[0..8].copy_from_slice(&encode_bc2_alpha_block(pixels));
+        block[8..16].copy_from_slice(&encode_bc1_block(pixels, false));
+        block
+    })
+}
+
+pub fn encode_bc3(image: &RgbaImage) -> Vec<u8> {
+    super::encode_blocks(image, |pixels| {
+        let mut block = [0u8; 16];
+        block[0..8].copy_from_slice(&encode_bc4_block(&pixels.map(|pixel| pixel.0[3])));
+        block[8..16].copy_from_slice(&encode_bc1_block(pixels, false));
+        block
+    })
+}
+
+/// Only the red channel is stored, like [`encode_bc5`] only stores red & green.
+pub fn encode_bc4(image: &RgbaImage) -> Vec<u8> {
+    super::encode_blocks(image, |pixels| {
+        encode_bc4_block(&pixels.map(|pixel| pixel.0[0]))
+    })
+}
+
+/// Only the red & green channels are stored.
+pub fn encode_bc5(image: &RgbImage) -> Vec<u8> {
+    super::encode_blocks(image, |pixels| {
+        let mut block = [0u8; 16];
+        block[0..8].copy_from_slice(&encode_bc4_block(&pixels.map(|pixel| pixel.0[0])));
+        block[8..16].copy_from_slice(&encode_bc4_block(&pixels.map(|pixel| pixel.0[1])));
+        block
+    })
+}
+
+pub fn encode_bc7(image: &RgbaImage) -> Vec<u8> {
+    super::encode_blocks(image, encode_bc7_block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bc4_endpoints() {
+        // Pixel 0 uses the first endpoint, pixel 1 the second, the rest the first interpolation.
+        let indices = (2..16).fold(1u64 << 3, |indices, pi| indices | (2 << (pi * 3)));
+        let mut block = [255, 0, 0, 0, 0, 0, 0, 0];
+        block[2..8].copy_from_slice(&indices.to_le_bytes()[0..6]);
+
+        let image = decode_bc4(&block, 4, 4, false);
+        assert_eq!(image.get_pixel(0, 0).0, [255]);
+        assert_eq!(image.get_pixel(1, 0).0, [0]);
+        assert_eq!(image.get_pixel(3, 3).0, [lerp_u8::<1, 7>(255, 0)]);
+
+        // Signed -127 & 127 are remapped to the full range.
+        let image = decode_bc4(&[0x81, 0x7F, 0, 0, 0, 0, 0, 0], 4, 4, true);
+        assert!(image.pixels().all(|pixel| pixel.0 == [0]));
+    }
+
+    #[test]
+    fn bc5_channels() {
+        let block = [255, 0, 0, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 0, 0, 0];
+        let image = decode_bc5(&block, 4, 4, false);
+        assert!(image.pixels().all(|pixel| pixel.0 == [255, 0, 0]));
+    }
+
+    #[test]
+    fn bc7_mode_6() {
+        let block: u128 = (1 << 6)
+            | (127 << 14)
+            | (127 << 28)
+            | (127 << 42)
+            | (127 << 49)
+            | (127 << 56)
+            | (1 << 64)
+            | (15 << 68);
+        let image = decode_bc7(&block.to_le_bytes(), 4, 4);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 254]);
+        assert_eq!(image.get_pixel(1, 0).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(2, 0).0, [0, 0, 0, 254]);
+    }
+
+    #[test]
+    fn bc6h_mode_11() {
+        // Untransformed 10 bit endpoints, the first endpoint is the largest value.
+        let block: u128 = 0b00011 | (1023 << 5) | (1023 << 15) | (1023 << 25);
+        let image = decode_bc6h(&block.to_le_bytes(), 4, 4, false);
+        assert!(image
+            .pixels()
+            .all(|pixel| pixel.0 == [65504.0, 65504.0, 65504.0, 1.0]));
+    }
+
+    /// Smooth gradients with a different ramp in every channel, 18x18 so edge blocks are partial.
+    fn gradient() -> RgbaImage {
+        RgbaImage::from_fn(18, 18, |x, y| {
+            Rgba([
+                (x * 14) as u8,
+                (y * 14) as u8,
+                ((x + y) * 7) as u8,
+                255 - (x * 7 + y * 7) as u8,
+            ])
+        })
+    }
+
+    /// Largest difference of each channel.
+    fn max_error<const N: usize>(a: &[u8], b: &[u8]) -> [u8; N] {
+        let mut error = [0u8; N];
+        for (i, (a, b)) in a.iter().zip(b).enumerate() {
+            error[i % N] = error[i % N].max(a.abs_diff(*b));
+        }
+        error
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let image = gradient();
+        let (width, height) = image.dimensions();
+        let rgb = image::DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+        let red: Vec<u8> = image.pixels().map(|pixel| pixel.0[0]).collect();
+
+        // Colors in a block don't lie on a line, so BC1 & BC7 mode 6 can't be exact.
+        let bc1 = decode_bc1(&encode_bc1(&image, false), width, height, Rgba([0; 4]));
+        let bc1 = image::DynamicImage::ImageRgba8(bc1).to_rgb8();
+        assert!(max_error::<3>(&rgb, &bc1).iter().all(|e| *e <= 32));
+        let bc3 = max_error::<4>(&image, &decode_bc3(&encode_bc3(&image), width, height));
+        assert!(bc3[0..3].iter().all(|e| *e <= 32) && bc3[3] <= 4);
+        let bc4 = decode_bc4(&encode_bc4(&image), width, height, false);
+        assert!(max_error::<1>(&red, &bc4)[0] <= 4);
+        let bc5 = max_error::<3>(&rgb, &decode_bc5(&encode_bc5(&rgb), width, height, false));
+        assert!(bc5[0] <= 4 && bc5[1] <= 4);
+        let bc7 = decode_bc7(&encode_bc7(&image), width, height);
+        assert!(max_error::<4>(&image, &bc7).iter().all(|e| *e <= 24));
+    }
+
+    #[test]
+    fn encode_round_trip_grayscale() {
+        // Every block is on a line, so the error is only from quantization.
+        let image = RgbaImage::from_fn(16, 16, |x, y| {
+            let v = (x * 8 + y * 7) as u8;
+            Rgba([v, v, v, 255])
+        });
+        let bc1 = decode_bc1(&encode_bc1(&image, false), 16, 16, Rgba([0; 4]));
+        assert!(max_error::<4>(&image, &bc1).iter().all(|e| *e <= 10));
+        let bc7 = decode_bc7(&encode_bc7(&image), 16, 16);
+        assert!(max_error::<4>(&image, &bc7).iter().all(|e| *e <= 2));
+    }
+
+    #[test]
+    fn bc4_stores_red() {
+        // Green & blue are noise that would leak into the luminance.
+        let image = RgbaImage::from_fn(8, 8, |x, y| {
+            Rgba([
+                (x * 30) as u8,
+                ((x * 97 + y * 31) % 256) as u8,
+                (y * 61) as u8,
+                255,
+            ])
+        });
+        let bc4 = decode_bc4(&encode_bc4(&image), 8, 8, false);
+        for (x, y, pixel) in bc4.enumerate_pixels() {
+            // Half of the largest step between the 8 palette values.
+            assert!(pixel.0[0].abs_diff(image.get_pixel(x, y).0[0]) <= 7);
+        }
+    }
+
+    #[test]
+    fn truncated_data() {
+        // Missing blocks are left empty instead of panicking.
+        let image = decode_bc7(&[0xFF; 20], 8, 8);
+        assert_eq!(image.dimensions(), (8, 8));
+        assert_eq!(image.get_pixel(7, 7).0, [0, 0, 0, 0]);
+    }
+}
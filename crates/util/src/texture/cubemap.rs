@@ -0,0 +1,147 @@
+// https://learn.microsoft.com/en-us/windows/win32/direct3d9/cubic-environment-mapping
+//
+// Directions are Z up, X forward & Y left, like Source. Faces are in Direct3D order.
+
+use anyhow::{anyhow, Result};
+use image::{DynamicImage, Rgba, Rgba32FImage};
+use std::f32::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubemapFace {
+    PositiveX = 0,
+    NegativeX = 1,
+    PositiveY = 2,
+    NegativeY = 3,
+    PositiveZ = 4,
+    NegativeZ = 5,
+}
+
+pub struct Cubemap {
+    faces: [Rgba32FImage; 6],
+    hdr: bool,
+}
+
+impl Cubemap {
+    /// Faces must be square & all the same size.
+    pub fn new(faces: &[DynamicImage]) -> Result<Cubemap> {
+        let faces: &[DynamicImage; 6] = faces
+            .get(..6)
+            .and_then(|faces| faces.try_into().ok())
+            .ok_or(anyhow!("Cubemap must have 6 faces"))?;
+        let size = faces[0].width();
+        if faces
+            .iter()
+            .any(|face| face.width() != size || face.height() != size)
+        {
+            return Err(anyhow!("Cubemap faces must be square & the same size"));
+        }
+
+        Ok(Cubemap {
+            hdr: faces.iter().any(|face| {
+                matches!(
+                    face,
+                    DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+                )
+            }),
+            faces: std::array::from_fn(|i| faces[i].to_rgba32f()),
+        })
+    }
+
+    pub fn size(&self) -> u32 {
+        self.faces[0].width()
+    }
+
+    /// Bilinear sample in a direction, faces are not filtered across their edges.
+    pub fn sample(&self, [x, y, z]: [f32; 3]) -> Rgba<f32> {
+        let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+        let (face, s, t, major) = if ax >= ay && ax >= az {
+            if x > 0.0 {
+                (CubemapFace::PositiveX, -z, -y, ax)
+            } else {
+                (CubemapFace::NegativeX, z, -y, ax)
+            }
+        } else if ay >= az {
+            if y > 0.0 {
+                (CubemapFace::PositiveY, x, z, ay)
+            } else {
+                (CubemapFace::NegativeY, x, -z, ay)
+            }
+        } else if z > 0.0 {
+            (CubemapFace::PositiveZ, x, -y, az)
+        } else {
+            (CubemapFace::NegativeZ, -x, -y, az)
+        };
+
+        let face = &self.faces[face as usize];
+        let size = face.width() as f32;
+        let u = ((s / major + 1.0) * 0.5 * size - 0.5).clamp(0.0, size - 1.0);
+        let v = ((t / major + 1.0) * 0.5 * size - 0.5).clamp(0.0, size - 1.0);
+
+        let (x0, y0) = (u.floor() as u32, v.floor() as u32);
+        let (x1, y1) = (
+            (x0 + 1).min(face.width() - 1),
+            (y0 + 1).min(face.height() - 1),
+        );
+        let (fx, fy) = (u.fract(), v.fract());
+        let lerp = |a: &Rgba<f32>, b: &Rgba<f32>, f: f32| -> Rgba<f32> {
+            Rgba(std::array::from_fn(|c| a[c] + (b[c] - a[c]) * f))
+        };
+        let top = lerp(face.get_pixel(x0, y0), face.get_pixel(x1, y0), fx);
+        let bottom = lerp(face.get_pixel(x0, y1), face.get_pixel(x1, y1), fx);
+        lerp(&top, &bottom, fy)
+    }
+
+    /// Float images stay float so HDR cubemaps keep their range.
+    fn output(&self, image: Rgba32FImage) -> DynamicImage {
+        if self.hdr {
+            DynamicImage::ImageRgba32F(image)
+        } else {
+            DynamicImage::ImageRgba8(DynamicImage::ImageRgba32F(image).to_rgba8())
+        }
+    }
+
+    /// Horizontal cross, the middle row is left, front, right & back.
+    pub fn to_cross(&self) -> DynamicImage {
+        let size = self.size();
+        // Cell position, forward, right & up of each view.
+        type View = ((u32, u32), [f32; 3], [f32; 3], [f32; 3]);
+        #[rustfmt::skip]
+        const VIEWS: [View; 6] = [
+            ((1, 0), [0.0, 0.0, 1.0], [0.0, -1.0, 0.0], [-1.0, 0.0, 0.0]),
+            ((0, 1), [0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            ((1, 1), [1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]),
+            ((2, 1), [0.0, -1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            ((3, 1), [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+            ((1, 2), [0.0, 0.0, -1.0], [0.0, -1.0, 0.0], [1.0, 0.0, 0.0]),
+        ];
+
+        let mut image = Rgba32FImage::new(size * 4, size * 3);
+        for ((cell_x, cell_y), forward, right, up) in VIEWS {
+            for y in 0..size {
+                for x in 0..size {
+                    let s = ((x as f32) + 0.5) / (size as f32) * 2.0 - 1.0;
+                    let t = ((y as f32) + 0.5) / (size as f32) * 2.0 - 1.0;
+                    let direction: [f32; 3] =
+                        std::array::from_fn(|i| forward[i] + s * right[i] - t * up[i]);
+                    image.put_pixel(cell_x * size + x, cell_y * size + y, self.sample(direction));
+                }
+            }
+        }
+        self.output(image)
+    }
+
+    /// 2:1 panorama, the center is the front.
+    pub fn to_equirectangular(&self) -> DynamicImage {
+        let (width, height) = (self.size() * 4, self.size() * 2);
+        let image = Rgba32FImage::from_fn(width, height, |x, y| {
+            let longitude = (0.5 - ((x as f32) + 0.5) / (width as f32)) * 2.0 * PI;
+            let latitude = (0.5 - ((y as f32) + 0.5) / (height as f32)) * PI;
+            self.sample([
+                latitude.cos() * longitude.cos(),
+                latitude.cos() * longitude.sin(),
+                latitude.sin(),
+            ])
+        });
+        self.output(image)
+    }
+}
@@ -0,0 +1,1070 @@
+// https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dds-header
+// https://learn.microsoft.com/en-us/windows/win32/api/dxgiformat/ne-dxgiformat-dxgi_format
+
+use crate::image_utils::SizeHint;
+use anyhow::{anyhow, Result};
+use image::{
+    DynamicImage, GrayImage, ImageBuffer, Luma, LumaA, Pixel, Rgb, Rgb32FImage, Rgba, RgbaImage,
+};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+#[allow(non_camel_case_types)]
+pub enum DxgiFormat {
+    R32G32B32A32_FLOAT = 2,
+    R32G32B32_FLOAT = 6,
+    R16G16B16A16_FLOAT = 10,
+    R16G16B16A16_UNORM = 11,
+    R32G32_FLOAT = 16,
+    R10G10B10A2_UNORM = 24,
+    R8G8B8A8_UNORM = 28,
+    R8G8B8A8_UNORM_SRGB = 29,
+    R16G16_FLOAT = 34,
+    R16G16_UNORM = 35,
+    R32_FLOAT = 41,
+    R8G8_UNORM = 49,
+    R16_FLOAT = 54,
+    R16_UNORM = 56,
+    R8_UNORM = 61,
+    A8_UNORM = 65,
+    BC1_UNORM = 71,
+    BC1_UNORM_SRGB = 72,
+    BC2_UNORM = 74,
+    BC2_UNORM_SRGB = 75,
+    BC3_UNORM = 77,
+    BC3_UNORM_SRGB = 78,
+    BC4_UNORM = 80,
+    BC4_SNORM = 81,
+    BC5_UNORM = 83,
+    BC5_SNORM = 84,
+    B5G6R5_UNORM = 85,
+    B5G5R5A1_UNORM = 86,
+    B8G8R8A8_UNORM = 87,
+    B8G8R8X8_UNORM = 88,
+    B8G8R8A8_UNORM_SRGB = 91,
+    B8G8R8X8_UNORM_SRGB = 93,
+    BC6H_UF16 = 95,
+    BC6H_SF16 = 96,
+    BC7_UNORM = 98,
+    BC7_UNORM_SRGB = 99,
+    B4G4R4A4_UNORM = 115,
+}
+
+impl DxgiFormat {
+    pub fn from(value: u32) -> Result<DxgiFormat> {
+        Ok(match value {
+            2 => DxgiFormat::R32G32B32A32_FLOAT,
+            6 => DxgiFormat::R32G32B32_FLOAT,
+            10 => DxgiFormat::R16G16B16A16_FLOAT,
+            11 => DxgiFormat::R16G16B16A16_UNORM,
+            16 => DxgiFormat::R32G32_FLOAT,
+            24 => DxgiFormat::R10G10B10A2_UNORM,
+            28 => DxgiFormat::R8G8B8A8_UNORM,
+            29 => DxgiFormat::R8G8B8A8_UNORM_SRGB,
+            34 => DxgiFormat::R16G16_FLOAT,
+            35 => DxgiFormat::R16G16_UNORM,
+            41 => DxgiFormat::R32_FLOAT,
+            49 => DxgiFormat::R8G8_UNORM,
+            54 => DxgiFormat::R16_FLOAT,
+            56 => DxgiFormat::R16_UNORM,
+            61 => DxgiFormat::R8_UNORM,
+            65 => DxgiFormat::A8_UNORM,
+            71 => DxgiFormat::BC1_UNORM,
+            72 => DxgiFormat::BC1_UNORM_SRGB,
+            74 => DxgiFormat::BC2_UNORM,
+            75 => DxgiFormat::BC2_UNORM_SRGB,
+            77 => DxgiFormat::BC3_UNORM,
+            78 => DxgiFormat::BC3_UNORM_SRGB,
+            80 => DxgiFormat::BC4_UNORM,
+            81 => DxgiFormat::BC4_SNORM,
+            83 => DxgiFormat::BC5_UNORM,
+            84 => DxgiFormat::BC5_SNORM,
+            85 => DxgiFormat::B5G6R5_UNORM,
+            86 => DxgiFormat::B5G5R5A1_UNORM,
+            87 => DxgiFormat::B8G8R8A8_UNORM,
+            88 => DxgiFormat::B8G8R8X8_UNORM,
+            91 => DxgiFormat::B8G8R8A8_UNORM_SRGB,
+            93 => DxgiFormat::B8G8R8X8_UNORM_SRGB,
+            95 => DxgiFormat::BC6H_UF16,
+            96 => DxgiFormat::BC6H_SF16,
+            98 => DxgiFormat::BC7_UNORM,
+            99 => DxgiFormat::BC7_UNORM_SRGB,
+            115 => DxgiFormat::B4G4R4A4_UNORM,
+            _ => return Err(anyhow!("DXGI format {} not supported", value)),
+        })
+    }
+
+    /// Block width & height in pixels, and block size in bytes.
+    ///
+    /// Uncompressed formats are treated as 1x1 blocks.
+    pub fn block_info(&self) -> (u32, usize) {
+        match self {
+            DxgiFormat::R32G32B32A32_FLOAT => (1, 16),
+            DxgiFormat::R32G32B32_FLOAT => (1, 12),
+            DxgiFormat::R16G16B16A16_FLOAT
+            | DxgiFormat::R16G16B16A16_UNORM
+            | DxgiFormat::R32G32_FLOAT => (1, 8),
+            DxgiFormat::R10G10B10A2_UNORM
+            | DxgiFormat::R8G8B8A8_UNORM
+            | DxgiFormat::R8G8B8A8_UNORM_SRGB
+            | DxgiFormat::R16G16_FLOAT
+            | DxgiFormat::R16G16_UNORM
+            | DxgiFormat::R32_FLOAT
+            | DxgiFormat::B8G8R8A8_UNORM
+            | DxgiFormat::B8G8R8X8_UNORM
+            | DxgiFormat::B8G8R8A8_UNORM_SRGB
+            | DxgiFormat::B8G8R8X8_UNORM_SRGB => (1, 4),
+            DxgiFormat::R8G8_UNORM
+            | DxgiFormat::R16_FLOAT
+            | DxgiFormat::R16_UNORM
+            | DxgiFormat::B5G6R5_UNORM
+            | DxgiFormat::B5G5R5A1_UNORM
+            | DxgiFormat::B4G4R4A4_UNORM => (1, 2),
+            DxgiFormat::R8_UNORM | DxgiFormat::A8_UNORM => (1, 1),
+            DxgiFormat::BC1_UNORM
+            | DxgiFormat::BC1_UNORM_SRGB
+            | DxgiFormat::BC4_UNORM
+            | DxgiFormat::BC4_SNORM => (4, 8),
+            DxgiFormat::BC2_UNORM
+            | DxgiFormat::BC2_UNORM_SRGB
+            | DxgiFormat::BC3_UNORM
+            | DxgiFormat::BC3_UNORM_SRGB
+            | DxgiFormat::BC5_UNORM
+            | DxgiFormat::BC5_SNORM
+            | DxgiFormat::BC6H_UF16
+            | DxgiFormat::BC6H_SF16
+            | DxgiFormat::BC7_UNORM
+            | DxgiFormat::BC7_UNORM_SRGB => (4, 16),
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.block_info().0 > 1
+    }
+
+    /// Size in bytes of a single surface of this format, `None` if it doesn't fit in a `usize`.
+    pub fn surface_size(&self, width: u32, height: u32) -> Option<usize> {
+        let (block, block_size) = self.block_info();
+        let blocks_x = width.div_ceil(block).max(1) as usize;
+        let blocks_y = height.div_ceil(block).max(1) as usize;
+        blocks_x.checked_mul(blocks_y)?.checked_mul(block_size)
+    }
+
+    /// Legacy pixel format: flags, FourCC, bit count, and RGBA bit masks.
+    fn legacy_pixel_format(&self) -> Option<(u32, [u8; 4], u32, [u32; 4])> {
+        Some(match self {
+            DxgiFormat::BC1_UNORM => (DDPF_FOURCC, *b"DXT1", 0, [0; 4]),
+            DxgiFormat::BC2_UNORM => (DDPF_FOURCC, *b"DXT3", 0, [0; 4]),
+            DxgiFormat::BC3_UNORM => (DDPF_FOURCC, *b"DXT5", 0, [0; 4]),
+            DxgiFormat::BC4_UNORM => (DDPF_FOURCC, *b"ATI1", 0, [0; 4]),
+            DxgiFormat::BC5_UNORM => (DDPF_FOURCC, *b"ATI2", 0, [0; 4]),
+            DxgiFormat::B8G8R8A8_UNORM => (
+                DDPF_RGB | DDPF_ALPHAPIXELS,
+                [0; 4],
+                32,
+                [0x00FF0000, 0x0000FF00, 0x000000FF, 0xFF000000],
+            ),
+            DxgiFormat::B8G8R8X8_UNORM => (
+                DDPF_RGB,
+                [0; 4],
+                32,
+                [0x00FF0000, 0x0000FF00, 0x000000FF, 0],
+            ),
+            DxgiFormat::R8G8B8A8_UNORM => (
+                DDPF_RGB | DDPF_ALPHAPIXELS,
+                [0; 4],
+                32,
+                [0x000000FF, 0x0000FF00, 0x00FF0000, 0xFF000000],
+            ),
+            DxgiFormat::R8_UNORM => (DDPF_LUMINANCE, [0; 4], 8, [0xFF, 0, 0, 0]),
+            _ => return None,
+        })
+    }
+}
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PITCH: u32 = 0x8;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDSD_LINEARSIZE: u32 = 0x80000;
+const DDSD_DEPTH: u32 = 0x800000;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_ALPHA: u32 = 0x2;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x400000;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const D3D10_RESOURCE_DIMENSION_TEXTURE3D: u32 = 4;
+const D3D10_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+/// Largest width, height & depth accepted when reading, well above what any GPU supports.
+const MAX_DIMENSION: u32 = 1 << 16;
+/// Largest texture array accepted when reading.
+const MAX_ARRAY_SIZE: u32 = 1 << 16;
+
+#[derive(Debug, Clone)]
+pub struct DdsHeader {
+    pub width: u32,
+    pub height: u32,
+    /// Only used by volume textures, 1 otherwise.
+    pub depth: u32,
+    pub mipmap_count: u32,
+    /// Number of textures in a texture array, cubemaps count each set of 6 faces as 1.
+    pub array_size: u32,
+    pub cubemap: bool,
+    pub format: DxgiFormat,
+}
+
+impl DdsHeader {
+    pub fn new(width: u32, height: u32, format: DxgiFormat) -> DdsHeader {
+        DdsHeader {
+            width,
+            height,
+            depth: 1,
+            mipmap_count: 1,
+            array_size: 1,
+            cubemap: false,
+            format,
+        }
+    }
+
+    /// Reads the `DDS ` magic & header, also returns if the data is legacy 24 bit RGB that needs to
+    /// be expanded to [`DxgiFormat::B8G8R8X8_UNORM`].
+    fn read(data: impl Read) -> Result<(DdsHeader, bool)> {
+        let mut reader = crate::reader::Reader::new_le(data);
+
+        if &reader.read::<[u8; 4]>()? != b"DDS " {
+            return Err(anyhow!("DDS identifier doesn't match"));
+        }
+        if reader.read::<u32>()? != 124 {
+            return Err(anyhow!("DDS invalid header size"));
+        }
+
+        let flags = reader.read::<u32>()?;
+        let height = reader.read::<u32>()?;
+        let width = reader.read::<u32>()?;
+        let _pitch_or_linear_size = reader.read::<u32>()?;
+        let depth = reader.read::<u32>()?;
+        let mipmap_count = reader.read::<u32>()?;
+        reader.skip(11 * 4)?;
+
+        let _pf_size = reader.read::<u32>()?;
+        let pf_flags = reader.read::<u32>()?;
+        let four_cc = reader.read::<[u8; 4]>()?;
+        let bit_count = reader.read::<u32>()?;
+        let masks = reader.read::<[u32; 4]>()?;
+
+        let _caps = reader.read::<u32>()?;
+        let caps2 = reader.read::<u32>()?;
+        reader.skip(3 * 4)?;
+
+        let mut header = DdsHeader {
+            width,
+            height,
+            depth: if flags & DDSD_DEPTH != 0 && caps2 & DDSCAPS2_VOLUME != 0 {
+                depth.max(1)
+            } else {
+                1
+            },
+            mipmap_count: if flags & DDSD_MIPMAPCOUNT != 0 {
+                mipmap_count.max(1)
+            } else {
+                1
+            },
+            array_size: 1,
+            cubemap: caps2 & DDSCAPS2_CUBEMAP != 0,
+            format: DxgiFormat::R8G8B8A8_UNORM,
+        };
+        let mut rgb24 = false;
+
+        if pf_flags & DDPF_FOURCC != 0 {
+            header.format = match &four_cc {
+                b"DX10" => {
+                    let format = DxgiFormat::from(reader.read::<u32>()?)?;
+                    let resource_dimension = reader.read::<u32>()?;
+                    let misc_flag = reader.read::<u32>()?;
+                    header.array_size = reader.read::<u32>()?.max(1);
+                    let _misc_flags2 = reader.read::<u32>()?;
+                    if misc_flag & D3D10_RESOURCE_MISC_TEXTURECUBE != 0 {
+                        header.cubemap = true;
+                    }
+                    if resource_dimension != D3D10_RESOURCE_DIMENSION_TEXTURE3D {
+                        header.depth = 1;
+                    }
+                    format
+                }
+                b"DXT1" => DxgiFormat::BC1_UNORM,
+                b"DXT2" | b"DXT3" => DxgiFormat::BC2_UNORM,
+                b"DXT4" | b"DXT5" => DxgiFormat::BC3_UNORM,
+                b"ATI1" | b"BC4U" => DxgiFormat::BC4_UNORM,
+                b"BC4S" => DxgiFormat::BC4_SNORM,
+                b"ATI2" | b"BC5U" => DxgiFormat::BC5_UNORM,
+                b"BC5S" => DxgiFormat::BC5_SNORM,
+                // D3DFORMAT values stored as the FourCC.
+                _ => match u32::from_le_bytes(four_cc) {
+                    36 => DxgiFormat::R16G16B16A16_UNORM,
+                    111 => DxgiFormat::R16_FLOAT,
+                    112 => DxgiFormat::R16G16_FLOAT,
+                    113 => DxgiFormat::R16G16B16A16_FLOAT,
+                    114 => DxgiFormat::R32_FLOAT,
+                    115 => DxgiFormat::R32G32_FLOAT,
+                    116 => DxgiFormat::R32G32B32A32_FLOAT,
+                    _ => {
+                        return Err(anyhow!(
+                            "DDS FourCC {:?} not supported",
+                            String::from_utf8_lossy(&four_cc)
+                        ))
+                    }
+                },
+            };
+        } else if pf_flags & DDPF_RGB != 0 {
+            let alpha = if pf_flags & DDPF_ALPHAPIXELS != 0 {
+                masks[3]
+            } else {
+                0
+            };
+            header.format = match (bit_count, [masks[0], masks[1], masks[2], alpha]) {
+                (32, [0x00FF0000, 0x0000FF00, 0x000000FF, 0xFF000000]) => {
+                    DxgiFormat::B8G8R8A8_UNORM
+                }
+                (32, [0x00FF0000, 0x0000FF00, 0x000000FF, 0]) => DxgiFormat::B8G8R8X8_UNORM,
+                (32, [0x000000FF, 0x0000FF00, 0x00FF0000, 0xFF000000]) => {
+                    DxgiFormat::R8G8B8A8_UNORM
+                }
+                (32, [0x3FF00000, 0x000FFC00, 0x000003FF, 0xC0000000]) => {
+                    DxgiFormat::R10G10B10A2_UNORM
+                }
+                (32, [0x0000FFFF, 0xFFFF0000, 0, 0]) => DxgiFormat::R16G16_UNORM,
+                (24, [0xFF0000, 0x00FF00, 0x0000FF, 0]) => {
+                    rgb24 = true;
+                    DxgiFormat::B8G8R8X8_UNORM
+                }
+                (16, [0xF800, 0x07E0, 0x001F, 0]) => DxgiFormat::B5G6R5_UNORM,
+                (16, [0x7C00, 0x03E0, 0x001F, 0x8000]) => DxgiFormat::B5G5R5A1_UNORM,
+                (16, [0x0F00, 0x00F0, 0x000F, 0xF000]) => DxgiFormat::B4G4R4A4_UNORM,
+                _ => return Err(anyhow!("DDS RGB pixel format not supported")),
+            };
+        } else if pf_flags & DDPF_LUMINANCE != 0 {
+            header.format = match bit_count {
+                8 => DxgiFormat::R8_UNORM,
+                16 if pf_flags & DDPF_ALPHAPIXELS == 0 => DxgiFormat::R16_UNORM,
+                _ => return Err(anyhow!("DDS luminance pixel format not supported")),
+            };
+        } else if pf_flags & DDPF_ALPHA != 0 && bit_count == 8 {
+            header.format = DxgiFormat::A8_UNORM;
+        } else {
+            return Err(anyhow!("DDS pixel format not supported"));
+        }
+
+        if header.width == 0
+            || header.height == 0
+            || header.width > MAX_DIMENSION
+            || header.height > MAX_DIMENSION
+            || header.depth > MAX_DIMENSION
+            || header.array_size > MAX_ARRAY_SIZE
+        {
+            return Err(anyhow!("DDS invalid size"));
+        }
+        // A full mipmap chain ends at 1x1x1.
+        let largest = header.width.max(header.height).max(header.depth);
+        if header.mipmap_count > u32::BITS - largest.leading_zeros() {
+            return Err(anyhow!("DDS invalid mipmap count"));
+        }
+
+        Ok((header, rgb24))
+    }
+
+    /// Number of faces, 6 for cubemaps & 1 otherwise.
+    pub fn faces(&self) -> u32 {
+        if self.cubemap {
+            6
+        } else {
+            1
+        }
+    }
+
+    /// Number of slices, either the depth of a volume texture or the size of a texture array.
+    pub fn slices(&self) -> u32 {
+        self.depth.max(self.array_size)
+    }
+
+    pub fn mipmap_size(&self, mipmap: u32) -> (u32, u32) {
+        (
+            self.width.checked_shr(mipmap).unwrap_or(0).max(1),
+            self.height.checked_shr(mipmap).unwrap_or(0).max(1),
+        )
+    }
+
+    fn mipmap_depth(&self, mipmap: u32) -> u32 {
+        self.depth.checked_shr(mipmap).unwrap_or(0).max(1)
+    }
+
+    /// Size of all depth slices of a mipmap.
+    fn mipmap_data_size(&self, mipmap: u32) -> Option<usize> {
+        let (width, height) = self.mipmap_size(mipmap);
+        self.format
+            .surface_size(width, height)?
+            .checked_mul(self.mipmap_depth(mipmap) as usize)
+    }
+
+    /// Size of all surfaces of all mipmaps in a single array layer or face.
+    fn layer_size(&self) -> Option<usize> {
+        (0..self.mipmap_count).try_fold(0usize, |size, mipmap| {
+            size.checked_add(self.mipmap_data_size(mipmap)?)
+        })
+    }
+
+    /// Offset & size of a surface relative to the start of the texture data.
+    pub fn surface_range(&self, mipmap: u32, face: u32, slice: u32) -> Option<(usize, usize)> {
+        if mipmap >= self.mipmap_count || face >= self.faces() {
+            return None;
+        }
+
+        let (width, height) = self.mipmap_size(mipmap);
+        let size = self.format.surface_size(width, height)?;
+
+        // Volume textures have their slices inside of each mipmap, arrays have each layer
+        // contain all mipmaps.
+        let (layer, depth_slice) = if self.depth > 1 {
+            if slice >= self.mipmap_depth(mipmap) {
+                return None;
+            }
+            (0, slice)
+        } else {
+            if slice >= self.array_size {
+                return None;
+            }
+            (slice * self.faces() + face, 0)
+        };
+
+        let mut offset = (layer as usize).checked_mul(self.layer_size()?)?;
+        for previous in 0..mipmap {
+            offset = offset.checked_add(self.mipmap_data_size(previous)?)?;
+        }
+        offset = offset.checked_add((depth_slice as usize).checked_mul(size)?)?;
+        Some((offset, size))
+    }
+
+    /// Writes the `DDS ` magic & header, the DX10 header is only written if the format has no
+    /// legacy equivalent.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let legacy = if self.array_size <= 1 {
+            self.format.legacy_pixel_format()
+        } else {
+            None
+        };
+
+        let mut flags = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT;
+        let pitch_or_linear_size = if self.format.is_compressed() {
+            flags |= DDSD_LINEARSIZE;
+            self.format.surface_size(self.width, self.height)
+        } else {
+            flags |= DDSD_PITCH;
+            self.format.surface_size(self.width, 1)
+        }
+        .and_then(|size| u32::try_from(size).ok())
+        .unwrap_or(0);
+        if self.mipmap_count > 1 {
+            flags |= DDSD_MIPMAPCOUNT;
+        }
+        if self.depth > 1 {
+            flags |= DDSD_DEPTH;
+        }
+
+        let mut caps = DDSCAPS_TEXTURE;
+        if self.mipmap_count > 1 {
+            caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+        }
+        let mut caps2 = 0;
+        if self.cubemap {
+            caps |= DDSCAPS_COMPLEX;
+            caps2 |= DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALLFACES;
+        }
+        if self.depth > 1 {
+            caps |= DDSCAPS_COMPLEX;
+            caps2 |= DDSCAPS2_VOLUME;
+        }
+
+        let (pf_flags, four_cc, bit_count, masks) =
+            legacy.unwrap_or((DDPF_FOURCC, *b"DX10", 0, [0; 4]));
+
+        let mut header = Vec::with_capacity(148);
+        header.extend_from_slice(b"DDS ");
+        for value in [
+            124,
+            flags,
+            self.height,
+            self.width,
+            pitch_or_linear_size,
+            self.depth,
+            self.mipmap_count,
+        ] {
+            header.extend_from_slice(&u32::to_le_bytes(value));
+        }
+        header.extend_from_slice(&[0; 11 * 4]);
+        header.extend_from_slice(&u32::to_le_bytes(32));
+        header.extend_from_slice(&u32::to_le_bytes(pf_flags));
+        header.extend_from_slice(&four_cc);
+        header.extend_from_slice(&u32::to_le_bytes(bit_count));
+        for mask in masks {
+            header.extend_from_slice(&u32::to_le_bytes(mask));
+        }
+        for value in [caps, caps2, 0, 0, 0] {
+            header.extend_from_slice(&u32::to_le_bytes(value));
+        }
+
+        if legacy.is_none() {
+            for value in [
+                self.format as u32,
+                if self.depth > 1 {
+                    D3D10_RESOURCE_DIMENSION_TEXTURE3D
+                } else {
+                    D3D10_RESOURCE_DIMENSION_TEXTURE2D
+                },
+                if self.cubemap {
+                    D3D10_RESOURCE_MISC_TEXTURECUBE
+                } else {
+                    0
+                },
+                self.array_size.max(1),
+                0,
+            ] {
+                header.extend_from_slice(&u32::to_le_bytes(value));
+            }
+        }
+
+        writer.write_all(&header)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Dds {
+    header: DdsHeader,
+    data: Vec<u8>,
+}
+
+impl Dds {
+    pub fn header(&self) -> &DdsHeader {
+        &self.header
+    }
+
+    pub fn format(&self) -> DxgiFormat {
+        self.header.format
+    }
+
+    pub fn width(&self) -> u32 {
+        self.header.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.header.height
+    }
+
+    pub fn mipmaps(&self) -> u32 {
+        self.header.mipmap_count
+    }
+
+    pub fn faces(&self) -> u32 {
+        self.header.faces()
+    }
+
+    pub fn slices(&self) -> u32 {
+        self.header.slices()
+    }
+
+    /// Index of a surface, for caching decoded surfaces.
+    pub fn texture_index(&self, mipmap: u32, face: u32, slice: u32) -> Option<usize> {
+        if mipmap >= self.mipmaps() || face >= self.faces() || slice >= self.slices() {
+            return None;
+        }
+        (mipmap as usize)
+            .checked_mul(self.faces() as usize)?
+            .checked_add(face as usize)?
+            .checked_mul(self.slices() as usize)?
+            .checked_add(slice as usize)
+    }
+
+    pub fn total_num_textures(&self) -> Option<usize> {
+        (self.mipmaps() as usize)
+            .checked_mul(self.faces() as usize)?
+            .checked_mul(self.slices() as usize)
+    }
+
+    pub fn surface(&self, mipmap: u32, face: u32, slice: u32) -> Option<&[u8]> {
+        let (offset, size) = self.header.surface_range(mipmap, face, slice)?;
+        self.data.get(offset..offset.checked_add(size)?)
+    }
+
+    pub fn to_image(&self, mipmap: u32, face: u32, slice: u32) -> Result<DynamicImage> {
+        let data = self
+            .surface(mipmap, face, slice)
+            .ok_or(anyhow!("DDS surface out of bounds"))?;
+        let (width, height) = self.header.mipmap_size(mipmap);
+        decode_surface(self.header.format, data, width, height)
+    }
+
+    /// 2D texture from an image, with a full mipmap chain if `mipmaps` is set.
+    pub fn from_image(image: &DynamicImage, format: DxgiFormat, mipmaps: bool) -> Result<Dds> {
+        let images = if mipmaps {
+            super::generate_mipmaps(image)
+        } else {
+            vec![image.clone()]
+        };
+
+        let mut header = DdsHeader::new(image.width(), image.height(), format);
+        header.mipmap_count = images.len() as u32;
+        let mut data = Vec::new();
+        for image in images {
+            data.extend(encode_surface(format, &image)?);
+        }
+
+        Ok(Dds { header, data })
+    }
+
+    pub fn save<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.header.write(writer)?;
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+
+    pub fn load(mut data: impl Read + Seek) -> Result<Dds> {
+        data.rewind()?;
+        let (header, rgb24) = DdsHeader::read(&mut data)?;
+
+        let mut texture_data = Vec::new();
+        data.read_to_end(&mut texture_data)?;
+        if rgb24 {
+            texture_data = expand_rgb24(&texture_data);
+        }
+
+        Ok(Dds {
+            header,
+            data: texture_data,
+        })
+    }
+
+    /// Decode only the first surface of the smallest mipmap that is still at least as big as the
+    /// hint.
+    pub fn load_thumbnail(mut data: impl Read + Seek, hint: SizeHint) -> Result<DynamicImage> {
+        data.rewind()?;
+        let (header, rgb24) = DdsHeader::read(&mut data)?;
+        let data_start = data.stream_position()?;
+
+        let mut mipmap = 0;
+        while mipmap < header.mipmap_count {
+            let (width, height) = header.mipmap_size(mipmap);
+            if hint.satisfies(width, height) {
+                // Go to previous mipmap so scaling is a bit more clean.
+                mipmap = mipmap.saturating_sub(1);
+                break;
+            }
+            mipmap += 1;
+        }
+        let mipmap = mipmap.min(header.mipmap_count - 1);
+
+        let (mut offset, mut size) = header
+            .surface_range(mipmap, 0, 0)
+            .ok_or(anyhow!("DDS surface out of bounds"))?;
+        if rgb24 {
+            offset = offset / 4 * 3;
+            size = size / 4 * 3;
+        }
+
+        let stream_size = data.seek(SeekFrom::End(0))?;
+        if data_start
+            .saturating_add(offset as u64)
+            .saturating_add(size as u64)
+            > stream_size
+        {
+            return Err(anyhow!("DDS surface out of bounds"));
+        }
+        data.seek(SeekFrom::Start(data_start + offset as u64))?;
+        let mut surface = vec![0u8; size];
+        data.read_exact(&mut surface)?;
+        if rgb24 {
+            surface = expand_rgb24(&surface);
+        }
+
+        let (width, height) = header.mipmap_size(mipmap);
+        decode_surface(header.format, &surface, width, height)
+    }
+}
+
+fn expand_rgb24(data: &[u8]) -> Vec<u8> {
+    data.chunks_exact(3)
+        .flat_map(|c| [c[0], c[1], c[2], 0])
+        .collect()
+}
+
+fn swizzle_image<const S: usize, P, F>(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    swizzle: F,
+) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+    P: Pixel + 'static,
+    F: Fn(&[u8; S]) -> P,
+{
+    let mut image = ImageBuffer::<P, Vec<P::Subpixel>>::new(width, height);
+    for (pixel, chunk) in image.pixels_mut().zip(data.chunks_exact(S)) {
+        *pixel = swizzle(chunk.try_into().unwrap());
+    }
+    image
+}
+
+#[inline]
+fn f16(lo: u8, hi: u8) -> f32 {
+    half::f16::from_bits(u16::from_le_bytes([lo, hi])).to_f32()
+}
+
+#[inline]
+fn f32(c: &[u8]) -> f32 {
+    f32::from_le_bytes([c[0], c[1], c[2], c[3]])
+}
+
+#[inline]
+fn expand_bits(value: u32, bits: u32) -> u8 {
+    ((value * 255 + ((1 << bits) - 1) / 2) / ((1 << bits) - 1)) as u8
+}
+
+/// Decodes a single surface of a DDS file.
+pub fn decode_surface(
+    format: DxgiFormat,
+    data: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<DynamicImage> {
+    let size = format
+        .surface_size(width, height)
+        .ok_or(anyhow!("DDS surface is too big"))?;
+    if data.len() < size {
+        return Err(anyhow!("DDS surface data is too small"));
+    }
+
+    Ok(match format {
+        DxgiFormat::R32G32B32A32_FLOAT => {
+            DynamicImage::ImageRgba32F(swizzle_image(data, width, height, |c: &[u8; 16]| {
+                Rgba([f32(&c[0..]), f32(&c[4..]), f32(&c[8..]), f32(&c[12..])])
+            }))
+        }
+        DxgiFormat::R32G32B32_FLOAT => {
+            DynamicImage::ImageRgb32F(swizzle_image(data, width, height, |c: &[u8; 12]| {
+                Rgb([f32(&c[0..]), f32(&c[4..]), f32(&c[8..])])
+            }))
+        }
+        DxgiFormat::R16G16B16A16_FLOAT => {
+            DynamicImage::ImageRgba32F(swizzle_image(data, width, height, |c: &[u8; 8]| {
+                Rgba([
+                    f16(c[0], c[1]),
+                    f16(c[2], c[3]),
+                    f16(c[4], c[5]),
+                    f16(c[6], c[7]),
+                ])
+            }))
+        }
+        DxgiFormat::R16G16B16A16_UNORM => {
+            DynamicImage::ImageRgba16(swizzle_image(data, width, height, |c: &[u8; 8]| {
+                Rgba([
+                    u16::from_le_bytes([c[0], c[1]]),
+                    u16::from_le_bytes([c[2], c[3]]),
+                    u16::from_le_bytes([c[4], c[5]]),
+                    u16::from_le_bytes([c[6], c[7]]),
+                ])
+            }))
+        }
+        DxgiFormat::R32G32_FLOAT => {
+            DynamicImage::ImageRgb32F(swizzle_image(data, width, height, |c: &[u8; 8]| {
+                Rgb([f32(&c[0..]), f32(&c[4..]), 0.0])
+            }))
+        }
+        DxgiFormat::R10G10B10A2_UNORM => {
+            DynamicImage::ImageRgba8(swizzle_image(data, width, height, |c: &[u8; 4]| {
+                let v = u32::from_le_bytes(*c);
+                Rgba([
+                    expand_bits(v & 0x3FF, 10),
+                    expand_bits((v >> 10) & 0x3FF, 10),
+                    expand_bits((v >> 20) & 0x3FF, 10),
+                    expand_bits(v >> 30, 2),
+                ])
+            }))
+        }
+        DxgiFormat::R8G8B8A8_UNORM | DxgiFormat::R8G8B8A8_UNORM_SRGB => DynamicImage::ImageRgba8(
+            RgbaImage::from_raw(width, height, data[..size].to_vec()).unwrap(),
+        ),
+        DxgiFormat::R16G16_FLOAT => {
+            DynamicImage::ImageRgb32F(swizzle_image(data, width, height, |c: &[u8; 4]| {
+                Rgb([f16(c[0], c[1]), f16(c[2], c[3]), 0.0])
+            }))
+        }
+        DxgiFormat::R16G16_UNORM => {
+            DynamicImage::ImageRgb16(swizzle_image(data, width, height, |c: &[u8; 4]| {
+                Rgb([
+                    u16::from_le_bytes([c[0], c[1]]),
+                    u16::from_le_bytes([c[2], c[3]]),
+                    0,
+                ])
+            }))
+        }
+        DxgiFormat::R32_FLOAT => {
+            let image: Rgb32FImage =
+                swizzle_image(data, width, height, |c: &[u8; 4]| Rgb([f32(c); 3]));
+            DynamicImage::ImageRgb32F(image)
+        }
+        DxgiFormat::R8G8_UNORM => {
+            DynamicImage::ImageRgb8(swizzle_image(data, width, height, |c: &[u8; 2]| {
+                Rgb([c[0], c[1], 0])
+            }))
+        }
+        DxgiFormat::R16_FLOAT => {
+            let image: Rgb32FImage =
+                swizzle_image(data, width, height, |c: &[u8; 2]| Rgb([f16(c[0], c[1]); 3]));
+            DynamicImage::ImageRgb32F(image)
+        }
+        DxgiFormat::R16_UNORM => {
+            DynamicImage::ImageLuma16(swizzle_image(data, width, height, |c: &[u8; 2]| {
+                Luma([u16::from_le_bytes(*c)])
+            }))
+        }
+        DxgiFormat::R8_UNORM => DynamicImage::ImageLuma8(
+            GrayImage::from_raw(width, height, data[..size].to_vec()).unwrap(),
+        ),
+        // There's no such thing as ImageA8, So we just use ImageLumaA8 & set Luma to be 0.
+        DxgiFormat::A8_UNORM => {
+            DynamicImage::ImageLumaA8(swizzle_image(data, width, height, |c: &[u8; 1]| {
+                LumaA([0, c[0]])
+            }))
+        }
+        DxgiFormat::BC1_UNORM | DxgiFormat::BC1_UNORM_SRGB => DynamicImage::ImageRgba8(
+            super::bc::decode_bc1(data, width, height, Rgba([0, 0, 0, 0])),
+        ),
+        DxgiFormat::BC2_UNORM | DxgiFormat::BC2_UNORM_SRGB => {
+            DynamicImage::ImageRgba8(super::bc::decode_bc2(data, width, height))
+        }
+        DxgiFormat::BC3_UNORM | DxgiFormat::BC3_UNORM_SRGB => {
+            DynamicImage::ImageRgba8(super::bc::decode_bc3(data, width, height))
+        }
+        DxgiFormat::B5G6R5_UNORM => {
+            DynamicImage::ImageRgb8(swizzle_image(data, width, height, |c: &[u8; 2]| {
+                let v = u16::from_le_bytes(*c) as u32;
+                Rgb([
+                    expand_bits(v >> 11, 5),
+                    expand_bits((v >> 5) & 0x3F, 6),
+                    expand_bits(v & 0x1F, 5),
+                ])
+            }))
+        }
+        DxgiFormat::B5G5R5A1_UNORM => {
+            DynamicImage::ImageRgba8(swizzle_image(data, width, height, |c: &[u8; 2]| {
+                let v = u16::from_le_bytes(*c) as u32;
+                Rgba([
+                    expand_bits((v >> 10) & 0x1F, 5),
+                    expand_bits((v >> 5) & 0x1F, 5),
+                    expand_bits(v & 0x1F, 5),
+                    expand_bits(v >> 15, 1),
+                ])
+            }))
+        }
+        DxgiFormat::B8G8R8A8_UNORM | DxgiFormat::B8G8R8A8_UNORM_SRGB => {
+            DynamicImage::ImageRgba8(swizzle_image(data, width, height, |c: &[u8; 4]| {
+                Rgba([c[2], c[1], c[0], c[3]])
+            }))
+        }
+        DxgiFormat::B8G8R8X8_UNORM | DxgiFormat::B8G8R8X8_UNORM_SRGB => {
+            DynamicImage::ImageRgb8(swizzle_image(data, width, height, |c: &[u8; 4]| {
+                Rgb([c[2], c[1], c[0]])
+            }))
+        }
+        DxgiFormat::B4G4R4A4_UNORM => {
+            DynamicImage::ImageRgba8(swizzle_image(data, width, height, |c: &[u8; 2]| {
+                let v = u16::from_le_bytes(*c) as u32;
+                Rgba([
+                    expand_bits((v >> 8) & 0xF, 4),
+                    expand_bits((v >> 4) & 0xF, 4),
+                    expand_bits(v & 0xF, 4),
+                    expand_bits(v >> 12, 4),
+                ])
+            }))
+        }
+        DxgiFormat::BC4_UNORM | DxgiFormat::BC4_SNORM => DynamicImage::ImageLuma8(
+            super::bc::decode_bc4(data, width, height, format == DxgiFormat::BC4_SNORM),
+        ),
+        DxgiFormat::BC5_UNORM | DxgiFormat::BC5_SNORM => DynamicImage::ImageRgb8(
+            super::bc::decode_bc5(data, width, height, format == DxgiFormat::BC5_SNORM),
+        ),
+        DxgiFormat::BC6H_UF16 | DxgiFormat::BC6H_SF16 => DynamicImage::ImageRgba32F(
+            super::bc::decode_bc6h(data, width, height, format == DxgiFormat::BC6H_SF16),
+        ),
+        DxgiFormat::BC7_UNORM | DxgiFormat::BC7_UNORM_SRGB => {
+            DynamicImage::ImageRgba8(super::bc::decode_bc7(data, width, height))
+        }
+    })
+}
+
+/// Encodes a single surface of a DDS file, only the common formats are supported.
+pub fn encode_surface(format: DxgiFormat, image: &DynamicImage) -> Result<Vec<u8>> {
+    Ok(match format {
+        DxgiFormat::R32G32B32A32_FLOAT => image
+            .to_rgba32f()
+            .into_raw()
+            .into_iter()
+            .flat_map(f32::to_le_bytes)
+            .collect(),
+        DxgiFormat::R8G8B8A8_UNORM | DxgiFormat::R8G8B8A8_UNORM_SRGB => image.to_rgba8().into_raw(),
+        DxgiFormat::B8G8R8A8_UNORM | DxgiFormat::B8G8R8A8_UNORM_SRGB => image
+            .to_rgba8()
+            .pixels()
+            .flat_map(|p| [p[2], p[1], p[0], p[3]])
+            .collect(),
+        DxgiFormat::R8_UNORM => image.to_rgba8().pixels().map(|p| p[0]).collect(),
+        DxgiFormat::BC1_UNORM | DxgiFormat::BC1_UNORM_SRGB => {
+            super::bc::encode_bc1(&image.to_rgba8(), true)
+        }
+        DxgiFormat::BC2_UNORM | DxgiFormat::BC2_UNORM_SRGB => {
+            super::bc::encode_bc2(&image.to_rgba8())
+        }
+        DxgiFormat::BC3_UNORM | DxgiFormat::BC3_UNORM_SRGB => {
+            super::bc::encode_bc3(&image.to_rgba8())
+        }
+        DxgiFormat::BC4_UNORM => super::bc::encode_bc4(&image.to_rgba8()),
+        DxgiFormat::BC5_UNORM => super::bc::encode_bc5(&image.to_rgb8()),
+        DxgiFormat::BC7_UNORM | DxgiFormat::BC7_UNORM_SRGB => {
+            super::bc::encode_bc7(&image.to_rgba8())
+        }
+        _ => return Err(anyhow!("DDS format {:?} encoding not supported", format)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(header: &DdsHeader) -> DdsHeader {
+        let mut data = Vec::new();
+        header.write(&mut data).unwrap();
+        let (read, rgb24) = DdsHeader::read(data.as_slice()).unwrap();
+        assert!(!rgb24);
+        read
+    }
+
+    fn rgba_header(width: u32, height: u32, mipmap_count: u32) -> DdsHeader {
+        let mut header = DdsHeader::new(width, height, DxgiFormat::R8G8B8A8_UNORM);
+        header.mipmap_count = mipmap_count;
+        header
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let mut legacy = DdsHeader::new(256, 128, DxgiFormat::BC1_UNORM);
+        legacy.mipmap_count = 9;
+        let read = round_trip(&legacy);
+        assert_eq!((read.width, read.height), (256, 128));
+        assert_eq!((read.depth, read.mipmap_count, read.array_size), (1, 9, 1));
+        assert!(!read.cubemap);
+        assert_eq!(read.format, DxgiFormat::BC1_UNORM);
+
+        let mut cubemap_array = DdsHeader::new(64, 64, DxgiFormat::BC7_UNORM);
+        cubemap_array.mipmap_count = 7;
+        cubemap_array.array_size = 3;
+        cubemap_array.cubemap = true;
+        let read = round_trip(&cubemap_array);
+        assert_eq!((read.mipmap_count, read.array_size), (7, 3));
+        assert!(read.cubemap);
+        assert_eq!(read.format, DxgiFormat::BC7_UNORM);
+
+        let mut volume = DdsHeader::new(16, 8, DxgiFormat::R16G16B16A16_FLOAT);
+        volume.depth = 4;
+        let read = round_trip(&volume);
+        assert_eq!((read.width, read.height, read.depth), (16, 8, 4));
+        assert_eq!(read.format, DxgiFormat::R16G16B16A16_FLOAT);
+    }
+
+    #[test]
+    fn surface_range() {
+        // 64 + 16 + 4 bytes per face.
+        let mut cubemap = rgba_header(4, 4, 3);
+        cubemap.cubemap = true;
+        assert_eq!(cubemap.surface_range(0, 0, 0), Some((0, 64)));
+        assert_eq!(cubemap.surface_range(1, 2, 0), Some((2 * 84 + 64, 16)));
+        assert_eq!(cubemap.surface_range(2, 5, 0), Some((5 * 84 + 80, 4)));
+        assert_eq!(cubemap.surface_range(3, 0, 0), None);
+        assert_eq!(cubemap.surface_range(0, 6, 0), None);
+        assert_eq!(cubemap.surface_range(0, 0, 1), None);
+
+        let mut cubemap_array = cubemap.clone();
+        cubemap_array.array_size = 2;
+        assert_eq!(cubemap_array.surface_range(0, 1, 1), Some((7 * 84, 64)));
+        assert_eq!(cubemap_array.surface_range(0, 0, 2), None);
+
+        // Mipmaps are 4x4x4, 2x2x2 & 1x1x1.
+        let mut volume = rgba_header(4, 4, 3);
+        volume.depth = 4;
+        assert_eq!(volume.surface_range(0, 0, 3), Some((3 * 64, 64)));
+        assert_eq!(volume.surface_range(1, 0, 1), Some((256 + 16, 16)));
+        assert_eq!(volume.surface_range(2, 0, 0), Some((256 + 32, 4)));
+        assert_eq!(volume.surface_range(1, 0, 2), None);
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            DxgiFormat::R32G32B32A32_FLOAT.surface_size(u32::MAX, u32::MAX),
+            None
+        );
+        assert_eq!(rgba_header(16, 16, 1).mipmap_size(40), (1, 1));
+
+        let mut array = rgba_header(u32::MAX, u32::MAX, 1);
+        array.array_size = u32::MAX;
+        assert_eq!(array.surface_range(0, 0, u32::MAX - 1), None);
+    }
+
+    #[test]
+    fn malformed_headers() {
+        let header = |patch: &dyn Fn(&mut Vec<u8>)| {
+            let mut data = Vec::new();
+            rgba_header(16, 16, 2).write(&mut data).unwrap();
+            patch(&mut data);
+            data.extend_from_slice(&[0; 16 * 16 * 4]);
+            data
+        };
+        let valid = header(&|_| {});
+        assert!(Dds::load(Cursor::new(&valid)).is_ok());
+
+        // 16x16 has at most 5 mipmaps.
+        let too_many_mipmaps = header(&|data| data[28..32].copy_from_slice(&40u32.to_le_bytes()));
+        assert!(Dds::load(Cursor::new(&too_many_mipmaps)).is_err());
+        assert!(Dds::load_thumbnail(Cursor::new(&too_many_mipmaps), SizeHint::Pixels(1)).is_err());
+
+        let huge = header(&|data| {
+            data[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+            data[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+        });
+        assert!(Dds::load(Cursor::new(&huge)).is_err());
+        assert!(Dds::load_thumbnail(Cursor::new(&huge), SizeHint::Pixels(1)).is_err());
+
+        let empty = header(&|data| data[16..20].copy_from_slice(&0u32.to_le_bytes()));
+        assert!(Dds::load(Cursor::new(&empty)).is_err());
+
+        // Valid header, but the file is far too small for it.
+        let truncated = header(&|data| {
+            data[12..16].copy_from_slice(&MAX_DIMENSION.to_le_bytes());
+            data[16..20].copy_from_slice(&MAX_DIMENSION.to_le_bytes());
+        });
+        assert!(Dds::load_thumbnail(Cursor::new(&truncated), SizeHint::Pixels(1)).is_err());
+    }
+}
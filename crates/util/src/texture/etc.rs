@@ -0,0 +1,507 @@
+// https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html#ETC1
+// https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html#ETC2
+
+use image::{ImageBuffer, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+
+const ETC1_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+const ETC2_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+#[inline(always)]
+fn bits(block: u64, high: u32, low: u32) -> i32 {
+    ((block >> low) & ((1 << (high - low + 1)) - 1)) as i32
+}
+
+#[inline(always)]
+fn extend_4(v: i32) -> i32 {
+    (v << 4) | v
+}
+
+#[inline(always)]
+fn extend_5(v: i32) -> i32 {
+    (v << 3) | (v >> 2)
+}
+
+#[inline(always)]
+fn extend_6(v: i32) -> i32 {
+    (v << 2) | (v >> 4)
+}
+
+#[inline(always)]
+fn extend_7(v: i32) -> i32 {
+    (v << 1) | (v >> 6)
+}
+
+#[inline(always)]
+fn clamp_color(color: [i32; 3]) -> Rgba<u8> {
+    Rgba([
+        color[0].clamp(0, 255) as u8,
+        color[1].clamp(0, 255) as u8,
+        color[2].clamp(0, 255) as u8,
+        255,
+    ])
+}
+
+#[inline(always)]
+fn offset_color(color: [i32; 3], offset: i32) -> [i32; 3] {
+    color.map(|c| c + offset)
+}
+
+/// Pixels are stored in column order, this maps them back to row order.
+#[inline(always)]
+fn pixel_index(block: u64, pi: usize) -> usize {
+    let i = (pi % 4) * 4 + pi / 4;
+    ((((block >> (i + 16)) & 1) << 1) | ((block >> i) & 1)) as usize
+}
+
+/// Decode the color part of an ETC1 or ETC2 block.
+///
+/// If `punchthrough` the differential bit is instead used as the opaque bit, for ETC2 RGBA1.
+fn decode_etc_block(data: &[u8; 8], etc2: bool, punchthrough: bool) -> [Rgba<u8>; 16] {
+    let block = u64::from_be_bytes(*data);
+    let differential = punchthrough || bits(block, 33, 33) == 1;
+    let opaque = !punchthrough || bits(block, 33, 33) == 1;
+    let flip = bits(block, 32, 32) == 1;
+
+    let (base_1, base_2) = if differential {
+        let base = [
+            bits(block, 63, 59),
+            bits(block, 55, 51),
+            bits(block, 47, 43),
+        ];
+        let delta = [
+            bits(block, 58, 56),
+            bits(block, 50, 48),
+            bits(block, 42, 40),
+        ]
+        .map(|d| (d << 29) >> 29);
+        let second = [base[0] + delta[0], base[1] + delta[1], base[2] + delta[2]];
+
+        if etc2 && !(0..32).contains(&second[0]) {
+            return decode_etc2_t_block(block, opaque);
+        } else if etc2 && !(0..32).contains(&second[1]) {
+            return decode_etc2_h_block(block, opaque);
+        } else if etc2 && !(0..32).contains(&second[2]) {
+            return decode_etc2_planar_block(block);
+        }
+
+        (base.map(extend_5), second.map(extend_5))
+    } else {
+        (
+            [
+                bits(block, 63, 60),
+                bits(block, 55, 52),
+                bits(block, 47, 44),
+            ]
+            .map(extend_4),
+            [
+                bits(block, 59, 56),
+                bits(block, 51, 48),
+                bits(block, 43, 40),
+            ]
+            .map(extend_4),
+        )
+    };
+    let tables = [
+        ETC1_MODIFIERS[bits(block, 39, 37) as usize],
+        ETC1_MODIFIERS[bits(block, 36, 34) as usize],
+    ];
+
+    std::array::from_fn(|pi| {
+        let (x, y) = (pi % 4, pi / 4);
+        let subblock = if flip { y >= 2 } else { x >= 2 } as usize;
+        let base = if subblock == 0 { base_1 } else { base_2 };
+        let index = pixel_index(block, pi);
+        if !opaque && index == 2 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let modifier = match index {
+            // Punchthrough blocks that aren't opaque don't use the smaller modifier.
+            0 if !opaque => 0,
+            0 => tables[subblock][0],
+            1 => tables[subblock][1],
+            2 => -tables[subblock][0],
+            _ => -tables[subblock][1],
+        };
+        clamp_color(offset_color(base, modifier))
+    })
+}
+
+fn decode_etc2_paint_block(block: u64, opaque: bool, paint: [[i32; 3]; 4]) -> [Rgba<u8>; 16] {
+    std::array::from_fn(|pi| {
+        let index = pixel_index(block, pi);
+        if !opaque && index == 2 {
+            Rgba([0, 0, 0, 0])
+        } else {
+            clamp_color(paint[index])
+        }
+    })
+}
+
+fn decode_etc2_t_block(block: u64, opaque: bool) -> [Rgba<u8>; 16] {
+    let color_1 = [
+        (bits(block, 60, 59) << 2) | bits(block, 57, 56),
+        bits(block, 55, 52),
+        bits(block, 51, 48),
+    ]
+    .map(extend_4);
+    let color_2 = [
+        bits(block, 47, 44),
+        bits(block, 43, 40),
+        bits(block, 39, 36),
+    ]
+    .map(extend_4);
+    let distance = ETC2_DISTANCES[((bits(block, 35, 34) << 1) | bits(block, 32, 32)) as usize];
+
+    decode_etc2_paint_block(
+        block,
+        opaque,
+        [
+            color_1,
+            offset_color(color_2, distance),
+            color_2,
+            offset_color(color_2, -distance),
+        ],
+    )
+}
+
+fn decode_etc2_h_block(block: u64, opaque: bool) -> [Rgba<u8>; 16] {
+    let color_1 = [
+        bits(block, 62, 59),
+        (bits(block, 58, 56) << 1) | bits(block, 52, 52),
+        (bits(block, 51, 51) << 3) | bits(block, 49, 47),
+    ]
+    .map(extend_4);
+    let color_2 = [
+        bits(block, 46, 43),
+        bits(block, 42, 39),
+        bits(block, 38, 35),
+    ]
+    .map(extend_4);
+
+    let value = |c: [i32; 3]| (c[0] << 16) | (c[1] << 8) | c[2];
+    let distance_index = (bits(block, 34, 34) << 2)
+        | (bits(block, 32, 32) << 1)
+        | (value(color_1) >= value(color_2)) as i32;
+    let distance = ETC2_DISTANCES[distance_index as usize];
+
+    decode_etc2_paint_block(
+        block,
+        opaque,
+        [
+            offset_color(color_1, distance),
+            offset_color(color_1, -distance),
+            offset_color(color_2, distance),
+            offset_color(color_2, -distance),
+        ],
+    )
+}
+
+fn decode_etc2_planar_block(block: u64) -> [Rgba<u8>; 16] {
+    let origin = [
+        extend_6(bits(block, 62, 57)),
+        extend_7((bits(block, 56, 56) << 6) | bits(block, 54, 49)),
+        extend_6((bits(block, 48, 48) << 5) | (bits(block, 44, 43) << 3) | bits(block, 41, 39)),
+    ];
+    let horizontal = [
+        extend_6((bits(block, 38, 34) << 1) | bits(block, 32, 32)),
+        extend_7(bits(block, 31, 25)),
+        extend_6(bits(block, 24, 19)),
+    ];
+    let vertical = [
+        extend_6(bits(block, 18, 13)),
+        extend_7(bits(block, 12, 6)),
+        extend_6(bits(block, 5, 0)),
+    ];
+
+    std::array::from_fn(|pi| {
+        let (x, y) = ((pi % 4) as i32, (pi / 4) as i32);
+        clamp_color(std::array::from_fn(|c| {
+            (x * (horizontal[c] - origin[c]) + y * (vertical[c] - origin[c]) + 4 * origin[c] + 2)
+                >> 2
+        }))
+    })
+}
+
+/// Decode an EAC block, the modifier of each pixel is passed to `value` with the block's base &
+/// multiplier.
+#[inline(always)]
+fn decode_eac_block<F: Fn(i32, i32, i32) -> i32>(data: &[u8], base: i32, value: F) -> [i32; 16] {
+    let block = u64::from_be_bytes(data.try_into().unwrap());
+    let multiplier = bits(block, 55, 52);
+    let modifiers = EAC_MODIFIERS[bits(block, 51, 48) as usize];
+
+    std::array::from_fn(|pi| {
+        let i = ((pi % 4) * 4 + pi / 4) as u32;
+        let modifier = modifiers[bits(block, 47 - i * 3, 45 - i * 3) as usize];
+        value(base, multiplier, modifier)
+    })
+}
+
+/// Decode an EAC alpha block to 8 bit values.
+fn decode_eac_alpha_block(data: &[u8]) -> [i32; 16] {
+    decode_eac_block(data, data[0] as i32, |base, multiplier, modifier| {
+        (base + modifier * multiplier).clamp(0, 255)
+    })
+}
+
+/// Decode an EAC R11 block to 11 bit values.
+fn decode_eac_r11_block(data: &[u8], signed: bool) -> [i32; 16] {
+    if signed {
+        let base = (data[0] as i8).max(-127) as i32;
+        decode_eac_block(data, base, |base, multiplier, modifier| {
+            let modifier = if multiplier == 0 {
+                modifier
+            } else {
+                modifier * multiplier * 8
+            };
+            (base * 8 + modifier).clamp(-1023, 1023)
+        })
+    } else {
+        decode_eac_block(data, data[0] as i32, |base, multiplier, modifier| {
+            let modifier = if multiplier == 0 {
+                modifier
+            } else {
+                modifier * multiplier * 8
+            };
+            (base * 8 + 4 + modifier).clamp(0, 2047)
+        })
+    }
+}
+
+/// Maps 11 bit EAC values to 16 bits, signed values are remapped from -1023..=1023.
+#[inline(always)]
+fn eac_to_u16(value: i32, signed: bool) -> u16 {
+    if signed {
+        ((value + 1023) * 65535 / 2046) as u16
+    } else {
+        ((value << 5) | (value >> 6)) as u16
+    }
+}
+
+pub fn decode_etc1(data: &[u8], width: u32, height: u32) -> RgbImage {
+    super::decode_blocks(data, width, height, (4, 4), |block: &[u8; 8]| {
+        decode_etc_block(block, false, false).map(|p| Rgb([p[0], p[1], p[2]]))
+    })
+}
+
+pub fn decode_etc2_rgb(data: &[u8], width: u32, height: u32) -> RgbImage {
+    super::decode_blocks(data, width, height, (4, 4), |block: &[u8; 8]| {
+        decode_etc_block(block, true, false).map(|p| Rgb([p[0], p[1], p[2]]))
+    })
+}
+
+/// ETC2 with 1 bit "punchthrough" alpha.
+pub fn decode_etc2_rgba1(data: &[u8], width: u32, height: u32) -> RgbaImage {
+    super::decode_blocks(data, width, height, (4, 4), |block: &[u8; 8]| {
+        decode_etc_block(block, true, true)
+    })
+}
+
+/// ETC2 with an EAC alpha block.
+pub fn decode_etc2_rgba8(data: &[u8], width: u32, height: u32) -> RgbaImage {
+    super::decode_blocks(data, width, height, (4, 4), |block: &[u8; 16]| {
+        let alpha = decode_eac_alpha_block(&block[0..8]);
+        let mut color = decode_etc_block(block[8..16].try_into().unwrap(), true, false);
+        for (pixel, alpha) in color.iter_mut().zip(alpha) {
+            pixel[3] = alpha as u8;
+        }
+        color
+    })
+}
+
+pub fn decode_eac_r11(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    signed: bool,
+) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+    super::decode_blocks(data, width, height, (4, 4), |block: &[u8; 8]| {
+        decode_eac_r11_block(block, signed).map(|r| Luma([eac_to_u16(r, signed)]))
+    })
+}
+
+/// Red & green channels are each stored as an EAC block, blue is left empty.
+pub fn decode_eac_rg11(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    signed: bool,
+) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+    super::decode_blocks(
+        data,
+        width,
+        height,
+        (4, 4),
+        |block: &[u8; 16]| -> [Rgb<u16>; 16] {
+            let red = decode_eac_r11_block(&block[0..8], signed);
+            let green = decode_eac_r11_block(&block[8..16], signed);
+            std::array::from_fn(|pi| {
+                Rgb([
+                    eac_to_u16(red[pi], signed),
+                    eac_to_u16(green[pi], signed),
+                    0,
+                ])
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etc1_individual_mode() {
+        // Left half has a red base color, right half black, every pixel uses the -8 modifier.
+        let block = [0xF0, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF];
+        let image = decode_etc1(&block, 4, 4);
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let expected = if x < 2 { [247, 0, 0] } else { [0, 0, 0] };
+            assert_eq!(pixel.0, expected, "pixel {}, {}", x, y);
+        }
+    }
+
+    #[test]
+    fn etc1_base_color() {
+        let image = decode_etc1(&[0; 8], 4, 4);
+        assert!(image.pixels().all(|pixel| pixel.0 == [2, 2, 2]));
+    }
+
+    fn rgb(pixels: [Rgba<u8>; 16]) -> [u32; 16] {
+        pixels.map(|pixel| u32::from_be_bytes([0, pixel[0], pixel[1], pixel[2]]))
+    }
+
+    fn rgba(pixels: [Rgba<u8>; 16]) -> [u32; 16] {
+        pixels.map(|pixel| u32::from_be_bytes(pixel.0))
+    }
+
+    // The expected pixels of the following blocks were produced by an independent decoder.
+
+    #[test]
+    fn etc2_t_mode() {
+        let block = u64::from_be_bytes([0x15, 0x9D, 0x81, 0x63, 0x3E, 0xC7, 0xF9, 0xDA]);
+        assert_eq!(
+            rgb(decode_etc2_t_block(block, true)),
+            [
+                0x881166, 0x8E176C, 0x8E176C, 0x820B60, 0x820B60, 0x9999DD, 0x881166, 0x820B60,
+                0x881166, 0x820B60, 0x881166, 0x8E176C, 0x8E176C, 0x820B60, 0x820B60, 0x8E176C,
+            ]
+        );
+
+        // Punchthrough alpha, transparent pixels are black.
+        let block = u64::from_be_bytes([0x04, 0xD4, 0x91, 0xB1, 0x60, 0x7A, 0xB6, 0x36]);
+        assert_eq!(
+            rgba(decode_etc2_t_block(block, false)),
+            [
+                0x00DD44FF, 0x930BB5FF, 0x00DD44FF, 0x9F17C1FF, 0x930BB5FF, 0x930BB5FF, 0x9F17C1FF,
+                0x930BB5FF, 0x9F17C1FF, 0x00000000, 0x9F17C1FF, 0x00000000, 0x00000000, 0x00DD44FF,
+                0x00DD44FF, 0x9F17C1FF,
+            ]
+        );
+    }
+
+    #[test]
+    fn etc2_h_mode() {
+        let block = u64::from_be_bytes([0x59, 0xFA, 0xA8, 0x4A, 0xF2, 0x55, 0xDE, 0xAA]);
+        assert_eq!(
+            rgb(decode_etc2_h_block(block, true)),
+            [
+                0x5B069F, 0x5B069F, 0xC139E3, 0x4F0093, 0xB52DD7, 0xB52DD7, 0x4F0093, 0x5B069F,
+                0x5B069F, 0x5B069F, 0xB52DD7, 0x4F0093, 0xB52DD7, 0xB52DD7, 0xB52DD7, 0x4F0093,
+            ]
+        );
+
+        let block = u64::from_be_bytes([0xD3, 0x0C, 0x74, 0xAD, 0x4F, 0x75, 0xA0, 0xD0]);
+        assert_eq!(
+            rgba(decode_etc2_h_block(block, false)),
+            [
+                0x00000000, 0xC5702CFF, 0x00000000, 0xD38FB1FF, 0xD38FB1FF, 0x00000000, 0x00000000,
+                0x813D5FFF, 0x00000000, 0xC5702CFF, 0x00000000, 0x00000000, 0xD38FB1FF, 0x813D5FFF,
+                0x00000000, 0x813D5FFF,
+            ]
+        );
+    }
+
+    #[test]
+    fn etc2_planar_mode() {
+        let block = u64::from_be_bytes([0x77, 0xB6, 0x06, 0x1B, 0x68, 0xD7, 0xEF, 0xF9]);
+        assert_eq!(
+            rgb(decode_etc2_planar_block(block)),
+            [
+                0xEFB710, 0xC0A326, 0x92903D, 0x637C53, 0xF3A946, 0xC4955C, 0x968172, 0x676E89,
+                0xF79B7C, 0xC88792, 0x9A73A8, 0x6B5FBE, 0xFB8CB1, 0xCC79C8, 0x9E65DE, 0x6F51F4,
+            ]
+        );
+    }
+
+    #[test]
+    fn eac_alpha() {
+        let block = [0xA2, 0xE8, 0x78, 0xF4, 0x0D, 0x97, 0x4D, 0x6E];
+        assert_eq!(
+            decode_eac_alpha_block(&block),
+            [22, 50, 176, 255, 255, 134, 232, 232, 78, 78, 255, 232, 255, 232, 176, 255]
+        );
+
+        // Without a multiplier every pixel is the base value.
+        let block = [0x80, 0x0F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        assert_eq!(decode_eac_alpha_block(&block), [0x80; 16]);
+    }
+
+    #[test]
+    fn eac_r11() {
+        // Modifier table 0 & multiplier 2, pixels use modifiers 0 to 7 in column order.
+        let mut block = [100, 0x20, 0, 0, 0, 0, 0, 0];
+        let indices = (0..16u64).fold(0, |indices, i| indices | ((i % 8) << (45 - i * 3)));
+        block[2..].copy_from_slice(&indices.to_be_bytes()[2..]);
+        assert_eq!(
+            decode_eac_r11_block(&block, false),
+            [756, 836, 756, 836, 708, 884, 708, 884, 660, 932, 660, 932, 564, 1028, 564, 1028,]
+        );
+        assert_eq!(
+            decode_eac_r11(&block, 4, 4, false).get_pixel(0, 0).0,
+            [24203]
+        );
+
+        // Table 13 without a multiplier, the base of -128 is treated as -127 & the results are
+        // clamped to -1023.
+        let mut block = [0x80, 0x0D, 0, 0, 0, 0, 0, 0];
+        block[2..].copy_from_slice(&indices.to_be_bytes()[2..]);
+        assert_eq!(
+            decode_eac_r11_block(&block, true),
+            [
+                -1017, -1016, -1017, -1016, -1018, -1015, -1018, -1015, -1019, -1014, -1019, -1014,
+                -1023, -1007, -1023, -1007,
+            ]
+        );
+        assert_eq!(decode_eac_r11(&block, 4, 4, true).get_pixel(0, 3).0, [0]);
+    }
+}
@@ -1 +1,108 @@
+use image::{imageops::FilterType, DynamicImage, ImageBuffer, Pixel};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
+
+pub mod astc;
 pub mod bc;
+pub mod cubemap;
+pub mod dds;
+pub mod etc;
+pub mod pvrtc;
+
+/// Decodes blocks of `B` bytes in parallel, one row of blocks at a time.
+///
+/// Each block decodes to its pixels in row order, pixels past the edge of the image or the block
+/// are discarded.
+pub(crate) fn decode_blocks<P, const B: usize, D, F>(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    (block_width, block_height): (u32, u32),
+    decode_block: F,
+) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+    P: Pixel + Send + Sync,
+    P::Subpixel: Send + Sync,
+    D: AsRef<[P]>,
+    F: Fn(&[u8; B]) -> D + Sync,
+{
+    let (block_width, block_height) = (block_width as usize, block_height as usize);
+    let num_blocks_x = (width as usize).div_ceil(block_width);
+    let channels = P::CHANNEL_COUNT as usize;
+    let row_length = (width as usize) * channels;
+
+    let mut img: ImageBuffer<P, Vec<P::Subpixel>> = ImageBuffer::new(width, height);
+    if row_length == 0 {
+        return img;
+    }
+    img.par_chunks_mut(row_length * block_height)
+        .enumerate()
+        .for_each(|(block_y, rows)| {
+            for block_x in 0..num_blocks_x {
+                let data_offset = (block_x + block_y * num_blocks_x) * B;
+                let Some(block) = data.get(data_offset..(data_offset + B)) else {
+                    return;
+                };
+                let pixels = decode_block(block.try_into().unwrap());
+
+                let block_pixels = pixels.as_ref().iter().take(block_width * block_height);
+                for (pi, pixel) in block_pixels.enumerate() {
+                    let x = block_x * block_width + pi % block_width;
+                    let offset = (pi / block_width) * row_length + x * channels;
+                    if x < (width as usize) && offset < rows.len() {
+                        rows[offset..(offset + channels)].copy_from_slice(pixel.channels());
+                    }
+                }
+            }
+        });
+    img
+}
+
+/// Encodes 4x4 blocks of `B` bytes in parallel, pixels past the edge of the image repeat the
+/// last row or column.
+pub(crate) fn encode_blocks<P, const B: usize, F>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    encode_block: F,
+) -> Vec<u8>
+where
+    P: Pixel + Send + Sync,
+    P::Subpixel: Send + Sync,
+    F: Fn(&[P; 16]) -> [u8; B] + Sync,
+{
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let num_blocks_x = width.div_ceil(4);
+    let num_blocks_y = height.div_ceil(4);
+
+    let mut data = vec![0u8; (num_blocks_x as usize) * (num_blocks_y as usize) * B];
+    data.par_chunks_mut((num_blocks_x as usize) * B)
+        .enumerate()
+        .for_each(|(block_y, row)| {
+            for (block_x, block) in row.chunks_exact_mut(B).enumerate() {
+                let pixels: [P; 16] = std::array::from_fn(|pi| {
+                    let x = ((block_x as u32) * 4 + (pi as u32) % 4).min(width - 1);
+                    let y = ((block_y as u32) * 4 + (pi as u32) / 4).min(height - 1);
+                    *image.get_pixel(x, y)
+                });
+                block.copy_from_slice(&encode_block(&pixels));
+            }
+        });
+    data
+}
+
+/// Full mipmap chain down to 1x1, starting with a copy of the image itself.
+pub fn generate_mipmaps(image: &DynamicImage) -> Vec<DynamicImage> {
+    let mut mipmaps = vec![image.clone()];
+    let (mut width, mut height) = (image.width(), image.height());
+    while width > 1 || height > 1 {
+        width = (width / 2).max(1);
+        height = (height / 2).max(1);
+        let previous = mipmaps.last().unwrap();
+        mipmaps.push(previous.resize_exact(width, height, FilterType::Triangle));
+    }
+    mipmaps
+}
@@ -0,0 +1,301 @@
+// https://www.imgtec.com/downloads/download-info/pvrtc-texture-compression-user-guide-2/
+// https://s3.amazonaws.com/pvr-sdk-live/sdk-documentation/PVRTC%20Specification%20and%20User%20Guide.pdf
+//
+// Only PVRTC1 is supported, PVRTC2 was never really used.
+
+use image::{Rgba, RgbaImage};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
+
+const BLOCK_HEIGHT: usize = 4;
+
+const STANDARD_WEIGHTS: [i8; 4] = [0, 3, 5, 8];
+const PUNCHTHROUGH_WEIGHTS: [i8; 4] = [0, 4, 4, 8];
+
+// 2bpp blocks may only store half of the weights, the other half are interpolated from their
+// neighbors.
+const FILL_VERTICAL: i8 = -1;
+const FILL_HORIZONTAL: i8 = -2;
+const FILL_ALL: i8 = -3;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PvrtcBlock {
+    // Colors are 5 bit RGB & 4 bit alpha.
+    color_a: [i32; 4],
+    color_b: [i32; 4],
+    weights: [i8; 32],
+    // 1 bit for each punched through pixel.
+    punchthrough: u32,
+}
+
+impl PvrtcBlock {
+    fn new(data: &[u8; 8], two_bpp: bool) -> PvrtcBlock {
+        let modulation = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let color_a = u16::from_le_bytes([data[4], data[5]]) as i32;
+        let color_b = u16::from_le_bytes([data[6], data[7]]) as i32;
+        let modulation_mode = color_a & 1 == 1;
+
+        let mut block = PvrtcBlock {
+            color_a: if color_a & 0x8000 != 0 {
+                [
+                    (color_a >> 10) & 0x1F,
+                    (color_a >> 5) & 0x1F,
+                    (color_a & 0x1E) | ((color_a >> 4) & 1),
+                    0xF,
+                ]
+            } else {
+                [
+                    ((color_a >> 7) & 0x1E) | ((color_a >> 11) & 1),
+                    ((color_a >> 3) & 0x1E) | ((color_a >> 7) & 1),
+                    ((color_a << 1) & 0x1C) | ((color_a >> 2) & 3),
+                    (color_a >> 11) & 0xE,
+                ]
+            },
+            color_b: if color_b & 0x8000 != 0 {
+                [
+                    (color_b >> 10) & 0x1F,
+                    (color_b >> 5) & 0x1F,
+                    color_b & 0x1F,
+                    0xF,
+                ]
+            } else {
+                [
+                    ((color_b >> 7) & 0x1E) | ((color_b >> 11) & 1),
+                    ((color_b >> 3) & 0x1E) | ((color_b >> 7) & 1),
+                    ((color_b << 1) & 0x1E) | ((color_b >> 3) & 1),
+                    (color_b >> 11) & 0xE,
+                ]
+            },
+            weights: [0; 32],
+            punchthrough: 0,
+        };
+
+        match (two_bpp, modulation_mode) {
+            (false, false) => {
+                for (i, weight) in block.weights.iter_mut().take(16).enumerate() {
+                    *weight = STANDARD_WEIGHTS[((modulation >> (i * 2)) & 0b11) as usize];
+                }
+            }
+            (false, true) => {
+                for (i, weight) in block.weights.iter_mut().take(16).enumerate() {
+                    let index = (modulation >> (i * 2)) & 0b11;
+                    *weight = PUNCHTHROUGH_WEIGHTS[index as usize];
+                    if index == 2 {
+                        block.punchthrough |= 1 << i;
+                    }
+                }
+            }
+            (true, false) => {
+                for (i, weight) in block.weights.iter_mut().enumerate() {
+                    *weight = if (modulation >> i) & 1 == 1 { 8 } else { 0 };
+                }
+            }
+            (true, true) => {
+                let fill = if modulation & 1 == 0 {
+                    FILL_ALL
+                } else if (modulation >> 20) & 1 == 1 {
+                    FILL_VERTICAL
+                } else {
+                    FILL_HORIZONTAL
+                };
+
+                // Weights are stored in a checkerboard pattern.
+                let mut index = 0;
+                for (i, weight) in block.weights.iter_mut().enumerate() {
+                    if ((i % 8) + (i / 8)) % 2 == 0 {
+                        *weight = STANDARD_WEIGHTS[((modulation >> (index * 2)) & 0b11) as usize];
+                        index += 1;
+                    } else {
+                        *weight = fill;
+                    }
+                }
+
+                // The lowest bit of these were used for the fill mode flags.
+                block.weights[0] = if block.weights[0] >= 5 { 8 } else { 0 };
+                if modulation & 1 == 1 {
+                    block.weights[20] = if block.weights[20] >= 5 { 8 } else { 0 };
+                }
+            }
+        }
+
+        block
+    }
+}
+
+/// Morton order, with the leftover bits of the longest side appended.
+fn morton_index(x: usize, y: usize, min_dimension: usize) -> usize {
+    let mut index = 0;
+    let mut bit = 0;
+    while (1 << bit) < min_dimension {
+        index |= ((y >> bit) & 1) << (bit * 2);
+        index |= ((x >> bit) & 1) << (bit * 2 + 1);
+        bit += 1;
+    }
+    index | (((x | y) >> bit) << (bit * 2))
+}
+
+/// Colors are bilinear upscaled from block centers, these are the weights of the previous,
+/// current & next block for a pixel on one axis.
+fn upscale_weights(position: usize, size: usize) -> [i32; 3] {
+    let offset = position as i32 - (size / 2) as i32;
+    [(-offset).max(0), size as i32 - offset.abs(), offset.max(0)]
+}
+
+/// Decode a block with its neighbors, `neighbors` are the 3x3 surrounding blocks in row order.
+fn decode_pvrtc_block(neighbors: &[PvrtcBlock; 9], block_width: usize) -> [Rgba<u8>; 32] {
+    let block = &neighbors[4];
+
+    // Weight of a pixel in any of the neighbors, relative to the center block.
+    let weight_at = |x: i32, y: i32| -> i32 {
+        let (block_x, pixel_x) = match x {
+            x if x < 0 => (0, x + block_width as i32),
+            x if x >= block_width as i32 => (2, x - block_width as i32),
+            x => (1, x),
+        };
+        let (block_y, pixel_y) = match y {
+            y if y < 0 => (0, y + BLOCK_HEIGHT as i32),
+            y if y >= BLOCK_HEIGHT as i32 => (2, y - BLOCK_HEIGHT as i32),
+            y => (1, y),
+        };
+        neighbors[block_y * 3 + block_x].weights
+            [(pixel_y as usize) * block_width + pixel_x as usize] as i32
+    };
+
+    // Upscaled colors are scaled by the total weight, which is 16 for 4bpp & 32 for 2bpp.
+    let shift = if block_width == 8 { 1 } else { 0 };
+    let normalize = |color: [i32; 4]| -> [i32; 4] {
+        [
+            (color[0] >> (1 + shift)) + (color[0] >> (6 + shift)),
+            (color[1] >> (1 + shift)) + (color[1] >> (6 + shift)),
+            (color[2] >> (1 + shift)) + (color[2] >> (6 + shift)),
+            (color[3] >> shift) + (color[3] >> (4 + shift)),
+        ]
+    };
+
+    std::array::from_fn(|i| {
+        let (x, y) = (i % block_width, i / block_width);
+        if y >= BLOCK_HEIGHT {
+            return Rgba([0, 0, 0, 0]);
+        }
+
+        let weights_x = upscale_weights(x, block_width);
+        let weights_y = upscale_weights(y, BLOCK_HEIGHT);
+        let mut color_a = [0; 4];
+        let mut color_b = [0; 4];
+        for (neighbor, block) in neighbors.iter().enumerate() {
+            let weight = weights_x[neighbor % 3] * weights_y[neighbor / 3];
+            for c in 0..4 {
+                color_a[c] += block.color_a[c] * weight;
+                color_b[c] += block.color_b[c] * weight;
+            }
+        }
+        let (color_a, color_b) = (normalize(color_a), normalize(color_b));
+
+        let (x, y) = (x as i32, y as i32);
+        let weight = match block.weights[i] {
+            FILL_VERTICAL => (weight_at(x, y - 1) + weight_at(x, y + 1) + 1) / 2,
+            FILL_HORIZONTAL => (weight_at(x - 1, y) + weight_at(x + 1, y) + 1) / 2,
+            FILL_ALL => {
+                (weight_at(x, y - 1)
+                    + weight_at(x, y + 1)
+                    + weight_at(x - 1, y)
+                    + weight_at(x + 1, y)
+                    + 2)
+                    / 4
+            }
+            weight => weight as i32,
+        };
+
+        let mut pixel = Rgba(std::array::from_fn(|c| {
+            ((color_a[c] * (8 - weight) + color_b[c] * weight) / 8) as u8
+        }));
+        if (block.punchthrough >> i) & 1 == 1 {
+            pixel[3] = 0;
+        }
+        pixel
+    })
+}
+
+/// PVRTC blocks aren't independent, each pixel is interpolated between the neighboring blocks.
+///
+/// Width & height are expected to be powers of 2.
+pub fn decode_pvrtc(data: &[u8], width: u32, height: u32, two_bpp: bool) -> RgbaImage {
+    let block_width = if two_bpp { 8 } else { 4 };
+    // Textures are always at least 2x2 blocks.
+    let num_blocks_x = (width as usize).div_ceil(block_width).max(2);
+    let num_blocks_y = (height as usize).div_ceil(BLOCK_HEIGHT).max(2);
+    let min_dimension = num_blocks_x.min(num_blocks_y);
+
+    let blocks: Vec<PvrtcBlock> = data
+        .chunks_exact(8)
+        .take(num_blocks_x * num_blocks_y)
+        .map(|block| PvrtcBlock::new(block.try_into().unwrap(), two_bpp))
+        .collect();
+    let block_at = |x: usize, y: usize| -> PvrtcBlock {
+        blocks
+            .get(morton_index(x, y, min_dimension))
+            .copied()
+            .unwrap_or_default()
+    };
+
+    let mut img = RgbaImage::new(width, height);
+    let row_length = (width as usize) * 4;
+    if row_length == 0 {
+        return img;
+    }
+    img.par_chunks_mut(row_length * BLOCK_HEIGHT)
+        .enumerate()
+        .for_each(|(block_y, rows)| {
+            for block_x in 0..(width as usize).div_ceil(block_width) {
+                let neighbors: [PvrtcBlock; 9] = std::array::from_fn(|i| {
+                    let x = (block_x + num_blocks_x + (i % 3) - 1) % num_blocks_x;
+                    let y = (block_y + num_blocks_y + (i / 3) - 1) % num_blocks_y;
+                    block_at(x, y)
+                });
+                let pixels = decode_pvrtc_block(&neighbors, block_width);
+
+                for (pi, pixel) in pixels.iter().take(block_width * BLOCK_HEIGHT).enumerate() {
+                    let x = block_x * block_width + pi % block_width;
+                    let offset = (pi / block_width) * row_length + x * 4;
+                    if x < (width as usize) && offset < rows.len() {
+                        rows[offset..(offset + 4)].copy_from_slice(&pixel.0);
+                    }
+                }
+            }
+        });
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniform_blocks(modulation: u32) -> Vec<u8> {
+        // Opaque black & white endpoints.
+        let block = [
+            modulation.to_le_bytes().as_slice(),
+            &0x8000u16.to_le_bytes(),
+            &0xFFFFu16.to_le_bytes(),
+        ]
+        .concat();
+        block.repeat(4)
+    }
+
+    #[test]
+    fn uniform_modulation() {
+        let image = decode_pvrtc(&uniform_blocks(0), 8, 8, false);
+        assert!(image.pixels().all(|pixel| pixel.0 == [0, 0, 0, 255]));
+        let image = decode_pvrtc(&uniform_blocks(u32::MAX), 8, 8, false);
+        assert!(image.pixels().all(|pixel| pixel.0 == [255, 255, 255, 255]));
+        let image = decode_pvrtc(&uniform_blocks(u32::MAX), 16, 8, true);
+        assert!(image.pixels().all(|pixel| pixel.0 == [255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn truncated_data() {
+        let image = decode_pvrtc(&uniform_blocks(0)[..20], 8, 8, false);
+        assert_eq!(image.dimensions(), (8, 8));
+    }
+}
@@ -0,0 +1,74 @@
+// https://wiki.multimedia.cx/index.php/Bink_Container
+
+use crate::info::{Track, TrackKind, VideoInfo};
+use anyhow::{anyhow, Result};
+use std::io::{Read, Seek};
+use util::reader::Reader;
+
+const AUDIO_STEREO: u16 = 0x2000;
+const AUDIO_DCT: u16 = 0x1000;
+
+pub(crate) fn load<R: Read + Seek>(reader: &mut Reader<R>) -> Result<VideoInfo> {
+    let signature = reader.read::<[u8; 4]>()?;
+    let container = match &signature[0..3] {
+        b"BIK" => "Bink",
+        b"KB2" => "Bink 2",
+        _ => return Err(anyhow!("Invalid Bink signature")),
+    };
+    // Revision letter.
+    let codec = format!("{} {}", container, signature[3] as char);
+
+    reader.read_le::<u32>()?;
+    let frames = reader.read_le::<u32>()?;
+    reader.read_le::<[u32; 2]>()?;
+    let width = reader.read_le::<u32>()?;
+    let height = reader.read_le::<u32>()?;
+    let fps_dividend = reader.read_le::<u32>()?;
+    let fps_divider = reader.read_le::<u32>()?;
+    reader.read_le::<u32>()?;
+    let audio_tracks = reader.read_le::<u32>()?;
+    if audio_tracks > 256 {
+        return Err(anyhow!("Bink file has too many audio tracks"));
+    }
+
+    let mut video = Track::new(TrackKind::Video, codec);
+    video.width = Some(width);
+    video.height = Some(height);
+    let frame_rate =
+        (fps_divider != 0 && fps_dividend != 0).then(|| fps_dividend as f64 / fps_divider as f64);
+    video.frame_rate = frame_rate;
+    let mut tracks = vec![video];
+
+    // Newer revisions have an unknown field before the audio tracks.
+    if audio_tracks > 0
+        && matches!(
+            (container, signature[3]),
+            ("Bink", b'k') | ("Bink 2", b'i'..=b'k')
+        )
+    {
+        reader.read_le::<u32>()?;
+    }
+    // Max decoded sizes, then sample rates & flags, then IDs.
+    reader.read_vec_le::<u32>(audio_tracks as usize)?;
+    for _ in 0..audio_tracks {
+        let sample_rate = reader.read_le::<u16>()?;
+        let flags = reader.read_le::<u16>()?;
+        let mut track = Track::new(
+            TrackKind::Audio,
+            if flags & AUDIO_DCT != 0 {
+                "Bink Audio (DCT)".to_owned()
+            } else {
+                "Bink Audio (RDFT)".to_owned()
+            },
+        );
+        track.sample_rate = Some(sample_rate as f64);
+        track.channels = Some(if flags & AUDIO_STEREO != 0 { 2 } else { 1 });
+        tracks.push(track);
+    }
+
+    Ok(VideoInfo {
+        container,
+        duration: frame_rate.map(|frame_rate| frames as f64 / frame_rate),
+        tracks,
+    })
+}
@@ -0,0 +1,49 @@
+// Decoding video is left to ffmpeg & ffplay when they are installed, nothing is bundled.
+
+use anyhow::{anyhow, Result};
+use image::DynamicImage;
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+/// Searches `PATH` for the program.
+pub fn find_program(name: &str) -> Option<PathBuf> {
+    let name = if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_owned()
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&name))
+        .find(|path| path.is_file())
+}
+
+/// Decodes a single frame at `time` seconds.
+pub fn extract_frame(ffmpeg: &Path, input: &Path, time: f64) -> Result<DynamicImage> {
+    let output = Command::new(ffmpeg)
+        .args(["-v", "error", "-ss", &format!("{:.3}", time), "-i"])
+        .arg(input)
+        .args(["-frames:v", "1", "-f", "image2pipe", "-c:v", "png", "-"])
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(anyhow!(
+            "ffmpeg failed to extract frame: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(image::load_from_memory_with_format(
+        &output.stdout,
+        image::ImageFormat::Png,
+    )?)
+}
+
+/// Opens the video in an ffplay window, which closes itself when done.
+pub fn play(ffplay: &Path, input: &Path, title: &str) -> Result<Child> {
+    Ok(Command::new(ffplay)
+        .args(["-v", "error", "-autoexit", "-window_title", title])
+        .arg(input)
+        .stdin(Stdio::null())
+        .spawn()?)
+}
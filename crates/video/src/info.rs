@@ -0,0 +1,72 @@
+// Only the container headers are read, nothing is decoded.
+
+use anyhow::{anyhow, Result};
+use std::io::{Read, Seek};
+use util::reader::Reader;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackKind {
+    Video,
+    Audio,
+    Subtitle,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub kind: TrackKind,
+    /// As the container names it, like `V_VP9` or `avc1`.
+    pub codec: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub sample_rate: Option<f64>,
+    pub channels: Option<u32>,
+}
+
+impl Track {
+    pub(crate) fn new(kind: TrackKind, codec: String) -> Track {
+        Track {
+            kind,
+            codec,
+            width: None,
+            height: None,
+            frame_rate: None,
+            sample_rate: None,
+            channels: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VideoInfo {
+    pub container: &'static str,
+    /// In seconds.
+    pub duration: Option<f64>,
+    pub tracks: Vec<Track>,
+}
+
+impl VideoInfo {
+    /// Recognises Matroska & WebM, MP4 & QuickTime, Ogg and Bink.
+    pub fn load<R: Read + Seek>(data: R) -> Result<VideoInfo> {
+        let mut reader = Reader::new_be(data);
+        reader.rewind()?;
+        let magic = reader.read::<[u8; 8]>()?;
+        reader.rewind()?;
+        match magic {
+            [0x1A, 0x45, 0xDF, 0xA3, ..] => crate::matroska::load(&mut reader),
+            [b'O', b'g', b'g', b'S', ..] => crate::ogg::load(&mut reader),
+            [b'B', b'I', b'K', ..] | [b'K', b'B', b'2', ..] => crate::bink::load(&mut reader),
+            [_, _, _, _, a, b, c, d] if crate::mp4::is_top_level_box(&[a, b, c, d]) => {
+                crate::mp4::load(&mut reader)
+            }
+            _ => Err(anyhow!("Unknown video container")),
+        }
+    }
+
+    pub fn video_track(&self) -> Option<&Track> {
+        self.tracks
+            .iter()
+            .find(|track| track.kind == TrackKind::Video)
+    }
+}
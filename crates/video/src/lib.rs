@@ -0,0 +1,10 @@
+extern crate anyhow;
+extern crate image;
+extern crate util;
+
+mod bink;
+pub mod ffmpeg;
+pub mod info;
+mod matroska;
+mod mp4;
+mod ogg;
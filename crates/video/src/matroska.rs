@@ -0,0 +1,321 @@
+// https://www.matroska.org/technical/elements.html
+//
+// EBML elements are an ID & size, both variable length integers. Only the header, segment info &
+// tracks are read, clusters hold the frames so reading stops there.
+
+use crate::info::{Track, TrackKind, VideoInfo};
+use anyhow::{anyhow, Result};
+use std::io::{Read, Seek, SeekFrom};
+use util::reader::Reader;
+
+const EBML: u32 = 0x1A45DFA3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x18538067;
+const INFO: u32 = 0x1549A966;
+const TIMESTAMP_SCALE: u32 = 0x2AD7B1;
+const DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const DEFAULT_DURATION: u32 = 0x23E383;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const CLUSTER: u32 = 0x1F43B675;
+
+/// Length is the number of leading zeros of the first byte plus one.
+fn read_vint<R: Read>(reader: &mut Reader<R>, keep_marker: bool) -> Result<(u64, u32)> {
+    let first = reader.read::<u8>()?;
+    if first == 0 {
+        return Err(anyhow!("Invalid EBML variable length integer"));
+    }
+    let length = first.leading_zeros() + 1;
+    let mut value = if keep_marker {
+        first as u64
+    } else {
+        (first as u64) & (0xFF >> length)
+    };
+    for _ in 1..length {
+        value = (value << 8) | reader.read::<u8>()? as u64;
+    }
+    Ok((value, length))
+}
+
+/// None if the size is unknown, which streamed files use for the segment & clusters.
+fn read_size<R: Read>(reader: &mut Reader<R>) -> Result<Option<u64>> {
+    let (size, length) = read_vint(reader, false)?;
+    if size == (1 << (7 * length)) - 1 {
+        Ok(None)
+    } else {
+        Ok(Some(size))
+    }
+}
+
+/// Calls `f` with the ID & size of every child until `end`, `f` returns false to stop.
+fn children<R: Read + Seek>(
+    reader: &mut Reader<R>,
+    end: u64,
+    mut f: impl FnMut(&mut Reader<R>, u32, u64) -> Result<bool>,
+) -> Result<()> {
+    while reader.position()? < end {
+        let (id, _) = read_vint(reader, true)?;
+        let size = read_size(reader)?;
+        let start = reader.position()?;
+        let size = size.unwrap_or(end - start);
+        if !f(reader, id as u32, size)? {
+            break;
+        }
+        reader.seek(SeekFrom::Start(start + size))?;
+    }
+    Ok(())
+}
+
+fn read_uint<R: Read>(reader: &mut Reader<R>, size: u64) -> Result<u64> {
+    if size > 8 {
+        return Err(anyhow!("EBML integer too big"));
+    }
+    reader
+        .read_buf(size as usize)?
+        .iter()
+        .try_fold(0u64, |value, &byte| Ok((value << 8) | byte as u64))
+}
+
+fn read_float<R: Read>(reader: &mut Reader<R>, size: u64) -> Result<f64> {
+    match size {
+        4 => Ok(reader.read_be::<f32>()? as f64),
+        8 => Ok(reader.read_be::<f64>()?),
+        _ => Err(anyhow!("EBML float must be 4 or 8 bytes")),
+    }
+}
+
+fn read_string<R: Read>(reader: &mut Reader<R>, size: u64) -> Result<String> {
+    if size > 4096 {
+        return Err(anyhow!("EBML string too big"));
+    }
+    let buf = reader.read_buf(size as usize)?;
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    Ok(String::from_utf8_lossy(&buf[..end]).into_owned())
+}
+
+fn read_track<R: Read + Seek>(reader: &mut Reader<R>, end: u64) -> Result<Track> {
+    let mut track = Track::new(TrackKind::Other, String::new());
+    children(reader, end, |reader, id, size| {
+        match id {
+            TRACK_TYPE => {
+                track.kind = match read_uint(reader, size)? {
+                    1 => TrackKind::Video,
+                    2 => TrackKind::Audio,
+                    0x11 => TrackKind::Subtitle,
+                    _ => TrackKind::Other,
+                }
+            }
+            CODEC_ID => track.codec = read_string(reader, size)?,
+            // Nanoseconds per frame.
+            DEFAULT_DURATION => {
+                let duration = read_uint(reader, size)?;
+                if duration > 0 {
+                    track.frame_rate = Some(1_000_000_000.0 / duration as f64);
+                }
+            }
+            VIDEO => {
+                let end = reader.position()? + size;
+                children(reader, end, |reader, id, size| {
+                    match id {
+                        PIXEL_WIDTH => track.width = Some(read_uint(reader, size)? as u32),
+                        PIXEL_HEIGHT => track.height = Some(read_uint(reader, size)? as u32),
+                        _ => {}
+                    }
+                    Ok(true)
+                })?;
+            }
+            AUDIO => {
+                let end = reader.position()? + size;
+                children(reader, end, |reader, id, size| {
+                    match id {
+                        SAMPLING_FREQUENCY => track.sample_rate = Some(read_float(reader, size)?),
+                        CHANNELS => track.channels = Some(read_uint(reader, size)? as u32),
+                        _ => {}
+                    }
+                    Ok(true)
+                })?;
+            }
+            _ => {}
+        }
+        Ok(true)
+    })?;
+    Ok(track)
+}
+
+pub(crate) fn load<R: Read + Seek>(reader: &mut Reader<R>) -> Result<VideoInfo> {
+    let file_end = reader.size()?;
+    let mut doc_type = None;
+    let mut timestamp_scale = 1_000_000;
+    let mut duration = None;
+    let mut tracks = Vec::new();
+
+    let mut first = true;
+    children(reader, file_end, |reader, id, size| {
+        if first && id != EBML {
+            return Err(anyhow!("Invalid EBML header"));
+        }
+        first = false;
+        let end = reader.position()? + size;
+        match id {
+            EBML => children(reader, end, |reader, id, size| {
+                if id == DOC_TYPE {
+                    doc_type = Some(read_string(reader, size)?);
+                }
+                Ok(true)
+            })?,
+            SEGMENT => children(reader, end, |reader, id, size| {
+                let end = reader.position()? + size;
+                match id {
+                    INFO => children(reader, end, |reader, id, size| {
+                        match id {
+                            TIMESTAMP_SCALE => timestamp_scale = read_uint(reader, size)?,
+                            DURATION => duration = Some(read_float(reader, size)?),
+                            _ => {}
+                        }
+                        Ok(true)
+                    })?,
+                    TRACKS => children(reader, end, |reader, id, size| {
+                        if id == TRACK_ENTRY {
+                            let end = reader.position()? + size;
+                            tracks.push(read_track(reader, end)?);
+                        }
+                        Ok(true)
+                    })?,
+                    // Everything needed comes before the frames.
+                    CLUSTER => return Ok(false),
+                    _ => {}
+                }
+                Ok(true)
+            })?,
+            _ => {}
+        }
+        Ok(true)
+    })?;
+
+    Ok(VideoInfo {
+        container: match doc_type.as_deref() {
+            Some("webm") => "WebM",
+            Some("matroska") => "Matroska",
+            _ => return Err(anyhow!("EBML file is not Matroska or WebM")),
+        },
+        // Duration is in timestamp units, which are in nanoseconds.
+        duration: duration.map(|duration| duration * timestamp_scale as f64 / 1_000_000_000.0),
+        tracks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn element(id: u32, payload: &[u8]) -> Vec<u8> {
+        let id = id.to_be_bytes();
+        let mut element: Vec<u8> = id.into_iter().skip_while(|&b| b == 0).collect();
+        if payload.len() < 127 {
+            element.push(0x80 | payload.len() as u8);
+        } else {
+            element.push(0x01);
+            element.extend_from_slice(&(payload.len() as u64).to_be_bytes()[1..]);
+        }
+        element.extend_from_slice(payload);
+        element
+    }
+
+    fn webm(doc_type: &str) -> Vec<u8> {
+        let video = [
+            element(TRACK_TYPE, &[1]),
+            element(CODEC_ID, b"V_VP9"),
+            element(DEFAULT_DURATION, &40_000_000u32.to_be_bytes()),
+            element(
+                VIDEO,
+                &[
+                    element(PIXEL_WIDTH, &640u16.to_be_bytes()),
+                    element(PIXEL_HEIGHT, &360u16.to_be_bytes()),
+                ]
+                .concat(),
+            ),
+        ]
+        .concat();
+        let audio = [
+            element(TRACK_TYPE, &[2]),
+            element(CODEC_ID, b"A_OPUS\0"),
+            element(
+                AUDIO,
+                &[
+                    element(SAMPLING_FREQUENCY, &48000.0f64.to_be_bytes()),
+                    element(CHANNELS, &[2]),
+                ]
+                .concat(),
+            ),
+        ]
+        .concat();
+        let segment = [
+            element(
+                INFO,
+                &[
+                    element(TIMESTAMP_SCALE, &[0x0F, 0x42, 0x40]),
+                    element(DURATION, &2500.0f32.to_be_bytes()),
+                ]
+                .concat(),
+            ),
+            element(
+                TRACKS,
+                &[element(TRACK_ENTRY, &video), element(TRACK_ENTRY, &audio)].concat(),
+            ),
+            // Not valid EBML, reading has to stop before it.
+            element(CLUSTER, &[0; 200]),
+        ]
+        .concat();
+
+        let mut data = element(EBML, &element(DOC_TYPE, doc_type.as_bytes()));
+        // Unknown size, like streamed files.
+        data.extend_from_slice(&SEGMENT.to_be_bytes());
+        data.push(0xFF);
+        data.extend_from_slice(&segment);
+        data
+    }
+
+    #[test]
+    fn probe() {
+        let info = VideoInfo::load(Cursor::new(webm("webm"))).unwrap();
+        assert_eq!(info.container, "WebM");
+        assert_eq!(info.duration, Some(2.5));
+        assert_eq!(info.tracks.len(), 2);
+
+        let video = info.video_track().unwrap();
+        assert_eq!(video.codec, "V_VP9");
+        assert_eq!((video.width, video.height), (Some(640), Some(360)));
+        assert_eq!(video.frame_rate, Some(25.0));
+        let audio = &info.tracks[1];
+        assert_eq!(audio.kind, TrackKind::Audio);
+        assert_eq!(audio.codec, "A_OPUS");
+        assert_eq!(
+            (audio.sample_rate, audio.channels),
+            (Some(48000.0), Some(2))
+        );
+
+        let info = VideoInfo::load(Cursor::new(webm("matroska"))).unwrap();
+        assert_eq!(info.container, "Matroska");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(VideoInfo::load(Cursor::new(webm("mka?"))).is_err());
+        let mut truncated = webm("webm");
+        truncated.truncate(60);
+        assert!(VideoInfo::load(Cursor::new(truncated)).is_err());
+        // Zero is never a valid variable length integer.
+        let mut zero = element(EBML, &element(DOC_TYPE, b"webm"));
+        zero.extend_from_slice(&[0; 8]);
+        assert!(VideoInfo::load(Cursor::new(zero)).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+// https://developer.apple.com/documentation/quicktime-file-format
+//
+// Boxes (atoms) are a big endian size & type, the movie box has the tracks & sample tables. The
+// media data box holds the frames so it is skipped.
+
+use crate::info::{Track, TrackKind, VideoInfo};
+use anyhow::{anyhow, Result};
+use std::io::{Read, Seek, SeekFrom};
+use util::reader::Reader;
+
+/// Boxes that files start with.
+pub(crate) fn is_top_level_box(kind: &[u8; 4]) -> bool {
+    matches!(
+        kind,
+        b"ftyp" | b"moov" | b"mdat" | b"free" | b"skip" | b"wide" | b"pnot"
+    )
+}
+
+/// Calls `f` with the type & size of every child until `end`.
+fn children<R: Read + Seek>(
+    reader: &mut Reader<R>,
+    end: u64,
+    mut f: impl FnMut(&mut Reader<R>, [u8; 4], u64) -> Result<()>,
+) -> Result<()> {
+    while reader.position()? + 8 <= end {
+        let start = reader.position()?;
+        let size = reader.read::<u32>()? as u64;
+        let kind = reader.read::<[u8; 4]>()?;
+        let size = match size {
+            // Too big for 32 bits.
+            1 => reader.read::<u64>()?,
+            // Runs to the end.
+            0 => end - start,
+            size => size,
+        };
+        let header = reader.position()? - start;
+        if size < header {
+            return Err(anyhow!("Invalid MP4 box size"));
+        }
+        f(reader, kind, size - header)?;
+        reader.seek(SeekFrom::Start(start + size))?;
+    }
+    Ok(())
+}
+
+/// Time scale & duration from `mvhd` or `mdhd`, which share a layout.
+fn read_header<R: Read>(reader: &mut Reader<R>) -> Result<(u32, u64)> {
+    let version = reader.read::<u8>()?;
+    reader.read::<[u8; 3]>()?;
+    if version == 1 {
+        reader.read::<[u64; 2]>()?;
+        Ok((reader.read::<u32>()?, reader.read::<u64>()?))
+    } else {
+        reader.read::<[u32; 2]>()?;
+        Ok((reader.read::<u32>()?, reader.read::<u32>()? as u64))
+    }
+}
+
+fn seconds(time_scale: u32, duration: u64) -> Option<f64> {
+    // Fragmented files leave the duration empty.
+    if time_scale == 0 || duration == 0 || duration == u32::MAX as u64 || duration == u64::MAX {
+        None
+    } else {
+        Some(duration as f64 / time_scale as f64)
+    }
+}
+
+#[derive(Default)]
+struct Media {
+    handler: Option<[u8; 4]>,
+    time_scale: u32,
+    duration: u64,
+    format: Option<[u8; 4]>,
+    width: Option<u32>,
+    height: Option<u32>,
+    channels: Option<u32>,
+    sample_rate: Option<f64>,
+    samples: u64,
+}
+
+fn read_sample_table<R: Read + Seek>(
+    reader: &mut Reader<R>,
+    end: u64,
+    media: &mut Media,
+) -> Result<()> {
+    children(reader, end, |reader, kind, _size| {
+        match &kind {
+            b"stsd" => {
+                reader.read::<u32>()?;
+                if reader.read::<u32>()? == 0 {
+                    return Ok(());
+                }
+                // Only the first sample description.
+                reader.read::<u32>()?;
+                media.format = Some(reader.read::<[u8; 4]>()?);
+                reader.read::<[u8; 8]>()?;
+                match media.handler.as_ref() {
+                    Some(b"vide") => {
+                        reader.read::<[u8; 16]>()?;
+                        media.width = Some(reader.read::<u16>()? as u32);
+                        media.height = Some(reader.read::<u16>()? as u32);
+                    }
+                    Some(b"soun") => {
+                        reader.read::<[u8; 8]>()?;
+                        media.channels = Some(reader.read::<u16>()? as u32);
+                        reader.read::<[u8; 6]>()?;
+                        // 16.16 fixed point.
+                        media.sample_rate = Some(reader.read::<u32>()? as f64 / 65536.0);
+                    }
+                    _ => {}
+                }
+            }
+            b"stts" => {
+                reader.read::<u32>()?;
+                let entries = reader.read::<u32>()?;
+                for _ in 0..entries {
+                    media.samples += reader.read::<u32>()? as u64;
+                    reader.read::<u32>()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    })
+}
+
+fn read_track<R: Read + Seek>(reader: &mut Reader<R>, end: u64) -> Result<Option<Track>> {
+    let mut media = Media::default();
+    children(reader, end, |reader, kind, size| {
+        if &kind != b"mdia" {
+            return Ok(());
+        }
+        let end = reader.position()? + size;
+        children(reader, end, |reader, kind, size| {
+            match &kind {
+                b"mdhd" => (media.time_scale, media.duration) = read_header(reader)?,
+                b"hdlr" => {
+                    reader.read::<[u32; 2]>()?;
+                    media.handler = Some(reader.read::<[u8; 4]>()?);
+                }
+                b"minf" => {
+                    let end = reader.position()? + size;
+                    children(reader, end, |reader, kind, size| {
+                        if &kind == b"stbl" {
+                            let end = reader.position()? + size;
+                            read_sample_table(reader, end, &mut media)?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                _ => {}
+            }
+            Ok(())
+        })
+    })?;
+
+    let Some(handler) = media.handler else {
+        return Ok(None);
+    };
+    let mut track = Track::new(
+        match &handler {
+            b"vide" => TrackKind::Video,
+            b"soun" => TrackKind::Audio,
+            b"sbtl" | b"subt" | b"text" => TrackKind::Subtitle,
+            _ => TrackKind::Other,
+        },
+        media
+            .format
+            .map(|format| String::from_utf8_lossy(&format).trim().to_owned())
+            .unwrap_or_default(),
+    );
+    track.width = media.width;
+    track.height = media.height;
+    track.channels = media.channels;
+    track.sample_rate = media.sample_rate;
+    if track.kind == TrackKind::Video && media.samples > 0 {
+        track.frame_rate = seconds(media.time_scale, media.duration)
+            .map(|duration| media.samples as f64 / duration);
+    }
+    Ok(Some(track))
+}
+
+pub(crate) fn load<R: Read + Seek>(reader: &mut Reader<R>) -> Result<VideoInfo> {
+    let file_end = reader.size()?;
+    let mut container = "MP4";
+    let mut duration = None;
+    let mut tracks = Vec::new();
+    let mut found_movie = false;
+
+    children(reader, file_end, |reader, kind, size| {
+        match &kind {
+            b"ftyp" if &reader.read::<[u8; 4]>()? == b"qt  " => container = "QuickTime",
+            b"moov" => {
+                found_movie = true;
+                let end = reader.position()? + size;
+                children(reader, end, |reader, kind, size| {
+                    match &kind {
+                        b"mvhd" => {
+                            let (time_scale, length) = read_header(reader)?;
+                            duration = seconds(time_scale, length);
+                        }
+                        b"trak" => {
+                            let end = reader.position()? + size;
+                            tracks.extend(read_track(reader, end)?);
+                        }
+                        _ => {}
+                    }
+                    Ok(())
+                })?;
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    if !found_movie {
+        return Err(anyhow!("MP4 file has no movie box"));
+    }
+
+    Ok(VideoInfo {
+        container,
+        duration,
+        tracks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = (payload.len() as u32 + 8).to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    fn ints(ints: &[u32]) -> Vec<u8> {
+        ints.iter().flat_map(|int| int.to_be_bytes()).collect()
+    }
+
+    /// Version 0 `mvhd` or `mdhd`.
+    fn header(kind: &[u8; 4], time_scale: u32, duration: u32) -> Vec<u8> {
+        mp4_box(kind, &ints(&[0, 0, 0, time_scale, duration, 0]))
+    }
+
+    fn track(handler: &[u8; 4], description: &[u8], samples: u32) -> Vec<u8> {
+        let stsd = [
+            ints(&[0, 1, description.len() as u32 + 8]),
+            description.to_vec(),
+        ]
+        .concat();
+        let stbl = [
+            mp4_box(b"stsd", &stsd),
+            mp4_box(b"stts", &ints(&[0, 2, samples / 2, 512, samples / 2, 512])),
+        ]
+        .concat();
+        let mdia = [
+            header(b"mdhd", 12800, 32000),
+            mp4_box(b"hdlr", &[ints(&[0, 0]), handler.to_vec()].concat()),
+            mp4_box(b"minf", &mp4_box(b"stbl", &stbl)),
+        ]
+        .concat();
+        mp4_box(b"trak", &mp4_box(b"mdia", &mdia))
+    }
+
+    fn mp4(brand: &[u8; 4]) -> Vec<u8> {
+        let video = [
+            b"avc1".to_vec(),
+            vec![0; 8 + 16],
+            ints(&[(1280 << 16) | 720]),
+        ]
+        .concat();
+        let audio = [
+            b"mp4a".to_vec(),
+            vec![0; 8 + 8],
+            vec![0, 2],
+            vec![0; 6],
+            ints(&[44100 << 16]),
+        ]
+        .concat();
+        let moov = [
+            header(b"mvhd", 1000, 2500),
+            track(b"vide", &video, 60),
+            track(b"soun", &audio, 100),
+            track(b"hint", &[], 0),
+        ]
+        .concat();
+
+        let mut data = mp4_box(b"ftyp", &[brand.to_vec(), ints(&[0])].concat());
+        // 64 bit size.
+        data.extend_from_slice(&ints(&[1]));
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(&24u64.to_be_bytes());
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&mp4_box(b"moov", &moov));
+        data
+    }
+
+    #[test]
+    fn probe() {
+        let info = VideoInfo::load(Cursor::new(mp4(b"isom"))).unwrap();
+        assert_eq!(info.container, "MP4");
+        assert_eq!(info.duration, Some(2.5));
+        assert_eq!(info.tracks.len(), 3);
+
+        let video = info.video_track().unwrap();
+        assert_eq!(video.codec, "avc1");
+        assert_eq!((video.width, video.height), (Some(1280), Some(720)));
+        assert_eq!(video.frame_rate, Some(24.0));
+        let audio = &info.tracks[1];
+        assert_eq!(audio.kind, TrackKind::Audio);
+        assert_eq!(audio.codec, "mp4a");
+        assert_eq!(
+            (audio.sample_rate, audio.channels),
+            (Some(44100.0), Some(2))
+        );
+        assert_eq!(info.tracks[2].kind, TrackKind::Other);
+
+        let info = VideoInfo::load(Cursor::new(mp4(b"qt  "))).unwrap();
+        assert_eq!(info.container, "QuickTime");
+    }
+
+    #[test]
+    fn invalid() {
+        let no_movie = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        assert!(VideoInfo::load(Cursor::new(no_movie)).is_err());
+        let mut bad_size = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        bad_size.extend_from_slice(&ints(&[4]));
+        bad_size.extend_from_slice(b"moov");
+        assert!(VideoInfo::load(Cursor::new(bad_size)).is_err());
+        let mut truncated = mp4(b"isom");
+        truncated.truncate(truncated.len() - 40);
+        assert!(VideoInfo::load(Cursor::new(truncated)).is_err());
+        assert!(VideoInfo::load(Cursor::new(b"\0\0\0\x08abcd".to_vec())).is_err());
+    }
+}
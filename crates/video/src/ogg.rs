@@ -0,0 +1,184 @@
+// https://xiph.org/ogg/doc/framing.html
+//
+// Every stream starts with a beginning of stream page holding the codec's identification header.
+// The duration comes from the granule position of the last pages, what a granule is depends on
+// the codec.
+
+use crate::info::{Track, TrackKind, VideoInfo};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::HashMap,
+    io::{Read, Seek, SeekFrom},
+};
+use util::reader::Reader;
+
+const BEGINNING_OF_STREAM: u8 = 0x02;
+/// How much of the end of the file is searched for the last pages.
+const TAIL_SIZE: u64 = 64 * 1024;
+
+struct Stream {
+    track: Track,
+    granule_to_seconds: Box<dyn Fn(i64) -> f64>,
+}
+
+fn u24_be(bytes: &[u8]) -> u32 {
+    ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32
+}
+
+fn u32_be(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn identify(packet: &[u8]) -> Stream {
+    match packet {
+        // https://www.theora.org/doc/Theora.pdf
+        [0x80, b't', b'h', b'e', b'o', b'r', b'a', ..] if packet.len() >= 42 => {
+            let mut track = Track::new(TrackKind::Video, "theora".to_owned());
+            track.width = Some(u24_be(&packet[14..]));
+            track.height = Some(u24_be(&packet[17..]));
+            let (numerator, denominator) = (u32_be(&packet[22..]), u32_be(&packet[26..]));
+            let frame_rate = (denominator != 0).then(|| numerator as f64 / denominator as f64);
+            track.frame_rate = frame_rate;
+            let shift = (u16::from_be_bytes([packet[40], packet[41]]) >> 5) & 0x1F;
+            Stream {
+                track,
+                // Keyframe number in the high bits, frames since it in the low bits.
+                granule_to_seconds: Box::new(move |granule| {
+                    let frames = (granule >> shift) + (granule & ((1 << shift) - 1));
+                    frame_rate.map_or(0.0, |frame_rate| frames as f64 / frame_rate)
+                }),
+            }
+        }
+        [0x01, b'v', b'o', b'r', b'b', b'i', b's', ..] if packet.len() >= 16 => {
+            let mut track = Track::new(TrackKind::Audio, "vorbis".to_owned());
+            let sample_rate = u32_le(&packet[12..]) as f64;
+            track.channels = Some(packet[11] as u32);
+            track.sample_rate = Some(sample_rate);
+            Stream {
+                track,
+                granule_to_seconds: Box::new(move |granule| granule as f64 / sample_rate),
+            }
+        }
+        [b'O', b'p', b'u', b's', b'H', b'e', b'a', b'd', ..] if packet.len() >= 12 => {
+            let mut track = Track::new(TrackKind::Audio, "opus".to_owned());
+            track.channels = Some(packet[9] as u32);
+            // Always decoded at 48kHz.
+            track.sample_rate = Some(48000.0);
+            let pre_skip = u16::from_le_bytes([packet[10], packet[11]]) as i64;
+            Stream {
+                track,
+                granule_to_seconds: Box::new(move |granule| (granule - pre_skip) as f64 / 48000.0),
+            }
+        }
+        [0x7F, b'F', b'L', b'A', b'C', ..] if packet.len() >= 30 => {
+            let mut track = Track::new(TrackKind::Audio, "flac".to_owned());
+            // Sample rate & channels from the STREAMINFO block after the mapping header.
+            let info = &packet[27..];
+            let sample_rate = (((info[0] as u32) << 12)
+                | ((info[1] as u32) << 4)
+                | ((info[2] as u32) >> 4)) as f64;
+            track.channels = Some(((info[2] >> 1) & 0x07) as u32 + 1);
+            track.sample_rate = Some(sample_rate);
+            Stream {
+                track,
+                granule_to_seconds: Box::new(move |granule| granule as f64 / sample_rate),
+            }
+        }
+        [b'S', b'p', b'e', b'e', b'x', b' ', b' ', b' ', ..] if packet.len() >= 52 => {
+            let mut track = Track::new(TrackKind::Audio, "speex".to_owned());
+            let sample_rate = u32_le(&packet[36..]) as f64;
+            track.channels = Some(u32_le(&packet[48..]));
+            track.sample_rate = Some(sample_rate);
+            Stream {
+                track,
+                granule_to_seconds: Box::new(move |granule| granule as f64 / sample_rate),
+            }
+        }
+        _ => Stream {
+            track: Track::new(TrackKind::Other, String::new()),
+            granule_to_seconds: Box::new(|_| 0.0),
+        },
+    }
+}
+
+/// Header type, granule position, serial number & body of the page.
+fn read_page<R: Read>(reader: &mut Reader<R>) -> Result<(u8, i64, u32, Vec<u8>)> {
+    if &reader.read::<[u8; 4]>()? != b"OggS" {
+        return Err(anyhow!("Invalid Ogg page"));
+    }
+    reader.read::<u8>()?;
+    let header_type = reader.read::<u8>()?;
+    let granule = reader.read_le::<i64>()?;
+    let serial = reader.read_le::<u32>()?;
+    reader.read::<[u32; 2]>()?;
+    let segments = reader.read::<u8>()?;
+    let size = reader
+        .read_buf(segments as usize)?
+        .iter()
+        .map(|&size| size as usize)
+        .sum();
+    Ok((header_type, granule, serial, reader.read_buf(size)?))
+}
+
+/// Last granule position of every stream.
+fn last_granules<R: Read + Seek>(reader: &mut Reader<R>) -> Result<HashMap<u32, i64>> {
+    let size = reader.size()?;
+    let start = size.saturating_sub(TAIL_SIZE);
+    reader.seek(SeekFrom::Start(start))?;
+    let tail = reader.read_buf((size - start) as usize)?;
+
+    let mut granules = HashMap::new();
+    for i in 0..tail.len().saturating_sub(27) {
+        if &tail[i..i + 4] != b"OggS" || tail[i + 4] != 0 {
+            continue;
+        }
+        let granule = i64::from_le_bytes(tail[i + 6..i + 14].try_into()?);
+        let serial = u32_le(&tail[i + 14..]);
+        // Pages where no packet finishes have no granule position.
+        if granule != -1 {
+            granules.insert(serial, granule);
+        }
+    }
+    Ok(granules)
+}
+
+pub(crate) fn load<R: Read + Seek>(reader: &mut Reader<R>) -> Result<VideoInfo> {
+    // Beginning of stream pages all come before any other page.
+    let mut streams = Vec::new();
+    while reader.bytes_remaining()? > 0 {
+        let (header_type, _, serial, body) = read_page(reader)?;
+        if header_type & BEGINNING_OF_STREAM == 0 {
+            break;
+        }
+        streams.push((serial, identify(&body)));
+    }
+    if streams.is_empty() {
+        return Err(anyhow!("Ogg file has no streams"));
+    }
+
+    let granules = last_granules(reader)?;
+    let duration = streams
+        .iter()
+        .filter_map(|(serial, stream)| {
+            granules
+                .get(serial)
+                .map(|&granule| (stream.granule_to_seconds)(granule))
+        })
+        .fold(None, |duration: Option<f64>, seconds| {
+            Some(duration.map_or(seconds, |duration| duration.max(seconds)))
+        })
+        .filter(|&duration| duration > 0.0);
+
+    Ok(VideoInfo {
+        container: "Ogg",
+        duration,
+        tracks: streams
+            .into_iter()
+            .map(|(_, stream)| stream.track)
+            .collect(),
+    })
+}